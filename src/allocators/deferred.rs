@@ -5,7 +5,7 @@
 
 use crossbeam_queue::SegQueue;
 
-use crate::allocators::slab::LocalPools;
+use crate::allocators::slab::{LocalPools, SlabRegistry};
 
 /// A pending deferred free.
 struct DeferredFree {
@@ -39,9 +39,9 @@ impl DeferredFreeQueue {
     /// Drain all pending frees into the local pools.
     ///
     /// Called by the owning thread to reclaim its memory.
-    pub fn drain(&self, pools: &mut LocalPools) {
+    pub fn drain(&self, pools: &mut LocalPools, registry: &SlabRegistry) {
         while let Some(deferred) = self.queue.pop() {
-            pools.drain_deferred(deferred.ptr, deferred.size);
+            pools.drain_deferred(deferred.ptr, deferred.size, registry);
         }
     }
 
@@ -0,0 +1,502 @@
+//! Eviction policies for the streaming allocator.
+//!
+//! `StreamPriority` always decides first: a lower-priority asset is evicted
+//! before any higher-priority one. Within a priority level, an
+//! `EvictionPolicy` scores candidates and the lowest score goes first.
+//!
+//! Built-in policies:
+//! - `LruPolicy`: least recently accessed first (the default)
+//! - `LfuPolicy`: least frequently accessed first
+//! - `TwoQueuePolicy`: 2Q; assets touched once go before re-referenced ones
+//! - `SizeWeightedPolicy`: lowest reload cost per byte first
+//! - `DistancePolicy`: farthest from the camera first
+//!
+//! `AccessTrace` records real streaming traffic and replays it against any
+//! policy, so policies can be compared on the same workload.
+
+use std::collections::HashMap;
+
+use super::streaming::{StreamId, StreamPriority, StreamingAllocator};
+
+/// Per-asset hints that policies may use to rank eviction candidates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EvictionHint {
+    /// Relative cost of reloading the asset (higher = keep longer)
+    pub cost: f32,
+    /// Distance from the camera or point of interest, if known
+    pub distance: Option<f32>,
+}
+
+impl Default for EvictionHint {
+    fn default() -> Self {
+        Self {
+            cost: 1.0,
+            distance: None,
+        }
+    }
+}
+
+/// An allocation that eviction would (or did) remove.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvictionCandidate {
+    /// Allocation ID
+    pub id: StreamId,
+    /// Reserved size in bytes
+    pub size: usize,
+    /// Priority of the allocation
+    pub priority: StreamPriority,
+    /// Frame of the last access
+    pub last_access: u64,
+    /// Number of accesses since the allocation was reserved
+    pub access_count: u64,
+    /// User-supplied eviction hint
+    pub hint: EvictionHint,
+    /// User-defined tag
+    pub tag: Option<&'static str>,
+    /// Score assigned by the policy (lower = evicted sooner)
+    pub score: f64,
+}
+
+/// Result of an eviction decision or dry-run.
+///
+/// Lists the allocations selected for eviction, in eviction order.
+#[derive(Debug, Clone, Default)]
+pub struct EvictionPlan {
+    /// Name of the policy that ranked the candidates
+    pub policy: &'static str,
+    /// Bytes that must be freed to fit the request
+    pub bytes_needed: usize,
+    /// Bytes the selected candidates free
+    pub bytes_freed: usize,
+    /// Allocations selected for eviction, in order
+    pub candidates: Vec<EvictionCandidate>,
+}
+
+impl EvictionPlan {
+    /// Whether evicting the candidates makes enough room.
+    pub fn is_sufficient(&self) -> bool {
+        self.bytes_freed >= self.bytes_needed
+    }
+
+    /// IDs of the allocations selected for eviction.
+    pub fn ids(&self) -> impl Iterator<Item = StreamId> + '_ {
+        self.candidates.iter().map(|c| c.id)
+    }
+}
+
+/// Ranks streaming allocations for eviction.
+///
+/// Policies see every reservation, access and removal, so stateful schemes
+/// can keep their own bookkeeping. Calls are made with the streaming
+/// allocator's lock held; keep them cheap and never call back into it.
+pub trait EvictionPolicy: Send {
+    /// Short name, reported in `EvictionPlan::policy`.
+    fn name(&self) -> &'static str;
+
+    /// Score a candidate. Lower scores are evicted first.
+    fn score(&self, candidate: &EvictionCandidate, current_frame: u64) -> f64;
+
+    /// Called after an allocation is reserved.
+    fn on_insert(&mut self, _id: StreamId, _size: usize) {}
+
+    /// Called when an allocation is accessed.
+    fn on_access(&mut self, _id: StreamId) {}
+
+    /// Called when an allocation is freed or evicted.
+    fn on_remove(&mut self, _id: StreamId) {}
+}
+
+/// Least recently used first.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LruPolicy;
+
+impl EvictionPolicy for LruPolicy {
+    fn name(&self) -> &'static str {
+        "lru"
+    }
+
+    fn score(&self, candidate: &EvictionCandidate, _current_frame: u64) -> f64 {
+        candidate.last_access as f64
+    }
+}
+
+/// Least frequently used first; ties go to the least recently used.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LfuPolicy;
+
+impl EvictionPolicy for LfuPolicy {
+    fn name(&self) -> &'static str {
+        "lfu"
+    }
+
+    fn score(&self, candidate: &EvictionCandidate, _current_frame: u64) -> f64 {
+        candidate.access_count as f64
+    }
+}
+
+/// Score offset placing the 2Q hot queue after the probation queue.
+///
+/// 2^52 keeps `offset + tick` exact in an `f64` for any realistic clock.
+const HOT_QUEUE_OFFSET: f64 = (1u64 << 52) as f64;
+
+/// 2Q: assets accessed at most once are evicted in FIFO order before any
+/// asset that was re-referenced, which are in turn evicted in LRU order.
+///
+/// This protects the working set from one-off streaming bursts (a camera
+/// fly-through touching everything once) that would flush a plain LRU.
+#[derive(Debug, Default, Clone)]
+pub struct TwoQueuePolicy {
+    /// Logical clock advanced on every insert and access
+    clock: u64,
+    /// Per-allocation (access count, clock of last event)
+    entries: HashMap<StreamId, (u32, u64)>,
+}
+
+impl TwoQueuePolicy {
+    /// Create an empty 2Q policy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl EvictionPolicy for TwoQueuePolicy {
+    fn name(&self) -> &'static str {
+        "2q"
+    }
+
+    fn score(&self, candidate: &EvictionCandidate, _current_frame: u64) -> f64 {
+        match self.entries.get(&candidate.id) {
+            // Hot queue sorts after the whole probation queue
+            Some(&(accesses, tick)) if accesses >= 2 => HOT_QUEUE_OFFSET + tick as f64,
+            Some(&(_, tick)) => tick as f64,
+            None => 0.0,
+        }
+    }
+
+    fn on_insert(&mut self, id: StreamId, _size: usize) {
+        self.clock += 1;
+        self.entries.insert(id, (0, self.clock));
+    }
+
+    fn on_access(&mut self, id: StreamId) {
+        self.clock += 1;
+        if let Some(entry) = self.entries.get_mut(&id) {
+            entry.0 = entry.0.saturating_add(1);
+            // Probation stays FIFO; only the hot queue tracks recency
+            if entry.0 >= 2 {
+                entry.1 = self.clock;
+            }
+        }
+    }
+
+    fn on_remove(&mut self, id: StreamId) {
+        self.entries.remove(&id);
+    }
+}
+
+/// Lowest reload cost per byte first, so large cheap assets go before
+/// small expensive ones. Ties go to the least recently used.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SizeWeightedPolicy;
+
+impl EvictionPolicy for SizeWeightedPolicy {
+    fn name(&self) -> &'static str {
+        "size-weighted"
+    }
+
+    fn score(&self, candidate: &EvictionCandidate, _current_frame: u64) -> f64 {
+        candidate.hint.cost as f64 / candidate.size.max(1) as f64
+    }
+}
+
+/// Farthest from the camera first, using `EvictionHint::distance`.
+///
+/// Assets without a distance hint are treated as being at the camera.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DistancePolicy;
+
+impl EvictionPolicy for DistancePolicy {
+    fn name(&self) -> &'static str {
+        "distance"
+    }
+
+    fn score(&self, candidate: &EvictionCandidate, _current_frame: u64) -> f64 {
+        -(candidate.hint.distance.unwrap_or(0.0) as f64)
+    }
+}
+
+/// A recorded streaming event.
+///
+/// Keys are the raw `StreamId`s seen while recording; replay maps them to
+/// fresh IDs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TraceEvent {
+    /// An asset was reserved
+    Reserve {
+        /// Recorded stream ID
+        key: u64,
+        /// Reserved size in bytes
+        size: usize,
+        /// Requested priority
+        priority: StreamPriority,
+    },
+    /// An asset was accessed
+    Access {
+        /// Recorded stream ID
+        key: u64,
+    },
+    /// An asset was freed explicitly
+    Free {
+        /// Recorded stream ID
+        key: u64,
+    },
+    /// An eviction hint was set
+    Hint {
+        /// Recorded stream ID
+        key: u64,
+        /// The hint
+        hint: EvictionHint,
+    },
+    /// The frame advanced
+    NextFrame,
+}
+
+/// A recorded sequence of streaming events.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccessTrace {
+    /// Events in recording order
+    pub events: Vec<TraceEvent>,
+}
+
+/// Outcome of replaying an `AccessTrace`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TraceReport {
+    /// Accesses that found the asset resident
+    pub hits: u64,
+    /// Accesses that found the asset evicted and had to reload it
+    pub misses: u64,
+    /// Allocations evicted during the replay
+    pub evictions: u64,
+    /// Reservations (including reloads) that could not be satisfied
+    pub failed_reservations: u64,
+}
+
+impl TraceReport {
+    /// Fraction of accesses that hit, from 0.0 to 1.0.
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            1.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+impl AccessTrace {
+    /// Create an empty trace.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append an event.
+    pub fn push(&mut self, event: TraceEvent) {
+        self.events.push(event);
+    }
+
+    /// Replay the trace against a streaming allocator.
+    ///
+    /// Reservations are marked loaded immediately. When one does not fit,
+    /// replay makes room with `evict_for(size, Critical)`, as a streaming
+    /// scheduler would. An access to an asset that has been evicted counts
+    /// as a miss and reloads it with its original size and priority.
+    /// Install the policy under test with
+    /// `StreamingAllocator::set_eviction_policy` first.
+    pub fn replay(&self, streaming: &StreamingAllocator) -> TraceReport {
+        let mut report = TraceReport::default();
+        let mut live: HashMap<u64, StreamId> = HashMap::new();
+        let mut reservations: HashMap<u64, (usize, StreamPriority)> = HashMap::new();
+        let mut hints: HashMap<u64, EvictionHint> = HashMap::new();
+        let evictions_before = streaming.eviction_count();
+
+        let load = |key: u64,
+                    size: usize,
+                    priority: StreamPriority,
+                    hints: &HashMap<u64, EvictionHint>|
+         -> Option<StreamId> {
+            let id = streaming.reserve(size, priority).or_else(|| {
+                streaming.evict_for(size, StreamPriority::Critical);
+                streaming.reserve(size, priority)
+            })?;
+            streaming.finish_load(id);
+            if let Some(hint) = hints.get(&key) {
+                streaming.set_eviction_hint(id, *hint);
+            }
+            Some(id)
+        };
+
+        for event in &self.events {
+            match *event {
+                TraceEvent::Reserve { key, size, priority } => {
+                    reservations.insert(key, (size, priority));
+                    match load(key, size, priority, &hints) {
+                        Some(id) => {
+                            live.insert(key, id);
+                        }
+                        None => report.failed_reservations += 1,
+                    }
+                }
+                TraceEvent::Access { key } => {
+                    let resident = live
+                        .get(&key)
+                        .is_some_and(|id| streaming.access(*id).is_some());
+                    if resident {
+                        report.hits += 1;
+                        continue;
+                    }
+
+                    let Some(&(size, priority)) = reservations.get(&key) else {
+                        continue;
+                    };
+                    report.misses += 1;
+                    match load(key, size, priority, &hints) {
+                        Some(id) => {
+                            live.insert(key, id);
+                            streaming.access(id);
+                        }
+                        None => report.failed_reservations += 1,
+                    }
+                }
+                TraceEvent::Free { key } => {
+                    reservations.remove(&key);
+                    if let Some(id) = live.remove(&key) {
+                        streaming.free(id);
+                    }
+                }
+                TraceEvent::Hint { key, hint } => {
+                    hints.insert(key, hint);
+                    if let Some(id) = live.get(&key) {
+                        streaming.set_eviction_hint(*id, hint);
+                    }
+                }
+                TraceEvent::NextFrame => streaming.next_frame(),
+            }
+        }
+
+        report.evictions = streaming.eviction_count() - evictions_before;
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KB: usize = 1024;
+
+    /// Asset 1 is hot, then a burst of one-off assets streams past it.
+    fn hot_asset_with_scan() -> AccessTrace {
+        let mut trace = AccessTrace::new();
+        trace.push(TraceEvent::Reserve { key: 1, size: KB, priority: StreamPriority::Low });
+        for _ in 0..5 {
+            trace.push(TraceEvent::Access { key: 1 });
+            trace.push(TraceEvent::NextFrame);
+        }
+        for key in 2..8 {
+            trace.push(TraceEvent::Reserve { key, size: KB, priority: StreamPriority::Low });
+            trace.push(TraceEvent::Access { key });
+            trace.push(TraceEvent::NextFrame);
+        }
+        trace.push(TraceEvent::Access { key: 1 });
+        trace
+    }
+
+    fn replay_with<P: EvictionPolicy + 'static>(trace: &AccessTrace, policy: P) -> TraceReport {
+        let streaming = StreamingAllocator::new(3 * KB);
+        streaming.set_eviction_policy(policy);
+        let report = trace.replay(&streaming);
+        assert_eq!(report.failed_reservations, 0);
+        report
+    }
+
+    #[test]
+    fn test_policies_on_scan_trace() {
+        let trace = hot_asset_with_scan();
+
+        let lru = replay_with(&trace, LruPolicy);
+        let lfu = replay_with(&trace, LfuPolicy);
+        let two_q = replay_with(&trace, TwoQueuePolicy::new());
+
+        // LRU lets the scan flush the hot asset; LFU and 2Q keep it
+        assert_eq!(lru.misses, 1);
+        assert_eq!(lfu.misses, 0);
+        assert_eq!(two_q.misses, 0);
+        assert!(lfu.hit_rate() > lru.hit_rate());
+    }
+
+    #[test]
+    fn test_size_weighted_and_distance_hints() {
+        let streaming = StreamingAllocator::new(4 * KB);
+        streaming.set_eviction_policy(SizeWeightedPolicy);
+
+        let big = streaming.reserve(2 * KB, StreamPriority::Low).unwrap();
+        let small = streaming.reserve(KB, StreamPriority::Low).unwrap();
+        for id in [big, small] {
+            streaming.finish_load(id);
+        }
+        let plan = streaming.plan_eviction(2 * KB, StreamPriority::Normal);
+        assert_eq!(plan.policy, "size-weighted");
+        assert_eq!(plan.candidates[0].id, big);
+
+        streaming.set_eviction_policy(DistancePolicy);
+        streaming.set_eviction_hint(big, EvictionHint { cost: 1.0, distance: Some(5.0) });
+        streaming.set_eviction_hint(small, EvictionHint { cost: 1.0, distance: Some(50.0) });
+        let plan = streaming.plan_eviction(2 * KB, StreamPriority::Normal);
+        assert_eq!(plan.candidates[0].id, small);
+    }
+
+    #[test]
+    fn test_last_eviction_is_logged() {
+        let streaming = StreamingAllocator::new(2 * KB);
+        let a = streaming.reserve(KB, StreamPriority::Low).unwrap();
+        let b = streaming.reserve(KB, StreamPriority::Low).unwrap();
+        streaming.finish_load(a);
+        streaming.finish_load(b);
+        streaming.next_frame();
+        streaming.access(a);
+
+        assert!(streaming.last_eviction().is_none());
+        streaming.reserve(KB, StreamPriority::High).unwrap();
+
+        let decision = streaming.last_eviction().unwrap();
+        assert_eq!(decision.policy, "lru");
+        assert_eq!(decision.ids().collect::<Vec<_>>(), vec![b]);
+        assert_eq!(streaming.eviction_count(), 1);
+    }
+
+    #[test]
+    fn test_recorded_trace_round_trip() {
+        let streaming = StreamingAllocator::new(2 * KB);
+        streaming.start_trace();
+        let a = streaming.reserve(KB, StreamPriority::Low).unwrap();
+        streaming.finish_load(a);
+        streaming.access(a);
+        streaming.next_frame();
+        streaming.free(a);
+        let trace = streaming.stop_trace().unwrap();
+
+        assert_eq!(
+            trace.events,
+            vec![
+                TraceEvent::Reserve { key: a.raw(), size: KB, priority: StreamPriority::Low },
+                TraceEvent::Access { key: a.raw() },
+                TraceEvent::NextFrame,
+                TraceEvent::Free { key: a.raw() },
+            ]
+        );
+
+        let report = trace.replay(&StreamingAllocator::new(2 * KB));
+        assert_eq!(report.hits, 1);
+        assert_eq!(report.misses, 0);
+    }
+}
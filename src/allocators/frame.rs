@@ -6,6 +6,11 @@
 use std::alloc::{alloc, dealloc, Layout};
 use std::ptr::NonNull;
 
+use crate::util::layout::padding_for;
+
+/// Alignment of the arena base (one cache line).
+const ARENA_ALIGN: usize = 64;
+
 /// A bump allocator for frame-temporary allocations.
 ///
 /// Allocations are extremely fast (just pointer increment).
@@ -19,12 +24,15 @@ pub struct FrameArena {
 
     /// Total capacity in bytes
     capacity: usize,
+
+    /// Highest head position reset past since the last `reset`
+    peak: usize,
 }
 
 impl FrameArena {
     /// Create a new frame arena with the given capacity.
     pub fn new(capacity: usize) -> Self {
-        let layout = Layout::from_size_align(capacity, 16).expect("Invalid arena layout");
+        let layout = Layout::from_size_align(capacity, ARENA_ALIGN).expect("Invalid arena layout");
 
         // SAFETY: We're allocating a block of memory with proper alignment
         let ptr = unsafe { alloc(layout) };
@@ -35,6 +43,7 @@ impl FrameArena {
             base,
             head: 0,
             capacity,
+            peak: 0,
         }
     }
 
@@ -47,16 +56,19 @@ impl FrameArena {
 
     /// Allocate memory with a specific layout.
     ///
-    /// Returns null if the arena is exhausted.
+    /// Any power-of-two alignment is honored. Returns null if the arena is
+    /// exhausted.
     pub fn alloc_layout(&mut self, layout: Layout) -> *mut u8 {
         let align = layout.align();
         let size = layout.size();
 
-        // Align the head
-        let aligned_head = (self.head + align - 1) & !(align - 1);
+        // Align the address rather than the offset, so alignments above
+        // the base alignment hold as well
+        let address = self.base.as_ptr() as usize + self.head;
+        let aligned_head = self.head + padding_for(address, align);
 
         // Check if we have enough space
-        if aligned_head + size > self.capacity {
+        if aligned_head.saturating_add(size) > self.capacity {
             return std::ptr::null_mut();
         }
 
@@ -82,6 +94,7 @@ impl FrameArena {
     /// Reset the arena, invalidating all allocations.
     pub fn reset(&mut self) {
         self.head = 0;
+        self.peak = 0;
 
         // Optionally poison memory in debug mode
         #[cfg(feature = "debug")]
@@ -93,9 +106,15 @@ impl FrameArena {
     /// Reset to a previously saved head position.
     pub fn reset_to(&mut self, head: usize) {
         debug_assert!(head <= self.head, "Cannot reset forward");
+        self.peak = self.peak.max(self.head);
         self.head = head;
     }
 
+    /// Get the most bytes allocated at once since the last `reset`.
+    pub fn high_water(&self) -> usize {
+        self.peak.max(self.head)
+    }
+
     /// Get remaining capacity.
     pub fn remaining(&self) -> usize {
         self.capacity - self.head
@@ -110,11 +129,17 @@ impl FrameArena {
     pub fn allocated(&self) -> usize {
         self.head
     }
+
+    /// Get the offset of `ptr` from the arena base, if it points into the arena.
+    pub fn offset_of(&self, ptr: *const u8) -> Option<usize> {
+        let offset = (ptr as usize).checked_sub(self.base.as_ptr() as usize)?;
+        (offset < self.capacity).then_some(offset)
+    }
 }
 
 impl Drop for FrameArena {
     fn drop(&mut self) {
-        let layout = Layout::from_size_align(self.capacity, 16).expect("Invalid arena layout");
+        let layout = Layout::from_size_align(self.capacity, ARENA_ALIGN).expect("Invalid arena layout");
 
         // SAFETY: We allocated this memory in `new()`
         unsafe {
@@ -151,12 +176,26 @@ mod tests {
         arena.reset();
 
         assert_eq!(arena.head(), 0);
+        assert_eq!(arena.high_water(), 0);
 
         // New allocation should reuse the same memory
         let ptr2 = arena.alloc::<u32>();
         assert_eq!(ptr1, ptr2);
     }
 
+    #[test]
+    fn test_high_water_survives_reset_to() {
+        let mut arena = FrameArena::new(1024);
+
+        let mark = arena.head();
+        let _ = arena.alloc::<[u8; 256]>();
+        arena.reset_to(mark);
+        let _ = arena.alloc::<[u8; 64]>();
+
+        assert_eq!(arena.head(), 64);
+        assert_eq!(arena.high_water(), 256);
+    }
+
     #[test]
     fn test_exhaustion() {
         let mut arena = FrameArena::new(32);
@@ -169,4 +208,17 @@ mod tests {
         let ptr = arena.alloc::<[u8; 16]>();
         assert!(ptr.is_null());
     }
+
+    #[test]
+    fn test_over_aligned_allocation() {
+        let mut arena = FrameArena::new(8192);
+
+        for align in [32, 64, 256, 4096] {
+            let _ = arena.alloc::<u8>();
+            let layout = Layout::from_size_align(24, align).unwrap();
+            let ptr = arena.alloc_layout(layout);
+            assert!(!ptr.is_null());
+            assert_eq!(ptr as usize % align, 0);
+        }
+    }
 }
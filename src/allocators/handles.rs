@@ -6,7 +6,11 @@
 use std::alloc::{alloc, dealloc, Layout};
 use std::marker::PhantomData;
 use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
 
+use crate::api::tagged;
+use crate::diagnostics::behavior::{AllocKind, BehaviorFilter};
+use crate::diagnostics::{MemoryPool, ProfilerSlot};
 use crate::sync::mutex::Mutex;
 
 /// Generation counter for handle validation.
@@ -86,6 +90,8 @@ struct Slot {
     ptr: *mut u8,
     /// Size of allocation
     size: usize,
+    /// Alignment of allocation, kept so relocation and free use the same layout
+    align: usize,
     /// Current generation
     generation: Generation,
     /// Whether this slot is in use
@@ -102,6 +108,7 @@ impl Slot {
         Self {
             ptr: std::ptr::null_mut(),
             size: 0,
+            align: 1,
             generation: 0,
             in_use: false,
             relocatable: true,
@@ -126,6 +133,12 @@ pub struct HandleAllocator {
     
     /// Number of relocations performed
     relocation_count: AtomicU64,
+
+    /// Profiler allocations, frees and relocations are reported to
+    profiler: Arc<ProfilerSlot>,
+
+    /// Behavior filter sizes and lifetimes are recorded in
+    behavior: Arc<BehaviorFilter>,
 }
 
 impl HandleAllocator {
@@ -137,9 +150,23 @@ impl HandleAllocator {
             total_allocated: AtomicU64::new(0),
             active_count: AtomicU32::new(0),
             relocation_count: AtomicU64::new(0),
+            profiler: Arc::new(ProfilerSlot::new()),
+            behavior: Arc::new(BehaviorFilter::new()),
         }
     }
 
+    /// Report allocations, frees and relocations to the profiler in `slot`.
+    pub(crate) fn with_profiler(mut self, slot: Arc<ProfilerSlot>) -> Self {
+        self.profiler = slot;
+        self
+    }
+
+    /// Record sizes and lifetimes in `filter`'s histograms.
+    pub(crate) fn with_behavior(mut self, filter: Arc<BehaviorFilter>) -> Self {
+        self.behavior = filter;
+        self
+    }
+
     /// Allocate memory and return a handle.
     pub fn alloc<T>(&self) -> Option<Handle<T>> {
         self.alloc_with_options::<T>(true, None)
@@ -161,10 +188,15 @@ impl HandleAllocator {
             return None;
         }
 
-        let (index, generation) = self.allocate_slot(ptr, size, relocatable, on_relocate);
+        let (index, generation) = self.allocate_slot(ptr, layout, relocatable, on_relocate);
         
         self.total_allocated.fetch_add(size as u64, Ordering::Relaxed);
         self.active_count.fetch_add(1, Ordering::Relaxed);
+        self.profiler.alloc(MemoryPool::Handle, ptr, size, None);
+        if self.behavior.histograms_enabled() {
+            let tag = tagged::current_tag().unwrap_or("untagged");
+            self.behavior.record_histogram_alloc(ptr, tag, AllocKind::Handle, size);
+        }
 
         Some(Handle {
             index,
@@ -177,7 +209,7 @@ impl HandleAllocator {
     fn allocate_slot(
         &self,
         ptr: *mut u8,
-        size: usize,
+        layout: Layout,
         relocatable: bool,
         on_relocate: Option<Box<dyn Fn(*mut u8, *mut u8) + Send + Sync>>,
     ) -> (u32, Generation) {
@@ -187,7 +219,8 @@ impl HandleAllocator {
         if let Some(index) = free_list.pop() {
             let slot = &mut slots[index as usize];
             slot.ptr = ptr;
-            slot.size = size;
+            slot.size = layout.size();
+            slot.align = layout.align();
             slot.generation = slot.generation.wrapping_add(1);
             slot.in_use = true;
             slot.relocatable = relocatable;
@@ -197,7 +230,8 @@ impl HandleAllocator {
             let index = slots.len() as u32;
             slots.push(Slot {
                 ptr,
-                size,
+                size: layout.size(),
+                align: layout.align(),
                 generation: 1,
                 in_use: true,
                 relocatable,
@@ -218,7 +252,9 @@ impl HandleAllocator {
 
         if let Some(slot) = slots.get_mut(handle.index as usize) {
             if slot.in_use && slot.generation == handle.generation {
-                let layout = Layout::from_size_align(slot.size, 1).expect("Invalid layout");
+                let layout = Layout::from_size_align(slot.size, slot.align).expect("Invalid layout");
+                self.profiler.free(MemoryPool::Handle, slot.ptr, slot.size);
+                self.behavior.record_histogram_free(slot.ptr);
                 unsafe {
                     dealloc(slot.ptr, layout);
                 }
@@ -329,8 +365,8 @@ impl HandleAllocator {
         for idx in relocatable {
             let slot = &mut slots[idx];
             
-            // Try to allocate new memory
-            let layout = match Layout::from_size_align(slot.size, 16) {
+            // Try to allocate new memory with the original alignment
+            let layout = match Layout::from_size_align(slot.size, slot.align) {
                 Ok(l) => l,
                 Err(_) => continue,
             };
@@ -353,6 +389,9 @@ impl HandleAllocator {
             }
 
             // Free old memory
+            self.profiler.free(MemoryPool::Handle, old_ptr, slot.size);
+            self.profiler.alloc(MemoryPool::Handle, new_ptr, slot.size, None);
+            self.behavior.record_histogram_move(old_ptr, new_ptr);
             unsafe {
                 dealloc(old_ptr, layout);
             }
@@ -532,4 +571,18 @@ mod tests {
         assert!(!allocator.is_valid(handle));
         assert!(allocator.resolve(handle).is_none());
     }
+
+    #[test]
+    fn test_defragment_keeps_alignment() {
+        #[repr(align(128))]
+        struct Aligned([u8; 128]);
+
+        let allocator = HandleAllocator::new();
+        let handle: Handle<Aligned> = allocator.alloc().unwrap();
+
+        assert_eq!(allocator.defragment(), 1);
+        let ptr = allocator.resolve(handle).unwrap();
+        assert_eq!(ptr as usize % 128, 0);
+        allocator.free(handle);
+    }
 }
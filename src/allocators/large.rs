@@ -0,0 +1,118 @@
+//! Large-object allocator for pool allocations that do not fit a slab class.
+//!
+//! Types bigger than the largest size class, or aligned beyond what slab
+//! pages guarantee, are served here straight from the system allocator.
+//! Unlike the plain heap path, every object is accounted separately so pool
+//! users can see how much of their memory bypasses the slabs.
+
+use std::alloc::{alloc, dealloc, Layout};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+/// Statistics for the large-object path.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LargeObjectStats {
+    /// Bytes currently allocated
+    pub allocated_bytes: usize,
+    /// Highest `allocated_bytes` seen
+    pub peak_bytes: usize,
+    /// Objects currently live
+    pub live_objects: u64,
+    /// Total allocations performed
+    pub allocation_count: u64,
+}
+
+/// Tracked allocator for oversized or over-aligned pool objects.
+pub struct LargeObjectAllocator {
+    allocated_bytes: AtomicUsize,
+    peak_bytes: AtomicUsize,
+    live_objects: AtomicU64,
+    allocation_count: AtomicU64,
+}
+
+impl LargeObjectAllocator {
+    /// Create a new large-object allocator.
+    pub fn new() -> Self {
+        Self {
+            allocated_bytes: AtomicUsize::new(0),
+            peak_bytes: AtomicUsize::new(0),
+            live_objects: AtomicU64::new(0),
+            allocation_count: AtomicU64::new(0),
+        }
+    }
+
+    /// Allocate memory with the given layout.
+    pub fn alloc(&self, layout: Layout) -> *mut u8 {
+        // Zero-sized objects still need a unique, aligned address
+        let layout = Layout::from_size_align(layout.size().max(1), layout.align())
+            .expect("Invalid large-object layout");
+
+        // SAFETY: layout has a non-zero size
+        let ptr = unsafe { alloc(layout) };
+        if !ptr.is_null() {
+            let total = self.allocated_bytes.fetch_add(layout.size(), Ordering::Relaxed)
+                + layout.size();
+            self.peak_bytes.fetch_max(total, Ordering::Relaxed);
+            self.live_objects.fetch_add(1, Ordering::Relaxed);
+            self.allocation_count.fetch_add(1, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    /// Free memory allocated by `alloc`.
+    ///
+    /// # Safety
+    ///
+    /// The pointer must come from `alloc` on this allocator with the same layout.
+    pub unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let layout = Layout::from_size_align(layout.size().max(1), layout.align())
+            .expect("Invalid large-object layout");
+
+        #[cfg(feature = "debug")]
+        {
+            crate::debug::poison::poison_freed(ptr, layout.size());
+        }
+
+        dealloc(ptr, layout);
+        self.allocated_bytes.fetch_sub(layout.size(), Ordering::Relaxed);
+        self.live_objects.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Get current statistics.
+    pub fn stats(&self) -> LargeObjectStats {
+        LargeObjectStats {
+            allocated_bytes: self.allocated_bytes.load(Ordering::Relaxed),
+            peak_bytes: self.peak_bytes.load(Ordering::Relaxed),
+            live_objects: self.live_objects.load(Ordering::Relaxed),
+            allocation_count: self.allocation_count.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Default for LargeObjectAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_large_object_tracking() {
+        let large = LargeObjectAllocator::new();
+        let layout = Layout::from_size_align(8192, 64).unwrap();
+
+        let ptr = large.alloc(layout);
+        assert!(!ptr.is_null());
+        assert_eq!(ptr as usize % 64, 0);
+        assert_eq!(large.stats().allocated_bytes, 8192);
+        assert_eq!(large.stats().live_objects, 1);
+
+        unsafe { large.dealloc(ptr, layout) };
+        let stats = large.stats();
+        assert_eq!(stats.allocated_bytes, 0);
+        assert_eq!(stats.peak_bytes, 8192);
+        assert_eq!(stats.allocation_count, 1);
+    }
+}
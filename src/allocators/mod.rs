@@ -4,8 +4,13 @@
 //! **These are the only modules that should contain `unsafe` code.**
 
 pub(crate) mod deferred;
+pub(crate) mod eviction;
 pub(crate) mod frame;
 pub(crate) mod handles;
 pub(crate) mod heap;
+pub(crate) mod large;
+pub(crate) mod page_source;
 pub(crate) mod slab;
+pub(crate) mod stream_loader;
 pub(crate) mod streaming;
+pub(crate) mod tlsf;
@@ -0,0 +1,229 @@
+//! Page sources for the slab registry.
+//!
+//! The slab registry asks a `PageSource` for every new page it carves into
+//! objects. The default `SystemPageSource` uses the global allocator. On
+//! Linux, `NumaPageSource` places pages on the NUMA node of the requesting
+//! thread and lets the registry keep a separate magazine depot per node, so
+//! threads refill from memory that is local to the core they run on.
+
+use std::alloc::{alloc, Layout};
+use std::sync::Arc;
+
+use crate::api::config::AllocConfig;
+
+/// Supplies pages to the slab registry.
+pub trait PageSource: Send + Sync {
+    /// Allocate a page for node `node`. Returns null on failure.
+    ///
+    /// Pages are never returned to the source.
+    fn alloc_page(&self, layout: Layout, node: usize) -> *mut u8;
+
+    /// Get the number of nodes this source distinguishes.
+    fn node_count(&self) -> usize {
+        1
+    }
+
+    /// Get the node the calling thread should draw pages from.
+    fn current_node(&self) -> usize {
+        0
+    }
+}
+
+/// Page source backed by the global allocator, with a single node.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemPageSource;
+
+impl PageSource for SystemPageSource {
+    fn alloc_page(&self, layout: Layout, _node: usize) -> *mut u8 {
+        // SAFETY: slab pages have a non-zero size
+        unsafe { alloc(layout) }
+    }
+}
+
+/// Get the page source selected by `config`.
+///
+/// `numa_local_slabs` picks `NumaPageSource` on Linux when the machine has
+/// more than one node, and the system source otherwise.
+pub fn page_source_for(config: &AllocConfig) -> Arc<dyn PageSource> {
+    #[cfg(target_os = "linux")]
+    if config.numa_local_slabs {
+        let numa = NumaPageSource::detect();
+        if numa.node_count() > 1 {
+            return Arc::new(numa);
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    let _ = config;
+
+    Arc::new(SystemPageSource)
+}
+
+#[cfg(target_os = "linux")]
+pub use numa::NumaPageSource;
+
+#[cfg(target_os = "linux")]
+mod numa {
+    use super::*;
+    use std::cell::Cell;
+    use std::fs;
+
+    /// Lookups served from the cached CPU before `/proc` is read again.
+    const CPU_REFRESH_INTERVAL: u32 = 256;
+
+    /// Size of the pages the kernel places on a node.
+    const OS_PAGE_SIZE: usize = 4096;
+
+    thread_local! {
+        /// The calling thread's last known CPU and lookups left before refresh
+        static CURRENT_CPU: Cell<(usize, u32)> = const { Cell::new((0, 0)) };
+    }
+
+    /// NUMA-aware page source for Linux.
+    ///
+    /// Reads the CPU-to-node topology from `/sys/devices/system/node` and
+    /// relies on the kernel's first-touch policy: every page is written by
+    /// the allocating thread before it is handed out, so its memory lands on
+    /// that thread's node. Threads are assumed to stay on one node for a
+    /// while (pinned job-system workers); the current CPU is re-read every
+    /// few hundred lookups.
+    #[derive(Debug, Clone)]
+    pub struct NumaPageSource {
+        /// Node of each CPU, indexed by CPU number
+        cpu_nodes: Vec<usize>,
+        /// Number of nodes
+        nodes: usize,
+    }
+
+    impl NumaPageSource {
+        /// Detect the machine's topology.
+        ///
+        /// Falls back to a single node when sysfs is unavailable.
+        pub fn detect() -> Self {
+            let mut cpu_nodes = Vec::new();
+            let mut nodes = 0;
+
+            if let Ok(entries) = fs::read_dir("/sys/devices/system/node") {
+                for entry in entries.flatten() {
+                    let name = entry.file_name();
+                    let Some(node) = name
+                        .to_str()
+                        .and_then(|n| n.strip_prefix("node"))
+                        .and_then(|n| n.parse::<usize>().ok())
+                    else {
+                        continue;
+                    };
+                    let Ok(list) = fs::read_to_string(entry.path().join("cpulist")) else {
+                        continue;
+                    };
+
+                    for cpu in parse_cpu_list(&list) {
+                        if cpu_nodes.len() <= cpu {
+                            cpu_nodes.resize(cpu + 1, 0);
+                        }
+                        cpu_nodes[cpu] = node;
+                    }
+                    nodes = nodes.max(node + 1);
+                }
+            }
+
+            Self {
+                cpu_nodes,
+                nodes: nodes.max(1),
+            }
+        }
+
+        /// Get the node a CPU belongs to.
+        pub fn node_of_cpu(&self, cpu: usize) -> usize {
+            self.cpu_nodes.get(cpu).copied().unwrap_or(0)
+        }
+    }
+
+    impl PageSource for NumaPageSource {
+        fn alloc_page(&self, layout: Layout, _node: usize) -> *mut u8 {
+            // SAFETY: slab pages have a non-zero size
+            let page = unsafe { alloc(layout) };
+            if page.is_null() {
+                return page;
+            }
+
+            // First touch from this thread commits the memory on its node
+            for offset in (0..layout.size()).step_by(OS_PAGE_SIZE) {
+                // SAFETY: offset is within the allocation
+                unsafe { page.add(offset).write_volatile(0) };
+            }
+            page
+        }
+
+        fn node_count(&self) -> usize {
+            self.nodes
+        }
+
+        fn current_node(&self) -> usize {
+            self.node_of_cpu(current_cpu())
+        }
+    }
+
+    /// Get the CPU the calling thread last ran on, re-reading it periodically.
+    fn current_cpu() -> usize {
+        CURRENT_CPU
+            .try_with(|cached| {
+                let (cpu, remaining) = cached.get();
+                if remaining > 0 {
+                    cached.set((cpu, remaining - 1));
+                    return cpu;
+                }
+                let cpu = read_current_cpu().unwrap_or(cpu);
+                cached.set((cpu, CPU_REFRESH_INTERVAL));
+                cpu
+            })
+            .unwrap_or(0)
+    }
+
+    /// Read the `processor` field (39) of `/proc/thread-self/stat`.
+    fn read_current_cpu() -> Option<usize> {
+        let stat = fs::read_to_string("/proc/thread-self/stat").ok()?;
+        // The command name may contain spaces; fields resume after the ')'
+        let fields = &stat[stat.rfind(')')? + 1..];
+        fields.split_whitespace().nth(36)?.parse().ok()
+    }
+
+    /// Parse a sysfs CPU list such as "0-3,8-11".
+    fn parse_cpu_list(list: &str) -> Vec<usize> {
+        let mut cpus = Vec::new();
+        for part in list.trim().split(',').filter(|p| !p.is_empty()) {
+            match part.split_once('-') {
+                Some((start, end)) => {
+                    if let (Ok(start), Ok(end)) = (start.parse::<usize>(), end.parse::<usize>()) {
+                        cpus.extend(start..=end);
+                    }
+                }
+                None => cpus.extend(part.parse::<usize>().ok()),
+            }
+        }
+        cpus
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_parse_cpu_list() {
+            assert_eq!(parse_cpu_list("0-3,8,10-11\n"), vec![0, 1, 2, 3, 8, 10, 11]);
+            assert!(parse_cpu_list("\n").is_empty());
+        }
+
+        #[test]
+        fn test_detect_and_touch() {
+            let source = NumaPageSource::detect();
+            assert!(source.node_count() >= 1);
+            assert!(source.current_node() < source.node_count());
+
+            let layout = Layout::from_size_align(16 * 1024, 64).unwrap();
+            let page = source.alloc_page(layout, source.current_node());
+            assert!(!page.is_null());
+            unsafe { std::alloc::dealloc(page, layout) };
+        }
+    }
+}
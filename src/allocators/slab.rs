@@ -1,21 +1,179 @@
 //! Slab allocator for small object pools.
 //!
 //! Uses size classes to efficiently allocate small objects.
-//! Thread-local pools avoid contention; global registry for refills.
+//! Thread-local pools avoid contention; they exchange fixed-size magazines
+//! with a global depot per size class (and per NUMA node, if the page
+//! source has several).
 
-use std::alloc::{alloc, Layout};
+use std::alloc::Layout;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
+use crate::allocators::page_source::{page_source_for, PageSource};
 use crate::api::config::AllocConfig;
+use crate::diagnostics::behavior::AllocKind;
 use crate::sync::mutex::Mutex;
 
 /// Number of size classes
-const NUM_SIZE_CLASSES: usize = 9;
+pub(crate) const NUM_SIZE_CLASSES: usize = 9;
 
 /// Default size classes (bytes)
 const DEFAULT_SIZE_CLASSES: [usize; NUM_SIZE_CLASSES] = [16, 32, 64, 128, 256, 512, 1024, 2048, 4096];
 
+/// Alignment guaranteed for every slab object.
+pub const SLAB_ALIGN: usize = 16;
+
+/// Largest alignment any slab class can guarantee.
+pub const MAX_SLAB_ALIGN: usize = 4096;
+
+/// Get the alignment guaranteed for objects of a size class.
+///
+/// Pages are aligned to this value and objects are spaced at the class
+/// size, so it is the largest power of two dividing the class size
+/// (between `SLAB_ALIGN` and `MAX_SLAB_ALIGN`).
+pub const fn slab_class_align(class_size: usize) -> usize {
+    let align = class_size & class_size.wrapping_neg();
+    if align < SLAB_ALIGN {
+        SLAB_ALIGN
+    } else if align > MAX_SLAB_ALIGN {
+        MAX_SLAB_ALIGN
+    } else {
+        align
+    }
+}
+
+/// Get the size classes a registry built from `config` uses.
+///
+/// Configured classes come first and extra ones are ignored. Missing slots
+/// take the default classes above the last configured one, so sizes routed
+/// to slabs keep a class, then repeat the largest class. A strictly
+/// ascending configured list therefore yields an ascending table; repeated
+/// classes are never picked.
+pub(crate) fn effective_size_classes(config: &AllocConfig) -> [usize; NUM_SIZE_CLASSES] {
+    let configured = &config.slab_size_classes[..config.slab_size_classes.len().min(NUM_SIZE_CLASSES)];
+    let Some(&last) = configured.last() else {
+        return DEFAULT_SIZE_CLASSES;
+    };
+    let mut fill = DEFAULT_SIZE_CLASSES.iter().copied().filter(|&class| class > last);
+    let mut largest = last;
+    std::array::from_fn(|i| match configured.get(i) {
+        Some(&class) => class,
+        None => {
+            largest = fill.next().unwrap_or(largest);
+            largest
+        }
+    })
+}
+
+/// Find a default class whose objects would land in a less-aligned class.
+///
+/// Pool routing picks a default class, and a registry serves it from the
+/// first of `classes` at least that large. Returns the default class and
+/// the class serving it if the latter guarantees less alignment.
+pub(crate) fn underaligned_class(classes: &[usize; NUM_SIZE_CLASSES]) -> Option<(usize, usize)> {
+    DEFAULT_SIZE_CLASSES.iter().find_map(|&class| {
+        let served = classes.iter().copied().find(|&c| c >= class)?;
+        (slab_class_align(served) < slab_class_align(class)).then_some((class, served))
+    })
+}
+
+/// Backend that serves a pool allocation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolBackend {
+    /// A slab size class
+    Slab {
+        /// Object size of the class
+        class_size: usize,
+    },
+    /// The large-object allocator, for types too big or too aligned for slabs
+    /// (above `MAX_SLAB_ALIGN`, or above the largest class's alignment)
+    LargeObject,
+}
+
+impl PoolBackend {
+    /// Choose the backend for an object of `size` bytes aligned to `align`.
+    ///
+    /// Picks the smallest class that is large enough and whose alignment
+    /// guarantee covers `align`.
+    pub const fn for_layout(size: usize, align: usize) -> Self {
+        let mut i = 0;
+        while i < NUM_SIZE_CLASSES {
+            let class_size = DEFAULT_SIZE_CLASSES[i];
+            if class_size >= size && slab_class_align(class_size) >= align {
+                return Self::Slab {
+                    class_size: DEFAULT_SIZE_CLASSES[i],
+                };
+            }
+            i += 1;
+        }
+        Self::LargeObject
+    }
+
+    /// Get the backend for `T`, resolved at compile time.
+    pub const fn of<T>() -> Self {
+        Route::<T>::BACKEND
+    }
+
+    /// Get the behavior-filter kind for this backend.
+    pub fn alloc_kind(self) -> AllocKind {
+        match self {
+            Self::Slab { .. } => AllocKind::Pool,
+            Self::LargeObject => AllocKind::LargeObject,
+        }
+    }
+}
+
+/// Compile-time backend choice for a type.
+struct Route<T>(std::marker::PhantomData<T>);
+
+impl<T> Route<T> {
+    const BACKEND: PoolBackend =
+        PoolBackend::for_layout(std::mem::size_of::<T>(), std::mem::align_of::<T>());
+}
+
+/// Get the default magazine size for a class.
+///
+/// Small objects move in larger batches so a refill amortizes the depot
+/// lock over more allocations.
+pub const fn default_batch_size(class_size: usize) -> usize {
+    let batch = 4096 / if class_size == 0 { 1 } else { class_size };
+    if batch < 8 {
+        8
+    } else if batch > 64 {
+        64
+    } else {
+        batch
+    }
+}
+
+/// A fixed-capacity batch of free objects of one size class.
+type Magazine = Vec<*mut u8>;
+
+/// Depot statistics for one size class.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DepotStats {
+    /// Object size of the class
+    pub class_size: usize,
+    /// Objects per magazine
+    pub batch_size: usize,
+    /// Full or partial magazines waiting in the depot, across all nodes
+    pub magazines: usize,
+    /// Refills served from the depot
+    pub depot_hits: u64,
+    /// Refills served from another node's depot because a page could not be allocated
+    pub remote_hits: u64,
+    /// Pages allocated from the page source
+    pub pages_allocated: u64,
+    /// Magazines returned by local pools
+    pub magazines_returned: u64,
+}
+
 /// Global slab registry - manages pages for all size classes.
+///
+/// Local pools exchange whole magazines with a per-class depot instead of
+/// taking pages, so a refill holds the depot lock only for a push or pop.
+/// With a multi-node page source each node has its own depot, and threads
+/// only fall back to another node's depot when a fresh page can't be had.
 pub struct SlabRegistry {
     /// Size classes
     size_classes: [usize; NUM_SIZE_CLASSES],
@@ -23,53 +181,68 @@ pub struct SlabRegistry {
     /// Page size
     page_size: usize,
 
-    /// Per-class page pools
+    /// Per-class depots
     classes: [SlabClass; NUM_SIZE_CLASSES],
 
+    /// Where new pages come from
+    page_source: Arc<dyn PageSource>,
+
     /// Total refill count
     refill_count: AtomicU64,
 }
 
 /// A single size class in the slab registry.
 struct SlabClass {
-    /// Free pages available for distribution
-    free_pages: Mutex<Vec<SlabPage>>,
+    /// Magazines ready for distribution, one depot per node
+    depots: Box<[Mutex<Vec<Magazine>>]>,
 
     /// Object size for this class
     object_size: usize,
-}
 
-/// A page of slab memory.
-struct SlabPage {
-    /// Base pointer
-    base: *mut u8,
-
-    /// Page size
-    size: usize,
+    /// Objects per magazine
+    batch_size: usize,
 
-    /// Free list within the page
-    free_list: Vec<*mut u8>,
+    depot_hits: AtomicU64,
+    remote_hits: AtomicU64,
+    pages_allocated: AtomicU64,
+    magazines_returned: AtomicU64,
 }
 
 impl SlabRegistry {
-    /// Create a new slab registry.
+    /// Create a new slab registry using the page source chosen by `config`.
     pub fn new(config: &AllocConfig) -> Self {
-        let mut size_classes = DEFAULT_SIZE_CLASSES;
-
-        // Override with config if provided
-        for (i, &size) in config.slab_size_classes.iter().take(NUM_SIZE_CLASSES).enumerate() {
-            size_classes[i] = size;
-        }
+        Self::with_page_source(config, page_source_for(config))
+    }
 
-        let classes = std::array::from_fn(|i| SlabClass {
-            free_pages: Mutex::new(Vec::new()),
-            object_size: size_classes[i],
+    /// Create a new slab registry drawing pages from `page_source`.
+    pub fn with_page_source(config: &AllocConfig, page_source: Arc<dyn PageSource>) -> Self {
+        let size_classes = effective_size_classes(config);
+        let nodes = page_source.node_count().max(1);
+        let classes = std::array::from_fn(|i| {
+            let object_size = size_classes[i];
+            let batch_size = match config.slab_batch_sizes.get(i) {
+                Some(&batch) if batch > 0 => batch,
+                _ => default_batch_size(object_size),
+            };
+            // A magazine never spans more than one page
+            let per_page = (config.slab_page_size / object_size.max(1)).max(1);
+
+            SlabClass {
+                depots: (0..nodes).map(|_| Mutex::new(Vec::new())).collect(),
+                object_size,
+                batch_size: batch_size.min(per_page),
+                depot_hits: AtomicU64::new(0),
+                remote_hits: AtomicU64::new(0),
+                pages_allocated: AtomicU64::new(0),
+                magazines_returned: AtomicU64::new(0),
+            }
         });
 
         Self {
             size_classes,
             page_size: config.slab_page_size,
             classes,
+            page_source,
             refill_count: AtomicU64::new(0),
         }
     }
@@ -79,9 +252,17 @@ impl SlabRegistry {
         self.size_classes.iter().position(|&s| s >= size)
     }
 
+    /// Get the magazine size used for objects of `size` bytes (0 if too large).
+    pub fn batch_size(&self, size: usize) -> usize {
+        self.size_class_index(size)
+            .map_or(0, |idx| self.classes[idx].batch_size)
+    }
+
     /// Refill a local pool from the global registry.
     ///
-    /// Returns a batch of pointers for the local pool.
+    /// Returns one magazine for the local pool: from the calling thread's
+    /// node depot, else carved from a new page, else from another node.
+    /// Empty if the size is too large or no memory is available.
     pub fn refill(&self, size: usize) -> Vec<*mut u8> {
         let class_idx = match self.size_class_index(size) {
             Some(idx) => idx,
@@ -89,54 +270,68 @@ impl SlabRegistry {
         };
 
         let class = &self.classes[class_idx];
-        let mut pages = class.free_pages.lock();
-
-        // Try to get an existing page
-        if let Some(mut page) = pages.pop() {
-            self.refill_count.fetch_add(1, Ordering::Relaxed);
-            let batch = std::mem::take(&mut page.free_list);
-            // Put page back if it might have more allocations
-            return batch;
+        let node = self.page_source.current_node() % class.depots.len();
+        self.refill_count.fetch_add(1, Ordering::Relaxed);
+
+        if let Some(magazine) = class.depots[node].lock().pop() {
+            class.depot_hits.fetch_add(1, Ordering::Relaxed);
+            return magazine;
         }
 
-        // No free pages, allocate a new one
-        drop(pages); // Release lock before allocation
+        // Depot lock is released; carve a page on this node
+        if let Some(magazine) = self.allocate_page(class, node) {
+            return magazine;
+        }
 
-        let page = self.allocate_page(class.object_size);
-        self.refill_count.fetch_add(1, Ordering::Relaxed);
+        for (other, depot) in class.depots.iter().enumerate() {
+            if other == node {
+                continue;
+            }
+            if let Some(magazine) = depot.lock().pop() {
+                class.remote_hits.fetch_add(1, Ordering::Relaxed);
+                return magazine;
+            }
+        }
 
-        page.free_list
+        Vec::new()
     }
 
-    /// Allocate a new page for a size class.
-    fn allocate_page(&self, object_size: usize) -> SlabPage {
-        let layout = Layout::from_size_align(self.page_size, 16).expect("Invalid page layout");
-
-        // SAFETY: Allocating with valid layout
-        let base = unsafe { alloc(layout) };
+    /// Allocate a new page for a class and split it into magazines.
+    ///
+    /// Keeps one magazine for the caller and stocks `node`'s depot with the rest.
+    fn allocate_page(&self, class: &SlabClass, node: usize) -> Option<Magazine> {
+        let align = slab_class_align(class.object_size);
+        let layout = Layout::from_size_align(self.page_size, align).expect("Invalid page layout");
 
+        let base = self.page_source.alloc_page(layout, node);
         if base.is_null() {
-            panic!("Failed to allocate slab page");
+            return None;
         }
+        class.pages_allocated.fetch_add(1, Ordering::Relaxed);
 
         // Carve page into objects
-        let objects_per_page = self.page_size / object_size;
-        let mut free_list = Vec::with_capacity(objects_per_page);
-
-        for i in 0..objects_per_page {
-            // SAFETY: We're within the allocated page
-            let ptr = unsafe { base.add(i * object_size) };
-            free_list.push(ptr);
-        }
-
-        SlabPage {
-            base,
-            size: self.page_size,
-            free_list,
+        let objects_per_page = self.page_size / class.object_size;
+        let mut magazines: Vec<Magazine> = (0..objects_per_page)
+            .step_by(class.batch_size)
+            .map(|start| {
+                let end = (start + class.batch_size).min(objects_per_page);
+                (start..end)
+                    // SAFETY: We're within the allocated page
+                    .map(|i| unsafe { base.add(i * class.object_size) })
+                    .collect()
+            })
+            .collect();
+
+        let mine = magazines.pop();
+        if !magazines.is_empty() {
+            class.depots[node].lock().append(&mut magazines);
         }
+        mine
     }
 
     /// Return objects to the global registry.
+    ///
+    /// The batch becomes a magazine in the calling thread's node depot.
     pub fn return_batch(&self, size: usize, batch: Vec<*mut u8>) {
         if batch.is_empty() {
             return;
@@ -148,15 +343,9 @@ impl SlabRegistry {
         };
 
         let class = &self.classes[class_idx];
-        let mut pages = class.free_pages.lock();
-
-        // Create a "virtual" page with the returned objects
-        // In a real implementation, we'd track which page objects belong to
-        pages.push(SlabPage {
-            base: std::ptr::null_mut(), // Virtual page
-            size: 0,
-            free_list: batch,
-        });
+        let node = self.page_source.current_node() % class.depots.len();
+        class.depots[node].lock().push(batch);
+        class.magazines_returned.fetch_add(1, Ordering::Relaxed);
     }
 
     /// Get the refill count.
@@ -168,6 +357,27 @@ impl SlabRegistry {
     pub fn size_classes(&self) -> &[usize; NUM_SIZE_CLASSES] {
         &self.size_classes
     }
+
+    /// Get the number of nodes with their own depot.
+    pub fn node_count(&self) -> usize {
+        self.classes[0].depots.len()
+    }
+
+    /// Get depot statistics for every size class.
+    pub fn depot_stats(&self) -> Vec<DepotStats> {
+        self.classes
+            .iter()
+            .map(|class| DepotStats {
+                class_size: class.object_size,
+                batch_size: class.batch_size,
+                magazines: class.depots.iter().map(|d| d.lock().len()).sum(),
+                depot_hits: class.depot_hits.load(Ordering::Relaxed),
+                remote_hits: class.remote_hits.load(Ordering::Relaxed),
+                pages_allocated: class.pages_allocated.load(Ordering::Relaxed),
+                magazines_returned: class.magazines_returned.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
 }
 
 // SAFETY: SlabRegistry is thread-safe through internal locking
@@ -176,25 +386,50 @@ unsafe impl Sync for SlabRegistry {}
 
 /// Thread-local pools for fast allocation.
 pub struct LocalPools {
-    /// Per-size-class free lists
+    /// Per-size-class magazines
     pools: [LocalPool; NUM_SIZE_CLASSES],
 }
 
 /// A single local pool for one size class.
+///
+/// Holds two magazines. Allocation pops from `loaded` and swaps in
+/// `previous` when it runs dry; freeing pushes to `loaded` and swaps when it
+/// fills. Only when both are empty (or both full) does the pool talk to the
+/// depot, so a thread alternating allocs and frees around a batch boundary
+/// doesn't thrash it.
 struct LocalPool {
-    /// Free list of available objects
-    free_list: Vec<*mut u8>,
+    /// Magazine allocations are served from
+    loaded: Magazine,
+
+    /// Spare magazine, either empty or full
+    previous: Magazine,
+
+    /// Magazine size, learned from the registry on first use
+    capacity: usize,
 
     /// Size of objects in this pool
     object_size: usize,
 }
 
+impl LocalPool {
+    /// Get the magazine size for this pool.
+    #[inline]
+    fn capacity(&mut self, registry: &SlabRegistry) -> usize {
+        if self.capacity == 0 {
+            self.capacity = registry.batch_size(self.object_size).max(1);
+        }
+        self.capacity
+    }
+}
+
 impl LocalPools {
     /// Create new local pools.
     pub fn new() -> Self {
         Self {
             pools: std::array::from_fn(|i| LocalPool {
-                free_list: Vec::with_capacity(64),
+                loaded: Vec::new(),
+                previous: Vec::new(),
+                capacity: 0,
                 object_size: DEFAULT_SIZE_CLASSES[i],
             }),
         }
@@ -214,23 +449,29 @@ impl LocalPools {
 
         let pool = &mut self.pools[pool_idx];
 
-        // Try local free list first
-        if let Some(ptr) = pool.free_list.pop() {
+        // Try the loaded magazine, then the spare
+        if let Some(ptr) = pool.loaded.pop() {
             return ptr;
         }
+        if !pool.previous.is_empty() {
+            std::mem::swap(&mut pool.loaded, &mut pool.previous);
+            return pool.loaded.pop().unwrap_or(std::ptr::null_mut());
+        }
 
         // Refill from global registry
-        let batch = registry.refill(pool.object_size);
-        if batch.is_empty() {
+        let magazine = registry.refill(pool.object_size);
+        if magazine.is_empty() {
             return std::ptr::null_mut();
         }
 
-        pool.free_list = batch;
-        pool.free_list.pop().unwrap_or(std::ptr::null_mut())
+        pool.loaded = magazine;
+        pool.loaded.pop().unwrap_or(std::ptr::null_mut())
     }
 
     /// Free to local pool.
-    pub fn free(&mut self, ptr: *mut u8, size: usize) {
+    ///
+    /// A full spare magazine is handed to the registry's depot.
+    pub fn free(&mut self, ptr: *mut u8, size: usize, registry: &SlabRegistry) {
         let pool_idx = match self.pool_index(size) {
             Some(idx) => idx,
             None => return, // Was not from slab
@@ -243,14 +484,36 @@ impl LocalPools {
         }
 
         let pool = &mut self.pools[pool_idx];
-        pool.free_list.push(ptr);
-
-        // Could return excess to global here if pool is too large
+        let capacity = pool.capacity(registry);
+
+        if pool.loaded.len() >= capacity {
+            if !pool.previous.is_empty() {
+                let full = std::mem::replace(&mut pool.previous, Vec::with_capacity(capacity));
+                registry.return_batch(pool.object_size, full);
+            }
+            std::mem::swap(&mut pool.loaded, &mut pool.previous);
+        }
+        pool.loaded.push(ptr);
     }
 
     /// Drain deferred frees into local pools.
-    pub fn drain_deferred(&mut self, ptr: *mut u8, size: usize) {
-        self.free(ptr, size);
+    pub fn drain_deferred(&mut self, ptr: *mut u8, size: usize, registry: &SlabRegistry) {
+        self.free(ptr, size, registry);
+    }
+
+    /// Return every free object to the global registry.
+    ///
+    /// Used when the owning thread is torn down. Returns the object count.
+    pub fn release_to(&mut self, registry: &SlabRegistry) -> usize {
+        let mut released = 0;
+        for pool in &mut self.pools {
+            for magazine in [&mut pool.loaded, &mut pool.previous] {
+                let batch = std::mem::take(magazine);
+                released += batch.len();
+                registry.return_batch(pool.object_size, batch);
+            }
+        }
+        released
     }
 }
 
@@ -273,10 +536,172 @@ mod tests {
         let ptr = pools.alloc(32, &registry);
         assert!(!ptr.is_null());
 
-        pools.free(ptr, 32);
+        pools.free(ptr, 32, &registry);
 
         // Should get same pointer back
         let ptr2 = pools.alloc(32, &registry);
         assert_eq!(ptr, ptr2);
     }
+
+    #[test]
+    fn test_release_to_registry() {
+        let config = AllocConfig::default();
+        let registry = SlabRegistry::new(&config);
+        let mut pools = LocalPools::new();
+
+        let ptr = pools.alloc(32, &registry);
+        pools.free(ptr, 32, &registry);
+        assert!(pools.release_to(&registry) > 0);
+
+        // Another thread's pools pick up the released objects
+        let refills = registry.refill_count();
+        let mut other = LocalPools::new();
+        assert!(!other.alloc(32, &registry).is_null());
+        assert_eq!(registry.refill_count(), refills + 1);
+        assert_eq!(pools.release_to(&registry), 0);
+    }
+
+    #[test]
+    fn test_pool_backend_routing() {
+        assert_eq!(PoolBackend::of::<u8>(), PoolBackend::Slab { class_size: 16 });
+        assert_eq!(PoolBackend::of::<[u64; 8]>(), PoolBackend::Slab { class_size: 64 });
+        assert_eq!(PoolBackend::of::<[u8; 4097]>(), PoolBackend::LargeObject);
+
+        #[repr(align(64))]
+        struct CacheLine([u8; 64]);
+        assert_eq!(PoolBackend::of::<CacheLine>(), PoolBackend::Slab { class_size: 64 });
+
+        #[repr(align(8192))]
+        struct Huge(u8);
+        assert_eq!(PoolBackend::of::<Huge>(), PoolBackend::LargeObject);
+        assert_eq!(PoolBackend::of::<Huge>().alloc_kind(), AllocKind::LargeObject);
+
+        // A small but over-aligned type moves up to a class that guarantees it
+        assert_eq!(PoolBackend::for_layout(8, 256), PoolBackend::Slab { class_size: 256 });
+    }
+
+    #[test]
+    fn test_short_class_list_stays_ascending() {
+        let classes = |slab_size_classes| {
+            effective_size_classes(&AllocConfig {
+                slab_size_classes,
+                ..AllocConfig::default()
+            })
+        };
+        assert_eq!(classes(vec![16, 64, 256]), [16, 64, 256, 512, 1024, 2048, 4096, 4096, 4096]);
+        assert_eq!(classes(vec![8192]), [8192; NUM_SIZE_CLASSES]);
+        assert_eq!(classes(Vec::new()), DEFAULT_SIZE_CLASSES);
+    }
+
+    #[test]
+    fn test_class_alignment_holds() {
+        let config = AllocConfig::default();
+        let registry = SlabRegistry::new(&config);
+        let mut pools = LocalPools::new();
+
+        for &class_size in &DEFAULT_SIZE_CLASSES {
+            let align = slab_class_align(class_size);
+            for _ in 0..4 {
+                let ptr = pools.alloc(class_size, &registry);
+                assert!(!ptr.is_null());
+                assert_eq!(ptr as usize % align, 0, "class {class_size}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_uneven_classes_keep_routed_alignment() {
+        let config = AllocConfig {
+            slab_size_classes: vec![16, 32, 48, 64, 128, 192, 256, 1024, 4096],
+            ..AllocConfig::default()
+        };
+        let classes = effective_size_classes(&config);
+        assert_eq!(underaligned_class(&classes), None);
+
+        let registry = SlabRegistry::new(&config);
+        let mut pools = LocalPools::new();
+        for &class_size in &DEFAULT_SIZE_CLASSES {
+            let ptr = pools.alloc(class_size, &registry);
+            assert!(!ptr.is_null());
+            assert_eq!(ptr as usize % slab_class_align(class_size), 0, "class {class_size}");
+        }
+
+        // 64-byte objects would come from the 32-aligned 96-byte class
+        let config = AllocConfig {
+            slab_size_classes: vec![16, 32, 48, 96],
+            ..AllocConfig::default()
+        };
+        assert_eq!(underaligned_class(&effective_size_classes(&config)), Some((64, 96)));
+    }
+
+    #[test]
+    fn test_magazines_cycle_through_depot() {
+        let config = AllocConfig::default().with_slab_batch_sizes(vec![0, 0, 8]);
+        let registry = SlabRegistry::new(&config);
+        assert_eq!(registry.batch_size(64), 8);
+        assert_eq!(registry.batch_size(16), default_batch_size(16));
+
+        let mut pools = LocalPools::new();
+        let ptrs: Vec<_> = (0..24).map(|_| pools.alloc(64, &registry)).collect();
+        assert!(ptrs.iter().all(|p| !p.is_null()));
+
+        // One page stocks the depot; later refills are depot hits
+        let stats = registry.depot_stats()[2];
+        assert_eq!(stats.pages_allocated, 1);
+        assert_eq!(stats.depot_hits, 2);
+
+        // Freeing 24 objects fills loaded and previous, so one magazine goes back
+        for ptr in ptrs {
+            pools.free(ptr, 64, &registry);
+        }
+        assert_eq!(registry.depot_stats()[2].magazines_returned, 1);
+
+        // Another thread's pool picks it up without a new page
+        let mut other = LocalPools::new();
+        assert!(!other.alloc(64, &registry).is_null());
+        assert_eq!(registry.depot_stats()[2].pages_allocated, 1);
+    }
+
+    /// Page source with two nodes, where the calling thread's node is settable.
+    struct TwoNodes;
+
+    thread_local! {
+        static NODE: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+    }
+
+    impl PageSource for TwoNodes {
+        fn alloc_page(&self, layout: Layout, node: usize) -> *mut u8 {
+            assert_eq!(node, NODE.with(|n| n.get()));
+            unsafe { std::alloc::alloc(layout) }
+        }
+
+        fn node_count(&self) -> usize {
+            2
+        }
+
+        fn current_node(&self) -> usize {
+            NODE.with(|n| n.get())
+        }
+    }
+
+    #[test]
+    fn test_node_local_depots() {
+        let config = AllocConfig::default();
+        let registry = SlabRegistry::with_page_source(&config, Arc::new(TwoNodes));
+        assert_eq!(registry.node_count(), 2);
+
+        let mut pools = LocalPools::new();
+        let ptr = pools.alloc(32, &registry);
+        pools.free(ptr, 32, &registry);
+        pools.release_to(&registry);
+
+        // A thread on node 1 gets a fresh page rather than node 0's magazines
+        NODE.with(|n| n.set(1));
+        let mut remote = LocalPools::new();
+        assert!(!remote.alloc(32, &registry).is_null());
+        let stats = registry.depot_stats()[1];
+        assert_eq!(stats.pages_allocated, 2);
+        assert_eq!(stats.remote_hits, 0);
+        NODE.with(|n| n.set(0));
+    }
 }
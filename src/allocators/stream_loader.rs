@@ -0,0 +1,815 @@
+//! Asynchronous load pipeline for the streaming allocator.
+//!
+//! `StreamingAllocator::begin_load` only hands out a raw pointer and leaves
+//! IO to the caller. This module moves that work onto background threads:
+//! - `StreamLoader` describes how to fill a reservation
+//! - `FileLoader` reads a whole file or a byte range of one
+//! - `StreamLoaderPool` runs loads on worker threads, highest priority first
+//! - `LoadTicket` reports completion by blocking wait, callback or `Future`
+
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::fs::File;
+use std::future::Future;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+use std::thread::JoinHandle;
+
+use crate::sync::condvar::Condvar;
+use crate::sync::mutex::Mutex;
+
+use super::streaming::{StreamId, StreamPriority, StreamState, StreamingAllocator};
+
+/// Default read granularity for `FileLoader` (256KB).
+const DEFAULT_CHUNK_SIZE: usize = 256 * 1024;
+
+/// Why a background load did not reach `Ready`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadError {
+    /// The ticket was cancelled before or during the load.
+    Cancelled,
+    /// The allocation was freed while the load was running.
+    Freed,
+    /// The allocation does not exist, is not `Reserved`, or is already being
+    /// loaded.
+    NotLoadable,
+    /// The source ended before the requested bytes were read.
+    ShortRead {
+        /// Bytes the loader expected to read
+        expected: usize,
+        /// Bytes actually read
+        actual: usize,
+    },
+    /// The source could not be read.
+    Io {
+        /// Kind of the underlying IO error
+        kind: io::ErrorKind,
+        /// Rendered error message
+        message: String,
+    },
+    /// The loader panicked.
+    Panicked,
+}
+
+impl From<io::Error> for LoadError {
+    fn from(err: io::Error) -> Self {
+        Self::Io {
+            kind: err.kind(),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cancelled => write!(f, "load cancelled"),
+            Self::Freed => write!(f, "allocation freed during load"),
+            Self::NotLoadable => write!(f, "allocation is not in a loadable state"),
+            Self::ShortRead { expected, actual } => {
+                write!(f, "short read: expected {} bytes, got {}", expected, actual)
+            }
+            Self::Io { message, .. } => write!(f, "io error: {}", message),
+            Self::Panicked => write!(f, "loader panicked"),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+/// Outcome of a background load.
+pub type LoadResult = Result<StreamId, LoadError>;
+
+/// Context passed to a `StreamLoader` while it fills a reservation.
+pub struct LoadContext<'a> {
+    streaming: &'a StreamingAllocator,
+    id: StreamId,
+    cancelled: &'a AtomicBool,
+}
+
+impl<'a> LoadContext<'a> {
+    /// The allocation being filled.
+    pub fn id(&self) -> StreamId {
+        self.id
+    }
+
+    /// Report how many bytes have been written so far.
+    pub fn report_progress(&self, bytes_loaded: usize) {
+        self.streaming.report_progress(self.id, bytes_loaded);
+    }
+
+    /// Whether the load has been cancelled.
+    ///
+    /// Long-running loaders should poll this between chunks.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+
+    /// Return `Err(LoadError::Cancelled)` if the load has been cancelled.
+    pub fn check_cancelled(&self) -> Result<(), LoadError> {
+        if self.is_cancelled() {
+            Err(LoadError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Fills a streaming reservation with data.
+///
+/// Implementations run on a `StreamLoaderPool` worker thread. `dest` is the
+/// whole reservation; it stays valid for the duration of the call even if
+/// the allocation is freed concurrently.
+pub trait StreamLoader: Send + 'static {
+    /// Write the asset's bytes into `dest`.
+    fn load(&mut self, dest: &mut [u8], ctx: &LoadContext<'_>) -> Result<(), LoadError>;
+}
+
+impl<F> StreamLoader for F
+where
+    F: FnMut(&mut [u8], &LoadContext<'_>) -> Result<(), LoadError> + Send + 'static,
+{
+    fn load(&mut self, dest: &mut [u8], ctx: &LoadContext<'_>) -> Result<(), LoadError> {
+        self(dest, ctx)
+    }
+}
+
+/// Loads a file, or a byte range of one, into a reservation.
+#[derive(Debug, Clone)]
+pub struct FileLoader {
+    path: PathBuf,
+    offset: u64,
+    len: Option<u64>,
+    chunk_size: usize,
+}
+
+impl FileLoader {
+    /// Load the whole file.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            offset: 0,
+            len: None,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        }
+    }
+
+    /// Load `len` bytes starting at `offset`.
+    pub fn range(path: impl AsRef<Path>, offset: u64, len: u64) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            offset,
+            len: Some(len),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        }
+    }
+
+    /// Set how many bytes are read between progress reports.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Get the file path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Number of bytes this loader will produce.
+    pub fn byte_len(&self) -> io::Result<u64> {
+        match self.len {
+            Some(len) => Ok(len),
+            None => {
+                let file_len = std::fs::metadata(&self.path)?.len();
+                Ok(file_len.saturating_sub(self.offset))
+            }
+        }
+    }
+}
+
+impl StreamLoader for FileLoader {
+    fn load(&mut self, dest: &mut [u8], ctx: &LoadContext<'_>) -> Result<(), LoadError> {
+        let expected = match self.len {
+            Some(len) => (len as usize).min(dest.len()),
+            None => dest.len(),
+        };
+
+        let mut file = File::open(&self.path)?;
+        if self.offset > 0 {
+            file.seek(SeekFrom::Start(self.offset))?;
+        }
+
+        let mut read = 0;
+        while read < expected {
+            ctx.check_cancelled()?;
+
+            let end = (read + self.chunk_size).min(expected);
+            match file.read(&mut dest[read..end]) {
+                Ok(0) => {
+                    return Err(LoadError::ShortRead {
+                        expected,
+                        actual: read,
+                    })
+                }
+                Ok(n) => {
+                    read += n;
+                    ctx.report_progress(read);
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Completion callback registered on a ticket.
+type CompletionCallback = Box<dyn FnOnce(&LoadResult) + Send>;
+
+/// Shared completion state behind a `LoadTicket`.
+struct TicketState {
+    id: StreamId,
+    cancelled: AtomicBool,
+    inner: Mutex<TicketInner>,
+    done: Condvar,
+}
+
+#[derive(Default)]
+struct TicketInner {
+    result: Option<LoadResult>,
+    callbacks: Vec<CompletionCallback>,
+    waker: Option<Waker>,
+}
+
+impl TicketState {
+    fn new(id: StreamId) -> Arc<Self> {
+        Arc::new(Self {
+            id,
+            cancelled: AtomicBool::new(false),
+            inner: Mutex::new(TicketInner::default()),
+            done: Condvar::new(),
+        })
+    }
+
+    fn complete(&self, result: LoadResult) {
+        let (callbacks, waker) = {
+            let mut inner = self.inner.lock();
+            inner.result = Some(result.clone());
+            (std::mem::take(&mut inner.callbacks), inner.waker.take())
+        };
+        self.done.notify_all();
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+
+        // Run callbacks outside the lock so they may query the ticket. A
+        // panicking callback must not skip the rest or kill the worker.
+        for callback in callbacks {
+            let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| callback(&result)));
+        }
+    }
+}
+
+/// Handle to a submitted load.
+///
+/// Cheap to clone. Also a `Future` resolving to the `LoadResult`, so it can
+/// be awaited from any executor.
+#[derive(Clone)]
+pub struct LoadTicket {
+    state: Arc<TicketState>,
+}
+
+impl LoadTicket {
+    /// The allocation being loaded.
+    pub fn id(&self) -> StreamId {
+        self.state.id
+    }
+
+    /// Request cancellation.
+    ///
+    /// A queued load is skipped; a running load stops at the loader's next
+    /// cancellation check. Either way the allocation returns to `Reserved`
+    /// and stays owned by the caller, who may `free` it at any time.
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::Release);
+    }
+
+    /// Whether cancellation has been requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::Acquire)
+    }
+
+    /// Whether the load has finished (successfully or not).
+    pub fn is_finished(&self) -> bool {
+        self.state.inner.lock().result.is_some()
+    }
+
+    /// Get the result if the load has finished.
+    pub fn try_result(&self) -> Option<LoadResult> {
+        self.state.inner.lock().result.clone()
+    }
+
+    /// Block until the load finishes.
+    pub fn wait(&self) -> LoadResult {
+        let mut inner = self.state.inner.lock();
+        loop {
+            if let Some(ref result) = inner.result {
+                return result.clone();
+            }
+            inner = self.state.done.wait(inner);
+        }
+    }
+
+    /// Register a callback to run when the load finishes.
+    ///
+    /// Runs on the worker thread that completed the load, or immediately on
+    /// the calling thread if the load has already finished. A panic in a
+    /// callback run by a worker is caught and does not affect other
+    /// callbacks or loads.
+    pub fn on_complete<F>(&self, callback: F)
+    where
+        F: FnOnce(&LoadResult) + Send + 'static,
+    {
+        let mut inner = self.state.inner.lock();
+        match inner.result.clone() {
+            Some(result) => {
+                drop(inner);
+                callback(&result);
+            }
+            None => inner.callbacks.push(Box::new(callback)),
+        }
+    }
+}
+
+impl Future for LoadTicket {
+    type Output = LoadResult;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut inner = self.state.inner.lock();
+        match inner.result {
+            Some(ref result) => Poll::Ready(result.clone()),
+            None => {
+                inner.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl fmt::Debug for LoadTicket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoadTicket")
+            .field("id", &self.state.id)
+            .field("cancelled", &self.is_cancelled())
+            .field("finished", &self.is_finished())
+            .finish()
+    }
+}
+
+/// A load waiting in the pool's queue.
+struct QueuedLoad {
+    priority: StreamPriority,
+    seq: u64,
+    loader: Box<dyn StreamLoader>,
+    ticket: Arc<TicketState>,
+}
+
+impl PartialEq for QueuedLoad {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority && self.seq == other.seq
+    }
+}
+
+impl Eq for QueuedLoad {}
+
+impl PartialOrd for QueuedLoad {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueuedLoad {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        // Max-heap: highest priority first, then oldest submission first
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+/// State shared between the pool handle and its workers.
+struct PoolShared {
+    streaming: Arc<StreamingAllocator>,
+    queue: Mutex<BinaryHeap<QueuedLoad>>,
+    available: Condvar,
+    shutdown: AtomicBool,
+    next_seq: AtomicU64,
+    running: AtomicUsize,
+    completed: AtomicU64,
+    failed: AtomicU64,
+}
+
+/// Background thread pool that fills streaming reservations.
+///
+/// Queued loads are ordered by the allocation's `StreamPriority`, then by
+/// submission order. Dropping the pool cancels anything still queued and
+/// joins the workers.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let pool = alloc.stream_loader(2);
+/// let ticket = pool.submit_file("assets/terrain.bin", StreamPriority::High)?;
+/// ticket.on_complete(|result| println!("terrain: {:?}", result));
+/// ```
+pub struct StreamLoaderPool {
+    shared: Arc<PoolShared>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl StreamLoaderPool {
+    /// Create a pool with the given number of worker threads (at least one).
+    pub fn new(streaming: Arc<StreamingAllocator>, workers: usize) -> Self {
+        let shared = Arc::new(PoolShared {
+            streaming,
+            queue: Mutex::new(BinaryHeap::new()),
+            available: Condvar::new(),
+            shutdown: AtomicBool::new(false),
+            next_seq: AtomicU64::new(0),
+            running: AtomicUsize::new(0),
+            completed: AtomicU64::new(0),
+            failed: AtomicU64::new(0),
+        });
+
+        let workers = (0..workers.max(1))
+            .map(|i| {
+                let shared = Arc::clone(&shared);
+                std::thread::Builder::new()
+                    .name(format!("framealloc-stream-{}", i))
+                    .spawn(move || worker_loop(&shared))
+                    .expect("failed to spawn stream loader thread")
+            })
+            .collect();
+
+        Self { shared, workers }
+    }
+
+    /// Queue a load into an existing reservation.
+    ///
+    /// The load is ordered by the allocation's priority. If the allocation
+    /// does not exist the returned ticket completes with `NotLoadable`.
+    pub fn submit<L: StreamLoader>(&self, id: StreamId, loader: L) -> LoadTicket {
+        let ticket = TicketState::new(id);
+
+        let Some(priority) = self.shared.streaming.priority(id) else {
+            ticket.complete(Err(LoadError::NotLoadable));
+            return LoadTicket { state: ticket };
+        };
+
+        let job = QueuedLoad {
+            priority,
+            seq: self.shared.next_seq.fetch_add(1, Ordering::Relaxed),
+            loader: Box::new(loader),
+            ticket: Arc::clone(&ticket),
+        };
+
+        self.shared.queue.lock().push(job);
+        self.shared.available.notify_one();
+
+        LoadTicket { state: ticket }
+    }
+
+    /// Reserve space for a file and queue its load.
+    ///
+    /// Returns `NotLoadable` if the streaming budget cannot fit the file.
+    pub fn submit_file(
+        &self,
+        path: impl AsRef<Path>,
+        priority: StreamPriority,
+    ) -> Result<LoadTicket, LoadError> {
+        let loader = FileLoader::new(path);
+        let size = loader.byte_len()? as usize;
+        let id = self
+            .shared
+            .streaming
+            .reserve(size, priority)
+            .ok_or(LoadError::NotLoadable)?;
+        Ok(self.submit(id, loader))
+    }
+
+    /// Number of loads waiting in the queue.
+    pub fn pending(&self) -> usize {
+        self.shared.queue.lock().len()
+    }
+
+    /// Number of loads currently running on worker threads.
+    pub fn running(&self) -> usize {
+        self.shared.running.load(Ordering::Relaxed)
+    }
+
+    /// Number of loads that reached `Ready`.
+    pub fn completed(&self) -> u64 {
+        self.shared.completed.load(Ordering::Relaxed)
+    }
+
+    /// Number of loads that finished with an error or were cancelled.
+    pub fn failed(&self) -> u64 {
+        self.shared.failed.load(Ordering::Relaxed)
+    }
+
+    /// Number of worker threads.
+    pub fn worker_count(&self) -> usize {
+        self.workers.len()
+    }
+}
+
+impl Drop for StreamLoaderPool {
+    fn drop(&mut self) {
+        self.shared.shutdown.store(true, Ordering::Release);
+        self.shared.available.notify_all();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+
+        // Anything still queued never touched its reservation
+        let remaining: Vec<_> = self.shared.queue.lock().drain().collect();
+        for job in remaining {
+            job.ticket.complete(Err(LoadError::Cancelled));
+        }
+    }
+}
+
+fn worker_loop(shared: &PoolShared) {
+    loop {
+        let job = {
+            let mut queue = shared.queue.lock();
+            loop {
+                if shared.shutdown.load(Ordering::Acquire) {
+                    return;
+                }
+                if let Some(job) = queue.pop() {
+                    break job;
+                }
+                queue = shared.available.wait(queue);
+            }
+        };
+
+        shared.running.fetch_add(1, Ordering::Relaxed);
+        let result = run_load(&shared.streaming, job.loader, &job.ticket);
+        shared.running.fetch_sub(1, Ordering::Relaxed);
+
+        if result.is_ok() {
+            shared.completed.fetch_add(1, Ordering::Relaxed);
+        } else {
+            shared.failed.fetch_add(1, Ordering::Relaxed);
+        }
+        job.ticket.complete(result);
+    }
+}
+
+fn run_load(
+    streaming: &StreamingAllocator,
+    mut loader: Box<dyn StreamLoader>,
+    ticket: &TicketState,
+) -> LoadResult {
+    let id = ticket.id;
+    if ticket.cancelled.load(Ordering::Acquire) {
+        return Err(LoadError::Cancelled);
+    }
+
+    let (ptr, size) = streaming.acquire_load(id).ok_or(LoadError::NotLoadable)?;
+
+    // SAFETY: `acquire_load` marked the allocation in flight, so neither
+    // `free` nor eviction releases it until `release_load` below.
+    let dest = unsafe { std::slice::from_raw_parts_mut(ptr, size) };
+    let ctx = LoadContext {
+        streaming,
+        id,
+        cancelled: &ticket.cancelled,
+    };
+
+    let outcome = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        loader.load(dest, &ctx)
+    }))
+    .unwrap_or(Err(LoadError::Panicked))
+    .and_then(|()| ctx.check_cancelled());
+
+    match (streaming.release_load(id, outcome.is_ok()), outcome) {
+        (None, _) => Err(LoadError::Freed),
+        (Some(StreamState::Ready), Ok(())) => Ok(id),
+        (Some(_), Ok(())) => Err(LoadError::NotLoadable),
+        (Some(_), Err(e)) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_closure_loader_reaches_ready() {
+        let streaming = Arc::new(StreamingAllocator::new(1024 * 1024));
+        let pool = StreamLoaderPool::new(Arc::clone(&streaming), 1);
+
+        let id = streaming.reserve(64, StreamPriority::Normal).unwrap();
+        let ticket = pool.submit(id, |dest: &mut [u8], ctx: &LoadContext<'_>| {
+            dest.fill(7);
+            ctx.report_progress(dest.len());
+            Ok(())
+        });
+
+        assert_eq!(ticket.wait(), Ok(id));
+        assert_eq!(streaming.state(id), Some(StreamState::Ready));
+        assert_eq!(streaming.total_loaded(), 64);
+
+        let ptr = streaming.access(id).unwrap();
+        let data = unsafe { std::slice::from_raw_parts(ptr, 64) };
+        assert!(data.iter().all(|&b| b == 7));
+    }
+
+    #[test]
+    fn test_priority_ordering() {
+        let streaming = Arc::new(StreamingAllocator::new(1024 * 1024));
+        let pool = StreamLoaderPool::new(Arc::clone(&streaming), 1);
+
+        // Park the only worker so the remaining jobs queue up
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let blocker = streaming.reserve(16, StreamPriority::Critical).unwrap();
+        let first = pool.submit(blocker, move |_: &mut [u8], _: &LoadContext<'_>| {
+            release_rx.recv().ok();
+            Ok(())
+        });
+        while pool.running() == 0 {
+            std::thread::yield_now();
+        }
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut tickets = Vec::new();
+        for priority in [StreamPriority::Low, StreamPriority::High, StreamPriority::Normal] {
+            let id = streaming.reserve(16, priority).unwrap();
+            let order = Arc::clone(&order);
+            tickets.push(pool.submit(id, move |_: &mut [u8], _: &LoadContext<'_>| {
+                order.lock().push(priority);
+                Ok(())
+            }));
+        }
+
+        release_tx.send(()).unwrap();
+        first.wait().unwrap();
+        for ticket in &tickets {
+            ticket.wait().unwrap();
+        }
+
+        assert_eq!(
+            *order.lock(),
+            vec![StreamPriority::High, StreamPriority::Normal, StreamPriority::Low]
+        );
+    }
+
+    #[test]
+    fn test_cancel_running_load_returns_to_reserved() {
+        let streaming = Arc::new(StreamingAllocator::new(1024 * 1024));
+        let pool = StreamLoaderPool::new(Arc::clone(&streaming), 1);
+
+        let id = streaming.reserve(32, StreamPriority::Normal).unwrap();
+        let ticket = pool.submit(id, |_: &mut [u8], ctx: &LoadContext<'_>| {
+            ctx.report_progress(8);
+            while !ctx.is_cancelled() {
+                std::thread::yield_now();
+            }
+            ctx.check_cancelled()
+        });
+
+        while streaming.state(id) != Some(StreamState::Loading) {
+            std::thread::yield_now();
+        }
+        ticket.cancel();
+
+        assert_eq!(ticket.wait(), Err(LoadError::Cancelled));
+        assert_eq!(streaming.state(id), Some(StreamState::Reserved));
+        assert_eq!(streaming.total_loaded(), 0);
+    }
+
+    #[test]
+    fn test_free_during_load_is_deferred() {
+        let streaming = Arc::new(StreamingAllocator::new(1024 * 1024));
+        let pool = StreamLoaderPool::new(Arc::clone(&streaming), 1);
+
+        let (started_tx, started_rx) = mpsc::channel::<()>();
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let id = streaming.reserve(128, StreamPriority::Low).unwrap();
+        let ticket = pool.submit(id, move |dest: &mut [u8], _: &LoadContext<'_>| {
+            started_tx.send(()).ok();
+            release_rx.recv().ok();
+            // Still valid: the free below is deferred until we return
+            dest.fill(1);
+            Ok(())
+        });
+
+        started_rx.recv().unwrap();
+        streaming.free(id);
+        assert_eq!(streaming.state(id), Some(StreamState::Evicting));
+        assert_eq!(streaming.stats().in_flight_count, 1);
+
+        release_tx.send(()).unwrap();
+        assert_eq!(ticket.wait(), Err(LoadError::Freed));
+        assert_eq!(streaming.state(id), None);
+        assert_eq!(streaming.total_reserved(), 0);
+    }
+
+    #[test]
+    fn test_panicking_callback_keeps_worker() {
+        let streaming = Arc::new(StreamingAllocator::new(1024 * 1024));
+        let pool = StreamLoaderPool::new(Arc::clone(&streaming), 1);
+
+        let (started_tx, started_rx) = mpsc::channel::<()>();
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let first = streaming.reserve(64, StreamPriority::Normal).unwrap();
+        let ticket = pool.submit(first, move |_: &mut [u8], _: &LoadContext<'_>| {
+            started_tx.send(()).ok();
+            release_rx.recv().ok();
+            Ok(())
+        });
+        started_rx.recv().unwrap();
+
+        let (done_tx, done_rx) = mpsc::channel();
+        ticket.on_complete(|_| panic!("callback panicked"));
+        ticket.on_complete(move |result| done_tx.send(result.clone()).unwrap());
+        let second = streaming.reserve(64, StreamPriority::Normal).unwrap();
+        let queued = pool.submit(second, |_: &mut [u8], _: &LoadContext<'_>| Ok(()));
+
+        release_tx.send(()).unwrap();
+        assert_eq!(done_rx.recv().unwrap(), Ok(first));
+        // The same worker goes on to the queued load
+        assert_eq!(queued.wait(), Ok(second));
+    }
+
+    #[test]
+    fn test_manual_and_pooled_loads_exclude_each_other() {
+        let streaming = Arc::new(StreamingAllocator::new(1024 * 1024));
+        let pool = StreamLoaderPool::new(Arc::clone(&streaming), 1);
+
+        // A manual load in progress is not handed to the pool
+        let manual = streaming.reserve(128, StreamPriority::Normal).unwrap();
+        assert!(streaming.begin_load(manual).is_some());
+        let ticket = pool.submit(manual, |_: &mut [u8], _: &LoadContext<'_>| Ok(()));
+        assert_eq!(ticket.wait(), Err(LoadError::NotLoadable));
+        assert_eq!(streaming.state(manual), Some(StreamState::Loading));
+
+        // A pooled load in progress is not handed out by `begin_load`
+        let (started_tx, started_rx) = mpsc::channel::<()>();
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let pooled = streaming.reserve(128, StreamPriority::Normal).unwrap();
+        let ticket = pool.submit(pooled, move |_: &mut [u8], _: &LoadContext<'_>| {
+            started_tx.send(()).ok();
+            release_rx.recv().ok();
+            Ok(())
+        });
+        started_rx.recv().unwrap();
+        assert!(streaming.begin_load(pooled).is_none());
+        release_tx.send(()).unwrap();
+        assert_eq!(ticket.wait(), Ok(pooled));
+    }
+
+    #[test]
+    fn test_file_range_loader_and_callback() {
+        let path = std::env::temp_dir().join(format!(
+            "framealloc_stream_loader_{}.bin",
+            std::process::id()
+        ));
+        let contents: Vec<u8> = (0..=255u8).collect();
+        std::fs::write(&path, &contents).unwrap();
+
+        let streaming = Arc::new(StreamingAllocator::new(1024 * 1024));
+        let pool = StreamLoaderPool::new(Arc::clone(&streaming), 2);
+
+        let loader = FileLoader::range(&path, 16, 32).with_chunk_size(5);
+        let id = streaming
+            .reserve(loader.byte_len().unwrap() as usize, StreamPriority::Normal)
+            .unwrap();
+        let ticket = pool.submit(id, loader);
+
+        let (done_tx, done_rx) = mpsc::channel();
+        ticket.on_complete(move |result| {
+            done_tx.send(result.clone()).ok();
+        });
+
+        assert_eq!(done_rx.recv().unwrap(), Ok(id));
+        let ptr = streaming.access(id).unwrap();
+        let data = unsafe { std::slice::from_raw_parts(ptr, 32) };
+        assert_eq!(data, &contents[16..48]);
+
+        let whole = pool.submit_file(&path, StreamPriority::High).unwrap();
+        assert!(whole.wait().is_ok());
+        assert_eq!(streaming.stats().ready_count, 2);
+
+        std::fs::remove_file(&path).ok();
+    }
+}
@@ -4,13 +4,39 @@
 //! - Are loaded incrementally (streamed from disk/network)
 //! - Have known final sizes
 //! - May be evicted under memory pressure
+//!
+//! By default each asset is a separate system allocation. `with_region`
+//! instead reserves the whole budget up front and sub-allocates it with TLSF,
+//! so streaming never fragments the general heap. Region-backed assets that
+//! are `Ready` and not pinned may be relocated by `defragment`; their
+//! `StreamId` stays the same, but pointers returned by `access` are
+//! invalidated. Reservations only defragment on their own when enabled
+//! with `set_auto_defragment`.
 
 use std::alloc::{alloc, dealloc, Layout};
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
 
+use super::eviction::{
+    AccessTrace, EvictionCandidate, EvictionHint, EvictionPlan, EvictionPolicy, LruPolicy,
+    TraceEvent,
+};
+use super::tlsf::Tlsf;
+use crate::diagnostics::{MemoryPool, ProfilerSlot};
 use crate::sync::mutex::Mutex;
 
+/// Default alignment of streaming allocations.
+const DEFAULT_STREAM_ALIGN: usize = 16;
+
+/// Largest alignment a streaming allocation may request.
+///
+/// Regions are aligned to this, so aligned offsets are aligned addresses.
+pub const MAX_STREAM_ALIGN: usize = 4096;
+
+/// Callback invoked after an allocation is relocated: `(id, old_ptr, new_ptr)`.
+type RelocationCallback = Box<dyn Fn(StreamId, *mut u8, *mut u8) + Send + Sync>;
+
 /// Unique identifier for a streaming allocation.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct StreamId(u64);
@@ -63,6 +89,8 @@ struct StreamAllocation {
     ptr: *mut u8,
     /// Total reserved size
     reserved_size: usize,
+    /// Alignment requested at reservation; kept across relocation
+    align: usize,
     /// Currently loaded bytes
     loaded_bytes: usize,
     /// Current state
@@ -73,6 +101,50 @@ struct StreamAllocation {
     last_access: u64,
     /// User-defined tag for categorization
     tag: Option<&'static str>,
+    /// A background loader currently holds the write pointer
+    in_flight: bool,
+    /// `free` was called while in flight; release once the loader is done
+    free_pending: bool,
+    /// Outstanding pins; pinned allocations are never relocated or evicted
+    pins: u32,
+    /// Shared owners; the allocation is freed when this drops to zero
+    ref_count: u32,
+    /// Never evict automatically, regardless of priority
+    never_evict: bool,
+    /// Minimum number of frames to stay resident after becoming `Ready`
+    min_residency: u64,
+    /// Frame at which the allocation became `Ready`
+    ready_frame: u64,
+    /// Number of accesses since reservation
+    access_count: u64,
+    /// User-supplied hint for eviction policies
+    hint: EvictionHint,
+}
+
+impl StreamAllocation {
+    /// Whether the memory can be released right now.
+    fn can_release(&self) -> bool {
+        !self.in_flight && self.pins == 0
+    }
+
+    /// Whether automatic eviction may pick this allocation.
+    fn is_evictable(&self, min_priority: StreamPriority, current_frame: u64) -> bool {
+        self.priority < min_priority
+            && self.state == StreamState::Ready
+            && self.can_release()
+            && !self.never_evict
+            && current_frame.saturating_sub(self.ready_frame) >= self.min_residency
+    }
+}
+
+/// Pre-reserved backing memory for region mode.
+struct Region {
+    /// Start of the region
+    base: *mut u8,
+    /// Layout the region was allocated with
+    layout: Layout,
+    /// Sub-allocator handing out offsets into the region
+    tlsf: Mutex<Tlsf>,
 }
 
 /// Streaming allocator for large assets.
@@ -97,6 +169,36 @@ pub struct StreamingAllocator {
     
     /// Eviction callback
     eviction_callback: Mutex<Option<Box<dyn Fn(StreamId) + Send + Sync>>>,
+
+    /// Backing region (None = one system allocation per asset)
+    region: Option<Region>,
+
+    /// Number of relocations performed by `defragment`
+    relocation_count: AtomicU64,
+
+    /// Whether reservations may defragment the region to make room
+    auto_defragment: AtomicBool,
+
+    /// Relocation callback
+    relocation_callback: Mutex<Option<RelocationCallback>>,
+
+    /// Ranks candidates within a priority level
+    policy: Mutex<Box<dyn EvictionPolicy>>,
+
+    /// Most recent eviction decision, for logging
+    last_eviction: Mutex<Option<EvictionPlan>>,
+
+    /// Number of allocations evicted
+    eviction_count: AtomicU64,
+
+    /// Whether events are being recorded into `trace`
+    tracing: AtomicBool,
+
+    /// Recorded access trace
+    trace: Mutex<Option<AccessTrace>>,
+
+    /// Profiler reservations and frees are reported to
+    profiler: Arc<ProfilerSlot>,
 }
 
 impl StreamingAllocator {
@@ -110,7 +212,73 @@ impl StreamingAllocator {
             budget,
             current_frame: AtomicU64::new(0),
             eviction_callback: Mutex::new(None),
+            region: None,
+            relocation_count: AtomicU64::new(0),
+            auto_defragment: AtomicBool::new(false),
+            relocation_callback: Mutex::new(None),
+            policy: Mutex::new(Box::new(LruPolicy)),
+            last_eviction: Mutex::new(None),
+            eviction_count: AtomicU64::new(0),
+            tracing: AtomicBool::new(false),
+            trace: Mutex::new(None),
+            profiler: Arc::new(ProfilerSlot::new()),
+        }
+    }
+
+    /// Report reservations and frees to the profiler in `slot`.
+    pub(crate) fn with_profiler(mut self, slot: Arc<ProfilerSlot>) -> Self {
+        self.profiler = slot;
+        self
+    }
+
+    /// Create a streaming allocator whose whole budget is one pre-reserved region.
+    ///
+    /// Assets are sub-allocated from the region instead of the system heap.
+    /// The region is the budget rounded up to 16 bytes, so a reservation of
+    /// the whole budget fits. When a reservation fits the budget but no single hole is large enough,
+    /// it fails unless `set_auto_defragment` is enabled; call `defragment`
+    /// at a point where no pointers from `access` are held.
+    pub fn with_region(budget: usize) -> Self {
+        let size = Tlsf::round_size(budget);
+        let layout = Layout::from_size_align(size, MAX_STREAM_ALIGN).expect("Invalid region layout");
+        let base = unsafe { alloc(layout) };
+        if base.is_null() {
+            std::alloc::handle_alloc_error(layout);
         }
+
+        let mut allocator = Self::new(budget);
+        allocator.region = Some(Region {
+            base,
+            layout,
+            tlsf: Mutex::new(Tlsf::new(size)),
+        });
+        allocator
+    }
+
+    /// Whether this allocator sub-allocates from a pre-reserved region.
+    pub fn is_region_backed(&self) -> bool {
+        self.region.is_some()
+    }
+
+    /// Let reservations defragment the region when it is too fragmented.
+    ///
+    /// Off by default. When enabled, any `reserve` may move unpinned `Ready`
+    /// assets, invalidating pointers previously returned by `access`; pin
+    /// assets whose pointers are held or use the relocation callback.
+    pub fn set_auto_defragment(&self, enable: bool) {
+        self.auto_defragment.store(enable, Ordering::Relaxed);
+    }
+
+    /// Set a callback for when allocations are relocated by `defragment`.
+    ///
+    /// Called with the stream ID, the old pointer and the new pointer. Raw
+    /// pointers obtained before the move must be refreshed with `access`.
+    pub fn set_relocation_callback<F>(&self, callback: F)
+    where
+        F: Fn(StreamId, *mut u8, *mut u8) + Send + Sync + 'static,
+    {
+        let mut cb = self.relocation_callback.lock();
+        *cb = Some(Box::new(callback));
     }
 
     /// Set a callback for when allocations are evicted.
@@ -136,6 +304,35 @@ impl StreamingAllocator {
         priority: StreamPriority,
         tag: Option<&'static str>,
     ) -> Option<StreamId> {
+        self.reserve_with(size, DEFAULT_STREAM_ALIGN, priority, tag)
+    }
+
+    /// Reserve memory aligned to `align` bytes.
+    ///
+    /// The alignment is kept when `defragment` relocates the allocation.
+    /// Returns None if `align` is not a power of two or exceeds
+    /// `MAX_STREAM_ALIGN`.
+    pub fn reserve_aligned(
+        &self,
+        size: usize,
+        align: usize,
+        priority: StreamPriority,
+    ) -> Option<StreamId> {
+        self.reserve_with(size, align, priority, None)
+    }
+
+    fn reserve_with(
+        &self,
+        size: usize,
+        align: usize,
+        priority: StreamPriority,
+        tag: Option<&'static str>,
+    ) -> Option<StreamId> {
+        if !align.is_power_of_two() || align > MAX_STREAM_ALIGN {
+            return None;
+        }
+        let align = align.max(DEFAULT_STREAM_ALIGN);
+
         // Check if we need to evict
         let current_reserved = self.total_reserved.load(Ordering::Relaxed);
         if current_reserved + size > self.budget {
@@ -145,13 +342,15 @@ impl StreamingAllocator {
             }
         }
 
-        // Allocate the memory
-        let layout = Layout::from_size_align(size, 16).ok()?;
-        let ptr = unsafe { alloc(layout) };
-        
-        if ptr.is_null() {
-            return None;
-        }
+        // Allocate the memory, compacting the region if it is too fragmented
+        let ptr = match self.alloc_memory(size, align) {
+            Some(ptr) => ptr,
+            None if self.region.is_some() && self.auto_defragment.load(Ordering::Relaxed) => {
+                self.defragment();
+                self.alloc_memory(size, align)?
+            }
+            None => return None,
+        };
 
         let id = StreamId(self.next_id.fetch_add(1, Ordering::Relaxed));
         let frame = self.current_frame.load(Ordering::Relaxed);
@@ -160,29 +359,49 @@ impl StreamingAllocator {
             id,
             ptr,
             reserved_size: size,
+            align,
             loaded_bytes: 0,
             state: StreamState::Reserved,
             priority,
             last_access: frame,
             tag,
+            in_flight: false,
+            free_pending: false,
+            pins: 0,
+            ref_count: 1,
+            never_evict: false,
+            min_residency: 0,
+            ready_frame: frame,
+            access_count: 0,
+            hint: EvictionHint::default(),
         };
 
         let mut allocs = self.allocations.lock();
         allocs.insert(id, allocation);
+        self.policy.lock().on_insert(id, size);
         self.total_reserved.fetch_add(size, Ordering::Relaxed);
+        drop(allocs);
+
+        self.record(TraceEvent::Reserve {
+            key: id.raw(),
+            size,
+            priority,
+        });
+        self.profiler.alloc(MemoryPool::Streaming, ptr, size, tag);
 
         Some(id)
     }
 
     /// Get a pointer for writing data into a streaming allocation.
     ///
-    /// Returns None if the ID is invalid or the allocation is not in a writable state.
+    /// Returns None if the ID is invalid, the allocation is not in a writable
+    /// state, or a background loader is writing into it.
     pub fn begin_load(&self, id: StreamId) -> Option<*mut u8> {
         let mut allocs = self.allocations.lock();
         let alloc = allocs.get_mut(&id)?;
 
         match alloc.state {
-            StreamState::Reserved | StreamState::Loading => {
+            StreamState::Reserved | StreamState::Loading if !alloc.in_flight => {
                 alloc.state = StreamState::Loading;
                 Some(alloc.ptr)
             }
@@ -210,9 +429,11 @@ impl StreamingAllocator {
     pub fn finish_load(&self, id: StreamId) {
         let mut allocs = self.allocations.lock();
         if let Some(alloc) = allocs.get_mut(&id) {
+            let frame = self.current_frame.load(Ordering::Relaxed);
             alloc.state = StreamState::Ready;
             alloc.loaded_bytes = alloc.reserved_size;
-            alloc.last_access = self.current_frame.load(Ordering::Relaxed);
+            alloc.last_access = frame;
+            alloc.ready_frame = frame;
         }
     }
 
@@ -220,15 +441,7 @@ impl StreamingAllocator {
     ///
     /// Updates the LRU timestamp.
     pub fn access(&self, id: StreamId) -> Option<*const u8> {
-        let mut allocs = self.allocations.lock();
-        let alloc = allocs.get_mut(&id)?;
-
-        if alloc.state == StreamState::Ready {
-            alloc.last_access = self.current_frame.load(Ordering::Relaxed);
-            Some(alloc.ptr as *const u8)
-        } else {
-            None
-        }
+        self.access_mut(id).map(|ptr| ptr as *const u8)
     }
 
     /// Access a ready allocation mutably.
@@ -237,86 +450,537 @@ impl StreamingAllocator {
         let alloc = allocs.get_mut(&id)?;
 
         if alloc.state == StreamState::Ready {
-            alloc.last_access = self.current_frame.load(Ordering::Relaxed);
-            Some(alloc.ptr)
+            self.touch(alloc);
+            let ptr = alloc.ptr;
+            drop(allocs);
+            self.record(TraceEvent::Access { key: id.raw() });
+            Some(ptr)
         } else {
             None
         }
     }
 
+    /// Update access bookkeeping for an allocation.
+    fn touch(&self, alloc: &mut StreamAllocation) {
+        alloc.last_access = self.current_frame.load(Ordering::Relaxed);
+        alloc.access_count += 1;
+        self.policy.lock().on_access(alloc.id);
+    }
+
     /// Free a streaming allocation.
+    ///
+    /// If a background loader is still writing into the allocation or a
+    /// `StreamGuard` pins it, it is marked `Evicting` and the memory is
+    /// released once the loader returns and the last pin is dropped.
     pub fn free(&self, id: StreamId) {
+        self.record(TraceEvent::Free { key: id.raw() });
         let mut allocs = self.allocations.lock();
+        self.free_locked(&mut allocs, id);
+    }
+
+    /// Free an allocation while holding the allocation table lock.
+    fn free_locked(&self, allocs: &mut HashMap<StreamId, StreamAllocation>, id: StreamId) {
+        if let Some(alloc) = allocs.get_mut(&id) {
+            if !alloc.can_release() {
+                alloc.state = StreamState::Evicting;
+                alloc.free_pending = true;
+                return;
+            }
+        }
         if let Some(alloc) = allocs.remove(&id) {
-            self.total_reserved.fetch_sub(alloc.reserved_size, Ordering::Relaxed);
+            self.release_memory(alloc);
+        }
+    }
+
+    /// Add a shared owner to an allocation.
+    ///
+    /// Reservations start with one owner. Returns the new count, or None
+    /// once the last owner has released it. Owners do not protect against
+    /// eviction; hold a `StreamGuard` for that.
+    pub fn retain(&self, id: StreamId) -> Option<u32> {
+        let mut allocs = self.allocations.lock();
+        let alloc = allocs.get_mut(&id)?;
+        if alloc.free_pending || alloc.ref_count == 0 {
+            return None;
+        }
+        alloc.ref_count += 1;
+        Some(alloc.ref_count)
+    }
+
+    /// Drop a shared owner, freeing the allocation when none remain.
+    ///
+    /// Returns the remaining count, or None if no owners were left.
+    pub fn release(&self, id: StreamId) -> Option<u32> {
+        let mut allocs = self.allocations.lock();
+        let alloc = allocs.get_mut(&id)?;
+        if alloc.ref_count == 0 {
+            return None;
+        }
+        alloc.ref_count -= 1;
+        let remaining = alloc.ref_count;
+        // Free under the same lock so a racing `retain` cannot revive it
+        if remaining == 0 {
+            self.free_locked(&mut allocs, id);
+            drop(allocs);
+            self.record(TraceEvent::Free { key: id.raw() });
+        }
+        Some(remaining)
+    }
+
+    /// Get the number of shared owners of an allocation.
+    pub fn ref_count(&self, id: StreamId) -> Option<u32> {
+        let allocs = self.allocations.lock();
+        allocs.get(&id).map(|a| a.ref_count)
+    }
+
+    /// Pin a ready allocation for as long as the returned guard lives.
+    ///
+    /// While pinned the allocation is never evicted or relocated, and `free`
+    /// is deferred until the guard drops, so the guard's pointer stays valid.
+    /// Returns None if the allocation is not `Ready`.
+    pub fn acquire(&self, id: StreamId) -> Option<StreamGuard<'_>> {
+        let mut allocs = self.allocations.lock();
+        let alloc = allocs.get_mut(&id)?;
+        if alloc.state != StreamState::Ready {
+            return None;
+        }
+
+        alloc.pins += 1;
+        self.touch(alloc);
+        let guard = StreamGuard {
+            allocator: self,
+            id,
+            ptr: alloc.ptr,
+            len: alloc.reserved_size,
+        };
+        drop(allocs);
+
+        self.record(TraceEvent::Access { key: id.raw() });
+        Some(guard)
+    }
+
+    /// Never evict an allocation automatically, regardless of priority.
+    pub fn set_never_evict(&self, id: StreamId, never_evict: bool) {
+        let mut allocs = self.allocations.lock();
+        if let Some(alloc) = allocs.get_mut(&id) {
+            alloc.never_evict = never_evict;
+        }
+    }
+
+    /// Keep an allocation resident for at least `frames` frames after it
+    /// becomes `Ready`.
+    pub fn set_min_residency(&self, id: StreamId, frames: u64) {
+        let mut allocs = self.allocations.lock();
+        if let Some(alloc) = allocs.get_mut(&id) {
+            alloc.min_residency = frames;
+        }
+    }
+
+    /// Whether an allocation is currently protected from eviction.
+    ///
+    /// True if it is pinned, marked never-evict, still inside its minimum
+    /// residency window, `Critical`, or not `Ready`.
+    pub fn is_eviction_protected(&self, id: StreamId) -> Option<bool> {
+        let allocs = self.allocations.lock();
+        let frame = self.current_frame.load(Ordering::Relaxed);
+        allocs
+            .get(&id)
+            .map(|a| !a.is_evictable(StreamPriority::Critical, frame))
+    }
+
+    /// Set a hint used by eviction policies to rank an allocation.
+    pub fn set_eviction_hint(&self, id: StreamId, hint: EvictionHint) {
+        let mut allocs = self.allocations.lock();
+        if let Some(alloc) = allocs.get_mut(&id) {
+            alloc.hint = hint;
+            drop(allocs);
+            self.record(TraceEvent::Hint { key: id.raw(), hint });
+        }
+    }
+
+    /// Replace the policy that ranks eviction candidates within a priority level.
+    ///
+    /// The default is `LruPolicy`. Live allocations are announced to the new
+    /// policy through `on_insert`.
+    pub fn set_eviction_policy<P: EvictionPolicy + 'static>(&self, policy: P) {
+        let allocs = self.allocations.lock();
+        let mut policy: Box<dyn EvictionPolicy> = Box::new(policy);
+        for alloc in allocs.values() {
+            policy.on_insert(alloc.id, alloc.reserved_size);
+        }
+        *self.policy.lock() = policy;
+    }
+
+    /// Name of the active eviction policy.
+    pub fn eviction_policy_name(&self) -> &'static str {
+        self.policy.lock().name()
+    }
+
+    /// The most recent eviction decision, if any eviction has happened.
+    pub fn last_eviction(&self) -> Option<EvictionPlan> {
+        self.last_eviction.lock().clone()
+    }
+
+    /// Get total allocations evicted.
+    pub fn eviction_count(&self) -> u64 {
+        self.eviction_count.load(Ordering::Relaxed)
+    }
+
+    /// Evict enough allocations below `priority` to fit a `size`-byte reservation.
+    ///
+    /// For streaming schedulers that make room ahead of a load. Returns the
+    /// decision; check `is_sufficient` to see whether the room was made.
+    pub fn evict_for(&self, size: usize, priority: StreamPriority) -> EvictionPlan {
+        let needed = (self.total_reserved.load(Ordering::Relaxed) + size)
+            .saturating_sub(self.budget);
+        if needed == 0 {
+            return EvictionPlan {
+                policy: self.eviction_policy_name(),
+                ..EvictionPlan::default()
+            };
+        }
+        self.evict(needed, priority)
+    }
+
+    /// Start recording reservations, accesses and frees into an `AccessTrace`.
+    pub fn start_trace(&self) {
+        *self.trace.lock() = Some(AccessTrace::new());
+        self.tracing.store(true, Ordering::Release);
+    }
+
+    /// Stop recording and return the trace.
+    pub fn stop_trace(&self) -> Option<AccessTrace> {
+        self.tracing.store(false, Ordering::Release);
+        self.trace.lock().take()
+    }
+
+    fn record(&self, event: TraceEvent) {
+        if self.tracing.load(Ordering::Acquire) {
+            if let Some(trace) = self.trace.lock().as_mut() {
+                trace.push(event);
+            }
+        }
+    }
+
+    /// Report what a reservation of `size` bytes at `priority` would evict.
+    ///
+    /// Nothing is freed; use this to plan streaming ahead of time.
+    pub fn plan_eviction(&self, size: usize, priority: StreamPriority) -> EvictionPlan {
+        let needed = (self.total_reserved.load(Ordering::Relaxed) + size)
+            .saturating_sub(self.budget);
+        if needed == 0 {
+            return EvictionPlan {
+                policy: self.eviction_policy_name(),
+                ..EvictionPlan::default()
+            };
+        }
+        let allocs = self.allocations.lock();
+        self.select_victims(&allocs, needed, priority)
+    }
+
+    /// Get the priority of an allocation.
+    pub fn priority(&self, id: StreamId) -> Option<StreamPriority> {
+        let allocs = self.allocations.lock();
+        allocs.get(&id).map(|a| a.priority)
+    }
+
+    /// Hand the write pointer to a background loader.
+    ///
+    /// Like `begin_load`, but also marks the allocation as in flight so that
+    /// `free` defers the release and eviction never selects it. Only
+    /// `Reserved` allocations are handed out; a `Loading` one is already
+    /// being written, by `begin_load` or another loader.
+    pub(crate) fn acquire_load(&self, id: StreamId) -> Option<(*mut u8, usize)> {
+        let mut allocs = self.allocations.lock();
+        let alloc = allocs.get_mut(&id)?;
+
+        match alloc.state {
+            StreamState::Reserved if !alloc.in_flight => {
+                alloc.state = StreamState::Loading;
+                alloc.in_flight = true;
+                Some((alloc.ptr, alloc.reserved_size))
+            }
+            _ => None,
+        }
+    }
+
+    /// Take the write pointer back from a background loader.
+    ///
+    /// On success the allocation becomes `Ready`; otherwise it returns to
+    /// `Reserved` with no loaded bytes. Returns `None` if the allocation was
+    /// freed while the loader was running.
+    pub(crate) fn release_load(&self, id: StreamId, completed: bool) -> Option<StreamState> {
+        let mut allocs = self.allocations.lock();
+        let alloc = allocs.get_mut(&id)?;
+        alloc.in_flight = false;
+
+        if alloc.free_pending {
+            if alloc.can_release() {
+                let alloc = allocs.remove(&id)?;
+                self.release_memory(alloc);
+            }
+            return None;
+        }
+
+        if completed {
+            let frame = self.current_frame.load(Ordering::Relaxed);
+            self.total_loaded
+                .fetch_add(alloc.reserved_size - alloc.loaded_bytes, Ordering::Relaxed);
+            alloc.loaded_bytes = alloc.reserved_size;
+            alloc.state = StreamState::Ready;
+            alloc.last_access = frame;
+            alloc.ready_frame = frame;
+        } else {
             self.total_loaded.fetch_sub(alloc.loaded_bytes, Ordering::Relaxed);
-            
-            let layout = Layout::from_size_align(alloc.reserved_size, 16)
-                .expect("Invalid layout");
-            unsafe {
-                dealloc(alloc.ptr, layout);
+            alloc.loaded_bytes = 0;
+            alloc.state = StreamState::Reserved;
+        }
+
+        Some(alloc.state)
+    }
+
+    /// Pin an allocation so it is never evicted or moved by `defragment`.
+    ///
+    /// Pins nest; each `pin` must be matched by an `unpin`. Prefer
+    /// `acquire`, which unpins automatically.
+    pub fn pin(&self, id: StreamId) -> bool {
+        let mut allocs = self.allocations.lock();
+        match allocs.get_mut(&id) {
+            Some(alloc) => {
+                alloc.pins += 1;
+                true
             }
+            None => false,
         }
     }
 
-    /// Try to evict allocations to free up the specified amount.
+    /// Release one pin on an allocation.
     ///
-    /// Returns true if enough memory was freed.
-    fn try_evict(&self, bytes_needed: usize, min_priority: StreamPriority) -> bool {
+    /// Completes a deferred `free` once the last pin is released.
+    pub fn unpin(&self, id: StreamId) {
         let mut allocs = self.allocations.lock();
-        
-        // Collect candidates for eviction (lower priority than requested)
-        let mut candidates: Vec<_> = allocs
+        if let Some(alloc) = allocs.get_mut(&id) {
+            alloc.pins = alloc.pins.saturating_sub(1);
+            if alloc.free_pending && alloc.can_release() {
+                if let Some(alloc) = allocs.remove(&id) {
+                    self.release_memory(alloc);
+                }
+            }
+        }
+    }
+
+    /// Compact the backing region by relocating movable allocations.
+    ///
+    /// Only `Ready`, unpinned allocations that no loader is writing are
+    /// moved, highest addresses first. Each is moved into the free block
+    /// the region's TLSF allocator picks for it, if that block is below its
+    /// current position. Returns the number of relocations; always 0 when
+    /// not region-backed.
+    pub fn defragment(&self) -> usize {
+        self.defragment_with_limit(usize::MAX)
+    }
+
+    /// Like `defragment`, but stops after moving `max_bytes`.
+    pub fn defragment_with_limit(&self, max_bytes: usize) -> usize {
+        let Some(region) = &self.region else {
+            return 0;
+        };
+
+        let mut allocs = self.allocations.lock();
+        let mut tlsf = region.tlsf.lock();
+
+        // Highest addresses first: they benefit most from moving down
+        let mut movable: Vec<_> = allocs
             .values()
-            .filter(|a| a.priority < min_priority && a.state == StreamState::Ready)
-            .map(|a| (a.id, a.priority, a.last_access, a.reserved_size))
+            .filter(|a| a.state == StreamState::Ready && a.pins == 0 && !a.in_flight)
+            .map(|a| (a.ptr as usize - region.base as usize, a.id))
             .collect();
+        movable.sort_unstable_by_key(|&(offset, _)| std::cmp::Reverse(offset));
 
-        // Sort by priority (ascending), then by last access (ascending = LRU first)
-        candidates.sort_by(|a, b| {
-            a.1.cmp(&b.1).then_with(|| a.2.cmp(&b.2))
-        });
-
-        let mut freed = 0;
-        let mut to_evict = Vec::new();
+        let mut moved = Vec::new();
+        let mut moved_bytes = 0;
 
-        for (id, _, _, size) in candidates {
-            if freed >= bytes_needed {
+        for (old_offset, id) in movable {
+            let alloc = allocs.get_mut(&id).expect("movable allocation vanished");
+            if moved_bytes + alloc.reserved_size > max_bytes {
                 break;
             }
-            to_evict.push(id);
-            freed += size;
+
+            let Some(new_offset) = tlsf.alloc_aligned(alloc.reserved_size, alloc.align) else {
+                continue;
+            };
+            if new_offset > old_offset {
+                tlsf.free(new_offset);
+                continue;
+            }
+
+            let old_ptr = alloc.ptr;
+            let new_ptr = unsafe { region.base.add(new_offset) };
+            // SAFETY: both blocks are live in the TLSF at this point, so they
+            // cannot overlap.
+            unsafe {
+                std::ptr::copy_nonoverlapping(old_ptr, new_ptr, alloc.reserved_size);
+            }
+            // Reported before the old block can be handed out again
+            self.profiler.free(MemoryPool::Streaming, old_ptr, alloc.reserved_size);
+            self.profiler.alloc(MemoryPool::Streaming, new_ptr, alloc.reserved_size, alloc.tag);
+            tlsf.free(old_offset);
+
+            alloc.ptr = new_ptr;
+            moved_bytes += alloc.reserved_size;
+            moved.push((id, old_ptr, new_ptr));
         }
 
-        // Actually evict
-        for id in &to_evict {
-            if let Some(alloc) = allocs.remove(id) {
-                self.total_reserved.fetch_sub(alloc.reserved_size, Ordering::Relaxed);
-                self.total_loaded.fetch_sub(alloc.loaded_bytes, Ordering::Relaxed);
-                
-                let layout = Layout::from_size_align(alloc.reserved_size, 16)
+        drop(tlsf);
+        drop(allocs); // Release lock before callback
+
+        self.relocation_count.fetch_add(moved.len() as u64, Ordering::Relaxed);
+        if let Some(ref callback) = *self.relocation_callback.lock() {
+            for &(id, old_ptr, new_ptr) in &moved {
+                callback(id, old_ptr, new_ptr);
+            }
+        }
+
+        moved.len()
+    }
+
+    /// Get total relocations performed.
+    pub fn relocation_count(&self) -> u64 {
+        self.relocation_count.load(Ordering::Relaxed)
+    }
+
+    /// Get memory for an asset from the region or the system allocator.
+    fn alloc_memory(&self, size: usize, align: usize) -> Option<*mut u8> {
+        match &self.region {
+            Some(region) => {
+                let offset = region.tlsf.lock().alloc_aligned(size, align)?;
+                Some(unsafe { region.base.add(offset) })
+            }
+            None => {
+                let layout = Layout::from_size_align(size, align).ok()?;
+                let ptr = unsafe { alloc(layout) };
+                (!ptr.is_null()).then_some(ptr)
+            }
+        }
+    }
+
+    /// Return an allocation's memory and update the byte counters.
+    fn release_memory(&self, alloc: StreamAllocation) {
+        self.profiler.free(MemoryPool::Streaming, alloc.ptr, alloc.reserved_size);
+        self.policy.lock().on_remove(alloc.id);
+        self.total_reserved.fetch_sub(alloc.reserved_size, Ordering::Relaxed);
+        self.total_loaded.fetch_sub(alloc.loaded_bytes, Ordering::Relaxed);
+
+        match &self.region {
+            Some(region) => {
+                let offset = alloc.ptr as usize - region.base as usize;
+                region.tlsf.lock().free(offset);
+            }
+            None => {
+                let layout = Layout::from_size_align(alloc.reserved_size, alloc.align)
                     .expect("Invalid layout");
                 unsafe {
                     dealloc(alloc.ptr, layout);
                 }
             }
         }
+    }
+
+    /// Try to evict allocations to free up the specified amount.
+    ///
+    /// Returns true if enough memory was freed.
+    fn try_evict(&self, bytes_needed: usize, min_priority: StreamPriority) -> bool {
+        self.evict(bytes_needed, min_priority).is_sufficient()
+    }
+
+    /// Evict up to `bytes_needed` bytes of allocations below `min_priority`.
+    fn evict(&self, bytes_needed: usize, min_priority: StreamPriority) -> EvictionPlan {
+        let mut allocs = self.allocations.lock();
+        let plan = self.select_victims(&allocs, bytes_needed, min_priority);
+
+        // Actually evict
+        for id in plan.ids() {
+            if let Some(alloc) = allocs.remove(&id) {
+                self.release_memory(alloc);
+            }
+        }
 
         drop(allocs); // Release lock before callback
 
+        if !plan.candidates.is_empty() {
+            self.eviction_count
+                .fetch_add(plan.candidates.len() as u64, Ordering::Relaxed);
+            *self.last_eviction.lock() = Some(plan.clone());
+        }
+
         // Notify about evictions
         if let Some(ref callback) = *self.eviction_callback.lock() {
-            for id in to_evict {
+            for id in plan.ids() {
                 callback(id);
             }
         }
 
-        freed >= bytes_needed
+        plan
+    }
+
+    /// Choose allocations to evict, without evicting them.
+    fn select_victims(
+        &self,
+        allocs: &HashMap<StreamId, StreamAllocation>,
+        bytes_needed: usize,
+        min_priority: StreamPriority,
+    ) -> EvictionPlan {
+        let frame = self.current_frame.load(Ordering::Relaxed);
+        let policy = self.policy.lock();
+
+        // Collect candidates for eviction (lower priority than requested, unprotected)
+        let mut candidates: Vec<_> = allocs
+            .values()
+            .filter(|a| a.is_evictable(min_priority, frame))
+            .map(|a| {
+                let mut candidate = EvictionCandidate {
+                    id: a.id,
+                    size: a.reserved_size,
+                    priority: a.priority,
+                    last_access: a.last_access,
+                    access_count: a.access_count,
+                    hint: a.hint,
+                    tag: a.tag,
+                    score: 0.0,
+                };
+                candidate.score = policy.score(&candidate, frame);
+                candidate
+            })
+            .collect();
+
+        // Sort by priority (ascending), then policy score, then last access (LRU first)
+        candidates.sort_by(|a, b| {
+            a.priority
+                .cmp(&b.priority)
+                .then_with(|| a.score.total_cmp(&b.score))
+                .then_with(|| a.last_access.cmp(&b.last_access))
+        });
+
+        let mut plan = EvictionPlan {
+            policy: policy.name(),
+            bytes_needed,
+            ..EvictionPlan::default()
+        };
+        for candidate in candidates {
+            if plan.bytes_freed >= bytes_needed {
+                break;
+            }
+            plan.bytes_freed += candidate.size;
+            plan.candidates.push(candidate);
+        }
+
+        plan
     }
 
     /// Advance to the next frame (for LRU tracking).
     pub fn next_frame(&self) {
         self.current_frame.fetch_add(1, Ordering::Relaxed);
+        self.record(TraceEvent::NextFrame);
     }
 
     /// Get the current memory budget.
@@ -357,9 +1021,31 @@ impl StreamingAllocator {
             reserved_count: 0,
             loading_count: 0,
             ready_count: 0,
+            in_flight_count: 0,
+            pinned_count: 0,
+            region_size: 0,
+            region_used: 0,
+            free_block_count: 0,
+            largest_free_block: 0,
+            relocation_count: self.relocation_count.load(Ordering::Relaxed),
+            eviction_count: self.eviction_count.load(Ordering::Relaxed),
         };
 
+        if let Some(region) = &self.region {
+            let tlsf = region.tlsf.lock();
+            stats.region_size = tlsf.capacity();
+            stats.region_used = tlsf.used();
+            stats.free_block_count = tlsf.free_block_count();
+            stats.largest_free_block = tlsf.largest_free_block();
+        }
+
         for alloc in allocs.values() {
+            if alloc.in_flight {
+                stats.in_flight_count += 1;
+            }
+            if alloc.pins > 0 {
+                stats.pinned_count += 1;
+            }
             match alloc.state {
                 StreamState::Reserved => stats.reserved_count += 1,
                 StreamState::Loading => stats.loading_count += 1,
@@ -372,10 +1058,79 @@ impl StreamingAllocator {
     }
 }
 
+impl Drop for StreamingAllocator {
+    fn drop(&mut self) {
+        let allocs = std::mem::take(&mut *self.allocations.lock());
+        match self.region.take() {
+            // Region-backed assets all live inside the region
+            Some(region) => unsafe { dealloc(region.base, region.layout) },
+            None => {
+                for alloc in allocs.into_values() {
+                    self.release_memory(alloc);
+                }
+            }
+        }
+    }
+}
+
 // SAFETY: StreamingAllocator uses internal synchronization
 unsafe impl Send for StreamingAllocator {}
 unsafe impl Sync for StreamingAllocator {}
 
+/// RAII guard that pins a ready streaming allocation.
+///
+/// While the guard lives the allocation cannot be evicted, relocated or
+/// released, so its pointer stays valid.
+pub struct StreamGuard<'a> {
+    allocator: &'a StreamingAllocator,
+    id: StreamId,
+    ptr: *mut u8,
+    len: usize,
+}
+
+impl<'a> StreamGuard<'a> {
+    /// Get the pinned allocation's ID.
+    pub fn id(&self) -> StreamId {
+        self.id
+    }
+
+    /// Get a pointer to the data (valid for lifetime of guard).
+    pub fn as_ptr(&self) -> *const u8 {
+        self.ptr
+    }
+
+    /// Get a mutable pointer to the data.
+    pub fn as_mut_ptr(&self) -> *mut u8 {
+        self.ptr
+    }
+
+    /// Size of the allocation in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the allocation is zero-sized.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// View the data as a byte slice.
+    ///
+    /// # Safety
+    ///
+    /// No one may write through `access_mut` or `as_mut_ptr` while the
+    /// slice is alive.
+    pub unsafe fn as_slice(&self) -> &[u8] {
+        std::slice::from_raw_parts(self.ptr, self.len)
+    }
+}
+
+impl<'a> Drop for StreamGuard<'a> {
+    fn drop(&mut self) {
+        self.allocator.unpin(self.id);
+    }
+}
+
 /// Statistics about streaming allocations.
 #[derive(Debug, Clone, Default)]
 pub struct StreamingStats {
@@ -393,6 +1148,22 @@ pub struct StreamingStats {
     pub loading_count: usize,
     /// Allocations in Ready state
     pub ready_count: usize,
+    /// Allocations currently being written by a background loader
+    pub in_flight_count: usize,
+    /// Allocations with at least one pin
+    pub pinned_count: usize,
+    /// Size of the backing region (0 when not region-backed)
+    pub region_size: usize,
+    /// Region bytes in use, including granularity rounding
+    pub region_used: usize,
+    /// Number of free holes in the region
+    pub free_block_count: usize,
+    /// Largest single reservation the region can satisfy without defragmenting
+    pub largest_free_block: usize,
+    /// Total relocations performed by defragmentation
+    pub relocation_count: u64,
+    /// Total allocations evicted
+    pub eviction_count: u64,
 }
 
 impl StreamingStats {
@@ -405,6 +1176,19 @@ impl StreamingStats {
         }
     }
 
+    /// Calculate region fragmentation percentage.
+    ///
+    /// 0% means all free space is one contiguous hole; values near 100% mean
+    /// free space is scattered across many small holes.
+    pub fn fragmentation_percent(&self) -> f64 {
+        let free = self.region_size.saturating_sub(self.region_used);
+        if free == 0 {
+            0.0
+        } else {
+            (1.0 - self.largest_free_block as f64 / free as f64) * 100.0
+        }
+    }
+
     /// Calculate load progress percentage.
     pub fn load_progress_percent(&self) -> f64 {
         if self.total_reserved == 0 {
@@ -477,4 +1261,215 @@ mod tests {
         let remaining = [id1, id2].iter().filter(|id| streaming.state(**id).is_some()).count();
         assert_eq!(remaining, 1);
     }
+
+    #[test]
+    fn test_guard_blocks_eviction_and_defers_free() {
+        let streaming = StreamingAllocator::new(1024);
+
+        let id1 = streaming.reserve(512, StreamPriority::Low).unwrap();
+        streaming.finish_load(id1);
+        let id2 = streaming.reserve(512, StreamPriority::Low).unwrap();
+        streaming.finish_load(id2);
+
+        let guard = streaming.acquire(id1).unwrap();
+        assert_eq!(streaming.stats().pinned_count, 1);
+
+        // Only the unpinned allocation is offered up
+        let plan = streaming.plan_eviction(512, StreamPriority::High);
+        assert!(plan.is_sufficient());
+        assert_eq!(plan.ids().collect::<Vec<_>>(), vec![id2]);
+
+        // Freeing a pinned allocation waits for the guard
+        streaming.free(id1);
+        assert_eq!(streaming.state(id1), Some(StreamState::Evicting));
+        assert!(streaming.acquire(id1).is_none());
+        drop(guard);
+        assert_eq!(streaming.state(id1), None);
+        assert_eq!(streaming.total_reserved(), 512);
+    }
+
+    #[test]
+    fn test_ref_counting() {
+        let streaming = StreamingAllocator::new(1024);
+
+        let id = streaming.reserve(128, StreamPriority::Normal).unwrap();
+        assert_eq!(streaming.ref_count(id), Some(1));
+        assert_eq!(streaming.retain(id), Some(2));
+
+        assert_eq!(streaming.release(id), Some(1));
+        assert!(streaming.state(id).is_some());
+        assert_eq!(streaming.release(id), Some(0));
+        assert_eq!(streaming.state(id), None);
+        assert_eq!(streaming.retain(id), None);
+
+        // A pinned allocation stays until unpinned but cannot be revived
+        let id = streaming.reserve(128, StreamPriority::Normal).unwrap();
+        streaming.finish_load(id);
+        let guard = streaming.acquire(id).unwrap();
+        assert_eq!(streaming.release(id), Some(0));
+        assert_eq!(streaming.retain(id), None);
+        assert_eq!(streaming.release(id), None);
+        drop(guard);
+        assert_eq!(streaming.state(id), None);
+    }
+
+    #[test]
+    fn test_eviction_protection() {
+        let streaming = StreamingAllocator::new(1024);
+
+        let never = streaming.reserve(256, StreamPriority::Low).unwrap();
+        streaming.finish_load(never);
+        streaming.set_never_evict(never, true);
+
+        let young = streaming.reserve(256, StreamPriority::Low).unwrap();
+        streaming.finish_load(young);
+        streaming.set_min_residency(young, 2);
+
+        let plain = streaming.reserve(512, StreamPriority::Low).unwrap();
+        streaming.finish_load(plain);
+
+        assert_eq!(streaming.is_eviction_protected(never), Some(true));
+        assert_eq!(streaming.is_eviction_protected(young), Some(true));
+        assert_eq!(streaming.is_eviction_protected(plain), Some(false));
+
+        // Dry-run does not free anything
+        let plan = streaming.plan_eviction(768, StreamPriority::Normal);
+        assert!(!plan.is_sufficient());
+        assert_eq!(plan.bytes_freed, 512);
+        assert_eq!(streaming.stats().allocation_count, 3);
+
+        streaming.next_frame();
+        streaming.next_frame();
+        assert_eq!(streaming.is_eviction_protected(young), Some(false));
+        let plan = streaming.plan_eviction(768, StreamPriority::Normal);
+        assert!(plan.is_sufficient());
+        assert!(!plan.ids().any(|id| id == never));
+    }
+
+    #[test]
+    fn test_region_reserve_and_free() {
+        let streaming = StreamingAllocator::with_region(4096);
+        assert!(streaming.is_region_backed());
+
+        let id = streaming.reserve(1000, StreamPriority::Normal).unwrap();
+        let ptr = streaming.begin_load(id).unwrap();
+        assert_eq!(ptr as usize % 16, 0);
+
+        let stats = streaming.stats();
+        assert_eq!(stats.region_size, 4096);
+        assert_eq!(stats.region_used, 1008);
+
+        streaming.free(id);
+        let stats = streaming.stats();
+        assert_eq!(stats.region_used, 0);
+        assert_eq!(stats.largest_free_block, 4096);
+    }
+
+    #[test]
+    fn test_region_full_budget_reserve() {
+        for (budget, size) in [(3008, 3008), (3008, 2950), (3000, 3000)] {
+            let streaming = StreamingAllocator::with_region(budget);
+            let id = streaming.reserve(size, StreamPriority::Normal);
+            assert!(id.is_some(), "reserve({size}) in a {budget}-byte region");
+        }
+    }
+
+    #[test]
+    fn test_region_defragment_fills_exact_hole() {
+        // 1008-byte blocks share a bin with smaller ones
+        let streaming = StreamingAllocator::with_region(3 * 1008);
+        let ids: Vec<_> = (0..3)
+            .map(|_| {
+                let id = streaming.reserve(1000, StreamPriority::Normal).unwrap();
+                streaming.finish_load(id);
+                id
+            })
+            .collect();
+
+        let hole = streaming.access(ids[0]).unwrap();
+        streaming.free(ids[0]);
+        assert_eq!(streaming.defragment(), 1);
+        assert_eq!(streaming.access(ids[2]).unwrap(), hole);
+    }
+
+    #[test]
+    fn test_region_defragment_keeps_ids() {
+        let streaming = StreamingAllocator::with_region(1024);
+
+        let ids: Vec<_> = (0..4u8)
+            .map(|i| {
+                let id = streaming.reserve(256, StreamPriority::Normal).unwrap();
+                let ptr = streaming.begin_load(id).unwrap();
+                unsafe { std::ptr::write_bytes(ptr, i, 256) };
+                streaming.finish_load(id);
+                id
+            })
+            .collect();
+
+        // Two 256-byte holes, but no 512-byte one
+        streaming.free(ids[0]);
+        streaming.free(ids[2]);
+        let stats = streaming.stats();
+        assert_eq!(stats.free_block_count, 2);
+        assert!(stats.fragmentation_percent() > 0.0);
+
+        // Pinned assets stay put
+        streaming.pin(ids[1]);
+        let pinned_ptr = streaming.access(ids[1]).unwrap();
+
+        // Reservations only compact when allowed to
+        assert!(streaming.reserve(512, StreamPriority::Normal).is_none());
+        assert_eq!(streaming.relocation_count(), 0);
+        streaming.set_auto_defragment(true);
+        let big = streaming.reserve(512, StreamPriority::Normal);
+        assert!(big.is_some());
+        assert_eq!(streaming.relocation_count(), 1);
+        assert_eq!(streaming.access(ids[1]).unwrap(), pinned_ptr);
+
+        let moved = streaming.access(ids[3]).unwrap();
+        let data = unsafe { std::slice::from_raw_parts(moved, 256) };
+        assert!(data.iter().all(|&b| b == 3));
+        streaming.unpin(ids[1]);
+    }
+
+    #[test]
+    fn test_region_pinned_blocks_compaction() {
+        let streaming = StreamingAllocator::with_region(768);
+
+        let a = streaming.reserve(256, StreamPriority::Normal).unwrap();
+        let b = streaming.reserve(256, StreamPriority::Normal).unwrap();
+        let c = streaming.reserve(256, StreamPriority::Normal).unwrap();
+        for id in [a, b, c] {
+            streaming.finish_load(id);
+        }
+        streaming.free(a);
+        streaming.free(c);
+
+        streaming.pin(b);
+        assert_eq!(streaming.defragment(), 0);
+        assert!(streaming.reserve(512, StreamPriority::Normal).is_none());
+
+        streaming.unpin(b);
+        assert_eq!(streaming.defragment(), 1);
+        assert_eq!(streaming.stats().largest_free_block, 512);
+    }
+
+    #[test]
+    fn test_aligned_reservations() {
+        for streaming in [StreamingAllocator::new(64 * 1024), StreamingAllocator::with_region(64 * 1024)] {
+            let small = streaming.reserve(48, StreamPriority::Normal).unwrap();
+            let aligned = streaming.reserve_aligned(1000, 256, StreamPriority::Normal).unwrap();
+            assert_eq!(streaming.begin_load(aligned).unwrap() as usize % 256, 0);
+            streaming.finish_load(aligned);
+
+            // Compaction moves the asset down without losing its alignment
+            streaming.free(small);
+            streaming.defragment();
+            assert_eq!(streaming.access(aligned).unwrap() as usize % 256, 0);
+            streaming.free(aligned);
+
+            assert!(streaming.reserve_aligned(64, 48, StreamPriority::Normal).is_none());
+            assert!(streaming.reserve_aligned(64, 2 * MAX_STREAM_ALIGN, StreamPriority::Normal).is_none());
+        }
+    }
 }
@@ -0,0 +1,384 @@
+//! Two-level segregated fit (TLSF) sub-allocator.
+//!
+//! Hands out offsets into a region owned by someone else. The allocator never
+//! touches the managed memory, so block metadata lives on the side:
+//! - Free blocks are binned by a first level (power of two) and a second
+//!   level (linear subdivision), with a bitmap per level for O(1) lookup
+//! - Freed blocks are coalesced with free physical neighbours immediately
+
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Allocation granularity and alignment of every block offset.
+pub(crate) const GRANULARITY: usize = 16;
+
+/// log2 of the number of second-level bins per first-level bin.
+const SL_LOG2: u32 = 4;
+
+/// Number of second-level bins per first-level bin.
+const SL_COUNT: usize = 1 << SL_LOG2;
+
+/// Number of first-level bins.
+const FL_COUNT: usize = usize::BITS as usize;
+
+/// A physical block in the managed range.
+#[derive(Debug, Clone, Copy)]
+struct Block {
+    size: usize,
+    free: bool,
+}
+
+/// TLSF allocator over `0..capacity`.
+pub(crate) struct Tlsf {
+    capacity: usize,
+    /// Every block (free or used), keyed by offset
+    blocks: BTreeMap<usize, Block>,
+    /// Free block offsets per (first level, second level) bin
+    bins: Vec<BTreeSet<usize>>,
+    /// Bit `fl` set when any bin in first level `fl` is non-empty
+    fl_bitmap: usize,
+    /// Bit `sl` of entry `fl` set when bin (fl, sl) is non-empty
+    sl_bitmaps: [u32; FL_COUNT],
+    /// Bytes in used blocks
+    used: usize,
+}
+
+impl Tlsf {
+    /// Create an allocator managing `capacity` bytes (rounded down to the granularity).
+    pub(crate) fn new(capacity: usize) -> Self {
+        let capacity = capacity & !(GRANULARITY - 1);
+        let mut tlsf = Self {
+            capacity,
+            blocks: BTreeMap::new(),
+            bins: (0..FL_COUNT * SL_COUNT).map(|_| BTreeSet::new()).collect(),
+            fl_bitmap: 0,
+            sl_bitmaps: [0; FL_COUNT],
+            used: 0,
+        };
+        if capacity > 0 {
+            tlsf.insert_free(0, capacity);
+        }
+        tlsf
+    }
+
+    /// Round a request up to the allocation granularity.
+    pub(crate) fn round_size(size: usize) -> usize {
+        (size.max(1) + GRANULARITY - 1) & !(GRANULARITY - 1)
+    }
+
+    /// Allocate `size` bytes, returning the block offset.
+    pub(crate) fn alloc(&mut self, size: usize) -> Option<usize> {
+        let size = Self::round_size(size);
+        if size > self.capacity {
+            return None;
+        }
+
+        let offset = self.find_free(size)?;
+        let block_size = self.blocks[&offset].size;
+        self.remove_free(offset, block_size);
+
+        // Sizes are granule multiples, so any tail is a valid block
+        if block_size > size {
+            self.insert_free(offset + size, block_size - size);
+        }
+
+        self.blocks.insert(offset, Block { size, free: false });
+        self.used += size;
+        Some(offset)
+    }
+
+    /// Allocate `size` bytes at an offset that is a multiple of `align`.
+    ///
+    /// `align` must be a power of two. Leading padding is split off as a
+    /// free block, so the returned offset is the start of a used block.
+    pub(crate) fn alloc_aligned(&mut self, size: usize, align: usize) -> Option<usize> {
+        if align <= GRANULARITY {
+            return self.alloc(size);
+        }
+
+        let size = Self::round_size(size);
+        let padded = size.checked_add(align - GRANULARITY)?;
+        if padded > self.capacity {
+            return None;
+        }
+
+        let offset = self.find_free(padded)?;
+        let block_size = self.blocks[&offset].size;
+        self.remove_free(offset, block_size);
+
+        // Neighbours of a free block are used, so the split pieces need no
+        // coalescing
+        let aligned = (offset + align - 1) & !(align - 1);
+        if aligned > offset {
+            self.insert_free(offset, aligned - offset);
+        }
+        let tail = block_size - (aligned - offset) - size;
+        if tail > 0 {
+            self.insert_free(aligned + size, tail);
+        }
+
+        self.blocks.insert(aligned, Block { size, free: false });
+        self.used += size;
+        Some(aligned)
+    }
+
+    /// Free the block at `offset`. Returns the block size, or `None` if no
+    /// used block starts there.
+    pub(crate) fn free(&mut self, offset: usize) -> Option<usize> {
+        let block = *self.blocks.get(&offset)?;
+        if block.free {
+            return None;
+        }
+        self.used -= block.size;
+
+        let mut start = offset;
+        let mut size = block.size;
+
+        // Merge with the following block
+        if let Some(next) = self.blocks.get(&(offset + size)).copied() {
+            if next.free {
+                self.remove_free(offset + size, next.size);
+                self.blocks.remove(&(offset + size));
+                size += next.size;
+            }
+        }
+
+        // Merge with the preceding block
+        if let Some((&prev_offset, &prev)) = self.blocks.range(..offset).next_back() {
+            if prev.free && prev_offset + prev.size == offset {
+                self.remove_free(prev_offset, prev.size);
+                start = prev_offset;
+                size += prev.size;
+            }
+        }
+
+        self.blocks.remove(&offset);
+        self.insert_free(start, size);
+        Some(block.size)
+    }
+
+    /// Size of the used block at `offset`.
+    pub(crate) fn block_size(&self, offset: usize) -> Option<usize> {
+        self.blocks
+            .get(&offset)
+            .filter(|b| !b.free)
+            .map(|b| b.size)
+    }
+
+    /// Total managed bytes.
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Bytes in used blocks (including rounding).
+    pub(crate) fn used(&self) -> usize {
+        self.used
+    }
+
+    /// Bytes in free blocks.
+    pub(crate) fn free_bytes(&self) -> usize {
+        self.capacity - self.used
+    }
+
+    /// Number of free blocks.
+    pub(crate) fn free_block_count(&self) -> usize {
+        self.bins.iter().map(|b| b.len()).sum()
+    }
+
+    /// Size of the largest free block.
+    pub(crate) fn largest_free_block(&self) -> usize {
+        if self.fl_bitmap == 0 {
+            return 0;
+        }
+        let fl = (usize::BITS - 1 - self.fl_bitmap.leading_zeros()) as usize;
+        let sl = (u32::BITS - 1 - self.sl_bitmaps[fl].leading_zeros()) as usize;
+        self.bins[fl * SL_COUNT + sl]
+            .iter()
+            .map(|offset| self.blocks[offset].size)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn insert_free(&mut self, offset: usize, size: usize) {
+        let (fl, sl) = Self::mapping(size);
+        self.blocks.insert(offset, Block { size, free: true });
+        self.bins[fl * SL_COUNT + sl].insert(offset);
+        self.fl_bitmap |= 1 << fl;
+        self.sl_bitmaps[fl] |= 1 << sl;
+    }
+
+    fn remove_free(&mut self, offset: usize, size: usize) {
+        let (fl, sl) = Self::mapping(size);
+        let bin = &mut self.bins[fl * SL_COUNT + sl];
+        bin.remove(&offset);
+        if bin.is_empty() {
+            self.sl_bitmaps[fl] &= !(1 << sl);
+            if self.sl_bitmaps[fl] == 0 {
+                self.fl_bitmap &= !(1 << fl);
+            }
+        }
+    }
+
+    /// Bin that a block of exactly `size` bytes belongs to.
+    fn mapping(size: usize) -> (usize, usize) {
+        let granules = size / GRANULARITY;
+        if granules < SL_COUNT {
+            (0, granules)
+        } else {
+            let log2 = usize::BITS - 1 - granules.leading_zeros();
+            let fl = (log2 - SL_LOG2 + 1) as usize;
+            let sl = (granules >> (log2 - SL_LOG2)) - SL_COUNT;
+            (fl, sl)
+        }
+    }
+
+    /// Lowest bin in which every block is at least `size` bytes.
+    fn mapping_search(size: usize) -> (usize, usize) {
+        let granules = size / GRANULARITY;
+        if granules < SL_COUNT {
+            return (0, granules);
+        }
+        let log2 = usize::BITS - 1 - granules.leading_zeros();
+        let rounded = granules.saturating_add((1 << (log2 - SL_LOG2)) - 1);
+        Self::mapping(rounded.saturating_mul(GRANULARITY))
+    }
+
+    /// Find a free block of at least `size` bytes.
+    ///
+    /// Takes the first block of the lowest bin whose blocks all fit. Failing
+    /// that, scans `size`'s own bin, whose blocks may or may not fit.
+    fn find_free(&self, size: usize) -> Option<usize> {
+        let (fl, sl) = Self::mapping_search(size);
+        if let Some((fl, sl)) = self.find_suitable(fl, sl) {
+            return self.bins[fl * SL_COUNT + sl].first().copied();
+        }
+
+        let (fl, sl) = Self::mapping(size);
+        self.bins[fl * SL_COUNT + sl]
+            .iter()
+            .copied()
+            .find(|offset| self.blocks[offset].size >= size)
+    }
+
+    fn find_suitable(&self, fl: usize, sl: usize) -> Option<(usize, usize)> {
+        let sl_map = self.sl_bitmaps[fl] & (u32::MAX << sl);
+        if sl_map != 0 {
+            return Some((fl, sl_map.trailing_zeros() as usize));
+        }
+
+        let fl_map = self.fl_bitmap & usize::MAX.checked_shl(fl as u32 + 1).unwrap_or(0);
+        if fl_map == 0 {
+            return None;
+        }
+        let fl = fl_map.trailing_zeros() as usize;
+        Some((fl, self.sl_bitmaps[fl].trailing_zeros() as usize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alloc_free_coalesces() {
+        let mut tlsf = Tlsf::new(4096);
+
+        let a = tlsf.alloc(1000).unwrap();
+        let b = tlsf.alloc(1000).unwrap();
+        let c = tlsf.alloc(1000).unwrap();
+        assert_eq!(tlsf.used(), 3 * Tlsf::round_size(1000));
+
+        tlsf.free(a);
+        tlsf.free(c);
+        assert_eq!(tlsf.free_block_count(), 2);
+
+        tlsf.free(b);
+        assert_eq!(tlsf.free_block_count(), 1);
+        assert_eq!(tlsf.largest_free_block(), 4096);
+        assert_eq!(tlsf.used(), 0);
+    }
+
+    #[test]
+    fn test_merged_next_block_is_forgotten() {
+        let mut tlsf = Tlsf::new(2048);
+        let _a = tlsf.alloc(256).unwrap();
+        let b = tlsf.alloc(256).unwrap();
+        let c = tlsf.alloc(256).unwrap();
+        let d = tlsf.alloc(256).unwrap();
+
+        tlsf.free(c);
+        tlsf.free(b);
+        let merged = tlsf.alloc(512).unwrap();
+        assert_eq!(merged, 256);
+        tlsf.free(d);
+
+        // Must not land inside the live block 256..768
+        let next = tlsf.alloc(256).unwrap();
+        assert!(next >= merged + 512, "overlapping block at {}", next);
+        assert!(tlsf.used() <= tlsf.capacity());
+    }
+
+    #[test]
+    fn test_fragmentation_blocks_large_request() {
+        let mut tlsf = Tlsf::new(1024);
+
+        let blocks: Vec<_> = (0..8).map(|_| tlsf.alloc(128).unwrap()).collect();
+        assert!(tlsf.alloc(16).is_none());
+
+        // Free every other block: 512 bytes free, but no 256-byte hole
+        for offset in blocks.iter().step_by(2) {
+            tlsf.free(*offset);
+        }
+        assert_eq!(tlsf.free_bytes(), 512);
+        assert_eq!(tlsf.largest_free_block(), 128);
+        assert!(tlsf.alloc(256).is_none());
+        assert!(tlsf.alloc(128).is_some());
+    }
+
+    #[test]
+    fn test_block_in_own_bin_is_found() {
+        // 3008 bytes sits in a bin that also holds smaller blocks
+        let mut tlsf = Tlsf::new(3008);
+        assert_eq!(tlsf.alloc(3008), Some(0));
+        tlsf.free(0);
+        assert_eq!(tlsf.alloc(2950), Some(0));
+        tlsf.free(0);
+
+        // An exact-size hole between used blocks is reused
+        let _a = tlsf.alloc(992).unwrap();
+        let b = tlsf.alloc(992).unwrap();
+        let _c = tlsf.alloc(992).unwrap();
+        tlsf.free(b);
+        assert_eq!(tlsf.alloc(992), Some(b));
+    }
+
+    #[test]
+    fn test_found_blocks_always_fit() {
+        let mut tlsf = Tlsf::new(1 << 20);
+        for size in [1, 17, 255, 256, 257, 1000, 4097, 65_537] {
+            let offset = tlsf.alloc(size).unwrap();
+            assert_eq!(offset % GRANULARITY, 0);
+            assert!(tlsf.block_size(offset).unwrap() >= size);
+        }
+        assert!(tlsf.alloc(2 << 20).is_none());
+    }
+
+    #[test]
+    fn test_aligned_alloc_splits_padding() {
+        let mut tlsf = Tlsf::new(8192);
+        let first = tlsf.alloc(16).unwrap();
+        assert_eq!(first, 0);
+
+        let offset = tlsf.alloc_aligned(100, 256).unwrap();
+        assert_eq!(offset % 256, 0);
+        assert_eq!(tlsf.block_size(offset), Some(112));
+
+        // The padding before it is reusable
+        assert_eq!(tlsf.alloc(32), Some(16));
+
+        tlsf.free(offset);
+        tlsf.free(16);
+        tlsf.free(first);
+        assert_eq!(tlsf.free_block_count(), 1);
+        assert_eq!(tlsf.used(), 0);
+    }
+}
@@ -4,27 +4,38 @@ use std::alloc::Layout;
 use std::sync::Arc;
 
 use crate::allocators::handles::HandleAllocator;
+use crate::allocators::large::LargeObjectStats;
+use crate::allocators::page_source::PageSource;
+use crate::allocators::slab::{DepotStats, PoolBackend};
+use crate::allocators::stream_loader::StreamLoaderPool;
 use crate::allocators::streaming::StreamingAllocator;
 use crate::api::checkpoint::{CheckpointGuard, FrameCheckpoint, SpeculativeResult};
 use crate::api::config::AllocConfig;
+use crate::api::frame_clock::FrameAuthority;
 use crate::api::frame_collections::{FrameMap, FrameVec};
 use crate::api::groups::GroupAllocator;
-use crate::api::phases::{self, Phase, PhaseGuard};
+use crate::api::phase_budget::{PhaseBudgetPolicy, PhaseBudgets, PhaseHighWater};
+use crate::api::phases::{Phase, PhaseGuard};
 use crate::api::promotion::{FrameSummary, PromotionProcessor, PromotionResult};
 use crate::api::retention::{
-    self, FrameRetained, Importance, PromotedAllocation, RetainedAllocation, RetainedMeta,
+    FrameRetained, Importance, PromotedAllocation, RetainedAllocation, RetainedMeta,
     RetentionPolicy,
 };
 use crate::diagnostics::behavior::{AllocKind, BehaviorFilter, BehaviorReport, BehaviorThresholds};
+use crate::diagnostics::histogram::AllocationHistograms;
 use crate::api::scope::FrameGuard;
+use crate::api::snapshot::{Snapshot, SnapshotSummary, ThreadSnapshot};
+use crate::api::sub_frame::{SubFrame, SubFrameRecord};
 use crate::api::scratch::ScratchRegistry;
 use crate::api::stats::AllocStats;
 use crate::api::tag::AllocationIntent;
 use crate::api::tagged::{self, TagGuard};
+use crate::api::threads::{ThreadInfo, ThreadTeardownStats};
 use crate::api::wrappers::{FrameBox, FrameSlice, HeapBox, PoolBox};
-use crate::core::global::GlobalState;
+use crate::core::global::{AllocatorId, GlobalState};
 use crate::core::tls;
-use crate::diagnostics::SharedDiagnostics;
+use crate::diagnostics::{self, MemoryPool, ProfilerHooks, SharedDiagnostics};
+use crate::util::layout::AlignedLayout;
 use crate::util::size::mb;
 
 /// The main smart allocator type.
@@ -51,29 +62,60 @@ pub struct SmartAlloc {
     groups: Arc<GroupAllocator>,
     diagnostics: Arc<SharedDiagnostics>,
     scratch: Arc<ScratchRegistry>,
-    frame_counter: Arc<std::sync::atomic::AtomicU64>,
     /// Behavior filter for detecting allocation pattern issues (v0.4.0)
     behavior_filter: Arc<BehaviorFilter>,
+    /// Per-phase frame budgets and high-water marks
+    phase_budgets: Arc<PhaseBudgets>,
 }
 
 impl SmartAlloc {
     /// Create a new allocator with the given configuration.
     pub fn new(config: AllocConfig) -> Self {
+        Self::from_state(GlobalState::new(config))
+    }
+
+    /// Create an allocator whose slab pages come from `page_source`.
+    ///
+    /// Sources reporting several nodes get a magazine depot per node.
+    pub fn with_page_source(config: AllocConfig, page_source: Arc<dyn PageSource>) -> Self {
+        Self::from_state(GlobalState::with_page_source(config, page_source))
+    }
+
+    fn from_state(state: GlobalState) -> Self {
+        let config = state.config();
         let streaming_budget = if config.global_memory_limit > 0 {
             config.global_memory_limit / 4 // 25% for streaming by default
         } else {
             mb(256) // 256MB default streaming budget
         };
+        let streaming = if config.streaming_region {
+            StreamingAllocator::with_region(streaming_budget)
+        } else {
+            StreamingAllocator::new(streaming_budget)
+        };
 
+        // Freeing a group resets the scratch pools bound to it
+        let groups = Arc::new(GroupAllocator::new());
+        let scratch = Arc::new(ScratchRegistry::default());
+        let bound_scratch = scratch.clone();
+        groups.add_free_listener(move |group| bound_scratch.reset_group(group));
+
+        let profiler = state.profiler().clone();
+        let behavior = state.behavior().clone();
+        let phase_budgets = Arc::new(PhaseBudgets::for_allocator(state.id()));
         Self {
-            inner: Arc::new(GlobalState::new(config)),
-            streaming: Arc::new(StreamingAllocator::new(streaming_budget)),
-            handles: Arc::new(HandleAllocator::new()),
-            groups: Arc::new(GroupAllocator::new()),
+            inner: Arc::new(state),
+            streaming: Arc::new(streaming.with_profiler(profiler.clone())),
+            handles: Arc::new(
+                HandleAllocator::new()
+                    .with_profiler(profiler)
+                    .with_behavior(behavior.clone()),
+            ),
+            groups,
             diagnostics: Arc::new(SharedDiagnostics::new()),
-            scratch: Arc::new(ScratchRegistry::default()),
-            frame_counter: Arc::new(std::sync::atomic::AtomicU64::new(0)),
-            behavior_filter: Arc::new(BehaviorFilter::new()),
+            scratch,
+            behavior_filter: behavior,
+            phase_budgets,
         }
     }
 
@@ -85,12 +127,18 @@ impl SmartAlloc {
     /// Begin a new frame.
     ///
     /// This should be called at the start of each game frame.
-    /// It prepares the frame arena for new allocations.
+    /// It prepares the frame arena for new allocations and starts a new
+    /// epoch for the calling thread. The global frame number only advances
+    /// when the frame authority calls this (see `set_frame_authority`), so
+    /// workers may call it freely. The authority's call also starts a new
+    /// rate-limit window for diagnostic sinks.
     pub fn begin_frame(&self) {
-        self.frame_counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-        phases::reset_phases();
-        tls::with_tls(|tls| {
-            tls.begin_frame();
+        let frame = self.inner.frame_clock().on_begin_frame();
+        if self.inner.frame_clock().is_authority() {
+            diagnostics::sinks().begin_frame();
+        }
+        tls::with_tls(&self.inner, |tls| {
+            tls.begin_frame(frame, &self.inner);
         });
     }
 
@@ -99,16 +147,109 @@ impl SmartAlloc {
     /// This resets the frame arena, invalidating all frame allocations.
     /// Any pointers from `frame_alloc` become invalid after this call.
     pub fn end_frame(&self) {
-        phases::reset_phases();
-        self.behavior_filter.end_frame();
-        tls::with_tls(|tls| {
+        self.behavior_filter.end_frame_at(self.frame_number());
+        let deferred = tls::with_tls(&self.inner, |tls| {
             tls.end_frame();
+            tls.deferred_len()
         });
+        self.profile_frame_end(deferred);
+    }
+
+    /// Emit the per-frame plots and the frame mark if the calling thread
+    /// is the frame authority.
+    fn profile_frame_end(&self, deferred_depth: usize) {
+        let Some(hooks) = self.inner.profiler().active() else {
+            return;
+        };
+        if !self.inner.frame_clock().is_authority() {
+            return;
+        }
+
+        let arena_bytes: usize = self.threads().iter().map(|t| t.last_frame_bytes).sum();
+        hooks.emit_plot("framealloc/frame_arena_bytes", arena_bytes as f64);
+        hooks.emit_plot(
+            "framealloc/pool_bytes",
+            self.inner.profiler().live_bytes(MemoryPool::Pool) as f64,
+        );
+        hooks.emit_plot("framealloc/deferred_queue_depth", deferred_depth as f64);
+        if let Some(budgets) = self.budgets() {
+            for budget in budgets.get_all_tag_budgets() {
+                let name = format!("framealloc/budget/{}", budget.name);
+                hooks.emit_plot(&name, budget.usage_percent());
+            }
+        }
+        hooks.emit_frame_mark(self.frame_number());
     }
 
-    /// Get the current frame number.
+    /// Get the current global frame number.
+    ///
+    /// Shared by all threads; advanced once per real frame by the frame
+    /// authority.
     pub fn frame_number(&self) -> u64 {
-        self.frame_counter.load(std::sync::atomic::Ordering::Relaxed)
+        self.inner.frame_clock().frame()
+    }
+
+    /// Get the calling thread's frame epoch.
+    ///
+    /// Counts this thread's `begin_frame` calls. Frame memory, checkpoints
+    /// and retained allocations belong to a single epoch.
+    pub fn thread_epoch(&self) -> u64 {
+        tls::with_tls(&self.inner, |tls| tls.epoch())
+    }
+
+    /// Get the global frame the calling thread last began.
+    pub fn thread_frame(&self) -> u64 {
+        tls::with_tls(&self.inner, |tls| tls.global_frame())
+    }
+
+    /// Choose who advances the global frame number.
+    ///
+    /// Defaults to `FrameAuthority::FirstCaller`.
+    pub fn set_frame_authority(&self, authority: FrameAuthority) {
+        self.inner.frame_clock().set_authority(authority);
+    }
+
+    /// Get who advances the global frame number.
+    pub fn frame_authority(&self) -> FrameAuthority {
+        self.inner.frame_clock().authority()
+    }
+
+    /// Advance the global frame number explicitly.
+    ///
+    /// Intended for `FrameAuthority::Manual`, e.g. from a job-system
+    /// coordinator. Returns the new frame number.
+    pub fn advance_frame(&self) -> u64 {
+        self.inner.frame_clock().advance()
+    }
+
+    /// Register the calling thread under `name`.
+    ///
+    /// Threads are registered implicitly on first use; this names them for
+    /// diagnostics and snapshots.
+    pub fn register_thread(&self, name: &str) {
+        tls::with_tls(&self.inner, |tls| self.inner.threads().name(tls.record(), name));
+    }
+
+    /// Tear down the calling thread's state for this allocator.
+    ///
+    /// Pending deferred frees are drained and the thread's pool free lists
+    /// return to the shared slab registry. Frame memory is released. This
+    /// also happens automatically when the thread exits. Returns false if
+    /// the thread had no state, or if it is refused because frame memory
+    /// may still be borrowed: call it between frames, after `end_frame` or
+    /// `reset_frame`, so no `FrameBox` can outlive the arena.
+    pub fn unregister_thread(&self) -> bool {
+        tls::release(&self.inner)
+    }
+
+    /// Get the threads currently using this allocator.
+    pub fn threads(&self) -> Vec<ThreadInfo> {
+        self.inner.threads().threads()
+    }
+
+    /// Get totals for threads that have been torn down.
+    pub fn thread_teardown_stats(&self) -> ThreadTeardownStats {
+        self.inner.threads().teardown_stats()
     }
 
     /// Create a frame scope guard.
@@ -137,12 +278,12 @@ impl SmartAlloc {
     /// Returns None on OOM instead of returning null.
     /// The memory is valid until `end_frame()` is called.
     pub fn try_frame_alloc<T>(&self) -> Option<*mut T> {
-        tls::with_tls(|tls| tls.try_frame_alloc::<T>())
+        tls::with_tls(&self.inner, |tls| tls.try_frame_alloc::<T>())
     }
 
     /// Allocate memory from the frame arena with explicit intent.
     pub fn frame_alloc_with_intent<T>(&self, _intent: AllocationIntent) -> *mut T {
-        tls::with_tls(|tls| tls.frame_alloc::<T>())
+        tls::with_tls(&self.inner, |tls| tls.frame_alloc::<T>())
     }
 
     /// Allocate from frame arena with a specific layout.
@@ -155,7 +296,27 @@ impl SmartAlloc {
     /// The caller must ensure the layout has non-zero size.
     /// The returned pointer must be used according to the layout's alignment.
     pub unsafe fn frame_alloc_layout(&self, layout: std::alloc::Layout) -> *mut u8 {
-        tls::with_tls(|tls| tls.frame_alloc_layout(layout))
+        tls::with_tls(&self.inner, |tls| tls.frame_alloc_layout(layout))
+    }
+
+    /// Allocate memory for a T in the frame arena, aligned to at least `A`.
+    ///
+    /// For SIMD data and cache-line-separated job state. `A` must be a
+    /// power of two; the result is aligned to `max(A, align_of::<T>())`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use framealloc::SmartAlloc;
+    /// # let alloc = SmartAlloc::with_defaults();
+    /// alloc.begin_frame();
+    /// let lanes = alloc.frame_alloc_aligned::<[f32; 16], 64>();
+    /// assert_eq!(lanes as usize % 64, 0);
+    /// alloc.end_frame();
+    /// ```
+    pub fn frame_alloc_aligned<T, const A: usize>(&self) -> *mut T {
+        let layout = AlignedLayout::<T, A>::LAYOUT;
+        tls::with_tls(&self.inner, |tls| tls.frame_alloc_layout(layout)) as *mut T
     }
 
     /// Allocate N instances of T with single bookkeeping update.
@@ -222,7 +383,7 @@ impl SmartAlloc {
     /// - Need automatic Drop handling
     /// - Prototyping (optimize later)
     pub fn frame_alloc_batch<T>(&self, count: usize) -> *mut T {
-        tls::with_tls(|tls| tls.frame_alloc_batch::<T>(count))
+        tls::with_tls(&self.inner, |tls| tls.frame_alloc_batch::<T>(count))
     }
 
     /// Allocate 2 instances of T with optimized single allocation.
@@ -267,7 +428,7 @@ impl SmartAlloc {
     /// alloc.end_frame();
     /// ```
     pub fn frame_alloc_2<T>(&self) -> *mut [T; 2] {
-        tls::with_tls(|tls| tls.frame_alloc_2::<T>())
+        tls::with_tls(&self.inner, |tls| tls.frame_alloc_2::<T>())
     }
 
     /// Allocate 4 instances of T with optimized single allocation.
@@ -310,7 +471,7 @@ impl SmartAlloc {
     /// alloc.end_frame();
     /// ```
     pub fn frame_alloc_4<T>(&self) -> *mut [T; 4] {
-        tls::with_tls(|tls| tls.frame_alloc_4::<T>())
+        tls::with_tls(&self.inner, |tls| tls.frame_alloc_4::<T>())
     }
 
     /// Allocate 8 instances of T with optimized single allocation.
@@ -353,15 +514,23 @@ impl SmartAlloc {
     /// alloc.end_frame();
     /// ```
     pub fn frame_alloc_8<T>(&self) -> *mut [T; 8] {
-        tls::with_tls(|tls| tls.frame_alloc_8::<T>())
+        tls::with_tls(&self.inner, |tls| tls.frame_alloc_8::<T>())
     }
 
     /// Allocate a value from the small object pool.
     ///
     /// This is fast O(1) allocation from thread-local pools.
+    /// Types larger than the biggest size class or aligned above 16 bytes
+    /// go to the large-object allocator instead; see `pool_backend`.
     /// The memory must be explicitly freed with `pool_free`.
     pub fn pool_alloc<T>(&self) -> *mut T {
-        tls::with_tls(|tls| tls.pool_alloc::<T>(&self.inner))
+        let ptr = tls::with_tls(&self.inner, |tls| tls.pool_alloc::<T>(&self.inner));
+        if !ptr.is_null() && self.behavior_filter.is_enabled() {
+            let kind = PoolBackend::of::<T>().alloc_kind();
+            let tag = tagged::current_tag().unwrap_or("untagged");
+            self.record_behavior_alloc(ptr as *const u8, tag, kind, std::mem::size_of::<T>());
+        }
+        ptr
     }
 
     /// Free a value back to the small object pool.
@@ -370,7 +539,29 @@ impl SmartAlloc {
     ///
     /// The pointer must have been allocated with `pool_alloc`.
     pub unsafe fn pool_free<T>(&self, ptr: *mut T) {
-        tls::with_tls(|tls| tls.pool_free(ptr, &self.inner));
+        if self.behavior_filter.is_enabled() {
+            let kind = PoolBackend::of::<T>().alloc_kind();
+            let tag = tagged::current_tag().unwrap_or("untagged");
+            self.record_behavior_free(ptr as *const u8, tag, kind, std::mem::size_of::<T>());
+        }
+        tls::with_tls(&self.inner, |tls| tls.pool_free(ptr, &self.inner));
+    }
+
+    /// Get the backend `pool_alloc::<T>()` uses.
+    ///
+    /// Decided at compile time from the size and alignment of T.
+    pub const fn pool_backend<T>() -> PoolBackend {
+        PoolBackend::of::<T>()
+    }
+
+    /// Get statistics for pool allocations served by the large-object path.
+    pub fn large_object_stats(&self) -> LargeObjectStats {
+        self.inner.large_objects().stats()
+    }
+
+    /// Get slab magazine depot statistics per size class.
+    pub fn depot_stats(&self) -> Vec<DepotStats> {
+        self.inner.slabs().depot_stats()
     }
 
     /// Allocate memory from the system heap.
@@ -390,11 +581,71 @@ impl SmartAlloc {
         self.inner.heap_free(ptr);
     }
 
+    /// Get the configuration this allocator was created with.
+    pub fn config(&self) -> &AllocConfig {
+        self.inner.config()
+    }
+
     /// Get current allocation statistics.
     pub fn stats(&self) -> AllocStats {
         self.inner.stats()
     }
 
+    /// Capture a snapshot stamped with the global frame number.
+    ///
+    /// Includes an entry for every registered thread with its name and
+    /// epoch. The calling thread reports live frame usage; others report
+    /// usage at their last `end_frame`. Diagnostics this allocator raised
+    /// since the frame authority's last `begin_frame` are included; ones
+    /// from other allocators or without an `allocator` field are not, but
+    /// can be added from `diagnostics::sinks().recent()`.
+    pub fn snapshot(&self) -> Snapshot {
+        let stats = self.stats();
+        let frame_bytes = tls::with_tls(&self.inner, |tls| tls.frame_head());
+        let current = std::thread::current().id();
+
+        let mut snapshot = Snapshot::new(self.frame_number()).with_summary(SnapshotSummary {
+            frame_bytes,
+            pool_bytes: stats.pool_allocated,
+            heap_bytes: stats.heap_allocated,
+            total_bytes: stats.total_allocated,
+            peak_bytes: stats.peak_allocated,
+        });
+        for thread in self.threads() {
+            let thread_frame_bytes = if thread.id == current {
+                frame_bytes
+            } else {
+                thread.last_frame_bytes
+            };
+            snapshot.add_thread(ThreadSnapshot {
+                id: format!("{:?}", thread.id),
+                name: thread.name,
+                frame_bytes: thread_frame_bytes,
+                // Net bytes go negative on threads that free others' memory
+                pool_bytes: thread.pool_bytes.max(0) as usize,
+                heap_bytes: thread.heap_bytes.max(0) as usize,
+                peak_bytes: thread.peak_frame_bytes.max(thread_frame_bytes),
+                epoch: thread.epoch,
+                budget: None,
+            });
+        }
+        let id = self.id().as_u64();
+        for diag in diagnostics::sinks().recent() {
+            if diag.allocator() == Some(id) {
+                snapshot.add_diagnostic(diag);
+            }
+        }
+        snapshot
+    }
+
+    /// Get this allocator's ID.
+    ///
+    /// Clones share an ID; each `SmartAlloc::new` gets a fresh one, with its
+    /// own frame arena and pools on every thread.
+    pub fn id(&self) -> AllocatorId {
+        self.inner.id()
+    }
+
     /// Get a reference to the global state (for advanced usage).
     pub(crate) fn global(&self) -> &Arc<GlobalState> {
         &self.inner
@@ -421,7 +672,7 @@ impl SmartAlloc {
     ///
     /// Elements are zero-initialized for primitive types.
     pub fn frame_slice<T: Default + Clone>(&self, len: usize) -> Option<FrameSlice<'_, T>> {
-        let ptr = tls::with_tls(|tls| {
+        let ptr = tls::with_tls(&self.inner, |tls| {
             tls.frame_alloc_slice::<T>(len)
         });
         if ptr.is_null() {
@@ -471,6 +722,14 @@ impl SmartAlloc {
         &self.streaming
     }
 
+    /// Create a background load pool for the streaming allocator.
+    ///
+    /// Loads submitted to the pool fill reservations on `workers` threads,
+    /// highest `StreamPriority` first.
+    pub fn stream_loader(&self, workers: usize) -> StreamLoaderPool {
+        StreamLoaderPool::new(Arc::clone(&self.streaming), workers)
+    }
+
     /// Access the handle-based allocator.
     pub fn handles(&self) -> &HandleAllocator {
         &self.handles
@@ -510,24 +769,79 @@ impl SmartAlloc {
     /// alloc.end_frame();
     /// ```
     pub fn begin_phase(&self, name: &'static str) {
-        phases::begin_phase(name);
+        let budgets = self.phase_budgets.clone();
+        tls::with_tls(&self.inner, |tls| tls.phases_mut().begin_phase_with(name, Some(budgets)));
+        if let Some(hooks) = self.profiler_hooks() {
+            hooks.emit_zone_begin(name);
+        }
     }
 
     /// End the current phase.
     pub fn end_phase(&self) -> Option<Phase> {
-        phases::end_phase()
+        let phase = tls::with_tls(&self.inner, |tls| tls.phases_mut().end_phase());
+        if let (Some(_), Some(hooks)) = (&phase, self.profiler_hooks()) {
+            hooks.emit_zone_end();
+        }
+        phase
     }
 
     /// Create a phase scope guard.
     ///
     /// The phase is automatically ended when the guard is dropped.
     pub fn phase_scope(&self, name: &'static str) -> PhaseGuard {
-        PhaseGuard::new(name)
+        PhaseGuard::for_allocator(name, &self.inner, self.profiler_hooks(), self.phase_budgets.clone())
+    }
+
+    /// Limit the frame memory a phase may use per frame on each thread.
+    ///
+    /// Phase budgets are compiled out under `minimal`, where this has no
+    /// effect.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// alloc.set_phase_budget("physics", mb(4), PhaseBudgetPolicy::Diagnostic);
+    /// ```
+    pub fn set_phase_budget(&self, phase: &'static str, limit: usize, policy: PhaseBudgetPolicy) {
+        self.phase_budgets.set(phase, limit, policy);
+    }
+
+    /// Access phase budgets, budget events and high-water marks.
+    pub fn phase_budgets(&self) -> &PhaseBudgets {
+        &self.phase_budgets
+    }
+
+    /// Get the high-water mark recorded for a phase across frames.
+    pub fn phase_high_water(&self, phase: &'static str) -> Option<PhaseHighWater> {
+        self.phase_budgets.high_water(phase)
+    }
+
+    /// Report allocator activity to a profiler.
+    ///
+    /// Phases entered through this allocator become zone begin/end events.
+    /// Frame, pool, heap, streaming and handle allocations are reported
+    /// with their `MemoryPool`; frame allocations are freed in bulk when
+    /// their thread ends the frame. When the frame authority ends a frame,
+    /// plots for arena usage, pool usage, deferred queue depth and budget
+    /// utilization per tag are emitted, followed by a frame mark.
+    ///
+    /// Pass `ProfilerHooks::tracy()` to view all of it in Tracy.
+    pub fn set_profiler_hooks(&self, hooks: ProfilerHooks) {
+        self.inner.profiler().set(hooks);
+    }
+
+    /// Stop reporting to the profiler.
+    pub fn clear_profiler_hooks(&self) {
+        self.inner.profiler().clear();
+    }
+
+    fn profiler_hooks(&self) -> Option<Arc<ProfilerHooks>> {
+        self.inner.profiler().hooks()
     }
 
     /// Get the current phase name.
     pub fn current_phase(&self) -> Option<&'static str> {
-        phases::current_phase()
+        tls::with_tls(&self.inner, |tls| tls.phases().current_phase())
     }
 
     // ==================== Frame Checkpoints (v0.2.0) ====================
@@ -546,8 +860,7 @@ impl SmartAlloc {
     /// }
     /// ```
     pub fn frame_checkpoint(&self) -> FrameCheckpoint {
-        let head = tls::with_tls(|tls| tls.frame_head());
-        FrameCheckpoint::new(head, self.frame_number())
+        tls::with_tls(&self.inner, |tls| FrameCheckpoint::new(tls.frame_mark(), tls.global_frame(), tls.epoch()))
     }
 
     /// Rollback to a previously saved checkpoint.
@@ -555,11 +868,55 @@ impl SmartAlloc {
     /// All allocations made after the checkpoint are invalidated.
     pub fn rollback_to(&self, checkpoint: FrameCheckpoint) {
         debug_assert_eq!(
-            checkpoint.frame_id(),
-            self.frame_number(),
+            checkpoint.epoch(),
+            self.thread_epoch(),
             "Cannot rollback to checkpoint from different frame"
         );
-        tls::with_tls(|tls| tls.reset_frame_to(checkpoint.head()));
+        tls::with_tls(&self.inner, |tls| tls.reset_frame_to(checkpoint.mark()));
+    }
+
+    /// Run `f` in a nested frame scope.
+    ///
+    /// Allocations made through the `SubFrame` are bounded by the closure
+    /// and the arena head is restored when it returns. Scopes nest via
+    /// `SubFrame::sub_frame`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a sub-frame of this allocator is already active on the
+    /// calling thread; nest through the outer `SubFrame` instead.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let path_len = alloc.sub_frame(|s| {
+    ///     let open = s.alloc_slice::<NodeId>(1024)?;
+    ///     search(open)
+    /// });
+    /// ```
+    pub fn sub_frame<R, F>(&self, f: F) -> R
+    where
+        F: for<'scope> FnOnce(&mut SubFrame<'scope>) -> R,
+    {
+        // Nesting here would let the outer scope allocate above this one
+        assert_eq!(self.sub_frame_depth(), 0, "nested sub-frames must be entered through SubFrame::sub_frame");
+        SubFrame::enter(&self.inner, f)
+    }
+
+    /// Get the calling thread's current sub-frame nesting depth.
+    pub fn sub_frame_depth(&self) -> usize {
+        tls::with_tls(&self.inner, |tls| tls.sub_frames().depth())
+    }
+
+    /// Get the deepest sub-frame nesting the calling thread reached this frame.
+    pub fn max_sub_frame_depth(&self) -> usize {
+        tls::with_tls(&self.inner, |tls| tls.sub_frames().max_depth())
+    }
+
+    /// Get the sub-frame scopes the calling thread completed this frame,
+    /// innermost first.
+    pub fn completed_sub_frames(&self) -> Vec<SubFrameRecord> {
+        tls::with_tls(&self.inner, |tls| tls.sub_frames().completed().to_vec())
     }
 
     /// Create a checkpoint guard for automatic rollback.
@@ -601,7 +958,7 @@ impl SmartAlloc {
     /// list.push(entity2);
     /// ```
     pub fn frame_vec<T>(&self, capacity: usize) -> Option<FrameVec<'_, T>> {
-        let ptr = tls::with_tls(|tls| tls.frame_alloc_slice::<T>(capacity));
+        let ptr = tls::with_tls(&self.inner, |tls| tls.frame_alloc_slice::<T>(capacity));
         unsafe { FrameVec::from_raw_parts(ptr, capacity) }
     }
 
@@ -609,8 +966,8 @@ impl SmartAlloc {
     ///
     /// Simple open-addressing map for frame-temporary lookups.
     pub fn frame_map<K: Eq + std::hash::Hash, V>(&self, capacity: usize) -> Option<FrameMap<'_, K, V>> {
-        let keys = tls::with_tls(|tls| tls.frame_alloc_slice::<Option<K>>(capacity));
-        let values = tls::with_tls(|tls| tls.frame_alloc_slice::<V>(capacity));
+        let keys = tls::with_tls(&self.inner, |tls| tls.frame_alloc_slice::<Option<K>>(capacity));
+        let values = tls::with_tls(&self.inner, |tls| tls.frame_alloc_slice::<V>(capacity));
         unsafe { FrameMap::from_raw_parts(keys, values, capacity) }
     }
 
@@ -664,11 +1021,28 @@ impl SmartAlloc {
     /// let buf = pool.alloc::<[Node; 1024]>();
     /// // ...
     /// pool.reset(); // Clear when done
+    ///
+    /// // Runtime names, reset when the level's group is freed
+    /// let level = alloc.groups().create_group("level_3");
+    /// alloc.scratch_pool(format!("level_{}/navmesh", 3)).bind_to_group(level);
     /// ```
-    pub fn scratch_pool(&self, name: &'static str) -> crate::api::scratch::ScratchPoolHandle<'_> {
+    pub fn scratch_pool(
+        &self,
+        name: impl Into<crate::api::scratch::ScratchKey>,
+    ) -> crate::api::scratch::ScratchPoolHandle<'_> {
         self.scratch.get_or_create(name)
     }
 
+    /// Check a scratch pool out for lock-free allocation on this thread.
+    ///
+    /// Returns None if another guard already holds the pool.
+    pub fn scratch_checkout(
+        &self,
+        name: impl Into<crate::api::scratch::ScratchKey>,
+    ) -> Option<crate::api::scratch::ScratchGuard<'_>> {
+        self.scratch.checkout(name)
+    }
+
     // ==================== Frame Retention (v0.3.0) ====================
 
     /// Allocate with a retention policy for post-frame survival.
@@ -691,7 +1065,7 @@ impl SmartAlloc {
     /// ```
     pub fn frame_retained<T>(&self, policy: RetentionPolicy) -> FrameRetained<'_, T> {
         // Allocate from frame arena
-        let ptr = tls::with_tls(|tls| tls.frame_alloc::<T>());
+        let ptr = tls::with_tls(&self.inner, |tls| tls.frame_alloc::<T>());
         
         // If policy is Discard, just return the handle without registering
         if !policy.promotes() {
@@ -704,6 +1078,8 @@ impl SmartAlloc {
             policy,
             size: std::mem::size_of::<T>(),
             tag,
+            phase: self.current_phase(),
+            epoch: self.thread_epoch(),
             type_name: std::any::type_name::<T>(),
         };
         
@@ -718,7 +1094,7 @@ impl SmartAlloc {
             }),
         };
         
-        let id = retention::register_retained(alloc);
+        let id = tls::with_tls(&self.inner, |tls| tls.retained_mut().register(alloc));
         FrameRetained::new(ptr, id)
     }
 
@@ -764,16 +1140,17 @@ impl SmartAlloc {
     /// ```
     pub fn end_frame_with_promotions(&self) -> PromotionResult {
         // Take all retained allocations
-        let retained = retention::take_retained();
+        let retained = tls::with_tls(&self.inner, |tls| tls.retained_mut().take_all());
         
         // Set up the promotion processor with allocator callbacks
         let inner = self.inner.clone();
         let scratch = self.scratch.clone();
         
         let processor = PromotionProcessor::new()
+            .with_epoch(self.thread_epoch())
             .with_pool_alloc(move |layout: Layout| {
                 // Use pool allocator
-                tls::with_tls(|tls| {
+                tls::with_tls(&self.inner, |tls| {
                     tls.pool_alloc_layout(layout, &inner)
                 })
             })
@@ -787,13 +1164,16 @@ impl SmartAlloc {
             });
         
         // Process promotions
-        let result = processor.process(retained);
+        let mut result = processor.process(retained);
+        let phases = tls::with_tls(&self.inner, |tls| tls.phases().frame_phases());
+        result.summary.record_phases(&phases);
         
         // Now do normal frame end
-        phases::reset_phases();
-        tls::with_tls(|tls| {
+        let deferred = tls::with_tls(&self.inner, |tls| {
             tls.end_frame();
+            tls.deferred_len()
         });
+        self.profile_frame_end(deferred);
         
         result
     }
@@ -808,7 +1188,7 @@ impl SmartAlloc {
 
     /// Get count of pending retained allocations.
     pub fn retained_count(&self) -> usize {
-        retention::retained_count()
+        tls::with_tls(&self.inner, |tls| tls.retained_mut().len())
     }
 
     /// Clear retained allocations without processing.
@@ -816,7 +1196,7 @@ impl SmartAlloc {
     /// Use this if you want to abandon retained allocations
     /// instead of promoting them.
     pub fn clear_retained(&self) {
-        retention::clear_retained();
+        tls::with_tls(&self.inner, |tls| tls.retained_mut().clear());
     }
 
     // ==================== Behavior Filter (v0.4.0) ====================
@@ -886,6 +1266,37 @@ impl SmartAlloc {
         self.behavior_filter.analyze()
     }
 
+    /// Record log2 histograms of allocation sizes, lifetimes and frame
+    /// arena high-water marks.
+    ///
+    /// Works with or without the behavior filter. Histograms appear in
+    /// `behavior_report()` and `allocation_histograms()`, and can pick slab
+    /// size classes and arena sizes from evidence:
+    ///
+    /// ```rust,ignore
+    /// alloc.enable_allocation_histograms();
+    /// // ... run representative frames ...
+    /// let histograms = alloc.allocation_histograms();
+    /// let config = AllocConfig {
+    ///     slab_size_classes: histograms.suggested_slab_size_classes(8),
+    ///     ..AllocConfig::default()
+    /// }
+    /// .with_frame_arena_size(histograms.suggested_frame_arena_size().unwrap_or(16 << 20));
+    /// ```
+    pub fn enable_allocation_histograms(&self) {
+        self.behavior_filter.enable_histograms();
+    }
+
+    /// Stop recording allocation histograms, keeping what was recorded.
+    pub fn disable_allocation_histograms(&self) {
+        self.behavior_filter.disable_histograms();
+    }
+
+    /// Get the allocation histograms recorded so far.
+    pub fn allocation_histograms(&self) -> AllocationHistograms {
+        self.behavior_filter.histograms()
+    }
+
     /// Reset behavior tracking statistics.
     pub fn reset_behavior_stats(&self) {
         self.behavior_filter.reset();
@@ -43,27 +43,23 @@ use crate::core::tls;
 /// ```
 #[derive(Clone)]
 pub struct FrameAllocator {
+    global: Arc<GlobalState>,
     _marker: std::marker::PhantomData<*const ()>,
 }
 
 impl FrameAllocator {
-    /// Create a new frame allocator.
-    pub fn new() -> Self {
+    /// Create a frame allocator over `global`'s frame arena.
+    pub fn new(global: Arc<GlobalState>) -> Self {
         Self {
+            global,
             _marker: std::marker::PhantomData,
         }
     }
 }
 
-impl Default for FrameAllocator {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 unsafe impl Allocator for FrameAllocator {
     fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
-        let ptr = tls::with_tls(|tls| {
+        let ptr = tls::with_tls(&self.global, |tls| {
             tls.frame_alloc_layout(layout)
         });
 
@@ -93,7 +89,7 @@ impl PoolAllocator {
 
 unsafe impl Allocator for PoolAllocator {
     fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
-        let ptr = tls::with_tls(|tls| {
+        let ptr = tls::with_tls(&self.global, |tls| {
             tls.pool_alloc_layout(layout, &self.global)
         });
 
@@ -103,7 +99,7 @@ unsafe impl Allocator for PoolAllocator {
     }
 
     unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
-        tls::with_tls(|tls| {
+        tls::with_tls(&self.global, |tls| {
             tls.pool_free_layout(ptr.as_ptr(), layout, &self.global);
         });
     }
@@ -6,30 +6,52 @@
 
 use std::marker::PhantomData;
 
+use crate::core::tls::FrameMark;
+
 /// A checkpoint representing a saved position in the frame arena.
 ///
 /// Checkpoints can be used to rollback speculative allocations.
-/// They are zero-cost when not used - just a usize.
+/// They are zero-cost when not used - just the arena head and a count
+/// of heap overflow blocks.
+///
+/// A checkpoint is only valid within the thread epoch it was taken in,
+/// since the thread's arena is reset between epochs.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct FrameCheckpoint {
-    /// The saved head position
-    head: usize,
-    /// Frame number when checkpoint was created (for validation)
+    /// The saved frame position
+    mark: FrameMark,
+    /// Global frame number when checkpoint was created
     frame_id: u64,
+    /// Thread epoch when checkpoint was created (for validation)
+    epoch: u64,
 }
 
 impl FrameCheckpoint {
     /// Create a new checkpoint at the given position.
-    pub(crate) fn new(head: usize, frame_id: u64) -> Self {
-        Self { head, frame_id }
+    pub(crate) fn new(mark: FrameMark, frame_id: u64, epoch: u64) -> Self {
+        Self {
+            mark,
+            frame_id,
+            epoch,
+        }
+    }
+
+    /// Get the thread epoch when this checkpoint was created.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
     }
 
     /// Get the saved head position.
     pub fn head(&self) -> usize {
-        self.head
+        self.mark.head()
+    }
+
+    /// Get the saved frame position.
+    pub(crate) fn mark(&self) -> FrameMark {
+        self.mark
     }
 
-    /// Get the frame ID when this checkpoint was created.
+    /// Get the global frame ID when this checkpoint was created.
     pub fn frame_id(&self) -> u64 {
         self.frame_id
     }
@@ -125,9 +147,10 @@ mod tests {
 
     #[test]
     fn test_checkpoint_creation() {
-        let cp = FrameCheckpoint::new(1024, 42);
+        let cp = FrameCheckpoint::new(FrameMark::new(1024, 0), 42, 7);
         assert_eq!(cp.head(), 1024);
         assert_eq!(cp.frame_id(), 42);
+        assert_eq!(cp.epoch(), 7);
     }
 
     #[test]
@@ -1,6 +1,81 @@
 //! Allocator configuration.
+//!
+//! Configs are built in code, or loaded from TOML or JSON files whose keys
+//! match the `AllocConfig` field names, with nested configs as tables:
+//!
+//! ```toml
+//! frame_arena_size = "32MiB"
+//! slab_size_classes = [16, 32, 64, 128, 256, 512, 1024, 2048, 4096]
+//! enable_budgets = true
+//! global_memory_limit = "1GiB"
+//!
+//! [tag_budgets.ai]
+//! soft_limit = "8MiB"
+//! hard_limit = "16MiB"
+//!
+//! [thread_budgets]
+//! frame_budget = "16MiB"
+//! frame_exceeded_policy = "fail"
+//!
+//! [deferred]
+//! mode = "incremental"
+//! per_alloc = 32
+//!
+//! [snapshot]
+//! directory = "target/framealloc"
+//! min_interval = "250ms"
+//! ```
+//!
+//! Sizes take a byte count or a string such as `"64KiB"` or `"1.5GB"`
+//! (units are powers of 1024), durations a millisecond count or a string
+//! such as `"250ms"`. Fields missing from a file keep their default, and
+//! `FRAMEALLOC_*` environment variables override any field (see
+//! `AllocConfig::from_env`). Loaded configs are validated.
+//!
+//! `SmartAlloc::new` applies the allocator fields and tag budgets. The
+//! `thread_budgets`, `deferred` and `snapshot` sections configure helpers
+//! the application owns, so hand them over when creating those:
+//!
+//! ```rust,ignore
+//! let config = AllocConfig::from_env()?;
+//! let alloc = SmartAlloc::new(config.clone());
+//! let budgets = ThreadBudgetManager::new();
+//! if let Some(threads) = config.thread_budgets {
+//!     budgets.set_default_config(threads);
+//! }
+//! let deferred = DeferredController::new(config.deferred);
+//! let snapshots = SnapshotEmitter::new(config.snapshot);
+//! ```
 
-use crate::util::size::{kb, mb};
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::allocators::slab::{effective_size_classes, slab_class_align, underaligned_class, NUM_SIZE_CLASSES, SLAB_ALIGN};
+use crate::api::deferred_control::{DeferredConfig, DeferredProcessing, QueueFullPolicy};
+use crate::api::snapshot::SnapshotConfig;
+use crate::api::thread_budget::{BudgetExceededPolicy, ThreadBudgetConfig};
+use crate::sync::mutex::Mutex;
+use crate::util::json::{escape_json_str, JsonValue};
+use crate::util::size::{format_bytes, format_size_exact, kb, mb, parse_size};
+use crate::util::toml::{self, TomlValue};
+
+/// Environment variable naming the config file `from_env` loads.
+pub const CONFIG_ENV_VAR: &str = "FRAMEALLOC_CONFIG";
+
+/// Prefix of environment variables that override config fields.
+const ENV_PREFIX: &str = "FRAMEALLOC_";
+
+/// `FRAMEALLOC_*` variables that are not config overrides.
+const OTHER_ENV_VARS: [&str; 2] = [CONFIG_ENV_VAR, "FRAMEALLOC_STRICT"];
+
+/// Frees per allocation for `mode = "incremental"` without `per_alloc`.
+const DEFAULT_PER_ALLOC: usize = 16;
 
 /// Configuration for the smart allocator.
 #[derive(Debug, Clone)]
@@ -8,23 +83,67 @@ pub struct AllocConfig {
     /// Size of the frame arena per thread (default: 16 MB)
     pub frame_arena_size: usize,
 
-    /// Size classes for the slab allocator
+    /// Size classes for the slab allocator, strictly ascending (a short list
+    /// is extended with the default classes above its last entry). Each
+    /// default class must be served by a class aligned at least as strictly.
     pub slab_size_classes: Vec<usize>,
 
     /// Number of pages to pre-allocate per size class
+    ///
+    /// Not read yet; `SmartAlloc` allocates slab pages on demand.
     pub slab_pages_per_class: usize,
 
     /// Page size for slab allocator (default: 64 KB)
     pub slab_page_size: usize,
 
+    /// Objects per magazine for each size class, in class order
+    /// (missing or 0 = derived from the class size)
+    pub slab_batch_sizes: Vec<usize>,
+
+    /// Keep NUMA-node-local slab pages and depots (Linux only, default: false)
+    pub numa_local_slabs: bool,
+
     /// Enable memory budgeting
     pub enable_budgets: bool,
 
     /// Global memory limit (0 = unlimited)
     pub global_memory_limit: usize,
 
+    /// Per-tag budgets registered when budgets are enabled
+    pub tag_budgets: Vec<TagBudgetConfig>,
+
+    /// Default per-thread budgets for a `ThreadBudgetManager` (None = unused)
+    ///
+    /// `SmartAlloc` does not read this; pass it to
+    /// `ThreadBudgetManager::set_default_config`.
+    pub thread_budgets: Option<ThreadBudgetConfig>,
+
+    /// Cross-thread free processing for a `DeferredController`
+    ///
+    /// `SmartAlloc` does not read this; pass it to `DeferredController::new`.
+    pub deferred: DeferredConfig,
+
+    /// Snapshot emission for a `SnapshotEmitter`
+    ///
+    /// `SmartAlloc` does not read this; pass it to `SnapshotEmitter::new`.
+    pub snapshot: SnapshotConfig,
+
     /// Enable debug features (memory poisoning, etc.)
     pub debug_mode: bool,
+
+    /// Back the streaming budget with one pre-reserved region (default: false)
+    pub streaming_region: bool,
+}
+
+/// Budget for one allocation tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TagBudgetConfig {
+    /// Tag name
+    pub tag: &'static str,
+    /// Usage that triggers a warning
+    pub soft_limit: usize,
+    /// Usage allocations may not exceed
+    pub hard_limit: usize,
 }
 
 impl Default for AllocConfig {
@@ -34,9 +153,16 @@ impl Default for AllocConfig {
             slab_size_classes: vec![16, 32, 64, 128, 256, 512, 1024, 2048, 4096],
             slab_pages_per_class: 4,
             slab_page_size: kb(64),
+            slab_batch_sizes: Vec::new(),
+            numa_local_slabs: false,
             enable_budgets: false,
             global_memory_limit: 0,
+            tag_budgets: Vec::new(),
+            thread_budgets: None,
+            deferred: DeferredConfig::default(),
+            snapshot: SnapshotConfig::default(),
             debug_mode: cfg!(feature = "debug"),
+            streaming_region: false,
         }
     }
 }
@@ -49,9 +175,8 @@ impl AllocConfig {
             slab_size_classes: vec![32, 128, 512, 2048],
             slab_pages_per_class: 1,
             slab_page_size: kb(16),
-            enable_budgets: false,
-            global_memory_limit: 0,
             debug_mode: false,
+            ..Self::default()
         }
     }
 
@@ -59,12 +184,11 @@ impl AllocConfig {
     pub fn high_performance() -> Self {
         Self {
             frame_arena_size: mb(64),
-            slab_size_classes: vec![16, 32, 64, 128, 256, 512, 1024, 2048, 4096, 8192],
+            slab_size_classes: vec![16, 32, 64, 128, 256, 512, 1024, 2048, 4096],
             slab_pages_per_class: 8,
             slab_page_size: kb(256),
-            enable_budgets: false,
-            global_memory_limit: 0,
             debug_mode: false,
+            ..Self::default()
         }
     }
 
@@ -80,6 +204,18 @@ impl AllocConfig {
         self
     }
 
+    /// Builder pattern: set magazine sizes per slab size class.
+    pub fn with_slab_batch_sizes(mut self, sizes: Vec<usize>) -> Self {
+        self.slab_batch_sizes = sizes;
+        self
+    }
+
+    /// Builder pattern: keep slab pages local to each NUMA node.
+    pub fn with_numa_local_slabs(mut self, enable: bool) -> Self {
+        self.numa_local_slabs = enable;
+        self
+    }
+
     /// Builder pattern: enable budgets.
     pub fn with_budgets(mut self, enable: bool) -> Self {
         self.enable_budgets = enable;
@@ -92,9 +228,989 @@ impl AllocConfig {
         self
     }
 
+    /// Builder pattern: add a tag budget.
+    pub fn with_tag_budget(mut self, tag: &'static str, soft_limit: usize, hard_limit: usize) -> Self {
+        self.tag_budgets.retain(|b| b.tag != tag);
+        self.tag_budgets.push(TagBudgetConfig { tag, soft_limit, hard_limit });
+        self
+    }
+
+    /// Builder pattern: set default per-thread budgets.
+    pub fn with_thread_budgets(mut self, config: ThreadBudgetConfig) -> Self {
+        self.thread_budgets = Some(config);
+        self
+    }
+
+    /// Builder pattern: set deferred free processing.
+    pub fn with_deferred(mut self, config: DeferredConfig) -> Self {
+        self.deferred = config;
+        self
+    }
+
+    /// Builder pattern: set snapshot emission.
+    pub fn with_snapshot(mut self, config: SnapshotConfig) -> Self {
+        self.snapshot = config;
+        self
+    }
+
     /// Builder pattern: enable debug mode.
     pub fn with_debug(mut self, enable: bool) -> Self {
         self.debug_mode = enable;
         self
     }
+
+    /// Builder pattern: back streaming with a single pre-reserved region.
+    pub fn with_streaming_region(mut self, enable: bool) -> Self {
+        self.streaming_region = enable;
+        self
+    }
+}
+
+/// Format of a config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Toml,
+    Json,
+}
+
+impl ConfigFormat {
+    /// Pick the format from a path's extension: `.json` or TOML.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Self::Json,
+            _ => Self::Toml,
+        }
+    }
+}
+
+/// Why a config could not be loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The file is not valid TOML or JSON.
+    Parse {
+        /// 1-based line of the syntax error
+        line: usize,
+        /// What was wrong
+        message: String,
+    },
+    /// The file or environment sets a field `AllocConfig` does not have.
+    UnknownField(String),
+    /// A field has a value of the wrong type.
+    InvalidValue {
+        /// Field name, or environment variable name
+        field: String,
+        /// What the field takes
+        expected: &'static str,
+    },
+    /// Fields have values that contradict each other or the allocator.
+    Invalid {
+        /// Field name
+        field: String,
+        /// What is wrong with it
+        reason: String,
+    },
+    /// The file could not be read.
+    Io {
+        /// Kind of the underlying IO error
+        kind: io::ErrorKind,
+        /// Rendered error message
+        message: String,
+    },
+}
+
+impl From<io::Error> for ConfigError {
+    fn from(err: io::Error) -> Self {
+        Self::Io {
+            kind: err.kind(),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse { line, message } => write!(f, "invalid config at line {}: {}", line, message),
+            Self::UnknownField(field) => write!(f, "unknown config field '{}'", field),
+            Self::InvalidValue { field, expected } => {
+                write!(f, "config field '{}' must be {}", field, expected)
+            }
+            Self::Invalid { field, reason } => write!(f, "invalid config field '{}': {}", field, reason),
+            Self::Io { message, .. } => write!(f, "io error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl AllocConfig {
+    /// Load and validate a config file, picking the format from its extension.
+    ///
+    /// Intended for startup, e.g. loading a config recommended by
+    /// `ConfigTuner` from an earlier run.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        Self::parse(&fs::read_to_string(path)?, ConfigFormat::from_path(path))
+    }
+
+    /// Parse and validate a config in `format`.
+    pub fn parse(text: &str, format: ConfigFormat) -> Result<Self, ConfigError> {
+        match format {
+            ConfigFormat::Toml => Self::from_toml(text),
+            ConfigFormat::Json => Self::from_json(text),
+        }
+    }
+
+    /// Parse and validate a TOML config.
+    pub fn from_toml(text: &str) -> Result<Self, ConfigError> {
+        let entries = toml::parse(text).map_err(|e| ConfigError::Parse {
+            line: e.line,
+            message: e.message.to_string(),
+        })?;
+        let mut config = Self::default();
+        config.apply_entries(entries)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Parse and validate a JSON config.
+    ///
+    /// A top-level `"justification"` object, as written by `ConfigTuner`,
+    /// is ignored.
+    pub fn from_json(text: &str) -> Result<Self, ConfigError> {
+        let root = JsonValue::parse(text).map_err(|e| ConfigError::Parse {
+            line: text[..e.offset.min(text.len())].matches('\n').count() + 1,
+            message: e.message.to_string(),
+        })?;
+        let Some(fields) = root.as_object() else {
+            return Err(ConfigError::Parse { line: 1, message: "expected an object".to_string() });
+        };
+
+        let mut entries = Vec::new();
+        for (key, value) in fields {
+            if key != "justification" {
+                flatten_json(key.clone(), value, &mut entries)?;
+            }
+        }
+        let mut config = Self::default();
+        config.apply_entries(entries)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Load the config for this process from the environment.
+    ///
+    /// Starts from the file named by `FRAMEALLOC_CONFIG` (or the defaults),
+    /// applies `FRAMEALLOC_*` overrides and validates the result. Overrides
+    /// are named after the field's path in a config file, upper-cased with
+    /// dots as underscores:
+    ///
+    /// ```text
+    /// FRAMEALLOC_FRAME_ARENA_SIZE=32MiB
+    /// FRAMEALLOC_SLAB_SIZE_CLASSES=[16, 32, 64, 128]
+    /// FRAMEALLOC_DEFERRED_MODE=at_frame_end
+    /// FRAMEALLOC_TAG_BUDGETS_AI_HARD_LIMIT=16MiB   # tag "ai"
+    /// ```
+    ///
+    /// Unknown `FRAMEALLOC_*` variables are rejected so typos do not go
+    /// unnoticed; `FRAMEALLOC_STRICT` is read by `strict::init_from_env`.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let mut config = match std::env::var_os(CONFIG_ENV_VAR) {
+            Some(path) => Self::from_file(PathBuf::from(path))?,
+            None => Self::default(),
+        };
+        config.apply_env_vars(std::env::vars_os())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Apply `FRAMEALLOC_*` environment overrides and validate the result.
+    pub fn apply_env(&mut self) -> Result<(), ConfigError> {
+        self.apply_env_vars(std::env::vars_os())?;
+        self.validate()
+    }
+
+    /// Apply overrides from `(name, value)` pairs, ignoring other variables.
+    ///
+    /// Only `FRAMEALLOC_*` variables must be Unicode; others are skipped
+    /// whatever their contents.
+    pub(crate) fn apply_env_vars(&mut self, vars: impl IntoIterator<Item = (OsString, OsString)>) -> Result<(), ConfigError> {
+        let mut overrides = Vec::new();
+        for (name, raw) in vars {
+            let name = name.to_string_lossy().into_owned();
+            let Some(rest) = name.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            if OTHER_ENV_VARS.contains(&name.as_str()) {
+                continue;
+            }
+            let key = env_key(&rest.to_ascii_lowercase()).ok_or_else(|| ConfigError::UnknownField(name.clone()))?;
+            let Some(raw) = raw.to_str() else {
+                return Err(ConfigError::InvalidValue {
+                    field: name,
+                    expected: "a UTF-8 value",
+                });
+            };
+            overrides.push((name, key, env_value(raw)));
+        }
+
+        sort_for_apply(&mut overrides, |(_, key, _)| key);
+        for (name, key, value) in &overrides {
+            self.set_field(key, value).map_err(|err| match err {
+                ConfigError::InvalidValue { expected, .. } => ConfigError::InvalidValue { field: name.clone(), expected },
+                other => other,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Check the config for contradictions the allocator cannot honor.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |field: &str, reason: String| {
+            Err(ConfigError::Invalid {
+                field: field.to_string(),
+                reason,
+            })
+        };
+
+        if self.frame_arena_size == 0 {
+            return invalid("frame_arena_size", "must be greater than zero".to_string());
+        }
+
+        let classes = &self.slab_size_classes;
+        if classes.len() > NUM_SIZE_CLASSES {
+            return invalid(
+                "slab_size_classes",
+                format!("{} classes given, at most {} are supported", classes.len(), NUM_SIZE_CLASSES),
+            );
+        }
+        if let Some(&class) = classes.iter().find(|&&c| c == 0 || c % SLAB_ALIGN != 0) {
+            return invalid(
+                "slab_size_classes",
+                format!("class {} is not a non-zero multiple of the {}-byte slab alignment", class, SLAB_ALIGN),
+            );
+        }
+        if let Some(pair) = classes.windows(2).find(|w| w[0] >= w[1]) {
+            return invalid(
+                "slab_size_classes",
+                format!("classes must be strictly ascending, found {} after {}", pair[1], pair[0]),
+            );
+        }
+        if self.slab_batch_sizes.len() > NUM_SIZE_CLASSES {
+            return invalid(
+                "slab_batch_sizes",
+                format!("{} sizes given, at most {} are supported", self.slab_batch_sizes.len(), NUM_SIZE_CLASSES),
+            );
+        }
+
+        let effective = effective_size_classes(self);
+        if let Some((class, served)) = underaligned_class(&effective) {
+            return invalid(
+                "slab_size_classes",
+                format!(
+                    "{}-byte objects would be served from the {}-byte class, which is only {}-byte aligned",
+                    class,
+                    served,
+                    slab_class_align(served)
+                ),
+            );
+        }
+
+        for class in effective {
+            if self.slab_page_size < class {
+                return invalid(
+                    "slab_page_size",
+                    format!("{} is smaller than the {}-byte size class", format_bytes(self.slab_page_size), class),
+                );
+            }
+            let align = slab_class_align(class);
+            if self.slab_page_size % align != 0 {
+                return invalid(
+                    "slab_page_size",
+                    format!(
+                        "{} bytes is not a multiple of the {}-byte alignment of the {}-byte size class",
+                        self.slab_page_size, align, class
+                    ),
+                );
+            }
+        }
+
+        if !self.enable_budgets && self.global_memory_limit > 0 {
+            return invalid("global_memory_limit", "requires enable_budgets = true".to_string());
+        }
+        if !self.enable_budgets && !self.tag_budgets.is_empty() {
+            return invalid("tag_budgets", "requires enable_budgets = true".to_string());
+        }
+        for budget in &self.tag_budgets {
+            let field = format!("tag_budgets.{}", budget.tag);
+            if budget.hard_limit == 0 {
+                return invalid(&field, "hard_limit must be greater than zero".to_string());
+            }
+            if budget.soft_limit > budget.hard_limit {
+                return invalid(
+                    &field,
+                    format!(
+                        "soft_limit {} exceeds hard_limit {}",
+                        format_bytes(budget.soft_limit),
+                        format_bytes(budget.hard_limit)
+                    ),
+                );
+            }
+        }
+
+        if let Some(threads) = &self.thread_budgets {
+            if threads.warning_threshold > 100 {
+                return invalid(
+                    "thread_budgets.warning_threshold",
+                    format!("{}% is over 100%", threads.warning_threshold),
+                );
+            }
+        }
+
+        let deferred = &self.deferred;
+        if deferred.mode == (DeferredProcessing::Incremental { per_alloc: 0 }) {
+            return invalid("deferred.per_alloc", "must be greater than zero".to_string());
+        }
+        if deferred.capacity == 0 && deferred.full_policy != QueueFullPolicy::Grow {
+            return invalid(
+                "deferred.full_policy",
+                format!("\"{}\" needs a bounded capacity", queue_policy_name(deferred.full_policy)),
+            );
+        }
+        if deferred.capacity > 0 && deferred.warning_threshold > deferred.capacity {
+            return invalid(
+                "deferred.warning_threshold",
+                format!("{} exceeds the capacity of {}", deferred.warning_threshold, deferred.capacity),
+            );
+        }
+
+        if self.snapshot.max_snapshots == 0 {
+            return invalid("snapshot.max_snapshots", "must be greater than zero".to_string());
+        }
+        Ok(())
+    }
+
+    /// Write the config as TOML.
+    pub fn to_toml(&self) -> String {
+        self.write_toml(|_| None)
+    }
+
+    /// Write the config as JSON.
+    pub fn to_json(&self) -> String {
+        self.write_json(Vec::new())
+    }
+
+    /// Write TOML, putting `comment(field)` above each field that has one.
+    pub(crate) fn write_toml(&self, comment: impl Fn(&str) -> Option<String>) -> String {
+        let mut out = String::new();
+        let mut section = String::new();
+        for (key, value) in self.fields() {
+            let (table, leaf) = match key.rfind('.') {
+                Some(dot) => (&key[..dot], &key[dot + 1..]),
+                None => ("", key.as_str()),
+            };
+            if table != section {
+                out.push_str(&format!("\n[{}]\n", table));
+                if let Some(comment) = comment(table) {
+                    out.push_str(&format!("# {}\n", comment));
+                }
+                section = table.to_string();
+            }
+            if let Some(comment) = comment(&key) {
+                out.push_str(&format!("# {}\n", comment));
+            }
+            out.push_str(&format!("{} = {}\n", leaf, value));
+        }
+        out
+    }
+
+    /// Write JSON, appending `extra` top-level `(key, JSON value)` entries.
+    pub(crate) fn write_json(&self, extra: Vec<(String, String)>) -> String {
+        let fields = self.fields();
+        let entries: Vec<(Vec<&str>, &str)> = fields
+            .iter()
+            .chain(&extra)
+            .map(|(key, value)| (key.split('.').collect(), value.as_str()))
+            .collect();
+        format!("{}\n", json_object(&entries, 0))
+    }
+
+    /// Get every field by its dotted path, as a value literal valid in both
+    /// TOML and JSON. Fields of one table are contiguous.
+    pub(crate) fn fields(&self) -> Vec<(String, String)> {
+        let mut fields = Vec::new();
+        let mut push = |key: String, value: String| fields.push((key, value));
+        let list = |values: &[usize]| {
+            let items: Vec<_> = values.iter().map(usize::to_string).collect();
+            format!("[{}]", items.join(", "))
+        };
+
+        push("frame_arena_size".into(), size_literal(self.frame_arena_size));
+        push("slab_size_classes".into(), list(&self.slab_size_classes));
+        push("slab_pages_per_class".into(), self.slab_pages_per_class.to_string());
+        push("slab_page_size".into(), size_literal(self.slab_page_size));
+        push("slab_batch_sizes".into(), list(&self.slab_batch_sizes));
+        push("numa_local_slabs".into(), self.numa_local_slabs.to_string());
+        push("enable_budgets".into(), self.enable_budgets.to_string());
+        push("global_memory_limit".into(), size_literal(self.global_memory_limit));
+        push("debug_mode".into(), self.debug_mode.to_string());
+        push("streaming_region".into(), self.streaming_region.to_string());
+
+        for budget in &self.tag_budgets {
+            push(format!("tag_budgets.{}.soft_limit", budget.tag), size_literal(budget.soft_limit));
+            push(format!("tag_budgets.{}.hard_limit", budget.tag), size_literal(budget.hard_limit));
+        }
+
+        if let Some(threads) = &self.thread_budgets {
+            push("thread_budgets.frame_budget".into(), size_literal(threads.frame_budget));
+            push("thread_budgets.pool_budget".into(), size_literal(threads.pool_budget));
+            push(
+                "thread_budgets.frame_exceeded_policy".into(),
+                string_literal(budget_policy_name(threads.frame_exceeded_policy)),
+            );
+            push(
+                "thread_budgets.pool_exceeded_policy".into(),
+                string_literal(budget_policy_name(threads.pool_exceeded_policy)),
+            );
+            push("thread_budgets.warning_threshold".into(), threads.warning_threshold.to_string());
+        }
+
+        let deferred = &self.deferred;
+        push("deferred.mode".into(), string_literal(deferred_mode_name(deferred.mode)));
+        if let DeferredProcessing::Incremental { per_alloc } = deferred.mode {
+            push("deferred.per_alloc".into(), per_alloc.to_string());
+        }
+        push("deferred.capacity".into(), deferred.capacity.to_string());
+        push("deferred.full_policy".into(), string_literal(queue_policy_name(deferred.full_policy)));
+        push("deferred.warning_threshold".into(), deferred.warning_threshold.to_string());
+
+        let snapshot = &self.snapshot;
+        push("snapshot.directory".into(), string_literal(&snapshot.directory.to_string_lossy()));
+        push("snapshot.max_snapshots".into(), snapshot.max_snapshots.to_string());
+        push("snapshot.min_interval".into(), string_literal(&duration_text(snapshot.min_interval)));
+        push("snapshot.check_request_file".into(), snapshot.check_request_file.to_string());
+        push("snapshot.auto_emit".into(), snapshot.auto_emit.to_string());
+        fields
+    }
+
+    fn apply_entries(&mut self, mut entries: Vec<(String, TomlValue)>) -> Result<(), ConfigError> {
+        sort_for_apply(&mut entries, |(key, _)| key);
+        for (key, value) in &entries {
+            self.set_field(key, value)?;
+        }
+        Ok(())
+    }
+
+    fn set_field(&mut self, key: &str, value: &TomlValue) -> Result<(), ConfigError> {
+        match key {
+            "frame_arena_size" => self.frame_arena_size = size_value(key, value)?,
+            "slab_size_classes" => self.slab_size_classes = size_list(key, value)?,
+            "slab_pages_per_class" => self.slab_pages_per_class = count_value(key, value)?,
+            "slab_page_size" => self.slab_page_size = size_value(key, value)?,
+            "slab_batch_sizes" => self.slab_batch_sizes = size_list(key, value)?,
+            "numa_local_slabs" => self.numa_local_slabs = bool_value(key, value)?,
+            "enable_budgets" => self.enable_budgets = bool_value(key, value)?,
+            "global_memory_limit" => self.global_memory_limit = size_value(key, value)?,
+            "debug_mode" => self.debug_mode = bool_value(key, value)?,
+            "streaming_region" => self.streaming_region = bool_value(key, value)?,
+            _ => {
+                let (section, field) = key.split_once('.').ok_or_else(|| ConfigError::UnknownField(key.to_string()))?;
+                match section {
+                    "tag_budgets" => self.set_tag_budget(key, field, value)?,
+                    "thread_budgets" => self.set_thread_budget(key, field, value)?,
+                    "deferred" => self.set_deferred(key, field, value)?,
+                    "snapshot" => self.set_snapshot(key, field, value)?,
+                    _ => return Err(ConfigError::UnknownField(key.to_string())),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn set_tag_budget(&mut self, key: &str, field: &str, value: &TomlValue) -> Result<(), ConfigError> {
+        let (tag, limit) = field.rsplit_once('.').ok_or_else(|| ConfigError::UnknownField(key.to_string()))?;
+        let index = match self.tag_budgets.iter().position(|b| b.tag == tag) {
+            Some(index) => index,
+            None => {
+                self.tag_budgets.push(TagBudgetConfig { tag: intern(tag), soft_limit: 0, hard_limit: 0 });
+                self.tag_budgets.len() - 1
+            }
+        };
+        let budget = &mut self.tag_budgets[index];
+        match limit {
+            "soft_limit" => budget.soft_limit = size_value(key, value)?,
+            "hard_limit" => budget.hard_limit = size_value(key, value)?,
+            _ => return Err(ConfigError::UnknownField(key.to_string())),
+        }
+        Ok(())
+    }
+
+    fn set_thread_budget(&mut self, key: &str, field: &str, value: &TomlValue) -> Result<(), ConfigError> {
+        let threads = self.thread_budgets.get_or_insert_with(ThreadBudgetConfig::default);
+        match field {
+            "frame_budget" => threads.frame_budget = size_value(key, value)?,
+            "pool_budget" => threads.pool_budget = size_value(key, value)?,
+            "frame_exceeded_policy" => threads.frame_exceeded_policy = budget_policy(key, value)?,
+            "pool_exceeded_policy" => threads.pool_exceeded_policy = budget_policy(key, value)?,
+            "warning_threshold" => {
+                threads.warning_threshold = u8::try_from(count_value(key, value)?)
+                    .map_err(|_| invalid_value(key, "a percentage"))?
+            }
+            _ => return Err(ConfigError::UnknownField(key.to_string())),
+        }
+        Ok(())
+    }
+
+    fn set_deferred(&mut self, key: &str, field: &str, value: &TomlValue) -> Result<(), ConfigError> {
+        let deferred = &mut self.deferred;
+        match field {
+            "mode" => {
+                let expected = "\"at_frame_begin\", \"at_frame_end\", \"incremental\", \"explicit\" or \"disabled\"";
+                deferred.mode = match string_value(key, value, expected)? {
+                    "at_frame_begin" => DeferredProcessing::AtFrameBegin,
+                    "at_frame_end" => DeferredProcessing::AtFrameEnd,
+                    "incremental" => match deferred.mode {
+                        DeferredProcessing::Incremental { per_alloc } => DeferredProcessing::Incremental { per_alloc },
+                        _ => DeferredProcessing::Incremental { per_alloc: DEFAULT_PER_ALLOC },
+                    },
+                    "explicit" => DeferredProcessing::Explicit,
+                    "disabled" => DeferredProcessing::Disabled,
+                    _ => return Err(invalid_value(key, expected)),
+                };
+            }
+            "per_alloc" => match &mut deferred.mode {
+                DeferredProcessing::Incremental { per_alloc } => *per_alloc = count_value(key, value)?,
+                _ => {
+                    return Err(ConfigError::Invalid {
+                        field: key.to_string(),
+                        reason: "requires mode = \"incremental\"".to_string(),
+                    })
+                }
+            },
+            "capacity" => deferred.capacity = count_value(key, value)?,
+            "full_policy" => {
+                let expected = "\"process_immediately\", \"drop_oldest\", \"fail\" or \"grow\"";
+                deferred.full_policy = match string_value(key, value, expected)? {
+                    "process_immediately" => QueueFullPolicy::ProcessImmediately,
+                    "drop_oldest" => QueueFullPolicy::DropOldest,
+                    "fail" => QueueFullPolicy::Fail,
+                    "grow" => QueueFullPolicy::Grow,
+                    _ => return Err(invalid_value(key, expected)),
+                };
+            }
+            "warning_threshold" => deferred.warning_threshold = count_value(key, value)?,
+            _ => return Err(ConfigError::UnknownField(key.to_string())),
+        }
+        Ok(())
+    }
+
+    fn set_snapshot(&mut self, key: &str, field: &str, value: &TomlValue) -> Result<(), ConfigError> {
+        let snapshot = &mut self.snapshot;
+        match field {
+            "directory" => snapshot.directory = PathBuf::from(string_value(key, value, "a path")?),
+            "max_snapshots" => snapshot.max_snapshots = count_value(key, value)?,
+            "min_interval" => snapshot.min_interval = duration_value(key, value)?,
+            "check_request_file" => snapshot.check_request_file = bool_value(key, value)?,
+            "auto_emit" => snapshot.auto_emit = bool_value(key, value)?,
+            _ => return Err(ConfigError::UnknownField(key.to_string())),
+        }
+        Ok(())
+    }
+}
+
+/// Order entries so `deferred.mode` applies before `deferred.per_alloc`.
+fn sort_for_apply<T>(entries: &mut [T], key: impl Fn(&T) -> &String) {
+    entries.sort_by_key(|entry| key(entry) == "deferred.per_alloc");
+}
+
+/// Intern a tag name loaded at runtime.
+///
+/// Each distinct name is leaked once for the life of the process.
+fn intern(name: &str) -> &'static str {
+    static NAMES: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+
+    let mut names = NAMES.get_or_init(|| Mutex::new(HashSet::new())).lock();
+    if let Some(&existing) = names.get(name) {
+        return existing;
+    }
+    let leaked: &'static str = Box::leak(name.to_owned().into_boxed_str());
+    names.insert(leaked);
+    leaked
+}
+
+/// Map a lower-cased environment variable suffix to a dotted field path.
+fn env_key(name: &str) -> Option<String> {
+    if let Some(tag_and_limit) = name.strip_prefix("tag_budgets_") {
+        for limit in ["soft_limit", "hard_limit"] {
+            if let Some(tag) = tag_and_limit.strip_suffix(limit).and_then(|t| t.strip_suffix('_')) {
+                return (!tag.is_empty()).then(|| format!("tag_budgets.{}.{}", tag, limit));
+            }
+        }
+        return None;
+    }
+
+    let known = AllocConfig::default()
+        .with_thread_budgets(ThreadBudgetConfig::default())
+        .with_deferred(DeferredConfig::incremental(DEFAULT_PER_ALLOC));
+    known
+        .fields()
+        .into_iter()
+        .map(|(key, _)| key)
+        .find(|key| key.replace('.', "_") == name)
+}
+
+/// Read an environment value as a TOML value, or as a bare string.
+fn env_value(raw: &str) -> TomlValue {
+    match toml::parse(&format!("value = {}", raw)) {
+        Ok(mut entries) if entries.len() == 1 => entries.remove(0).1,
+        _ => TomlValue::String(raw.trim().to_string()),
+    }
+}
+
+fn invalid_value(field: &str, expected: &'static str) -> ConfigError {
+    ConfigError::InvalidValue {
+        field: field.to_string(),
+        expected,
+    }
+}
+
+fn count_value(field: &str, value: &TomlValue) -> Result<usize, ConfigError> {
+    match value {
+        TomlValue::Integer(n) => usize::try_from(*n).map_err(|_| invalid_value(field, "a non-negative integer")),
+        _ => Err(invalid_value(field, "a non-negative integer")),
+    }
+}
+
+fn size_value(field: &str, value: &TomlValue) -> Result<usize, ConfigError> {
+    const EXPECTED: &str = "a byte count or a size such as \"64KiB\"";
+    match value {
+        TomlValue::Integer(n) => usize::try_from(*n).map_err(|_| invalid_value(field, EXPECTED)),
+        TomlValue::String(text) => parse_size(text).ok_or_else(|| invalid_value(field, EXPECTED)),
+        _ => Err(invalid_value(field, EXPECTED)),
+    }
+}
+
+fn size_list(field: &str, value: &TomlValue) -> Result<Vec<usize>, ConfigError> {
+    const EXPECTED: &str = "a list of byte counts or sizes";
+    match value {
+        TomlValue::Array(items) => items
+            .iter()
+            .map(|item| size_value(field, item).map_err(|_| invalid_value(field, EXPECTED)))
+            .collect(),
+        _ => Err(invalid_value(field, EXPECTED)),
+    }
+}
+
+fn bool_value(field: &str, value: &TomlValue) -> Result<bool, ConfigError> {
+    match value {
+        TomlValue::Bool(b) => Ok(*b),
+        _ => Err(invalid_value(field, "true or false")),
+    }
+}
+
+fn string_value<'a>(field: &str, value: &'a TomlValue, expected: &'static str) -> Result<&'a str, ConfigError> {
+    match value {
+        TomlValue::String(s) => Ok(s),
+        _ => Err(invalid_value(field, expected)),
+    }
+}
+
+fn duration_value(field: &str, value: &TomlValue) -> Result<Duration, ConfigError> {
+    const EXPECTED: &str = "milliseconds or a duration such as \"250ms\" or \"2s\"";
+    let text = match value {
+        TomlValue::Integer(ms) => return u64::try_from(*ms).map(Duration::from_millis).map_err(|_| invalid_value(field, EXPECTED)),
+        TomlValue::String(text) => text.trim(),
+        _ => return Err(invalid_value(field, EXPECTED)),
+    };
+    let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: u64 = number.parse().map_err(|_| invalid_value(field, EXPECTED))?;
+    match unit.trim() {
+        "us" => Ok(Duration::from_micros(number)),
+        "" | "ms" => Ok(Duration::from_millis(number)),
+        "s" => Ok(Duration::from_secs(number)),
+        _ => Err(invalid_value(field, EXPECTED)),
+    }
+}
+
+fn duration_text(duration: Duration) -> String {
+    if duration.subsec_nanos() % 1_000_000 == 0 {
+        format!("{}ms", duration.as_millis())
+    } else {
+        format!("{}us", duration.as_micros())
+    }
+}
+
+fn budget_policy(field: &str, value: &TomlValue) -> Result<BudgetExceededPolicy, ConfigError> {
+    const EXPECTED: &str = "\"fail\", \"warn\", \"allow\", \"promote\" or \"custom\"";
+    Ok(match string_value(field, value, EXPECTED)? {
+        "fail" => BudgetExceededPolicy::Fail,
+        "warn" => BudgetExceededPolicy::Warn,
+        "allow" => BudgetExceededPolicy::Allow,
+        "promote" => BudgetExceededPolicy::Promote,
+        "custom" => BudgetExceededPolicy::Custom,
+        _ => return Err(invalid_value(field, EXPECTED)),
+    })
+}
+
+fn budget_policy_name(policy: BudgetExceededPolicy) -> &'static str {
+    match policy {
+        BudgetExceededPolicy::Fail => "fail",
+        BudgetExceededPolicy::Warn => "warn",
+        BudgetExceededPolicy::Allow => "allow",
+        BudgetExceededPolicy::Promote => "promote",
+        BudgetExceededPolicy::Custom => "custom",
+    }
+}
+
+fn deferred_mode_name(mode: DeferredProcessing) -> &'static str {
+    match mode {
+        DeferredProcessing::AtFrameBegin => "at_frame_begin",
+        DeferredProcessing::AtFrameEnd => "at_frame_end",
+        DeferredProcessing::Incremental { .. } => "incremental",
+        DeferredProcessing::Explicit => "explicit",
+        DeferredProcessing::Disabled => "disabled",
+    }
+}
+
+fn queue_policy_name(policy: QueueFullPolicy) -> &'static str {
+    match policy {
+        QueueFullPolicy::ProcessImmediately => "process_immediately",
+        QueueFullPolicy::DropOldest => "drop_oldest",
+        QueueFullPolicy::Fail => "fail",
+        QueueFullPolicy::Grow => "grow",
+    }
+}
+
+fn size_literal(bytes: usize) -> String {
+    let text = format_size_exact(bytes);
+    if text.ends_with("iB") {
+        string_literal(&text)
+    } else {
+        text
+    }
+}
+
+fn string_literal(text: &str) -> String {
+    format!("\"{}\"", escape_json_str(text))
+}
+
+/// Render dotted `(path, literal)` entries as nested JSON objects.
+fn json_object(entries: &[(Vec<&str>, &str)], depth: usize) -> String {
+    let indent = "  ".repeat(depth + 1);
+    let mut items = Vec::new();
+    let mut i = 0;
+    while i < entries.len() {
+        let (path, literal) = &entries[i];
+        let name = escape_json_str(path[0]);
+        if path.len() == 1 {
+            items.push(format!("{}\"{}\": {}", indent, name, literal));
+            i += 1;
+            continue;
+        }
+        let mut children = Vec::new();
+        while i < entries.len() && entries[i].0.len() > 1 && entries[i].0[0] == path[0] {
+            children.push((entries[i].0[1..].to_vec(), entries[i].1));
+            i += 1;
+        }
+        items.push(format!("{}\"{}\": {}", indent, name, json_object(&children, depth + 1)));
+    }
+    format!("{{\n{}\n{}}}", items.join(",\n"), "  ".repeat(depth))
+}
+
+/// Flatten a JSON value into TOML-style dotted entries.
+fn flatten_json(key: String, value: &JsonValue, out: &mut Vec<(String, TomlValue)>) -> Result<(), ConfigError> {
+    if let Some(fields) = value.as_object() {
+        for (name, child) in fields {
+            flatten_json(format!("{}.{}", key, name), child, out)?;
+        }
+        return Ok(());
+    }
+    let converted = json_to_toml(value).ok_or_else(|| invalid_value(&key, "a number, string, boolean or list"))?;
+    out.push((key, converted));
+    Ok(())
+}
+
+fn json_to_toml(value: &JsonValue) -> Option<TomlValue> {
+    match value {
+        JsonValue::Bool(b) => Some(TomlValue::Bool(*b)),
+        JsonValue::Number(n) => n
+            .parse()
+            .map(TomlValue::Integer)
+            .ok()
+            .or_else(|| n.parse().ok().map(TomlValue::Float)),
+        JsonValue::String(s) => Some(TomlValue::String(s.clone())),
+        JsonValue::Array(items) => items.iter().map(json_to_toml).collect::<Option<_>>().map(TomlValue::Array),
+        JsonValue::Null | JsonValue::Object(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(vars: &[(&str, &str)]) -> Vec<(OsString, OsString)> {
+        vars.iter().map(|(k, v)| (OsString::from(k), OsString::from(v))).collect()
+    }
+
+    #[test]
+    fn test_toml_and_json_round_trip() {
+        let mut config = AllocConfig::minimal()
+            .with_budgets(true)
+            .with_memory_limit(mb(512))
+            .with_tag_budget("ai", mb(1), mb(2))
+            .with_thread_budgets(ThreadBudgetConfig::default())
+            .with_deferred(DeferredConfig::incremental(8))
+            .with_snapshot(SnapshotConfig::default().with_min_interval(Duration::from_micros(1500)));
+        config.slab_batch_sizes = vec![64, 0, 8];
+
+        for format in [ConfigFormat::Toml, ConfigFormat::Json] {
+            let text = match format {
+                ConfigFormat::Toml => config.to_toml(),
+                ConfigFormat::Json => config.to_json(),
+            };
+            let loaded = AllocConfig::parse(&text, format).unwrap();
+            assert_eq!(loaded.fields(), config.fields(), "{}", text);
+        }
+        assert!(config.to_toml().contains("\n[tag_budgets.ai]\nsoft_limit = \"1MiB\"\n"));
+
+        // Missing fields keep their defaults
+        let loaded = AllocConfig::from_toml("frame_arena_size = 4096\n").unwrap();
+        assert_eq!(loaded.frame_arena_size, 4096);
+        assert_eq!(loaded.slab_size_classes, AllocConfig::default().slab_size_classes);
+    }
+
+    #[test]
+    fn test_sections_and_human_sizes() {
+        let config = AllocConfig::from_toml(
+            "frame_arena_size = \"32MiB\"\nenable_budgets = true\nglobal_memory_limit = \"1.5 GB\"\n\n\
+             [tag_budgets.physics]\nsoft_limit = \"8MiB\"\nhard_limit = 16777216\n\n\
+             [thread_budgets]\npool_exceeded_policy = \"fail\"\n\n\
+             [deferred]\nper_alloc = 4\nmode = \"incremental\"\n\n\
+             [snapshot]\ndirectory = \"/tmp/snaps\"\nmin_interval = \"2s\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.frame_arena_size, mb(32));
+        assert_eq!(config.global_memory_limit, mb(1536));
+        assert_eq!(config.tag_budgets, vec![TagBudgetConfig { tag: "physics", soft_limit: mb(8), hard_limit: mb(16) }]);
+        let threads = config.thread_budgets.unwrap();
+        assert_eq!(threads.pool_exceeded_policy, BudgetExceededPolicy::Fail);
+        assert_eq!(threads.frame_budget, ThreadBudgetConfig::default().frame_budget);
+        assert_eq!(config.deferred.mode, DeferredProcessing::Incremental { per_alloc: 4 });
+        assert_eq!(config.snapshot.directory, PathBuf::from("/tmp/snaps"));
+        assert_eq!(config.snapshot.min_interval, Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let mut config = AllocConfig::default();
+        config
+            .apply_env_vars(env(&[
+                ("FRAMEALLOC_FRAME_ARENA_SIZE", "32MiB"),
+                ("FRAMEALLOC_SLAB_SIZE_CLASSES", "[16, 64, 256]"),
+                ("FRAMEALLOC_ENABLE_BUDGETS", "true"),
+                ("FRAMEALLOC_TAG_BUDGETS_AUDIO_HARD_LIMIT", "4MiB"),
+                ("FRAMEALLOC_DEFERRED_PER_ALLOC", "2"),
+                ("FRAMEALLOC_DEFERRED_MODE", "incremental"),
+                ("FRAMEALLOC_SNAPSHOT_DIRECTORY", "out/snaps"),
+                ("FRAMEALLOC_STRICT", "error"),
+                ("PATH", "/usr/bin"),
+            ]))
+            .unwrap();
+        config.validate().unwrap();
+        assert_eq!(config.frame_arena_size, mb(32));
+        assert_eq!(config.slab_size_classes, vec![16, 64, 256]);
+        assert_eq!(config.tag_budgets[0].tag, "audio");
+        assert_eq!(config.tag_budgets[0].hard_limit, mb(4));
+        assert_eq!(config.deferred.mode, DeferredProcessing::Incremental { per_alloc: 2 });
+        assert_eq!(config.snapshot.directory, PathBuf::from("out/snaps"));
+
+        assert_eq!(
+            config.apply_env_vars(env(&[("FRAMEALLOC_FRAME_ARENA", "1")])).unwrap_err(),
+            ConfigError::UnknownField("FRAMEALLOC_FRAME_ARENA".to_string())
+        );
+        assert_eq!(
+            config.apply_env_vars(env(&[("FRAMEALLOC_SLAB_PAGE_SIZE", "lots")])).unwrap_err().to_string(),
+            "config field 'FRAMEALLOC_SLAB_PAGE_SIZE' must be a byte count or a size such as \"64KiB\""
+        );
+
+        // Non-Unicode values only matter for our own variables
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStringExt;
+            let bad = || OsString::from_vec(vec![b'1', 0xff]);
+            config.apply_env_vars([(OsString::from("LANG"), bad())]).unwrap();
+            assert_eq!(
+                config.apply_env_vars([(OsString::from("FRAMEALLOC_FRAME_ARENA_SIZE"), bad())]).unwrap_err(),
+                ConfigError::InvalidValue {
+                    field: "FRAMEALLOC_FRAME_ARENA_SIZE".to_string(),
+                    expected: "a UTF-8 value",
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_load_errors() {
+        assert_eq!(
+            AllocConfig::from_toml("frame_arena = 1").unwrap_err(),
+            ConfigError::UnknownField("frame_arena".to_string())
+        );
+        assert_eq!(
+            AllocConfig::from_json("{\n  \"enable_budgets\": 1\n}").unwrap_err().to_string(),
+            "config field 'enable_budgets' must be true or false"
+        );
+        assert_eq!(
+            AllocConfig::from_toml("slab_size_classes = [16, -1]").unwrap_err(),
+            ConfigError::InvalidValue {
+                field: "slab_size_classes".to_string(),
+                expected: "a list of byte counts or sizes",
+            }
+        );
+        assert!(matches!(
+            AllocConfig::from_json("{\n  \"debug_mode\": tru\n}"),
+            Err(ConfigError::Parse { line: 2, .. })
+        ));
+        assert!(matches!(
+            AllocConfig::from_toml("[deferred]\nper_alloc = 4"),
+            Err(ConfigError::Invalid { .. })
+        ));
+        assert!(matches!(
+            AllocConfig::from_file("/nonexistent/framealloc.toml"),
+            Err(ConfigError::Io { kind: io::ErrorKind::NotFound, .. })
+        ));
+    }
+
+    #[test]
+    fn test_validation() {
+        let reject = |text: &str, field: &str| match AllocConfig::from_toml(text) {
+            Err(ConfigError::Invalid { field: f, reason }) => {
+                assert_eq!(f, field, "{}", reason);
+                reason
+            }
+            other => panic!("expected {} to be rejected, got {:?}", field, other),
+        };
+
+        let reason = reject("slab_size_classes = [16, 64, 32]", "slab_size_classes");
+        assert_eq!(reason, "classes must be strictly ascending, found 32 after 64");
+        reject("slab_size_classes = [16, 24, 32]", "slab_size_classes");
+        reject("slab_size_classes = [16, 32, 48, 64, 80, 96, 112, 128, 144, 160]", "slab_size_classes");
+        let reason = reject("slab_size_classes = [16, 32, 48, 96]", "slab_size_classes");
+        assert_eq!(reason, "64-byte objects would be served from the 96-byte class, which is only 32-byte aligned");
+        assert!(AllocConfig::from_toml("slab_size_classes = [16, 32, 48, 64, 128, 192, 256, 1024, 4096]").is_ok());
+        let reason = reject("slab_page_size = \"2KiB\"", "slab_page_size");
+        assert_eq!(reason, "2.00 KB is smaller than the 4096-byte size class");
+        reject("slab_page_size = 12000\nslab_size_classes = [16, 32, 64, 128, 256, 512, 1024, 2048, 4000]", "slab_page_size");
+        reject("frame_arena_size = 0", "frame_arena_size");
+        reject("global_memory_limit = \"1GiB\"", "global_memory_limit");
+        reject("enable_budgets = true\n[tag_budgets.ai]\nsoft_limit = \"2MiB\"\nhard_limit = \"1MiB\"", "tag_budgets.ai");
+        reject("[thread_budgets]\nwarning_threshold = 120", "thread_budgets.warning_threshold");
+        reject("[deferred]\nfull_policy = \"fail\"", "deferred.full_policy");
+        reject("[deferred]\ncapacity = 64", "deferred.warning_threshold");
+        reject("[snapshot]\nmax_snapshots = 0", "snapshot.max_snapshots");
+
+        AllocConfig::default().validate().unwrap();
+        AllocConfig::minimal().validate().unwrap();
+        AllocConfig::high_performance().validate().unwrap();
+    }
 }
@@ -0,0 +1,180 @@
+//! Global frame numbers versus per-thread frame epochs.
+//!
+//! The **global frame** counts real frames. Exactly one authority advances
+//! it: by default the first thread to call `begin_frame`, or an explicitly
+//! chosen thread, or nobody (manual `advance_frame` calls only). A thread
+//! that claimed authority as first caller gives it up when it unregisters
+//! or exits, and the next thread to call `begin_frame` takes over.
+//!
+//! Each thread's **epoch** counts how many times that thread has called
+//! `begin_frame`. Its frame arena, checkpoints and retained allocations are
+//! only meaningful within one epoch, whereas cross-thread reporting
+//! (snapshots, the behavior filter) uses the global frame.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread::{self, ThreadId};
+
+use crate::sync::mutex::Mutex;
+
+/// Who advances the global frame number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FrameAuthority {
+    /// The first thread to call `begin_frame` becomes the authority until it
+    /// unregisters or exits
+    #[default]
+    FirstCaller,
+    /// Only `begin_frame` on this thread advances the frame
+    Thread(ThreadId),
+    /// `begin_frame` never advances the frame; call `advance_frame`
+    Manual,
+}
+
+/// Shared global frame counter with a single advancing authority.
+pub struct FrameClock {
+    frame: AtomicU64,
+    authority: Mutex<AuthorityState>,
+}
+
+/// The current authority and how it was chosen.
+struct AuthorityState {
+    authority: FrameAuthority,
+    /// The authority is a thread that claimed it under `FirstCaller`
+    claimed: bool,
+}
+
+impl FrameClock {
+    /// Create a clock at frame 0.
+    pub fn new() -> Self {
+        Self {
+            frame: AtomicU64::new(0),
+            authority: Mutex::new(AuthorityState {
+                authority: FrameAuthority::FirstCaller,
+                claimed: false,
+            }),
+        }
+    }
+
+    /// Get the current global frame.
+    pub fn frame(&self) -> u64 {
+        self.frame.load(Ordering::Acquire)
+    }
+
+    /// Advance the global frame unconditionally. Returns the new frame.
+    pub fn advance(&self) -> u64 {
+        self.frame.fetch_add(1, Ordering::AcqRel) + 1
+    }
+
+    /// Get the current authority.
+    pub fn authority(&self) -> FrameAuthority {
+        self.authority.lock().authority
+    }
+
+    /// Change who advances the global frame.
+    pub fn set_authority(&self, authority: FrameAuthority) {
+        *self.authority.lock() = AuthorityState {
+            authority,
+            claimed: false,
+        };
+    }
+
+    /// Hand authority back to `FirstCaller` if `thread` claimed it that way.
+    ///
+    /// Called when the thread unregisters or exits. An authority chosen with
+    /// `set_authority` is kept.
+    pub(crate) fn release(&self, thread: ThreadId) {
+        let mut state = self.authority.lock();
+        if state.claimed && state.authority == FrameAuthority::Thread(thread) {
+            state.authority = FrameAuthority::FirstCaller;
+            state.claimed = false;
+        }
+    }
+
+    /// Check whether the calling thread advances the global frame.
+    pub fn is_authority(&self) -> bool {
+        self.authority() == FrameAuthority::Thread(thread::current().id())
+    }
+
+    /// Handle `begin_frame` on the calling thread.
+    ///
+    /// Advances the frame if this thread is the authority (claiming it if
+    /// unclaimed) and returns the global frame the thread is now in.
+    pub(crate) fn on_begin_frame(&self) -> u64 {
+        let current = thread::current().id();
+        let advances = {
+            let mut state = self.authority.lock();
+            if state.authority == FrameAuthority::FirstCaller {
+                state.authority = FrameAuthority::Thread(current);
+                state.claimed = true;
+            }
+            state.authority == FrameAuthority::Thread(current)
+        };
+
+        if advances {
+            self.advance()
+        } else {
+            self.frame()
+        }
+    }
+}
+
+impl Default for FrameClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_authority_advances() {
+        let clock = FrameClock::new();
+        assert_eq!(clock.on_begin_frame(), 1);
+        assert!(clock.is_authority());
+
+        let clock = std::sync::Arc::new(clock);
+        let workers: Vec<_> = (0..4)
+            .map(|_| {
+                let clock = clock.clone();
+                thread::spawn(move || clock.on_begin_frame())
+            })
+            .collect();
+        for worker in workers {
+            assert_eq!(worker.join().unwrap(), 1);
+        }
+
+        assert_eq!(clock.on_begin_frame(), 2);
+    }
+
+    #[test]
+    fn test_claimed_authority_released() {
+        let clock = std::sync::Arc::new(FrameClock::new());
+        let worker = clock.clone();
+        let id = thread::spawn(move || {
+            assert_eq!(worker.on_begin_frame(), 1);
+            thread::current().id()
+        })
+        .join()
+        .unwrap();
+
+        clock.release(id);
+        assert_eq!(clock.authority(), FrameAuthority::FirstCaller);
+        assert_eq!(clock.on_begin_frame(), 2);
+        assert!(clock.is_authority());
+
+        // An explicitly chosen authority is kept
+        clock.set_authority(FrameAuthority::Thread(id));
+        clock.release(id);
+        assert_eq!(clock.authority(), FrameAuthority::Thread(id));
+    }
+
+    #[test]
+    fn test_manual_authority() {
+        let clock = FrameClock::new();
+        clock.set_authority(FrameAuthority::Manual);
+        assert_eq!(clock.on_begin_frame(), 0);
+        assert_eq!(clock.advance(), 1);
+        assert_eq!(clock.on_begin_frame(), 1);
+    }
+}
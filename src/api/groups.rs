@@ -1,51 +1,198 @@
 //! Allocation groups - named collections of allocations that can be freed together.
+//!
+//! Each group is backed by chunked bump arenas. Every thread keeps its own
+//! cursor into a chunk of the group, so allocation is a pointer bump with no
+//! locks once a thread has touched the group; only fetching a new chunk takes
+//! the group's chunk lock. `free_group` runs any registered destructors and
+//! then releases whole chunks, after waiting out allocations already in
+//! progress on other threads.
 
 use std::alloc::{alloc, dealloc, Layout};
+use std::cell::RefCell;
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
 
+use crossbeam_queue::SegQueue;
+
+use crate::core::global::AllocatorId;
 use crate::sync::mutex::Mutex;
+use crate::util::layout::align_up;
+use crate::util::size::kb;
+
+/// Default chunk size for group arenas.
+const DEFAULT_CHUNK_SIZE: usize = kb(64);
+
+/// Minimum alignment of group chunks.
+const CHUNK_ALIGN: usize = 16;
 
 /// Unique identifier for an allocation group.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct GroupId(u64);
 
-/// Metadata for a single allocation within a group.
-struct GroupAllocation {
+/// A block of memory owned by a group.
+struct Chunk {
     ptr: *mut u8,
     layout: Layout,
 }
 
+// SAFETY: a chunk is plain owned memory; it is only deallocated by the
+// group that owns it.
+unsafe impl Send for Chunk {}
+
+/// A destructor to run when the group is freed.
+struct DropEntry {
+    ptr: *mut u8,
+    drop_fn: unsafe fn(*mut u8),
+}
+
+// SAFETY: the entry is only used by `free_group`, after allocations in
+// progress have finished and new ones are refused.
+unsafe impl Send for DropEntry {}
+
+unsafe fn drop_erased<T>(ptr: *mut u8) {
+    std::ptr::drop_in_place(ptr as *mut T);
+}
+
 /// A group of allocations that can be freed together.
 struct Group {
     name: String,
-    allocations: Vec<GroupAllocation>,
-    total_bytes: usize,
+    chunk_size: usize,
+    /// Chunks handed out to threads; locked only on refill and free
+    chunks: Mutex<Vec<Chunk>>,
+    /// Registered destructors
+    drops: SegQueue<DropEntry>,
+    /// Set once the group has been freed
+    freed: AtomicBool,
+    /// Busy flags of every thread's cursor, waited on by `release`
+    cursor_flags: Mutex<Vec<Arc<AtomicBool>>>,
+    allocation_count: AtomicUsize,
+    total_bytes: AtomicUsize,
+    reserved_bytes: AtomicUsize,
 }
 
+impl Group {
+    fn new(name: String, chunk_size: usize) -> Self {
+        Self {
+            name,
+            chunk_size,
+            chunks: Mutex::new(Vec::new()),
+            drops: SegQueue::new(),
+            freed: AtomicBool::new(false),
+            cursor_flags: Mutex::new(Vec::new()),
+            allocation_count: AtomicUsize::new(0),
+            total_bytes: AtomicUsize::new(0),
+            reserved_bytes: AtomicUsize::new(0),
+        }
+    }
+
+    /// Allocate a new chunk that can hold at least `layout`.
+    fn new_chunk(&self, layout: Layout) -> Option<(*mut u8, usize)> {
+        let size = self.chunk_size.max(layout.size());
+        let chunk_layout = Layout::from_size_align(size, layout.align().max(CHUNK_ALIGN)).ok()?;
+        let ptr = unsafe { alloc(chunk_layout) };
+        if ptr.is_null() {
+            return None;
+        }
+
+        self.chunks.lock().push(Chunk {
+            ptr,
+            layout: chunk_layout,
+        });
+        self.reserved_bytes.fetch_add(size, Ordering::Relaxed);
+        Some((ptr, size))
+    }
+
+    /// Run destructors and release all chunks.
+    ///
+    /// New allocations are refused first, then allocations already in
+    /// progress are waited for, so no thread writes into a released chunk.
+    fn release(&self) {
+        self.freed.store(true, Ordering::Release);
+        for busy in self.cursor_flags.lock().iter() {
+            lock_flag(busy);
+            busy.store(false, Ordering::Release);
+        }
+
+        // Destroy in reverse registration order
+        let mut drops = Vec::with_capacity(self.drops.len());
+        while let Some(entry) = self.drops.pop() {
+            drops.push(entry);
+        }
+        for entry in drops.into_iter().rev() {
+            unsafe { (entry.drop_fn)(entry.ptr) };
+        }
+
+        for chunk in self.chunks.lock().drain(..) {
+            unsafe { dealloc(chunk.ptr, chunk.layout) };
+        }
+    }
+}
+
+/// Spin until `flag` is taken.
+fn lock_flag(flag: &AtomicBool) {
+    while flag
+        .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+        .is_err()
+    {
+        std::hint::spin_loop();
+    }
+}
+
+/// A thread's bump cursor into one group.
+struct Cursor {
+    group: Arc<Group>,
+    /// Held while this thread allocates; only `release` contends for it
+    busy: Arc<AtomicBool>,
+    /// Next free address in the current chunk
+    ptr: usize,
+    /// End of the current chunk
+    end: usize,
+}
+
+thread_local! {
+    /// Cursors keyed by (allocator ID, group ID).
+    static CURSORS: RefCell<HashMap<(AllocatorId, GroupId), Cursor>> = RefCell::new(HashMap::new());
+}
+
+/// Callback invoked after a group is freed.
+pub type GroupFreeListener = Arc<dyn Fn(GroupId) + Send + Sync>;
+
 /// Manages allocation groups.
 pub struct GroupAllocator {
-    groups: Mutex<HashMap<GroupId, Group>>,
+    groups: Mutex<HashMap<GroupId, Arc<Group>>>,
     next_id: AtomicU64,
+    /// Distinguishes this allocator in the thread-local cursor cache
+    allocator_id: AllocatorId,
+    chunk_size: usize,
+    /// Notified after `free_group` releases a group
+    free_listeners: Mutex<Vec<GroupFreeListener>>,
 }
 
 impl GroupAllocator {
     /// Create a new group allocator.
     pub fn new() -> Self {
+        Self::with_chunk_size(DEFAULT_CHUNK_SIZE)
+    }
+
+    /// Create a group allocator whose arenas grow in chunks of `chunk_size` bytes.
+    ///
+    /// Allocations larger than a chunk get a dedicated chunk.
+    pub fn with_chunk_size(chunk_size: usize) -> Self {
         Self {
             groups: Mutex::new(HashMap::new()),
             next_id: AtomicU64::new(1),
+            allocator_id: AllocatorId::next(),
+            chunk_size: chunk_size.max(CHUNK_ALIGN),
+            free_listeners: Mutex::new(Vec::new()),
         }
     }
 
     /// Create a new allocation group.
     pub fn create_group(&self, name: impl Into<String>) -> GroupId {
         let id = GroupId(self.next_id.fetch_add(1, Ordering::Relaxed));
-        let group = Group {
-            name: name.into(),
-            allocations: Vec::new(),
-            total_bytes: 0,
-        };
+        let group = Arc::new(Group::new(name.into(), self.chunk_size));
 
         let mut groups = self.groups.lock();
         groups.insert(id, group);
@@ -59,31 +206,113 @@ impl GroupAllocator {
     }
 
     /// Allocate memory with a specific layout within a group.
+    ///
+    /// Lock-free once the calling thread has allocated from the group before,
+    /// unless a new chunk is needed. Returns None if the group does not exist.
     pub fn alloc_layout(&self, group_id: GroupId, layout: Layout) -> Option<*mut u8> {
-        let ptr = unsafe { alloc(layout) };
-        if ptr.is_null() {
-            return None;
+        self.alloc_with(group_id, layout, |ptr, _| ptr)
+    }
+
+    /// Allocate `layout` and pass the memory to `init` before `free_group`
+    /// can release it.
+    fn alloc_with<R>(&self, group_id: GroupId, layout: Layout, init: impl FnOnce(*mut u8, &Group) -> R) -> Option<R> {
+        CURSORS.with(|cursors| {
+            let mut cursors = cursors.borrow_mut();
+            let key = (self.allocator_id, group_id);
+
+            match cursors.get(&key) {
+                Some(cursor) if cursor.group.freed.load(Ordering::Acquire) => {
+                    cursors.remove(&key);
+                    return None;
+                }
+                Some(_) => {}
+                // First use on this thread: drop cursors of groups freed elsewhere
+                None => cursors.retain(|_, c| !c.group.freed.load(Ordering::Relaxed)),
+            }
+
+            let cursor = match cursors.entry(key) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let group = Arc::clone(self.groups.lock().get(&group_id)?);
+                    let busy = Arc::new(AtomicBool::new(false));
+                    group.cursor_flags.lock().push(Arc::clone(&busy));
+                    entry.insert(Cursor { group, busy, ptr: 0, end: 0 })
+                }
+            };
+
+            // Pairs with `Group::release`, which sets `freed` and then takes
+            // every cursor's flag: either it waits for this allocation, or
+            // this takes the flag after it and sees the group freed
+            lock_flag(&cursor.busy);
+            if cursor.group.freed.load(Ordering::Relaxed) {
+                cursor.busy.store(false, Ordering::Release);
+                cursors.remove(&key);
+                return None;
+            }
+
+            let result = Self::bump(cursor, layout).map(|ptr| {
+                let group = &cursor.group;
+                group.allocation_count.fetch_add(1, Ordering::Relaxed);
+                group.total_bytes.fetch_add(layout.size(), Ordering::Relaxed);
+                init(ptr, group)
+            });
+            cursor.busy.store(false, Ordering::Release);
+            result
+        })
+    }
+
+    /// Bump-allocate from a cursor, fetching a new chunk if needed.
+    fn bump(cursor: &mut Cursor, layout: Layout) -> Option<*mut u8> {
+        if layout.size() == 0 {
+            return Some(layout.align() as *mut u8);
         }
 
-        let mut groups = self.groups.lock();
-        if let Some(group) = groups.get_mut(&group_id) {
-            group.allocations.push(GroupAllocation { ptr, layout });
-            group.total_bytes += layout.size();
-            Some(ptr)
-        } else {
-            // Group doesn't exist, free the memory
-            unsafe { dealloc(ptr, layout) };
-            None
+        if cursor.ptr != 0 {
+            let start = align_up(cursor.ptr, layout.align());
+            if start + layout.size() <= cursor.end {
+                cursor.ptr = start + layout.size();
+                return Some(start as *mut u8);
+            }
         }
+
+        let (chunk, size) = cursor.group.new_chunk(layout)?;
+
+        // Oversized allocations keep the current chunk for later small ones
+        if layout.size() * 2 <= size {
+            cursor.ptr = chunk as usize + layout.size();
+            cursor.end = chunk as usize + size;
+        }
+        Some(chunk)
     }
 
     /// Allocate and initialize a value within a group.
+    ///
+    /// The value is never dropped; use `alloc_val_with_drop` for types that
+    /// own resources.
     pub fn alloc_val<T>(&self, group_id: GroupId, value: T) -> Option<*mut T> {
-        let ptr = self.alloc::<T>(group_id)?;
-        unsafe {
-            std::ptr::write(ptr, value);
-        }
-        Some(ptr)
+        self.alloc_with(group_id, Layout::new::<T>(), |ptr, _| {
+            let ptr = ptr as *mut T;
+            unsafe { std::ptr::write(ptr, value) };
+            ptr
+        })
+    }
+
+    /// Allocate a value whose destructor runs when the group is freed.
+    ///
+    /// Types without drop glue are not registered, so this costs the same as
+    /// `alloc_val` for them.
+    pub fn alloc_val_with_drop<T>(&self, group_id: GroupId, value: T) -> Option<*mut T> {
+        self.alloc_with(group_id, Layout::new::<T>(), |ptr, group| {
+            let ptr = ptr as *mut T;
+            unsafe { std::ptr::write(ptr, value) };
+            if std::mem::needs_drop::<T>() {
+                group.drops.push(DropEntry {
+                    ptr: ptr as *mut u8,
+                    drop_fn: drop_erased::<T>,
+                });
+            }
+            ptr
+        })
     }
 
     /// Allocate a slice within a group.
@@ -93,27 +322,60 @@ impl GroupAllocator {
     }
 
     /// Free all allocations in a group.
+    ///
+    /// Runs registered destructors, then releases the group's chunks.
+    /// Allocations into the group racing on other threads either finish
+    /// first or return None; pointers they returned dangle afterwards.
     pub fn free_group(&self, group_id: GroupId) {
-        let mut groups = self.groups.lock();
-        if let Some(group) = groups.remove(&group_id) {
-            for alloc in group.allocations {
-                unsafe {
-                    dealloc(alloc.ptr, alloc.layout);
-                }
+        let group = self.groups.lock().remove(&group_id);
+        if let Some(group) = group {
+            group.release();
+            CURSORS.with(|cursors| {
+                cursors.borrow_mut().remove(&(self.allocator_id, group_id));
+            });
+
+            let listeners = self.free_listeners.lock().clone();
+            for listener in listeners {
+                listener(group_id);
             }
         }
     }
 
+    /// Register a callback to run after each `free_group`.
+    ///
+    /// Used to tie other resources, such as scratch pools, to a group's lifetime.
+    pub fn add_free_listener<F>(&self, listener: F)
+    where
+        F: Fn(GroupId) + Send + Sync + 'static,
+    {
+        self.free_listeners.lock().push(Arc::new(listener));
+    }
+
     /// Get the total bytes allocated in a group.
     pub fn group_size(&self, group_id: GroupId) -> usize {
         let groups = self.groups.lock();
-        groups.get(&group_id).map(|g| g.total_bytes).unwrap_or(0)
+        groups
+            .get(&group_id)
+            .map(|g| g.total_bytes.load(Ordering::Relaxed))
+            .unwrap_or(0)
     }
 
     /// Get the number of allocations in a group.
     pub fn group_count(&self, group_id: GroupId) -> usize {
         let groups = self.groups.lock();
-        groups.get(&group_id).map(|g| g.allocations.len()).unwrap_or(0)
+        groups
+            .get(&group_id)
+            .map(|g| g.allocation_count.load(Ordering::Relaxed))
+            .unwrap_or(0)
+    }
+
+    /// Get the bytes of chunk memory reserved by a group.
+    pub fn group_reserved(&self, group_id: GroupId) -> usize {
+        let groups = self.groups.lock();
+        groups
+            .get(&group_id)
+            .map(|g| g.reserved_bytes.load(Ordering::Relaxed))
+            .unwrap_or(0)
     }
 
     /// Get the name of a group.
@@ -129,8 +391,11 @@ impl GroupAllocator {
 
         for group in groups.values() {
             stats.total_groups += 1;
-            stats.total_allocations += group.allocations.len();
-            stats.total_bytes += group.total_bytes;
+            stats.total_allocations += group.allocation_count.load(Ordering::Relaxed);
+            stats.total_bytes += group.total_bytes.load(Ordering::Relaxed);
+            stats.reserved_bytes += group.reserved_bytes.load(Ordering::Relaxed);
+            stats.total_chunks += group.chunks.lock().len();
+            stats.pending_drops += group.drops.len();
         }
 
         stats
@@ -149,6 +414,14 @@ impl Default for GroupAllocator {
     }
 }
 
+impl Drop for GroupAllocator {
+    fn drop(&mut self) {
+        for (_, group) in self.groups.lock().drain() {
+            group.release();
+        }
+    }
+}
+
 // Safety: GroupAllocator uses internal synchronization
 unsafe impl Send for GroupAllocator {}
 unsafe impl Sync for GroupAllocator {}
@@ -162,6 +435,12 @@ pub struct GroupStats {
     pub total_allocations: usize,
     /// Total bytes allocated across all groups
     pub total_bytes: usize,
+    /// Chunk memory reserved across all groups
+    pub reserved_bytes: usize,
+    /// Number of arena chunks across all groups
+    pub total_chunks: usize,
+    /// Destructors waiting to run at group free
+    pub pending_drops: usize,
 }
 
 /// A handle to a specific group for convenient allocation.
@@ -191,6 +470,11 @@ impl<'a> GroupHandle<'a> {
         self.allocator.alloc_val(self.id, value)
     }
 
+    /// Allocate a value whose destructor runs when this group is freed.
+    pub fn alloc_val_with_drop<T>(&self, value: T) -> Option<*mut T> {
+        self.allocator.alloc_val_with_drop(self.id, value)
+    }
+
     /// Allocate a slice in this group.
     pub fn alloc_slice<T>(&self, len: usize) -> Option<*mut T> {
         self.allocator.alloc_slice::<T>(self.id, len)
@@ -236,6 +520,34 @@ mod tests {
         assert!(!allocator.group_exists(group));
     }
 
+    #[test]
+    fn test_free_group_waits_for_racing_allocations() {
+        let allocator = Arc::new(GroupAllocator::with_chunk_size(256));
+        let group = allocator.create_group("racing");
+
+        let workers: Vec<_> = (0..4)
+            .map(|_| {
+                let allocator = Arc::clone(&allocator);
+                std::thread::spawn(move || {
+                    let mut count = 0usize;
+                    while allocator.alloc_val_with_drop(group, vec![0u8; 32]).is_some() {
+                        count += 1;
+                    }
+                    count
+                })
+            })
+            .collect();
+
+        while allocator.group_count(group) < 64 {
+            std::thread::yield_now();
+        }
+        allocator.free_group(group);
+
+        let allocated: usize = workers.into_iter().map(|w| w.join().unwrap()).sum();
+        assert!(allocated >= 64);
+        assert!(allocator.alloc_val(group, 1u64).is_none());
+    }
+
     #[test]
     fn test_group_handle() {
         let allocator = GroupAllocator::new();
@@ -267,4 +579,79 @@ mod tests {
 
         allocator.free_group(group2);
     }
+
+    #[test]
+    fn test_allocations_share_chunks() {
+        let allocator = GroupAllocator::with_chunk_size(4096);
+        let group = allocator.create_group("level");
+
+        for i in 0..1000u64 {
+            let ptr = allocator.alloc_val(group, i).unwrap();
+            assert_eq!(ptr as usize % std::mem::align_of::<u64>(), 0);
+        }
+
+        let stats = allocator.stats();
+        assert_eq!(stats.total_allocations, 1000);
+        assert_eq!(stats.total_bytes, 8000);
+        assert_eq!(stats.total_chunks, 2);
+
+        // Oversized allocations get their own chunk
+        allocator.alloc_slice::<u8>(group, 10_000).unwrap();
+        assert_eq!(allocator.stats().total_chunks, 3);
+
+        allocator.free_group(group);
+        assert_eq!(allocator.stats().reserved_bytes, 0);
+        assert!(allocator.alloc::<u64>(group).is_none());
+    }
+
+    #[test]
+    fn test_drop_glue_runs_at_free() {
+        use std::sync::atomic::AtomicUsize;
+
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+        struct Tracked(#[allow(dead_code)] Vec<u8>);
+        impl Drop for Tracked {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let allocator = GroupAllocator::new();
+        let group = allocator.create_group("entities");
+
+        for _ in 0..3 {
+            allocator.alloc_val_with_drop(group, Tracked(vec![1, 2, 3])).unwrap();
+        }
+        // Plain values and Copy types are not registered
+        allocator.alloc_val(group, Tracked(Vec::new())).unwrap();
+        allocator.alloc_val_with_drop(group, 7u32).unwrap();
+        assert_eq!(allocator.stats().pending_drops, 3);
+
+        allocator.free_group(group);
+        assert_eq!(DROPS.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn test_alloc_from_multiple_threads() {
+        let allocator = Arc::new(GroupAllocator::new());
+        let group = allocator.create_group("shared");
+
+        let workers: Vec<_> = (0..4)
+            .map(|t| {
+                let allocator = Arc::clone(&allocator);
+                std::thread::spawn(move || {
+                    for i in 0..500u64 {
+                        let ptr = allocator.alloc_val(group, t * 1000 + i).unwrap();
+                        assert_eq!(unsafe { *ptr }, t * 1000 + i);
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        assert_eq!(allocator.group_count(group), 2000);
+        allocator.free_group(group);
+    }
 }
@@ -7,16 +7,20 @@ pub mod alloc;
 pub mod allocator_impl;
 pub mod checkpoint;
 pub mod config;
+pub mod frame_clock;
 pub mod frame_collections;
 pub mod groups;
+pub mod phase_budget;
 pub mod phases;
 pub mod promotion;
 pub mod retention;
 pub mod scope;
 pub mod scratch;
 pub mod stats;
+pub mod sub_frame;
 pub mod tag;
 pub mod tagged;
+pub mod tuner;
 pub mod wrappers;
 
 // v0.6.0: Thread coordination and observability
@@ -24,7 +28,12 @@ pub mod barrier;
 pub mod deferred_control;
 pub mod lifecycle;
 pub mod thread_budget;
+pub mod threads;
 pub mod transfer;
 
 // v0.7.0: IDE integration and snapshots
 pub mod snapshot;
+pub mod snapshot_diff;
+pub mod snapshot_recording;
+#[cfg(feature = "telemetry")]
+pub mod telemetry;
@@ -0,0 +1,189 @@
+//! Per-phase frame budgets.
+//!
+//! A budget caps the frame memory one thread may allocate inside a named
+//! phase during a single frame, e.g. "physics may use 4 MB". What happens
+//! when a phase goes over is decided by its `PhaseBudgetPolicy`.
+//!
+//! High-water marks are kept per phase name across frames, whether or not a
+//! budget is set, so budgets can be tuned from real data.
+
+use std::collections::HashMap;
+
+use crate::api::phases::Phase;
+use crate::core::budget::BudgetEvent;
+use crate::core::global::AllocatorId;
+use crate::sync::mutex::Mutex;
+
+/// What to do when a phase exceeds its frame budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PhaseBudgetPolicy {
+    /// Emit a `BudgetEvent::PhaseLimitExceeded` to the budget callback
+    #[default]
+    Event,
+    /// Emit the event and an FA303 diagnostic
+    Diagnostic,
+    /// Emit the event and serve further frame allocations in the phase
+    /// from the heap; they are released at `end_frame`
+    Fallback,
+}
+
+/// A frame memory budget for one phase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhaseBudget {
+    /// Phase name
+    pub phase: &'static str,
+    /// Frame bytes allowed per phase instance
+    pub limit: usize,
+    /// Action taken when the limit is exceeded
+    pub policy: PhaseBudgetPolicy,
+}
+
+/// Usage recorded for a phase name across frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhaseHighWater {
+    /// Phase name
+    pub phase: &'static str,
+    /// Most frame bytes used by a single phase instance
+    pub peak_frame_bytes: usize,
+    /// Most bytes (frame, pool and heap) allocated by a single phase instance
+    pub peak_bytes_allocated: usize,
+    /// Frame bytes used by the most recent instance
+    pub last_frame_bytes: usize,
+    /// Number of completed phase instances
+    pub samples: u64,
+    /// Number of instances that exceeded their budget
+    pub over_budget: u64,
+}
+
+impl PhaseHighWater {
+    fn new(phase: &'static str) -> Self {
+        Self {
+            phase,
+            peak_frame_bytes: 0,
+            peak_bytes_allocated: 0,
+            last_frame_bytes: 0,
+            samples: 0,
+            over_budget: 0,
+        }
+    }
+}
+
+/// Budget callback type.
+type BudgetCallback = Box<dyn Fn(BudgetEvent) + Send + Sync>;
+
+/// Per-phase budgets and high-water marks for an allocator.
+pub struct PhaseBudgets {
+    budgets: Mutex<HashMap<&'static str, PhaseBudget>>,
+    high_water: Mutex<HashMap<&'static str, PhaseHighWater>>,
+    event_callback: Mutex<Option<BudgetCallback>>,
+    /// Allocator whose diagnostics this table reports, if any
+    allocator: Option<AllocatorId>,
+}
+
+impl PhaseBudgets {
+    /// Create an empty budget table.
+    pub fn new() -> Self {
+        Self {
+            budgets: Mutex::new(HashMap::new()),
+            high_water: Mutex::new(HashMap::new()),
+            event_callback: Mutex::new(None),
+            allocator: None,
+        }
+    }
+
+    /// Create an empty budget table for the allocator `id`.
+    pub(crate) fn for_allocator(id: AllocatorId) -> Self {
+        Self {
+            allocator: Some(id),
+            ..Self::new()
+        }
+    }
+
+    /// Get the allocator this table belongs to, if any.
+    pub(crate) fn allocator(&self) -> Option<AllocatorId> {
+        self.allocator
+    }
+
+    /// Set the frame budget for a phase.
+    ///
+    /// Takes effect the next time the phase begins.
+    pub fn set(&self, phase: &'static str, limit: usize, policy: PhaseBudgetPolicy) {
+        self.budgets.lock().insert(
+            phase,
+            PhaseBudget {
+                phase,
+                limit,
+                policy,
+            },
+        );
+    }
+
+    /// Remove the budget for a phase.
+    pub fn remove(&self, phase: &'static str) {
+        self.budgets.lock().remove(phase);
+    }
+
+    /// Get the budget for a phase.
+    pub fn get(&self, phase: &'static str) -> Option<PhaseBudget> {
+        self.budgets.lock().get(phase).copied()
+    }
+
+    /// Get all budgets.
+    pub fn budgets(&self) -> Vec<PhaseBudget> {
+        self.budgets.lock().values().copied().collect()
+    }
+
+    /// Set a callback for phase budget events.
+    ///
+    /// The callback runs on the allocating thread once the allocator's
+    /// thread-local state is released, so it may call back into it.
+    pub fn set_event_callback<F>(&self, callback: F)
+    where
+        F: Fn(BudgetEvent) + Send + Sync + 'static,
+    {
+        *self.event_callback.lock() = Some(Box::new(callback));
+    }
+
+    /// Get the high-water mark for a phase.
+    pub fn high_water(&self, phase: &'static str) -> Option<PhaseHighWater> {
+        self.high_water.lock().get(phase).copied()
+    }
+
+    /// Get high-water marks for every phase seen so far.
+    pub fn high_water_marks(&self) -> Vec<PhaseHighWater> {
+        self.high_water.lock().values().copied().collect()
+    }
+
+    /// Clear all high-water marks (budgets are kept).
+    pub fn reset_high_water(&self) {
+        self.high_water.lock().clear();
+    }
+
+    /// Record a completed phase instance.
+    pub(crate) fn record(&self, phase: &Phase, over_budget: bool) {
+        let mut marks = self.high_water.lock();
+        let mark = marks
+            .entry(phase.name)
+            .or_insert_with(|| PhaseHighWater::new(phase.name));
+        mark.peak_frame_bytes = mark.peak_frame_bytes.max(phase.frame_bytes);
+        mark.peak_bytes_allocated = mark.peak_bytes_allocated.max(phase.bytes_allocated);
+        mark.last_frame_bytes = phase.frame_bytes;
+        mark.samples += 1;
+        if over_budget {
+            mark.over_budget += 1;
+        }
+    }
+
+    /// Emit a budget event to the callback.
+    pub(crate) fn emit_event(&self, event: BudgetEvent) {
+        if let Some(ref callback) = *self.event_callback.lock() {
+            callback(event);
+        }
+    }
+}
+
+impl Default for PhaseBudgets {
+    fn default() -> Self {
+        Self::new()
+    }
+}
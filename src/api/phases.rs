@@ -3,10 +3,25 @@
 //! Phases divide a frame into logical sections (input, physics, render, etc.)
 //! without changing allocation semantics. They integrate with diagnostics
 //! and profiling for better visibility.
+//!
+//! Phases begun through `SmartAlloc::begin_phase` live in that allocator's
+//! thread-local state and are charged with its allocations. The free
+//! functions here drive a standalone per-thread tracker that no allocator
+//! charges; they only name sections and time them.
 
 use std::cell::RefCell;
+use std::sync::Arc;
+
+use crate::api::phase_budget::{PhaseBudget, PhaseBudgetPolicy, PhaseBudgets};
+use crate::core::budget::BudgetEvent;
+use crate::core::global::GlobalState;
+use crate::core::tls;
+use crate::diagnostics::{ProfilerHooks, RuntimeDiagnostic, FA303};
 
 /// A named phase within a frame.
+///
+/// Frame, pool and heap allocations made while the phase is on the stack
+/// are charged to it, so an outer phase includes its nested phases.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Phase {
     /// Name of the phase
@@ -15,9 +30,20 @@ pub struct Phase {
     pub bytes_allocated: usize,
     /// Number of allocations in this phase
     pub allocation_count: usize,
+    /// Bytes of `bytes_allocated` that came from the frame arena
+    pub frame_bytes: usize,
+    /// Bytes of `frame_bytes` allocated while no nested phase was active
+    pub own_frame_bytes: usize,
+    /// Bytes freed during this phase
+    pub bytes_freed: usize,
+    /// Highest net bytes (allocated minus freed) during this phase
+    pub peak_bytes: usize,
     /// Start time (if timing enabled)
     #[cfg(feature = "diagnostics")]
     pub start_time: Option<std::time::Instant>,
+    /// End time, set when the phase ends (if timing enabled)
+    #[cfg(feature = "diagnostics")]
+    pub end_time: Option<std::time::Instant>,
 }
 
 impl Phase {
@@ -27,8 +53,14 @@ impl Phase {
             name,
             bytes_allocated: 0,
             allocation_count: 0,
+            frame_bytes: 0,
+            own_frame_bytes: 0,
+            bytes_freed: 0,
+            peak_bytes: 0,
             #[cfg(feature = "diagnostics")]
             start_time: Some(std::time::Instant::now()),
+            #[cfg(feature = "diagnostics")]
+            end_time: None,
         }
     }
 
@@ -36,21 +68,109 @@ impl Phase {
     pub fn record_alloc(&mut self, size: usize) {
         self.bytes_allocated += size;
         self.allocation_count += 1;
+        self.peak_bytes = self.peak_bytes.max(self.net_bytes());
+    }
+
+    /// Record a free in this phase.
+    pub fn record_free(&mut self, size: usize) {
+        self.bytes_freed += size;
+    }
+
+    /// Get bytes allocated minus bytes freed in this phase.
+    pub fn net_bytes(&self) -> usize {
+        self.bytes_allocated.saturating_sub(self.bytes_freed)
     }
 
     /// Get phase duration (if timing enabled).
+    ///
+    /// For a phase that is still running this is the time elapsed so far.
     #[cfg(feature = "diagnostics")]
     pub fn duration(&self) -> Option<std::time::Duration> {
-        self.start_time.map(|t| t.elapsed())
+        let end = self.end_time.unwrap_or_else(std::time::Instant::now);
+        self.start_time.map(|t| end.duration_since(t))
     }
+
+    /// Get phase duration (always None without the `diagnostics` feature).
+    #[cfg(not(feature = "diagnostics"))]
+    pub fn duration(&self) -> Option<std::time::Duration> {
+        None
+    }
+}
+
+/// Which allocator an allocation charged to a phase came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhaseAllocKind {
+    /// Frame arena
+    Frame,
+    /// Small object pool
+    Pool,
+    /// System heap
+    Heap,
 }
 
 /// Tracks the current phase stack for a thread.
 pub struct PhaseTracker {
     /// Stack of active phases (supports nesting)
     stack: Vec<Phase>,
+    /// Budget state for each entry of `stack`
+    budgets: Vec<ActiveBudget>,
+    /// Number of active phases with a budget
+    budgeted: usize,
     /// Completed phases this frame
     completed: Vec<Phase>,
+    /// Budget breaches waiting to be emitted
+    breaches: Vec<BudgetBreach>,
+}
+
+/// Budget state attached to an active phase.
+#[derive(Default)]
+struct ActiveBudget {
+    /// Where high-water marks and events go
+    registry: Option<Arc<PhaseBudgets>>,
+    /// Budget in force for this instance
+    budget: Option<PhaseBudget>,
+    /// Whether this instance has exceeded its budget
+    exceeded: bool,
+}
+
+/// A phase that just went over its budget.
+///
+/// Breaches are queued while the allocator's thread-local state is
+/// borrowed and emitted once it is released, so callbacks and sinks may
+/// use the allocator.
+pub(crate) struct BudgetBreach {
+    registry: Arc<PhaseBudgets>,
+    budget: PhaseBudget,
+    current: usize,
+}
+
+impl BudgetBreach {
+    /// Report the breach as a budget event, and as FA303 if diagnostic.
+    pub(crate) fn emit(self) {
+        self.registry.emit_event(BudgetEvent::PhaseLimitExceeded {
+            phase: self.budget.phase,
+            current: self.current,
+            limit: self.budget.limit,
+        });
+        if self.budget.policy == PhaseBudgetPolicy::Diagnostic {
+            let mut diag = RuntimeDiagnostic::from(&FA303)
+                .with_phase(self.budget.phase)
+                .with_size(self.current)
+                .with_limit(self.budget.limit);
+            // Prefer the name registered with this allocator
+            let registered = self.registry.allocator().and_then(|id| {
+                tls::with_existing(id, |tls| tls.record().registered_name()).flatten()
+            });
+            diag = match registered {
+                Some(name) => diag.with_thread(name),
+                None => diag.with_current_thread(),
+            };
+            if let Some(id) = self.registry.allocator() {
+                diag = diag.with_allocator(id.as_u64());
+            }
+            crate::diagnostics::emit::emit_runtime(&diag);
+        }
+    }
 }
 
 impl PhaseTracker {
@@ -58,23 +178,99 @@ impl PhaseTracker {
     pub fn new() -> Self {
         Self {
             stack: Vec::with_capacity(8),
+            budgets: Vec::with_capacity(8),
+            budgeted: 0,
             completed: Vec::with_capacity(16),
+            breaches: Vec::new(),
         }
     }
 
     /// Begin a new phase.
     pub fn begin_phase(&mut self, name: &'static str) {
+        self.begin_phase_with(name, None);
+    }
+
+    /// Begin a phase whose budget and high-water mark live in `registry`.
+    pub fn begin_phase_with(&mut self, name: &'static str, registry: Option<Arc<PhaseBudgets>>) {
+        let budget = registry.as_ref().and_then(|r| r.get(name));
+        if budget.is_some() {
+            self.budgeted += 1;
+        }
         self.stack.push(Phase::new(name));
+        self.budgets.push(ActiveBudget {
+            registry,
+            budget,
+            exceeded: false,
+        });
     }
 
     /// End the current phase.
     pub fn end_phase(&mut self) -> Option<Phase> {
-        if let Some(phase) = self.stack.pop() {
-            self.completed.push(phase.clone());
-            Some(phase)
-        } else {
-            None
+        #[allow(unused_mut)]
+        let mut phase = self.stack.pop()?;
+        #[cfg(feature = "diagnostics")]
+        {
+            phase.end_time = Some(std::time::Instant::now());
+        }
+
+        let active = self.budgets.pop().unwrap_or_default();
+        if active.budget.is_some() {
+            self.budgeted -= 1;
+        }
+        if let Some(registry) = &active.registry {
+            registry.record(&phase, active.exceeded);
+        }
+
+        self.completed.push(phase.clone());
+        Some(phase)
+    }
+
+    /// Check a pending frame allocation against the active phase budgets.
+    ///
+    /// Queues the phases that just went over budget and returns whether
+    /// the allocation should fall back to the heap.
+    fn check_frame_budget(&mut self, size: usize) -> bool {
+        let mut fallback = false;
+
+        for (phase, active) in self.stack.iter().zip(self.budgets.iter_mut()) {
+            let Some(budget) = active.budget else { continue };
+            let projected = phase.frame_bytes + size;
+            if projected <= budget.limit {
+                continue;
+            }
+
+            fallback |= budget.policy == PhaseBudgetPolicy::Fallback;
+            if !active.exceeded {
+                active.exceeded = true;
+                if let Some(registry) = &active.registry {
+                    self.breaches.push(BudgetBreach {
+                        registry: registry.clone(),
+                        budget,
+                        current: projected,
+                    });
+                }
+            }
+        }
+
+        fallback
+    }
+
+    /// Check a pending frame allocation against the active phase budgets.
+    ///
+    /// Queues events and diagnostics for phases that just went over (see
+    /// `take_breaches`), and returns true if the allocation should be served
+    /// from the heap instead.
+    #[inline]
+    pub(crate) fn frame_budget_fallback(&mut self, size: usize) -> bool {
+        if self.budgeted == 0 {
+            return false;
         }
+        self.check_frame_budget(size)
+    }
+
+    /// Take the budget breaches queued since the last call.
+    pub(crate) fn take_breaches(&mut self) -> Vec<BudgetBreach> {
+        std::mem::take(&mut self.breaches)
     }
 
     /// Get the current phase name.
@@ -82,10 +278,30 @@ impl PhaseTracker {
         self.stack.last().map(|p| p.name)
     }
 
-    /// Record an allocation in the current phase.
+    /// Record an allocation in every active phase.
     pub fn record_alloc(&mut self, size: usize) {
-        if let Some(phase) = self.stack.last_mut() {
+        self.record_alloc_kind(size, PhaseAllocKind::Pool);
+    }
+
+    /// Record an allocation from a specific allocator in every active phase.
+    pub fn record_alloc_kind(&mut self, size: usize, kind: PhaseAllocKind) {
+        for phase in &mut self.stack {
             phase.record_alloc(size);
+            if kind == PhaseAllocKind::Frame {
+                phase.frame_bytes += size;
+            }
+        }
+        if kind == PhaseAllocKind::Frame {
+            if let Some(innermost) = self.stack.last_mut() {
+                innermost.own_frame_bytes += size;
+            }
+        }
+    }
+
+    /// Record a free in every active phase.
+    pub fn record_free(&mut self, size: usize) {
+        for phase in &mut self.stack {
+            phase.record_free(size);
         }
     }
 
@@ -94,9 +310,23 @@ impl PhaseTracker {
         &self.completed
     }
 
+    /// Get the phases still on the stack, outermost first.
+    pub fn active_phases(&self) -> &[Phase] {
+        &self.stack
+    }
+
+    /// Get every phase seen this frame: completed ones, then those still active.
+    pub fn frame_phases(&self) -> Vec<Phase> {
+        let mut phases = self.completed.clone();
+        phases.extend_from_slice(&self.stack);
+        phases
+    }
+
     /// Reset for a new frame.
     pub fn reset(&mut self) {
         self.stack.clear();
+        self.budgets.clear();
+        self.budgeted = 0;
         self.completed.clear();
     }
 
@@ -121,58 +351,96 @@ thread_local! {
     static PHASE_TRACKER: RefCell<PhaseTracker> = RefCell::new(PhaseTracker::new());
 }
 
-/// Begin a named phase within the current frame.
+/// Begin a named phase on this thread's standalone tracker.
 pub fn begin_phase(name: &'static str) {
     PHASE_TRACKER.with(|t| t.borrow_mut().begin_phase(name));
 }
 
-/// End the current phase.
+/// End the current standalone phase.
 pub fn end_phase() -> Option<Phase> {
     PHASE_TRACKER.with(|t| t.borrow_mut().end_phase())
 }
 
-/// Get the current phase name.
+/// Get the current standalone phase name.
 pub fn current_phase() -> Option<&'static str> {
     PHASE_TRACKER.with(|t| t.borrow().current_phase())
 }
 
-/// Record an allocation in the current phase.
+/// Record an allocation in the active standalone phases.
 pub fn record_phase_alloc(size: usize) {
     PHASE_TRACKER.with(|t| t.borrow_mut().record_alloc(size));
 }
 
-/// Reset phases for a new frame.
+/// Reset the standalone phases for a new frame.
 pub fn reset_phases() {
     PHASE_TRACKER.with(|t| t.borrow_mut().reset());
 }
 
-/// Check if currently in a phase.
+/// Check if currently in a standalone phase.
 pub fn is_in_phase() -> bool {
     PHASE_TRACKER.with(|t| t.borrow().is_in_phase())
 }
 
 /// RAII guard for a phase scope.
 pub struct PhaseGuard {
-    _private: (),
+    /// Profiler notified of the phase boundaries
+    hooks: Option<Arc<ProfilerHooks>>,
+    /// Allocator whose phase stack holds the phase (None = standalone)
+    global: Option<Arc<GlobalState>>,
 }
 
 impl PhaseGuard {
-    /// Create a new phase guard.
+    /// Create a new phase guard on the standalone tracker.
     pub fn new(name: &'static str) -> Self {
         begin_phase(name);
-        Self { _private: () }
+        Self { hooks: None, global: None }
+    }
+
+    /// Create a standalone phase guard that also opens a profiler zone.
+    pub fn with_hooks(name: &'static str, hooks: Option<Arc<ProfilerHooks>>) -> Self {
+        begin_phase(name);
+        if let Some(hooks) = &hooks {
+            hooks.emit_zone_begin(name);
+        }
+        Self { hooks, global: None }
+    }
+
+    /// Create a phase guard on an allocator's phase stack, governed by its
+    /// phase budgets.
+    pub(crate) fn for_allocator(
+        name: &'static str,
+        global: &Arc<GlobalState>,
+        hooks: Option<Arc<ProfilerHooks>>,
+        budgets: Arc<PhaseBudgets>,
+    ) -> Self {
+        tls::with_tls(global, |tls| tls.phases_mut().begin_phase_with(name, Some(budgets)));
+        if let Some(hooks) = &hooks {
+            hooks.emit_zone_begin(name);
+        }
+        Self { hooks, global: Some(global.clone()) }
     }
 }
 
 impl Drop for PhaseGuard {
     fn drop(&mut self) {
-        end_phase();
+        match &self.global {
+            Some(global) => {
+                tls::with_tls(global, |tls| tls.phases_mut().end_phase());
+            }
+            None => {
+                end_phase();
+            }
+        }
+        if let Some(hooks) = &self.hooks {
+            hooks.emit_zone_end();
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     #[test]
     fn test_phase_tracking() {
@@ -205,6 +473,80 @@ mod tests {
         assert_eq!(current_phase(), None);
     }
 
+    #[test]
+    fn test_nested_phases_are_charged() {
+        let mut tracker = PhaseTracker::new();
+
+        tracker.begin_phase("update");
+        tracker.record_alloc_kind(100, PhaseAllocKind::Frame);
+        tracker.begin_phase("physics");
+        tracker.record_alloc_kind(400, PhaseAllocKind::Pool);
+        tracker.record_free(400);
+        tracker.record_alloc_kind(50, PhaseAllocKind::Heap);
+
+        let physics = tracker.end_phase().unwrap();
+        assert_eq!(physics.bytes_allocated, 450);
+        assert_eq!(physics.peak_bytes, 400);
+        assert_eq!(physics.net_bytes(), 50);
+
+        let update = tracker.end_phase().unwrap();
+        assert_eq!(update.bytes_allocated, 550);
+        assert_eq!(update.allocation_count, 3);
+        assert_eq!(update.frame_bytes, 100);
+        assert_eq!(update.peak_bytes, 500);
+
+        // Outside any phase nothing is recorded
+        tracker.record_alloc_kind(64, PhaseAllocKind::Frame);
+        assert_eq!(tracker.frame_phases().len(), 2);
+    }
+
+    #[test]
+    fn test_phase_budget_breach() {
+        let mut tracker = PhaseTracker::new();
+        let budgets = Arc::new(PhaseBudgets::new());
+        budgets.set("physics", 1000, PhaseBudgetPolicy::Fallback);
+
+        let events = Arc::new(AtomicUsize::new(0));
+        let counter = events.clone();
+        budgets.set_event_callback(move |event| {
+            assert!(matches!(
+                event,
+                BudgetEvent::PhaseLimitExceeded { phase: "physics", current: 1200, limit: 1000 }
+            ));
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+
+        tracker.begin_phase_with("physics", Some(budgets.clone()));
+        assert!(!tracker.frame_budget_fallback(800));
+        tracker.record_alloc_kind(800, PhaseAllocKind::Frame);
+        assert!(tracker.frame_budget_fallback(400));
+        assert!(tracker.frame_budget_fallback(400));
+        tracker.record_alloc_kind(400, PhaseAllocKind::Frame);
+        tracker.end_phase();
+        assert_eq!(events.load(Ordering::Relaxed), 0);
+        for breach in tracker.take_breaches() {
+            breach.emit();
+        }
+
+        // The event fires once per phase instance
+        assert_eq!(events.load(Ordering::Relaxed), 1);
+
+        let mark = budgets.high_water("physics").unwrap();
+        assert_eq!(mark.peak_frame_bytes, 1200);
+        assert_eq!(mark.samples, 1);
+        assert_eq!(mark.over_budget, 1);
+
+        // High-water marks persist across frames
+        tracker.reset();
+        tracker.begin_phase_with("physics", Some(budgets.clone()));
+        tracker.record_alloc_kind(100, PhaseAllocKind::Frame);
+        tracker.end_phase();
+        let mark = budgets.high_water("physics").unwrap();
+        assert_eq!(mark.peak_frame_bytes, 1200);
+        assert_eq!(mark.last_frame_bytes, 100);
+        assert_eq!(mark.samples, 2);
+    }
+
     #[test]
     fn test_phase_guard() {
         reset_phases();
@@ -4,6 +4,9 @@
 //! to their designated destination allocators.
 
 use std::alloc::Layout;
+use std::time::Duration;
+
+use crate::api::phases::Phase;
 
 use crate::api::retention::{
     PromotedAllocation, PromotionFailure, RetainedAllocation, RetainedMeta, RetentionPolicy,
@@ -76,6 +79,7 @@ pub struct FailureBreakdown {
     pub scratch_pool_not_found: usize,
     pub scratch_pool_full: usize,
     pub too_large: usize,
+    pub stale_epoch: usize,
     pub internal_error: usize,
 }
 
@@ -86,6 +90,7 @@ impl FailureBreakdown {
             PromotionFailure::ScratchPoolNotFound => self.scratch_pool_not_found += 1,
             PromotionFailure::ScratchPoolFull => self.scratch_pool_full += 1,
             PromotionFailure::TooLarge => self.too_large += 1,
+            PromotionFailure::StaleEpoch => self.stale_epoch += 1,
             PromotionFailure::InternalError => self.internal_error += 1,
         }
     }
@@ -101,14 +106,70 @@ pub struct TagSummary {
 }
 
 /// Per-phase summary.
-#[derive(Debug, Clone)]
+///
+/// Phases that ran more than once in a frame are merged by name.
+#[derive(Debug, Clone, Default)]
 pub struct PhaseSummary {
     pub phase: &'static str,
+    /// Frame, pool and heap bytes allocated in the phase
+    pub bytes_allocated: usize,
+    /// Number of allocations in the phase
+    pub allocation_count: usize,
+    /// Highest net bytes held during the phase
+    pub peak_bytes: usize,
+    /// Time spent in the phase (None without the `diagnostics` feature)
+    pub duration: Option<Duration>,
     pub discarded_bytes: usize,
     pub promoted_bytes: usize,
     pub failed_bytes: usize,
 }
 
+impl FrameSummary {
+    /// Get the summary for a phase, creating it if needed.
+    fn phase_entry(&mut self, phase: &'static str) -> &mut PhaseSummary {
+        let index = match self.by_phase.iter().position(|p| p.phase == phase) {
+            Some(index) => index,
+            None => {
+                self.by_phase.push(PhaseSummary {
+                    phase,
+                    ..Default::default()
+                });
+                self.by_phase.len() - 1
+            }
+        };
+        &mut self.by_phase[index]
+    }
+
+    /// Fold the frame's phase accounting into `by_phase`.
+    ///
+    /// Frame bytes that were neither promoted nor failed count as discarded.
+    /// Each byte is charged to the innermost phase only, so nested phases
+    /// are not counted twice.
+    pub(crate) fn record_phases(&mut self, phases: &[Phase]) {
+        let mut frame_bytes: Vec<(&'static str, usize)> = Vec::new();
+
+        for phase in phases {
+            let entry = self.phase_entry(phase.name);
+            entry.bytes_allocated += phase.bytes_allocated;
+            entry.allocation_count += phase.allocation_count;
+            entry.peak_bytes = entry.peak_bytes.max(phase.peak_bytes);
+            if let Some(duration) = phase.duration() {
+                entry.duration = Some(entry.duration.unwrap_or_default() + duration);
+            }
+
+            match frame_bytes.iter_mut().find(|(name, _)| *name == phase.name) {
+                Some((_, bytes)) => *bytes += phase.own_frame_bytes,
+                None => frame_bytes.push((phase.name, phase.own_frame_bytes)),
+            }
+        }
+
+        for (name, bytes) in frame_bytes {
+            let entry = self.phase_entry(name);
+            entry.discarded_bytes = bytes.saturating_sub(entry.promoted_bytes + entry.failed_bytes);
+        }
+    }
+}
+
 /// Result of processing retained allocations.
 pub struct PromotionResult {
     /// Successfully promoted allocations
@@ -125,6 +186,8 @@ pub struct PromotionProcessor<'a> {
     heap_alloc: Option<Box<dyn FnMut(Layout) -> *mut u8 + 'a>>,
     /// Scratch pool allocator callback
     scratch_alloc: Option<Box<dyn FnMut(&'static str, Layout) -> Option<*mut u8> + 'a>>,
+    /// Thread epoch being ended; older allocations are stale
+    epoch: Option<u64>,
 }
 
 impl<'a> PromotionProcessor<'a> {
@@ -133,8 +196,15 @@ impl<'a> PromotionProcessor<'a> {
             pool_alloc: None,
             heap_alloc: None,
             scratch_alloc: None,
+            epoch: None,
         }
     }
+
+    /// Reject allocations made in any thread epoch other than `epoch`.
+    pub fn with_epoch(mut self, epoch: u64) -> Self {
+        self.epoch = Some(epoch);
+        self
+    }
     
     pub fn with_pool_alloc<F>(mut self, f: F) -> Self
     where
@@ -167,7 +237,14 @@ impl<'a> PromotionProcessor<'a> {
         let mut summary = FrameSummary::default();
         
         for alloc in retained {
-            let result = self.promote_one(&alloc.meta);
+            let result = match self.epoch {
+                // The frame memory behind a stale allocation has been reset
+                Some(epoch) if alloc.meta.epoch != epoch => PromotedAllocation::Failed {
+                    reason: PromotionFailure::StaleEpoch,
+                    meta: alloc.meta.clone(),
+                },
+                _ => self.promote_one(&alloc.meta),
+            };
             
             match &result {
                 PromotedAllocation::Pool { size, .. } => {
@@ -215,6 +292,15 @@ impl<'a> PromotionProcessor<'a> {
                     summary.failures_by_reason.record(*reason);
                 }
             }
+
+            if let Some(phase) = alloc.meta.phase {
+                let entry = summary.phase_entry(phase);
+                if let PromotedAllocation::Failed { .. } = result {
+                    entry.failed_bytes += alloc.meta.size;
+                } else {
+                    entry.promoted_bytes += alloc.meta.size;
+                }
+            }
             
             promoted.push(result);
         }
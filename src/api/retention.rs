@@ -27,7 +27,6 @@
 //! }
 //! ```
 
-use std::cell::RefCell;
 use std::marker::PhantomData;
 
 /// Policy for what happens to a frame allocation at frame end.
@@ -127,6 +126,10 @@ pub struct RetainedMeta {
     pub size: usize,
     /// Allocation tag (if any)
     pub tag: Option<&'static str>,
+    /// Phase active when the allocation was made (if any)
+    pub phase: Option<&'static str>,
+    /// Thread epoch the allocation was made in
+    pub epoch: u64,
     /// Type name (for diagnostics)
     pub type_name: &'static str,
 }
@@ -224,6 +227,8 @@ pub enum PromotionFailure {
     ScratchPoolFull,
     /// Allocation too large for destination
     TooLarge,
+    /// Allocated in an earlier thread epoch; its frame memory was reset
+    StaleEpoch,
     /// Internal error
     InternalError,
 }
@@ -235,6 +240,7 @@ impl std::fmt::Display for PromotionFailure {
             Self::ScratchPoolNotFound => write!(f, "scratch pool not found"),
             Self::ScratchPoolFull => write!(f, "scratch pool full"),
             Self::TooLarge => write!(f, "allocation too large"),
+            Self::StaleEpoch => write!(f, "allocated in an earlier frame epoch"),
             Self::InternalError => write!(f, "internal error"),
         }
     }
@@ -300,7 +306,7 @@ impl<'a, T> std::ops::DerefMut for FrameRetained<'a, T> {
     }
 }
 
-/// Thread-local registry of retained allocations.
+/// Registry of retained allocations, kept per allocator and thread.
 pub(crate) struct RetentionRegistry {
     allocations: Vec<RetainedAllocation>,
     next_id: usize,
@@ -346,31 +352,6 @@ impl Default for RetentionRegistry {
     }
 }
 
-thread_local! {
-    pub(crate) static RETENTION_REGISTRY: RefCell<RetentionRegistry> = 
-        RefCell::new(RetentionRegistry::new());
-}
-
-/// Register a retained allocation.
-pub(crate) fn register_retained(alloc: RetainedAllocation) -> usize {
-    RETENTION_REGISTRY.with(|r| r.borrow_mut().register(alloc))
-}
-
-/// Take all retained allocations for processing.
-pub(crate) fn take_retained() -> Vec<RetainedAllocation> {
-    RETENTION_REGISTRY.with(|r| r.borrow_mut().take_all())
-}
-
-/// Clear retained allocations without processing.
-pub(crate) fn clear_retained() {
-    RETENTION_REGISTRY.with(|r| r.borrow_mut().clear());
-}
-
-/// Get count of retained allocations.
-pub(crate) fn retained_count() -> usize {
-    RETENTION_REGISTRY.with(|r| r.borrow().len())
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -397,12 +378,14 @@ mod tests {
     
     #[test]
     fn test_retention_registry() {
-        clear_retained();
+        let mut registry = RetentionRegistry::new();
         
         let meta = RetainedMeta {
             policy: RetentionPolicy::PromoteToPool,
             size: 64,
             tag: None,
+            phase: None,
+            epoch: 0,
             type_name: "TestType",
         };
         
@@ -417,12 +400,12 @@ mod tests {
             }),
         };
         
-        let id = register_retained(alloc);
+        let id = registry.register(alloc);
         assert_eq!(id, 0);
-        assert_eq!(retained_count(), 1);
+        assert_eq!(registry.len(), 1);
         
-        let taken = take_retained();
+        let taken = registry.take_all();
         assert_eq!(taken.len(), 1);
-        assert_eq!(retained_count(), 0);
+        assert_eq!(registry.len(), 0);
     }
 }
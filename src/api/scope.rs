@@ -1,7 +1,7 @@
 //! Frame scope guards for RAII-style frame management.
 
 use crate::api::alloc::SmartAlloc;
-use crate::core::tls;
+use crate::core::tls::{self, FrameMark};
 
 /// A guard that represents a frame scope.
 ///
@@ -23,14 +23,14 @@ use crate::core::tls;
 /// ```
 pub struct FrameGuard<'a> {
     alloc: &'a SmartAlloc,
-    saved_head: usize,
+    saved: FrameMark,
 }
 
 impl<'a> FrameGuard<'a> {
     /// Create a new frame guard.
     pub(crate) fn new(alloc: &'a SmartAlloc) -> Self {
-        let saved_head = tls::with_tls(|tls| tls.frame_head());
-        Self { alloc, saved_head }
+        let saved = tls::with_tls(alloc.global(), |tls| tls.frame_mark());
+        Self { alloc, saved }
     }
 
     /// Allocate from this scope's frame arena.
@@ -41,8 +41,8 @@ impl<'a> FrameGuard<'a> {
 
 impl<'a> Drop for FrameGuard<'a> {
     fn drop(&mut self) {
-        tls::with_tls(|tls| {
-            tls.reset_frame_to(self.saved_head);
+        tls::with_tls(self.alloc.global(), |tls| {
+            tls.reset_frame_to(self.saved);
         });
     }
 }
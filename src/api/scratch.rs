@@ -2,12 +2,184 @@
 //!
 //! Scratch pools bridge frame and pool semantics. They're like frame arenas
 //! but live longer - cleared manually, on level unload, or via groups.
+//!
+//! Pools are looked up by `ScratchKey`, which accepts string literals, owned
+//! strings (e.g. per-level or per-chunk names) and interned strings. Hot
+//! loops can `checkout` a pool into an exclusive guard and bump-allocate
+//! without touching the registry lock.
 
 use std::alloc::{alloc, dealloc, Layout};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::{Deref, DerefMut};
 use std::ptr::NonNull;
+use std::sync::{Arc, OnceLock};
 
+use crate::api::groups::GroupId;
 use crate::sync::mutex::Mutex;
+use crate::util::layout::padding_for;
+
+/// Name of a scratch pool.
+///
+/// Built from a `&'static str` without allocating, from an owned `String`,
+/// or with `interned` for names that recur at runtime.
+#[derive(Clone)]
+pub struct ScratchKey(KeyRepr);
+
+#[derive(Clone)]
+enum KeyRepr {
+    Static(&'static str),
+    Shared(Arc<str>),
+}
+
+impl ScratchKey {
+    /// Intern a runtime name.
+    ///
+    /// Each distinct name is stored once for the life of the process, so
+    /// repeated lookups of the same name never allocate. Use an owned
+    /// `String` instead for names that are unbounded over time.
+    pub fn interned(name: &str) -> Self {
+        static INTERNER: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+
+        let mut names = INTERNER.get_or_init(|| Mutex::new(HashSet::new())).lock();
+        if let Some(&existing) = names.get(name) {
+            return Self(KeyRepr::Static(existing));
+        }
+        let leaked: &'static str = Box::leak(name.to_owned().into_boxed_str());
+        names.insert(leaked);
+        Self(KeyRepr::Static(leaked))
+    }
+
+    /// Get the name as a string slice.
+    pub fn as_str(&self) -> &str {
+        match &self.0 {
+            KeyRepr::Static(s) => s,
+            KeyRepr::Shared(s) => s,
+        }
+    }
+}
+
+impl PartialEq for ScratchKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for ScratchKey {}
+
+impl Hash for ScratchKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl fmt::Debug for ScratchKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for ScratchKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<&'static str> for ScratchKey {
+    fn from(name: &'static str) -> Self {
+        Self(KeyRepr::Static(name))
+    }
+}
+
+impl From<String> for ScratchKey {
+    fn from(name: String) -> Self {
+        Self(KeyRepr::Shared(name.into()))
+    }
+}
+
+impl From<Arc<str>> for ScratchKey {
+    fn from(name: Arc<str>) -> Self {
+        Self(KeyRepr::Shared(name))
+    }
+}
+
+impl From<&ScratchKey> for ScratchKey {
+    fn from(key: &ScratchKey) -> Self {
+        key.clone()
+    }
+}
+
+/// How a scratch pool grows when an allocation does not fit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScratchGrowth {
+    /// Never grow; allocations fail when the pool is full
+    Fixed,
+    /// Add chunks twice the size of the previous one, up to `max_capacity` in total
+    Double {
+        /// Upper bound on the pool's total capacity
+        max_capacity: usize,
+    },
+    /// Add chunks of `chunk_size` bytes, up to `max_capacity` in total
+    Linear {
+        /// Size of each additional chunk
+        chunk_size: usize,
+        /// Upper bound on the pool's total capacity
+        max_capacity: usize,
+    },
+}
+
+/// Capacity and growth settings for a scratch pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScratchPoolConfig {
+    /// Initial capacity in bytes
+    pub capacity: usize,
+    /// Growth policy once the initial capacity is used up
+    pub growth: ScratchGrowth,
+}
+
+impl ScratchPoolConfig {
+    /// A fixed-size pool.
+    pub fn fixed(capacity: usize) -> Self {
+        Self {
+            capacity,
+            growth: ScratchGrowth::Fixed,
+        }
+    }
+
+    /// Builder pattern: set the growth policy.
+    pub fn with_growth(mut self, growth: ScratchGrowth) -> Self {
+        self.growth = growth;
+        self
+    }
+}
+
+/// Alignment of chunk bases (one cache line).
+const CHUNK_ALIGN: usize = 64;
+
+/// A contiguous block owned by a scratch pool.
+struct ScratchChunk {
+    base: NonNull<u8>,
+    capacity: usize,
+}
+
+impl ScratchChunk {
+    fn new(capacity: usize) -> Self {
+        let layout = Layout::from_size_align(capacity, CHUNK_ALIGN).expect("Invalid layout");
+        let ptr = unsafe { alloc(layout) };
+        let base = NonNull::new(ptr).expect("Failed to allocate scratch pool");
+        Self { base, capacity }
+    }
+}
+
+impl Drop for ScratchChunk {
+    fn drop(&mut self) {
+        let layout = Layout::from_size_align(self.capacity, CHUNK_ALIGN).expect("Invalid layout");
+        unsafe {
+            dealloc(self.base.as_ptr(), layout);
+        }
+    }
+}
 
 /// A named scratch pool for cross-frame temporary allocations.
 ///
@@ -15,35 +187,58 @@ use crate::sync::mutex::Mutex;
 /// - Lives longer than a frame
 /// - But is still scratch-like (bulk freed)
 /// - Is associated with a subsystem or task
+///
+/// A growable pool adds chunks as it fills and consolidates them into a
+/// single chunk of the combined size on `reset`.
 pub struct ScratchPool {
     /// Name of this pool
-    name: &'static str,
-    /// Base pointer
-    base: NonNull<u8>,
-    /// Current head
+    name: ScratchKey,
+    /// Backing chunks; allocation happens in the last one
+    chunks: Vec<ScratchChunk>,
+    /// Current head within the last chunk
     head: usize,
-    /// Capacity
-    capacity: usize,
+    /// Bytes used in earlier chunks
+    filled: usize,
+    /// Growth policy
+    growth: ScratchGrowth,
+    /// Highest `allocated()` since creation
+    peak: usize,
 }
 
+// SAFETY: the pool exclusively owns its chunks.
+unsafe impl Send for ScratchPool {}
+
 impl ScratchPool {
-    /// Create a new scratch pool.
-    pub fn new(name: &'static str, capacity: usize) -> Self {
-        let layout = Layout::from_size_align(capacity, 16).expect("Invalid layout");
-        let ptr = unsafe { alloc(layout) };
-        let base = NonNull::new(ptr).expect("Failed to allocate scratch pool");
+    /// Create a new fixed-size scratch pool.
+    ///
+    /// A capacity of 0 is raised to one byte.
+    pub fn new(name: impl Into<ScratchKey>, capacity: usize) -> Self {
+        Self::with_config(name, ScratchPoolConfig::fixed(capacity))
+    }
 
+    /// Create a scratch pool with explicit capacity and growth settings.
+    ///
+    /// A capacity of 0 is raised to one byte.
+    pub fn with_config(name: impl Into<ScratchKey>, config: ScratchPoolConfig) -> Self {
         Self {
-            name,
-            base,
+            name: name.into(),
+            // The allocator may not be asked for a zero-size chunk
+            chunks: vec![ScratchChunk::new(config.capacity.max(1))],
             head: 0,
-            capacity,
+            filled: 0,
+            growth: config.growth,
+            peak: 0,
         }
     }
 
     /// Get the pool name.
-    pub fn name(&self) -> &'static str {
-        self.name
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Get the pool key.
+    pub fn key(&self) -> &ScratchKey {
+        &self.name
     }
 
     /// Allocate memory from this pool.
@@ -52,21 +247,53 @@ impl ScratchPool {
     }
 
     /// Allocate with a specific layout.
+    ///
+    /// Returns null if the pool is full and its growth policy forbids growing.
     pub fn alloc_layout(&mut self, layout: Layout) -> *mut u8 {
         let align = layout.align();
         let size = layout.size();
 
-        let aligned_head = (self.head + align - 1) & !(align - 1);
+        // Align the address, not the offset, so any alignment holds
+        let current = self.chunks.last().expect("scratch pool has no chunk");
+        let address = current.base.as_ptr() as usize + self.head;
+        let aligned_head = self.head + padding_for(address, align);
 
-        if aligned_head + size > self.capacity {
-            return std::ptr::null_mut();
+        if aligned_head.saturating_add(size) > current.capacity {
+            return match self.grow(size + align) {
+                Some(()) => self.alloc_layout(layout),
+                None => std::ptr::null_mut(),
+            };
         }
 
-        let ptr = unsafe { self.base.as_ptr().add(aligned_head) };
+        let ptr = unsafe { current.base.as_ptr().add(aligned_head) };
         self.head = aligned_head + size;
+        self.peak = self.peak.max(self.allocated());
         ptr
     }
 
+    /// Add a chunk able to hold at least `min_size` bytes, if the policy allows.
+    fn grow(&mut self, min_size: usize) -> Option<()> {
+        let last = self.chunks.last()?.capacity;
+        let (next, max_capacity) = match self.growth {
+            ScratchGrowth::Fixed => return None,
+            ScratchGrowth::Double { max_capacity } => (last.saturating_mul(2), max_capacity),
+            ScratchGrowth::Linear {
+                chunk_size,
+                max_capacity,
+            } => (chunk_size, max_capacity),
+        };
+
+        let next = next.max(min_size);
+        if self.capacity() + next > max_capacity {
+            return None;
+        }
+
+        self.filled += self.head;
+        self.head = 0;
+        self.chunks.push(ScratchChunk::new(next));
+        Some(())
+    }
+
     /// Allocate a slice.
     pub fn alloc_slice<T>(&mut self, count: usize) -> *mut T {
         let layout = Layout::array::<T>(count).expect("Invalid array layout");
@@ -74,116 +301,251 @@ impl ScratchPool {
     }
 
     /// Reset the pool, invalidating all allocations.
+    ///
+    /// A pool that grew is consolidated into one chunk of its total capacity.
     pub fn reset(&mut self) {
         self.head = 0;
+        self.filled = 0;
+
+        if self.chunks.len() > 1 {
+            let capacity = self.capacity();
+            self.chunks.clear();
+            self.chunks.push(ScratchChunk::new(capacity));
+        }
 
         #[cfg(feature = "debug")]
-        unsafe {
-            std::ptr::write_bytes(self.base.as_ptr(), 0xCD, self.capacity);
+        for chunk in &self.chunks {
+            unsafe {
+                std::ptr::write_bytes(chunk.base.as_ptr(), 0xCD, chunk.capacity);
+            }
         }
     }
 
     /// Get bytes allocated.
     pub fn allocated(&self) -> usize {
-        self.head
+        self.filled + self.head
     }
 
-    /// Get remaining capacity.
+    /// Get remaining capacity in the current chunk.
     pub fn remaining(&self) -> usize {
-        self.capacity - self.head
+        self.chunks.last().map_or(0, |c| c.capacity) - self.head
     }
 
     /// Get total capacity.
     pub fn capacity(&self) -> usize {
-        self.capacity
+        self.chunks.iter().map(|c| c.capacity).sum()
+    }
+
+    /// Get the number of backing chunks.
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Get the highest number of bytes allocated at once.
+    pub fn peak(&self) -> usize {
+        self.peak
+    }
+
+    fn stats(&self, checked_out: bool, group: Option<GroupId>) -> ScratchPoolStats {
+        ScratchPoolStats {
+            name: self.name.clone(),
+            allocated: self.allocated(),
+            capacity: self.capacity(),
+            peak: self.peak,
+            chunk_count: self.chunks.len(),
+            checked_out,
+            group,
+        }
     }
 }
 
-impl Drop for ScratchPool {
-    fn drop(&mut self) {
-        let layout = Layout::from_size_align(self.capacity, 16).expect("Invalid layout");
-        unsafe {
-            dealloc(self.base.as_ptr(), layout);
+/// Registry entry for one pool.
+struct PoolSlot {
+    /// The pool, or None while checked out
+    pool: Option<ScratchPool>,
+    /// Stats captured at checkout time
+    checkout_stats: Option<ScratchPoolStats>,
+    /// Reset requested while checked out
+    reset_on_return: bool,
+    /// Group whose unload resets this pool
+    group: Option<GroupId>,
+}
+
+impl PoolSlot {
+    fn new(pool: ScratchPool) -> Self {
+        Self {
+            pool: Some(pool),
+            checkout_stats: None,
+            reset_on_return: false,
+            group: None,
+        }
+    }
+
+    fn reset(&mut self) {
+        match self.pool.as_mut() {
+            Some(pool) => pool.reset(),
+            None => self.reset_on_return = true,
         }
     }
 }
 
 /// Registry of named scratch pools.
 pub struct ScratchRegistry {
-    pools: Mutex<HashMap<&'static str, ScratchPool>>,
-    default_capacity: usize,
+    pools: Mutex<HashMap<ScratchKey, PoolSlot>>,
+    default_config: ScratchPoolConfig,
 }
 
 impl ScratchRegistry {
-    /// Create a new registry.
+    /// Create a new registry whose pools are fixed at `default_capacity`.
     pub fn new(default_capacity: usize) -> Self {
+        Self::with_config(ScratchPoolConfig::fixed(default_capacity))
+    }
+
+    /// Create a new registry with default capacity and growth settings.
+    pub fn with_config(default_config: ScratchPoolConfig) -> Self {
         Self {
             pools: Mutex::new(HashMap::new()),
-            default_capacity,
+            default_config,
         }
     }
 
     /// Get or create a scratch pool by name.
-    pub fn get_or_create(&self, name: &'static str) -> ScratchPoolHandle<'_> {
+    pub fn get_or_create(&self, name: impl Into<ScratchKey>) -> ScratchPoolHandle<'_> {
+        self.get_or_create_with(name, self.default_config)
+    }
+
+    /// Get or create a scratch pool, using `config` if it has to be created.
+    pub fn get_or_create_with(
+        &self,
+        name: impl Into<ScratchKey>,
+        config: ScratchPoolConfig,
+    ) -> ScratchPoolHandle<'_> {
+        let key = name.into();
         let mut pools = self.pools.lock();
-        if !pools.contains_key(name) {
-            pools.insert(name, ScratchPool::new(name, self.default_capacity));
-        }
-        ScratchPoolHandle { registry: self, name }
+        pools
+            .entry(key.clone())
+            .or_insert_with(|| PoolSlot::new(ScratchPool::with_config(key.clone(), config)));
+        ScratchPoolHandle { registry: self, key }
     }
 
     /// Get a pool by name if it exists.
-    pub fn get(&self, name: &'static str) -> Option<ScratchPoolHandle<'_>> {
+    pub fn get(&self, name: impl Into<ScratchKey>) -> Option<ScratchPoolHandle<'_>> {
+        let key = name.into();
         let pools = self.pools.lock();
-        if pools.contains_key(name) {
-            Some(ScratchPoolHandle { registry: self, name })
+        if pools.contains_key(&key) {
+            Some(ScratchPoolHandle { registry: self, key })
         } else {
             None
         }
     }
 
+    /// Take exclusive ownership of a pool for lock-free allocation.
+    ///
+    /// Creates the pool if needed. Until the guard drops, the pool is absent
+    /// from the registry: handle allocations return null, and resets are
+    /// applied when the guard returns it. Returns None if the pool is
+    /// already checked out.
+    pub fn checkout(&self, name: impl Into<ScratchKey>) -> Option<ScratchGuard<'_>> {
+        let key = name.into();
+        let mut pools = self.pools.lock();
+        let slot = pools.entry(key.clone()).or_insert_with(|| {
+            PoolSlot::new(ScratchPool::with_config(key.clone(), self.default_config))
+        });
+
+        let pool = slot.pool.take()?;
+        slot.checkout_stats = Some(pool.stats(true, slot.group));
+        Some(ScratchGuard {
+            registry: self,
+            pool: Some(pool),
+        })
+    }
+
+    /// Return a checked-out pool.
+    fn check_in(&self, mut pool: ScratchPool) {
+        let mut pools = self.pools.lock();
+        // A pool removed while checked out is simply dropped here
+        if let Some(slot) = pools.get_mut(pool.key()) {
+            if std::mem::take(&mut slot.reset_on_return) {
+                pool.reset();
+            }
+            slot.checkout_stats = None;
+            slot.pool = Some(pool);
+        }
+    }
+
     /// Reset a pool by name.
-    pub fn reset(&self, name: &'static str) {
+    pub fn reset(&self, name: impl Into<ScratchKey>) {
         let mut pools = self.pools.lock();
-        if let Some(pool) = pools.get_mut(name) {
-            pool.reset();
+        if let Some(slot) = pools.get_mut(&name.into()) {
+            slot.reset();
         }
     }
 
     /// Reset all pools.
     pub fn reset_all(&self) {
         let mut pools = self.pools.lock();
-        for pool in pools.values_mut() {
-            pool.reset();
+        for slot in pools.values_mut() {
+            slot.reset();
         }
     }
 
     /// Remove a pool by name.
-    pub fn remove(&self, name: &'static str) {
+    pub fn remove(&self, name: impl Into<ScratchKey>) {
         let mut pools = self.pools.lock();
-        pools.remove(name);
+        pools.remove(&name.into());
+    }
+
+    /// Tie a pool to an allocation group.
+    ///
+    /// When the group is freed (e.g. its level unloads) the pool is reset.
+    /// Returns false if the pool does not exist.
+    pub fn bind_to_group(&self, name: impl Into<ScratchKey>, group: GroupId) -> bool {
+        let mut pools = self.pools.lock();
+        match pools.get_mut(&name.into()) {
+            Some(slot) => {
+                slot.group = Some(group);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Reset every pool bound to `group`.
+    ///
+    /// Called automatically when a `SmartAlloc` group is freed.
+    pub fn reset_group(&self, group: GroupId) {
+        let mut pools = self.pools.lock();
+        for slot in pools.values_mut().filter(|s| s.group == Some(group)) {
+            slot.reset();
+        }
     }
 
     /// Get stats for all pools.
+    ///
+    /// Checked-out pools report their state at checkout time.
     pub fn stats(&self) -> Vec<ScratchPoolStats> {
         let pools = self.pools.lock();
         pools
             .values()
-            .map(|p| ScratchPoolStats {
-                name: p.name,
-                allocated: p.allocated(),
-                capacity: p.capacity,
+            .filter_map(|slot| match &slot.pool {
+                Some(pool) => Some(pool.stats(false, slot.group)),
+                None => slot.checkout_stats.clone(),
             })
             .collect()
     }
 
     /// Execute a closure with mutable access to a pool.
-    pub fn with_pool<F, R>(&self, name: &'static str, f: F) -> Option<R>
+    ///
+    /// Returns None if the pool does not exist or is checked out.
+    pub fn with_pool<F, R>(&self, name: impl Into<ScratchKey>, f: F) -> Option<R>
     where
         F: FnOnce(&mut ScratchPool) -> R,
     {
         let mut pools = self.pools.lock();
-        pools.get_mut(name).map(f)
+        pools
+            .get_mut(&name.into())
+            .and_then(|slot| slot.pool.as_mut())
+            .map(f)
     }
 }
 
@@ -193,48 +555,89 @@ impl Default for ScratchRegistry {
     }
 }
 
+/// Exclusive, lock-free access to a checked-out scratch pool.
+///
+/// Dereferences to `ScratchPool`. The pool goes back to the registry when
+/// the guard is dropped.
+pub struct ScratchGuard<'a> {
+    registry: &'a ScratchRegistry,
+    pool: Option<ScratchPool>,
+}
+
+impl<'a> Deref for ScratchGuard<'a> {
+    type Target = ScratchPool;
+
+    fn deref(&self) -> &ScratchPool {
+        self.pool.as_ref().expect("scratch guard already returned")
+    }
+}
+
+impl<'a> DerefMut for ScratchGuard<'a> {
+    fn deref_mut(&mut self) -> &mut ScratchPool {
+        self.pool.as_mut().expect("scratch guard already returned")
+    }
+}
+
+impl<'a> Drop for ScratchGuard<'a> {
+    fn drop(&mut self) {
+        if let Some(pool) = self.pool.take() {
+            self.registry.check_in(pool);
+        }
+    }
+}
+
 /// A handle to a named scratch pool.
 pub struct ScratchPoolHandle<'a> {
     registry: &'a ScratchRegistry,
-    name: &'static str,
+    key: ScratchKey,
 }
 
 impl<'a> ScratchPoolHandle<'a> {
     /// Get the pool name.
-    pub fn name(&self) -> &'static str {
-        self.name
+    pub fn name(&self) -> &str {
+        self.key.as_str()
     }
 
     /// Allocate from this pool.
     pub fn alloc<T>(&self) -> *mut T {
         self.registry
-            .with_pool(self.name, |p| p.alloc::<T>())
+            .with_pool(&self.key, |p| p.alloc::<T>())
             .unwrap_or(std::ptr::null_mut())
     }
 
     /// Allocate a slice from this pool.
     pub fn alloc_slice<T>(&self, count: usize) -> *mut T {
         self.registry
-            .with_pool(self.name, |p| p.alloc_slice::<T>(count))
+            .with_pool(&self.key, |p| p.alloc_slice::<T>(count))
             .unwrap_or(std::ptr::null_mut())
     }
 
+    /// Check this pool out for lock-free allocation.
+    pub fn checkout(&self) -> Option<ScratchGuard<'a>> {
+        self.registry.checkout(&self.key)
+    }
+
+    /// Reset this pool when `group` is freed.
+    pub fn bind_to_group(&self, group: GroupId) -> bool {
+        self.registry.bind_to_group(&self.key, group)
+    }
+
     /// Reset this pool.
     pub fn reset(&self) {
-        self.registry.reset(self.name);
+        self.registry.reset(&self.key);
     }
 
     /// Get allocated bytes.
     pub fn allocated(&self) -> usize {
         self.registry
-            .with_pool(self.name, |p| p.allocated())
+            .with_pool(&self.key, |p| p.allocated())
             .unwrap_or(0)
     }
 
     /// Get remaining capacity.
     pub fn remaining(&self) -> usize {
         self.registry
-            .with_pool(self.name, |p| p.remaining())
+            .with_pool(&self.key, |p| p.remaining())
             .unwrap_or(0)
     }
 }
@@ -243,11 +646,19 @@ impl<'a> ScratchPoolHandle<'a> {
 #[derive(Debug, Clone)]
 pub struct ScratchPoolStats {
     /// Pool name
-    pub name: &'static str,
+    pub name: ScratchKey,
     /// Bytes currently allocated
     pub allocated: usize,
     /// Total capacity
     pub capacity: usize,
+    /// Highest number of bytes allocated at once
+    pub peak: usize,
+    /// Number of backing chunks
+    pub chunk_count: usize,
+    /// Whether the pool is currently checked out
+    pub checked_out: bool,
+    /// Group whose unload resets this pool
+    pub group: Option<GroupId>,
 }
 
 #[cfg(test)]
@@ -270,6 +681,21 @@ mod tests {
         assert_eq!(pool.allocated(), 0);
     }
 
+    #[test]
+    fn test_over_aligned_across_growth() {
+        let config = ScratchPoolConfig::fixed(256)
+            .with_growth(ScratchGrowth::Double { max_capacity: 64 * 1024 });
+        let mut pool = ScratchPool::with_config("simd", config);
+
+        for align in [32, 64, 128, 512, 2048] {
+            let _ = pool.alloc::<u8>();
+            let ptr = pool.alloc_layout(Layout::from_size_align(100, align).unwrap());
+            assert!(!ptr.is_null());
+            assert_eq!(ptr as usize % align, 0);
+        }
+        assert!(pool.chunk_count() > 1);
+    }
+
     #[test]
     fn test_scratch_registry() {
         let registry = ScratchRegistry::new(4096);
@@ -283,4 +709,89 @@ mod tests {
         handle.reset();
         assert_eq!(handle.allocated(), 0);
     }
+
+    #[test]
+    fn test_dynamic_names() {
+        let registry = ScratchRegistry::new(1024);
+
+        for level in 0..3 {
+            let handle = registry.get_or_create(format!("level_{}", level));
+            assert!(!handle.alloc::<u64>().is_null());
+        }
+        assert_eq!(registry.stats().len(), 3);
+
+        // Owned, interned and static keys address the same pool
+        let interned = ScratchKey::interned("level_1");
+        assert!(registry.get(interned.clone()).is_some());
+        assert_eq!(registry.get("level_1").unwrap().allocated(), 8);
+        assert_eq!(
+            ScratchKey::interned("level_1").as_str().as_ptr(),
+            interned.as_str().as_ptr()
+        );
+    }
+
+    #[test]
+    fn test_checkout_is_exclusive() {
+        let registry = ScratchRegistry::new(4096);
+        let handle = registry.get_or_create("jobs");
+
+        {
+            let mut guard = handle.checkout().unwrap();
+            assert!(!guard.alloc::<[u8; 64]>().is_null());
+
+            // Registry access is blocked while checked out
+            assert!(registry.checkout("jobs").is_none());
+            assert!(handle.alloc::<u8>().is_null());
+            assert!(registry.stats()[0].checked_out);
+
+            // Resets are deferred until the pool comes back
+            registry.reset("jobs");
+            assert_eq!(guard.allocated(), 64);
+        }
+
+        assert_eq!(handle.allocated(), 0);
+        assert!(!handle.alloc::<u8>().is_null());
+    }
+
+    #[test]
+    fn test_growth_policies() {
+        let mut fixed = ScratchPool::new("fixed", 256);
+        assert!(fixed.alloc::<[u8; 512]>().is_null());
+
+        let mut empty = ScratchPool::new("empty", 0);
+        assert!(empty.alloc::<u64>().is_null());
+        empty.reset();
+
+        let config = ScratchPoolConfig::fixed(256)
+            .with_growth(ScratchGrowth::Double { max_capacity: 1024 });
+        let mut growing = ScratchPool::with_config("growing", config);
+        for _ in 0..6 {
+            assert!(!growing.alloc::<[u8; 100]>().is_null());
+        }
+        assert_eq!(growing.chunk_count(), 2);
+        assert_eq!(growing.capacity(), 768);
+        assert!(growing.alloc::<[u8; 600]>().is_null());
+
+        // Reset consolidates into one chunk
+        growing.reset();
+        assert_eq!(growing.chunk_count(), 1);
+        assert_eq!(growing.capacity(), 768);
+        assert_eq!(growing.peak(), 600);
+    }
+
+    #[test]
+    fn test_group_unload_resets_pools() {
+        use crate::api::alloc::SmartAlloc;
+        use crate::api::config::AllocConfig;
+
+        let alloc = SmartAlloc::new(AllocConfig::minimal());
+        let level = alloc.groups().create_group("level_1");
+
+        let handle = alloc.scratch_pool("level_1/navmesh");
+        assert!(handle.bind_to_group(level));
+        assert!(!handle.alloc::<[u8; 128]>().is_null());
+
+        alloc.groups().free_group(level);
+        assert_eq!(handle.allocated(), 0);
+    }
 }
@@ -38,14 +38,24 @@
 //!     .with_duration(frame_start.elapsed());
 //! emitter.maybe_emit(&snapshot); // Checks for request file
 //! ```
+//!
+//! Creating `record.start` in the snapshot directory starts a compact
+//! per-frame recording (see `snapshot_recording`); `record.stop` ends it.
 
+use std::fmt;
 use std::fs;
-use std::io::Write;
-use std::path::PathBuf;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Mutex;
 use std::time::{Duration, Instant, SystemTime};
 
+use super::snapshot_recording::{RecordingStats, SnapshotRecorder, RECORDING_EXTENSION};
+use crate::diagnostics::{DiagnosticKind, FieldValue};
+use crate::util::json::{escape_json_str, JsonValue};
+
+pub use crate::diagnostics::runtime::RuntimeDiagnostic;
+
 /// Snapshot schema version.
 pub const SNAPSHOT_VERSION: u32 = 1;
 
@@ -122,7 +132,7 @@ pub struct Snapshot {
     /// ISO 8601 timestamp
     pub timestamp: String,
     
-    /// Frame number
+    /// Global frame number
     pub frame: u64,
     
     /// Frame duration in microseconds
@@ -169,6 +179,8 @@ pub struct ThreadSnapshot {
     pub pool_bytes: usize,
     pub heap_bytes: usize,
     pub peak_bytes: usize,
+    /// The thread's frame epoch (its `begin_frame` count)
+    pub epoch: u64,
     pub budget: Option<BudgetInfo>,
 }
 
@@ -215,14 +227,6 @@ pub struct DeferredStats {
     pub processed_this_frame: usize,
 }
 
-/// Runtime diagnostic.
-#[derive(Debug, Clone)]
-pub struct RuntimeDiagnostic {
-    pub code: String,
-    pub tag: Option<String>,
-    pub message: String,
-}
-
 impl Snapshot {
     /// Create a new empty snapshot.
     pub fn new(frame: u64) -> Self {
@@ -311,6 +315,7 @@ impl Snapshot {
             json.push_str(&format!("      \"pool_bytes\": {},\n", thread.pool_bytes));
             json.push_str(&format!("      \"heap_bytes\": {},\n", thread.heap_bytes));
             json.push_str(&format!("      \"peak_bytes\": {},\n", thread.peak_bytes));
+            json.push_str(&format!("      \"epoch\": {},\n", thread.epoch));
             if let Some(ref budget) = thread.budget {
                 json.push_str("      \"budget\": {\n");
                 json.push_str(&format!("        \"limit\": {},\n", budget.limit));
@@ -379,12 +384,14 @@ impl Snapshot {
         for (i, diag) in self.diagnostics.iter().enumerate() {
             json.push_str("    {\n");
             json.push_str(&format!("      \"code\": \"{}\",\n", escape_json_str(&diag.code)));
-            if let Some(ref tag) = diag.tag {
+            json.push_str(&format!("      \"kind\": \"{}\",\n", diag.kind.prefix()));
+            if let Some(tag) = diag.tag() {
                 json.push_str(&format!("      \"tag\": \"{}\",\n", escape_json_str(tag)));
             } else {
                 json.push_str("      \"tag\": null,\n");
             }
-            json.push_str(&format!("      \"message\": \"{}\"\n", escape_json_str(&diag.message)));
+            json.push_str(&format!("      \"message\": \"{}\",\n", escape_json_str(&diag.message)));
+            json.push_str(&format!("      \"fields\": {}\n", diag.fields_json()));
             if i < self.diagnostics.len() - 1 {
                 json.push_str("    },\n");
             } else {
@@ -396,6 +403,222 @@ impl Snapshot {
         json.push_str("}\n");
         json
     }
+
+    /// Parse a snapshot written by `to_json`.
+    ///
+    /// Snapshots from older schema versions load with missing sections left
+    /// at their defaults; newer versions are rejected.
+    pub fn from_json(json: &str) -> Result<Self, SnapshotError> {
+        let root = JsonValue::parse(json).map_err(|e| SnapshotError::Parse {
+            offset: e.offset,
+            message: e.message.to_string(),
+        })?;
+
+        let version = u64_field(&root, "version")? as u32;
+        if version == 0 || version > SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion {
+                found: version,
+                supported: SNAPSHOT_VERSION,
+            });
+        }
+
+        let summary = root.get("summary").ok_or(SnapshotError::MissingField("summary"))?;
+        let mut snapshot = Snapshot {
+            version,
+            timestamp: str_field(&root, "timestamp")?.to_string(),
+            frame: u64_field(&root, "frame")?,
+            duration_us: opt_u64(&root, "duration_us"),
+            summary: SnapshotSummary {
+                frame_bytes: opt_u64(summary, "frame_bytes") as usize,
+                pool_bytes: opt_u64(summary, "pool_bytes") as usize,
+                heap_bytes: opt_u64(summary, "heap_bytes") as usize,
+                total_bytes: opt_u64(summary, "total_bytes") as usize,
+                peak_bytes: opt_u64(summary, "peak_bytes") as usize,
+            },
+            threads: Vec::new(),
+            tags: Vec::new(),
+            promotions: PromotionStats::default(),
+            transfers: TransferStats::default(),
+            deferred: DeferredStats::default(),
+            diagnostics: Vec::new(),
+        };
+
+        for thread in array_field(&root, "threads") {
+            let budget = thread.get("budget").filter(|b| !b.is_null()).map(|b| BudgetInfo {
+                limit: opt_u64(b, "limit") as usize,
+                used: opt_u64(b, "used") as usize,
+                percent: opt_u64(b, "percent") as u8,
+            });
+            snapshot.threads.push(ThreadSnapshot {
+                id: str_field(thread, "id")?.to_string(),
+                name: str_field(thread, "name")?.to_string(),
+                frame_bytes: opt_u64(thread, "frame_bytes") as usize,
+                pool_bytes: opt_u64(thread, "pool_bytes") as usize,
+                heap_bytes: opt_u64(thread, "heap_bytes") as usize,
+                peak_bytes: opt_u64(thread, "peak_bytes") as usize,
+                epoch: opt_u64(thread, "epoch"),
+                budget,
+            });
+        }
+
+        for tag in array_field(&root, "tags") {
+            snapshot.tags.push(TagSnapshot {
+                path: str_field(tag, "path")?.to_string(),
+                thread: str_field(tag, "thread")?.to_string(),
+                alloc_kind: str_field(tag, "alloc_kind")?.to_string(),
+                alloc_count: opt_u64(tag, "alloc_count") as usize,
+                bytes: opt_u64(tag, "bytes") as usize,
+                avg_lifetime_frames: opt_f64(tag, "avg_lifetime_frames") as f32,
+                promotion_rate: opt_f64(tag, "promotion_rate") as f32,
+                diagnostics: array_field(tag, "diagnostics")
+                    .iter()
+                    .filter_map(|d| d.as_str().map(String::from))
+                    .collect(),
+            });
+        }
+
+        if let Some(promotions) = root.get("promotions") {
+            snapshot.promotions = PromotionStats {
+                to_pool: opt_u64(promotions, "to_pool") as usize,
+                to_heap: opt_u64(promotions, "to_heap") as usize,
+                failed: opt_u64(promotions, "failed") as usize,
+            };
+        }
+        if let Some(transfers) = root.get("transfers") {
+            snapshot.transfers = TransferStats {
+                pending: opt_u64(transfers, "pending") as usize,
+                completed_this_frame: opt_u64(transfers, "completed_this_frame") as usize,
+            };
+        }
+        if let Some(deferred) = root.get("deferred") {
+            snapshot.deferred = DeferredStats {
+                queue_depth: opt_u64(deferred, "queue_depth") as usize,
+                processed_this_frame: opt_u64(deferred, "processed_this_frame") as usize,
+            };
+        }
+
+        for diag in array_field(&root, "diagnostics") {
+            let kind = diag
+                .get("kind")
+                .and_then(JsonValue::as_str)
+                .and_then(DiagnosticKind::from_prefix)
+                .unwrap_or(DiagnosticKind::Warning);
+            let mut parsed =
+                RuntimeDiagnostic::new(kind, str_field(diag, "code")?, str_field(diag, "message")?);
+            for (key, value) in diag.get("fields").and_then(JsonValue::as_object).unwrap_or(&[]) {
+                let value = match (key.as_str(), value) {
+                    ("size" | "limit", v) if v.as_u64().is_some() => {
+                        FieldValue::Bytes(v.as_u64().unwrap_or(0) as usize)
+                    }
+                    (_, JsonValue::String(s)) => FieldValue::Text(s.clone()),
+                    (_, v) => match v.as_u64() {
+                        Some(n) => FieldValue::Number(n),
+                        None => continue,
+                    },
+                };
+                parsed = parsed.with_field(key.as_str(), value);
+            }
+            // Version 1 snapshots written before fields existed only carry a tag
+            if let Some(tag) = diag.get("tag").and_then(JsonValue::as_str) {
+                if parsed.tag().is_none() {
+                    parsed = parsed.with_tag(tag);
+                }
+            }
+            snapshot.diagnostics.push(parsed);
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Load a snapshot file written by `SnapshotEmitter`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, SnapshotError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+}
+
+/// Why a snapshot could not be loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// The file is not valid JSON.
+    Parse {
+        /// Byte offset of the syntax error
+        offset: usize,
+        /// What was wrong
+        message: String,
+    },
+    /// The snapshot was written by a newer (or invalid) schema version.
+    UnsupportedVersion {
+        /// Version found in the file
+        found: u32,
+        /// Newest version this build reads
+        supported: u32,
+    },
+    /// A required field is missing or has the wrong type.
+    MissingField(&'static str),
+    /// A binary recording is damaged or not a recording.
+    Corrupt(&'static str),
+    /// The file could not be read.
+    Io {
+        /// Kind of the underlying IO error
+        kind: io::ErrorKind,
+        /// Rendered error message
+        message: String,
+    },
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(err: io::Error) -> Self {
+        Self::Io {
+            kind: err.kind(),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse { offset, message } => {
+                write!(f, "invalid snapshot JSON at byte {}: {}", offset, message)
+            }
+            Self::UnsupportedVersion { found, supported } => write!(
+                f,
+                "unsupported snapshot version {} (this build reads up to {})",
+                found, supported
+            ),
+            Self::MissingField(field) => write!(f, "snapshot field '{}' is missing or invalid", field),
+            Self::Corrupt(what) => write!(f, "corrupt snapshot recording: {}", what),
+            Self::Io { message, .. } => write!(f, "io error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+fn u64_field(value: &JsonValue, key: &'static str) -> Result<u64, SnapshotError> {
+    value
+        .get(key)
+        .and_then(JsonValue::as_u64)
+        .ok_or(SnapshotError::MissingField(key))
+}
+
+fn str_field<'a>(value: &'a JsonValue, key: &'static str) -> Result<&'a str, SnapshotError> {
+    value
+        .get(key)
+        .and_then(JsonValue::as_str)
+        .ok_or(SnapshotError::MissingField(key))
+}
+
+fn opt_u64(value: &JsonValue, key: &str) -> u64 {
+    value.get(key).and_then(JsonValue::as_u64).unwrap_or(0)
+}
+
+fn opt_f64(value: &JsonValue, key: &str) -> f64 {
+    value.get(key).and_then(JsonValue::as_f64).unwrap_or(0.0)
+}
+
+fn array_field<'a>(value: &'a JsonValue, key: &str) -> &'a [JsonValue] {
+    value.get(key).and_then(JsonValue::as_array).unwrap_or(&[])
 }
 
 /// Snapshot emitter that handles file I/O and rate limiting.
@@ -410,6 +633,7 @@ pub struct SnapshotEmitter {
     last_emit: Mutex<Option<Instant>>,
     enabled: AtomicBool,
     emit_count: AtomicU64,
+    recorder: Mutex<Option<SnapshotRecorder>>,
 }
 
 /// Cleanup frequency: only scan directory every N emissions.
@@ -423,6 +647,7 @@ impl SnapshotEmitter {
             last_emit: Mutex::new(None),
             enabled: AtomicBool::new(true),
             emit_count: AtomicU64::new(0),
+            recorder: Mutex::new(None),
         }
     }
     
@@ -452,10 +677,16 @@ impl SnapshotEmitter {
     /// when called from multiple threads.
     /// 
     /// Returns true if a snapshot was emitted.
+    ///
+    /// Also handles the `record.start` / `record.stop` request files and
+    /// appends every snapshot passed here to an active recording, without
+    /// rate limiting.
     pub fn maybe_emit(&self, snapshot: &Snapshot) -> bool {
         if !self.is_enabled() {
             return false;
         }
+
+        self.poll_recording(snapshot);
         
         // Hold mutex across rate limit check AND update to prevent races
         let mut last = self.last_emit.lock().unwrap();
@@ -535,6 +766,74 @@ impl SnapshotEmitter {
         true
     }
     
+    /// Start recording every frame into `recording_<frame>.fasr`.
+    ///
+    /// Replaces any recording in progress. Returns the recording's path.
+    pub fn start_recording(&self, frame: u64) -> io::Result<PathBuf> {
+        let path = self
+            .config
+            .directory
+            .join(format!("recording_{:016}.{}", frame, RECORDING_EXTENSION));
+        let recorder = SnapshotRecorder::create(&path)?;
+        if let Some(previous) = self.recorder.lock().unwrap().replace(recorder) {
+            previous.finish()?;
+        }
+        Ok(path)
+    }
+
+    /// Stop recording, returning what was recorded.
+    pub fn stop_recording(&self) -> io::Result<Option<RecordingStats>> {
+        match self.recorder.lock().unwrap().take() {
+            Some(recorder) => recorder.finish().map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Check if a recording is in progress.
+    pub fn is_recording(&self) -> bool {
+        self.recorder.lock().unwrap().is_some()
+    }
+
+    /// Append a snapshot to the active recording, if any.
+    ///
+    /// Returns true if the snapshot was recorded.
+    pub fn record(&self, snapshot: &Snapshot) -> bool {
+        let mut recorder = self.recorder.lock().unwrap();
+        let Some(active) = recorder.as_mut() else {
+            return false;
+        };
+        if let Err(e) = active.record(snapshot) {
+            eprintln!("framealloc: failed to record snapshot, stopping recording: {}", e);
+            *recorder = None;
+            return false;
+        }
+        true
+    }
+
+    /// Apply recording request files, then record the snapshot.
+    fn poll_recording(&self, snapshot: &Snapshot) {
+        if self.config.check_request_file {
+            let start = self.config.directory.join("record.start");
+            let stop = self.config.directory.join("record.stop");
+
+            if start.exists() {
+                let _ = fs::remove_file(&start);
+                if let Err(e) = self.start_recording(snapshot.frame) {
+                    eprintln!("framealloc: failed to start recording: {}", e);
+                }
+            }
+            if stop.exists() {
+                let _ = fs::remove_file(&stop);
+                if let Err(e) = self.stop_recording() {
+                    eprintln!("framealloc: failed to finish recording: {}", e);
+                }
+                return;
+            }
+        }
+
+        self.record(snapshot);
+    }
+
     /// Check if request file exists.
     fn check_request_file(&self) -> bool {
         if !self.config.check_request_file {
@@ -558,11 +857,7 @@ impl SnapshotEmitter {
         let mut snapshots: Vec<_> = match fs::read_dir(dir) {
             Ok(entries) => entries
                 .filter_map(|e| e.ok())
-                .filter(|e| {
-                    let name = e.file_name();
-                    let name = name.to_string_lossy();
-                    name.starts_with("snapshot_") && name.ends_with(".json")
-                })
+                .filter(|e| is_snapshot_file(&e.file_name().to_string_lossy()))
                 .collect(),
             Err(_) => return,
         };
@@ -590,9 +885,7 @@ impl SnapshotEmitter {
         
         for entry in fs::read_dir(dir)? {
             let entry = entry?;
-            let name = entry.file_name();
-            let name = name.to_string_lossy();
-            if name.starts_with("snapshot_") && name.ends_with(".json") {
+            if is_snapshot_file(&entry.file_name().to_string_lossy()) {
                 fs::remove_file(entry.path())?;
                 removed += 1;
             }
@@ -602,27 +895,71 @@ impl SnapshotEmitter {
     }
 }
 
-/// Escape a string for JSON output.
-/// 
-/// Handles all JSON special characters: quotes, backslashes, and control characters.
-#[inline]
-fn escape_json_str(s: &str) -> String {
-    let mut result = String::with_capacity(s.len() + 16);
-    for c in s.chars() {
-        match c {
-            '"' => result.push_str("\\\""),
-            '\\' => result.push_str("\\\\"),
-            '\n' => result.push_str("\\n"),
-            '\r' => result.push_str("\\r"),
-            '\t' => result.push_str("\\t"),
-            c if c.is_control() => {
-                // Unicode escape for control characters
-                result.push_str(&format!("\\u{:04x}", c as u32));
+/// Check whether a file name is an emitted snapshot.
+fn is_snapshot_file(name: &str) -> bool {
+    name.starts_with("snapshot_") && name.ends_with(".json")
+}
+
+/// Reads the snapshots `SnapshotEmitter` retains in a directory.
+///
+/// File names embed the zero-padded frame number, so name order is
+/// frame order.
+#[derive(Debug, Clone)]
+pub struct SnapshotReader {
+    directory: PathBuf,
+}
+
+impl SnapshotReader {
+    /// Create a reader for a snapshot directory.
+    pub fn new<P: Into<PathBuf>>(directory: P) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+
+    /// Create a reader for the directory an emitter writes to.
+    pub fn from_config(config: &SnapshotConfig) -> Self {
+        Self::new(config.directory.clone())
+    }
+
+    /// Get the directory being read.
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// List snapshot files, oldest frame first.
+    ///
+    /// A missing directory has no snapshots.
+    pub fn paths(&self) -> io::Result<Vec<PathBuf>> {
+        let entries = match fs::read_dir(&self.directory) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut paths = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            if is_snapshot_file(&entry.file_name().to_string_lossy()) {
+                paths.push(entry.path());
             }
-            c => result.push(c),
+        }
+        paths.sort();
+        Ok(paths)
+    }
+
+    /// Load every retained snapshot, oldest frame first.
+    pub fn load_all(&self) -> Result<Vec<Snapshot>, SnapshotError> {
+        self.paths()?.iter().map(Snapshot::from_file).collect()
+    }
+
+    /// Load the most recent snapshot, if any.
+    pub fn latest(&self) -> Result<Option<Snapshot>, SnapshotError> {
+        match self.paths()?.last() {
+            Some(path) => Snapshot::from_file(path).map(Some),
+            None => Ok(None),
         }
     }
-    result
 }
 
 /// Convert Unix timestamp to approximate ISO 8601 string.
@@ -675,6 +1012,7 @@ fn time_to_iso8601(secs: u64) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::api::snapshot_recording::SnapshotRecording;
     
     #[test]
     fn test_snapshot_to_json() {
@@ -750,4 +1088,114 @@ mod tests {
         let json = snapshot.to_json();
         assert!(json.contains("\"duration_us\": 16667"));
     }
+
+    #[test]
+    fn test_snapshot_json_round_trip() {
+        let mut snapshot = Snapshot::new(42)
+            .with_duration(Duration::from_micros(900))
+            .with_summary(SnapshotSummary {
+                frame_bytes: 1024,
+                pool_bytes: 2048,
+                heap_bytes: 0,
+                total_bytes: 3072,
+                peak_bytes: 4096,
+            });
+        snapshot.add_thread(ThreadSnapshot {
+            id: "ThreadId(1)".to_string(),
+            name: "main \"render\"".to_string(),
+            frame_bytes: 1024,
+            pool_bytes: 2048,
+            heap_bytes: 0,
+            peak_bytes: 4096,
+            epoch: 42,
+            budget: Some(BudgetInfo { limit: 8192, used: 3072, percent: 37 }),
+        });
+        snapshot.add_tag(TagSnapshot {
+            path: "ai::planner".to_string(),
+            thread: "main".to_string(),
+            alloc_kind: "pool".to_string(),
+            alloc_count: 3,
+            bytes: 2048,
+            avg_lifetime_frames: 1.5,
+            promotion_rate: 0.25,
+            diagnostics: vec!["FA302".to_string()],
+        });
+        snapshot.add_diagnostic(
+            RuntimeDiagnostic::error("FA303", "phase budget exceeded")
+                .with_phase("physics")
+                .with_size(512)
+                .with_frame(42),
+        );
+
+        let parsed = Snapshot::from_json(&snapshot.to_json()).unwrap();
+        assert_eq!(parsed.to_json(), snapshot.to_json());
+        assert_eq!(parsed.threads[0].budget.as_ref().map(|b| b.percent), Some(37));
+        assert_eq!(parsed.diagnostics[0].kind, DiagnosticKind::Error);
+        assert_eq!(parsed.diagnostics[0].field("size"), Some(&FieldValue::Bytes(512)));
+        assert_eq!(parsed.diagnostics[0].field("frame"), Some(&FieldValue::Number(42)));
+    }
+
+    #[test]
+    fn test_snapshot_rejects_unsupported_versions() {
+        let json = Snapshot::new(1).to_json();
+        let newer = json.replace(
+            "\"version\": 1,",
+            &format!("\"version\": {},", SNAPSHOT_VERSION + 1),
+        );
+        assert_eq!(
+            Snapshot::from_json(&newer).unwrap_err(),
+            SnapshotError::UnsupportedVersion { found: SNAPSHOT_VERSION + 1, supported: SNAPSHOT_VERSION }
+        );
+
+        let missing = json.replace("\"frame\": 1,", "");
+        assert_eq!(Snapshot::from_json(&missing).unwrap_err(), SnapshotError::MissingField("frame"));
+        assert!(matches!(Snapshot::from_json("{\"version\": "), Err(SnapshotError::Parse { .. })));
+    }
+
+    #[test]
+    fn test_reader_loads_retained_snapshots_in_frame_order() {
+        let directory = std::env::temp_dir()
+            .join(format!("framealloc_snapshot_reader_{}", std::process::id()));
+        let emitter = SnapshotEmitter::new(SnapshotConfig::default().with_directory(&directory));
+        for frame in [11, 9, 10] {
+            assert!(emitter.emit(&Snapshot::new(frame)));
+        }
+        fs::write(directory.join("snapshot.request"), "").unwrap();
+
+        let reader = SnapshotReader::new(&directory);
+        let frames: Vec<_> = reader.load_all().unwrap().iter().map(|s| s.frame).collect();
+        assert_eq!(frames, vec![9, 10, 11]);
+        assert_eq!(reader.latest().unwrap().map(|s| s.frame), Some(11));
+
+        fs::remove_dir_all(&directory).unwrap();
+        assert!(reader.load_all().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_request_files_start_and_stop_recording() {
+        let directory = std::env::temp_dir()
+            .join(format!("framealloc_snapshot_recording_{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let emitter = SnapshotEmitter::new(SnapshotConfig::default().with_directory(&directory));
+
+        emitter.maybe_emit(&Snapshot::new(1));
+        assert!(!emitter.is_recording());
+
+        fs::write(directory.join("record.start"), "").unwrap();
+        for frame in 2..=4 {
+            emitter.maybe_emit(&Snapshot::new(frame));
+        }
+        assert!(emitter.is_recording());
+
+        fs::write(directory.join("record.stop"), "").unwrap();
+        emitter.maybe_emit(&Snapshot::new(5));
+        assert!(!emitter.is_recording());
+        assert!(!directory.join("record.stop").exists());
+
+        let recording = SnapshotRecording::open(directory.join("recording_0000000000000002.fasr")).unwrap();
+        let frames: Vec<_> = recording.snapshots(..).unwrap().iter().map(|s| s.frame).collect();
+        assert_eq!(frames, vec![2, 3, 4]);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
 }
@@ -0,0 +1,296 @@
+//! Differences between two snapshots.
+//!
+//! `SnapshotDiff` compares two snapshots, typically from two builds running
+//! the same scene, and reports how the summary, each thread and each tag
+//! changed. Entries that did not change are left out, so an empty diff
+//! means the memory profile is identical.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::snapshot::{Snapshot, TagSnapshot, ThreadSnapshot};
+
+/// How an entry changed between two snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeltaStatus {
+    /// Only present in the later snapshot
+    Added,
+    /// Only present in the earlier snapshot
+    Removed,
+    /// Present in both with different values
+    Changed,
+}
+
+impl DeltaStatus {
+    fn between<T>(before: Option<T>, after: Option<T>) -> Self {
+        match (before, after) {
+            (None, Some(_)) => Self::Added,
+            (Some(_), None) => Self::Removed,
+            _ => Self::Changed,
+        }
+    }
+
+    fn symbol(self) -> char {
+        match self {
+            Self::Added => '+',
+            Self::Removed => '-',
+            Self::Changed => '~',
+        }
+    }
+}
+
+/// Change in the allocator-wide totals.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SummaryDelta {
+    pub frame_bytes: i64,
+    pub pool_bytes: i64,
+    pub heap_bytes: i64,
+    pub total_bytes: i64,
+    pub peak_bytes: i64,
+}
+
+/// Change in one thread's usage, matched by thread name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreadDelta {
+    pub name: String,
+    pub status: DeltaStatus,
+    pub frame_bytes: i64,
+    pub pool_bytes: i64,
+    pub heap_bytes: i64,
+    pub peak_bytes: i64,
+}
+
+impl ThreadDelta {
+    /// Get the combined change in frame, pool and heap bytes.
+    pub fn total_bytes(&self) -> i64 {
+        self.frame_bytes + self.pool_bytes + self.heap_bytes
+    }
+}
+
+/// Change in one tag's usage, matched by path, thread and allocation kind.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagDelta {
+    pub path: String,
+    pub thread: String,
+    pub alloc_kind: String,
+    pub status: DeltaStatus,
+    pub bytes: i64,
+    pub alloc_count: i64,
+}
+
+/// Per-tag and per-thread differences between two snapshots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotDiff {
+    /// Frame of the earlier snapshot
+    pub from_frame: u64,
+    /// Frame of the later snapshot
+    pub to_frame: u64,
+    /// Change in totals
+    pub summary: SummaryDelta,
+    /// Threads that changed, sorted by name
+    pub threads: Vec<ThreadDelta>,
+    /// Tags that changed, sorted by path, thread and kind
+    pub tags: Vec<TagDelta>,
+}
+
+impl SnapshotDiff {
+    /// Compute what changed from `before` to `after`.
+    pub fn between(before: &Snapshot, after: &Snapshot) -> Self {
+        let summary = SummaryDelta {
+            frame_bytes: delta(before.summary.frame_bytes, after.summary.frame_bytes),
+            pool_bytes: delta(before.summary.pool_bytes, after.summary.pool_bytes),
+            heap_bytes: delta(before.summary.heap_bytes, after.summary.heap_bytes),
+            total_bytes: delta(before.summary.total_bytes, after.summary.total_bytes),
+            peak_bytes: delta(before.summary.peak_bytes, after.summary.peak_bytes),
+        };
+
+        Self {
+            from_frame: before.frame,
+            to_frame: after.frame,
+            summary,
+            threads: thread_deltas(&before.threads, &after.threads),
+            tags: tag_deltas(&before.tags, &after.tags),
+        }
+    }
+
+    /// Check if nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.summary == SummaryDelta::default() && self.threads.is_empty() && self.tags.is_empty()
+    }
+
+    /// Get the tags whose usage grew by at least `min_bytes`, largest first.
+    ///
+    /// Useful as a CI gate between two builds.
+    pub fn tags_grown_by(&self, min_bytes: u64) -> Vec<&TagDelta> {
+        let mut grown: Vec<_> = self
+            .tags
+            .iter()
+            .filter(|t| t.bytes > 0 && t.bytes as u64 >= min_bytes)
+            .collect();
+        grown.sort_by_key(|t| std::cmp::Reverse(t.bytes));
+        grown
+    }
+}
+
+impl fmt::Display for SnapshotDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Snapshot diff: frame {} -> {}", self.from_frame, self.to_frame)?;
+        writeln!(
+            f,
+            "  total: {:+} bytes (frame {:+}, pool {:+}, heap {:+}, peak {:+})",
+            self.summary.total_bytes,
+            self.summary.frame_bytes,
+            self.summary.pool_bytes,
+            self.summary.heap_bytes,
+            self.summary.peak_bytes
+        )?;
+        for thread in &self.threads {
+            writeln!(
+                f,
+                "  {} thread {}: {:+} bytes",
+                thread.status.symbol(),
+                thread.name,
+                thread.total_bytes()
+            )?;
+        }
+        for tag in &self.tags {
+            writeln!(
+                f,
+                "  {} tag {} [{}, {}]: {:+} bytes, {:+} allocs",
+                tag.status.symbol(),
+                tag.path,
+                tag.thread,
+                tag.alloc_kind,
+                tag.bytes,
+                tag.alloc_count
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn delta(before: usize, after: usize) -> i64 {
+    after as i64 - before as i64
+}
+
+fn thread_deltas(before: &[ThreadSnapshot], after: &[ThreadSnapshot]) -> Vec<ThreadDelta> {
+    let mut pairs: BTreeMap<&str, (Option<&ThreadSnapshot>, Option<&ThreadSnapshot>)> =
+        BTreeMap::new();
+    for thread in before {
+        pairs.entry(&thread.name).or_default().0 = Some(thread);
+    }
+    for thread in after {
+        pairs.entry(&thread.name).or_default().1 = Some(thread);
+    }
+
+    let bytes = |t: Option<&ThreadSnapshot>| {
+        t.map_or((0, 0, 0, 0), |t| (t.frame_bytes, t.pool_bytes, t.heap_bytes, t.peak_bytes))
+    };
+
+    pairs
+        .into_iter()
+        .filter_map(|(name, (b, a))| {
+            let (bf, bp, bh, bk) = bytes(b);
+            let (af, ap, ah, ak) = bytes(a);
+            let changed = b.is_none() || a.is_none() || (bf, bp, bh, bk) != (af, ap, ah, ak);
+            changed.then(|| ThreadDelta {
+                name: name.to_string(),
+                status: DeltaStatus::between(b, a),
+                frame_bytes: delta(bf, af),
+                pool_bytes: delta(bp, ap),
+                heap_bytes: delta(bh, ah),
+                peak_bytes: delta(bk, ak),
+            })
+        })
+        .collect()
+}
+
+type TagKey<'a> = (&'a str, &'a str, &'a str);
+
+fn tag_deltas(before: &[TagSnapshot], after: &[TagSnapshot]) -> Vec<TagDelta> {
+    let mut pairs: BTreeMap<TagKey<'_>, (Option<&TagSnapshot>, Option<&TagSnapshot>)> =
+        BTreeMap::new();
+    for tag in before {
+        pairs.entry((&tag.path, &tag.thread, &tag.alloc_kind)).or_default().0 = Some(tag);
+    }
+    for tag in after {
+        pairs.entry((&tag.path, &tag.thread, &tag.alloc_kind)).or_default().1 = Some(tag);
+    }
+
+    pairs
+        .into_iter()
+        .filter_map(|((path, thread, alloc_kind), (b, a))| {
+            let (bb, bc) = b.map_or((0, 0), |t| (t.bytes, t.alloc_count));
+            let (ab, ac) = a.map_or((0, 0), |t| (t.bytes, t.alloc_count));
+            let changed = b.is_none() || a.is_none() || (bb, bc) != (ab, ac);
+            changed.then(|| TagDelta {
+                path: path.to_string(),
+                thread: thread.to_string(),
+                alloc_kind: alloc_kind.to_string(),
+                status: DeltaStatus::between(b, a),
+                bytes: delta(bb, ab),
+                alloc_count: delta(bc, ac),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::snapshot::SnapshotSummary;
+
+    fn tag(path: &str, bytes: usize, alloc_count: usize) -> TagSnapshot {
+        TagSnapshot {
+            path: path.to_string(),
+            thread: "main".to_string(),
+            alloc_kind: "pool".to_string(),
+            alloc_count,
+            bytes,
+            avg_lifetime_frames: 0.0,
+            promotion_rate: 0.0,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_diff_reports_changed_added_and_removed_tags() {
+        let mut before = Snapshot::new(10).with_summary(SnapshotSummary {
+            total_bytes: 4096,
+            ..Default::default()
+        });
+        before.add_tag(tag("ai", 1024, 4));
+        before.add_tag(tag("audio", 512, 2));
+        before.add_tag(tag("physics", 2048, 8));
+
+        let mut after = Snapshot::new(20).with_summary(SnapshotSummary {
+            total_bytes: 6144,
+            ..Default::default()
+        });
+        after.add_tag(tag("ai", 3072, 6));
+        after.add_tag(tag("physics", 2048, 8));
+        after.add_tag(tag("render", 1024, 1));
+
+        let diff = SnapshotDiff::between(&before, &after);
+        assert_eq!((diff.from_frame, diff.to_frame), (10, 20));
+        assert_eq!(diff.summary.total_bytes, 2048);
+
+        let paths: Vec<_> = diff.tags.iter().map(|t| (t.path.as_str(), t.status)).collect();
+        assert_eq!(
+            paths,
+            vec![
+                ("ai", DeltaStatus::Changed),
+                ("audio", DeltaStatus::Removed),
+                ("render", DeltaStatus::Added),
+            ]
+        );
+        assert_eq!(diff.tags[0].bytes, 2048);
+        assert_eq!(diff.tags[1].bytes, -512);
+
+        let grown: Vec<_> = diff.tags_grown_by(1024).iter().map(|t| t.path.as_str()).collect();
+        assert_eq!(grown, vec!["ai", "render"]);
+
+        assert!(diff.to_string().contains("~ tag ai [main, pool]: +2048 bytes, +2 allocs"));
+        assert!(SnapshotDiff::between(&after, &after).is_empty());
+    }
+}
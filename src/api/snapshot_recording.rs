@@ -0,0 +1,647 @@
+//! Compact binary recording of per-frame snapshots.
+//!
+//! JSON snapshots are meant for occasional inspection. A recording keeps
+//! one snapshot per frame for a whole session: every value is a varint,
+//! sizes and counts are stored as deltas against the previous frame, and
+//! strings (tag paths, thread names, diagnostic text) are written once
+//! into a string table and referenced by index afterwards.
+//!
+//! # Format
+//!
+//! ```text
+//! header:  "FASR" | format version (u8) | snapshot version (varint)
+//! record:  payload length (varint) | payload
+//! payload: new strings | frame delta | timestamp | duration | summary
+//!          | threads | tags | promotions | transfers | deferred | diagnostics
+//! ```
+//!
+//! Records are self-delimiting, so a recording cut short by a crash is
+//! still readable up to its last complete frame.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! let mut recorder = SnapshotRecorder::create("target/framealloc/session.fasr")?;
+//! // In your frame loop:
+//! alloc.end_frame();
+//! recorder.record(&alloc.snapshot())?;
+//!
+//! // Later, in tooling:
+//! let recording = SnapshotRecording::open("target/framealloc/session.fasr")?;
+//! for (frame, bytes) in recording.memory_timeline(1000..2000)? { /* ... */ }
+//! ```
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::ops::RangeBounds;
+use std::path::{Path, PathBuf};
+
+use super::snapshot::{
+    BudgetInfo, DeferredStats, PromotionStats, RuntimeDiagnostic, Snapshot, SnapshotError,
+    SnapshotSummary, TagSnapshot, ThreadSnapshot, TransferStats, SNAPSHOT_VERSION,
+};
+use crate::diagnostics::{DiagnosticKind, FieldValue};
+use crate::util::varint::{write_i64, write_u64, VarintReader};
+
+/// Magic bytes at the start of every recording.
+const RECORDING_MAGIC: &[u8; 4] = b"FASR";
+
+/// Binary recording format version.
+pub const RECORDING_VERSION: u8 = 1;
+
+/// File extension used for recordings.
+pub const RECORDING_EXTENSION: &str = "fasr";
+
+const FIELD_BYTES: u8 = 0;
+const FIELD_NUMBER: u8 = 1;
+const FIELD_TEXT: u8 = 2;
+
+/// Values carried from one frame to the next for delta coding.
+///
+/// Encoder and decoder keep identical copies, keyed by string index.
+#[derive(Default)]
+struct DeltaState {
+    frame: u64,
+    summary: [u64; 5],
+    threads: HashMap<u64, [u64; 5]>,
+    tags: HashMap<(u64, u64, u64), [u64; 2]>,
+}
+
+fn summary_values(summary: &SnapshotSummary) -> [u64; 5] {
+    [
+        summary.frame_bytes as u64,
+        summary.pool_bytes as u64,
+        summary.heap_bytes as u64,
+        summary.total_bytes as u64,
+        summary.peak_bytes as u64,
+    ]
+}
+
+fn write_deltas(out: &mut Vec<u8>, previous: &mut [u64], current: &[u64]) {
+    for (prev, &value) in previous.iter_mut().zip(current) {
+        write_i64(out, value.wrapping_sub(*prev) as i64);
+        *prev = value;
+    }
+}
+
+/// Turns snapshots into record payloads.
+#[derive(Default)]
+struct Encoder {
+    strings: HashMap<String, u64>,
+    pending: Vec<String>,
+    state: DeltaState,
+}
+
+impl Encoder {
+    fn intern(&mut self, s: &str) -> u64 {
+        if let Some(&index) = self.strings.get(s) {
+            return index;
+        }
+        let index = self.strings.len() as u64;
+        self.strings.insert(s.to_string(), index);
+        self.pending.push(s.to_string());
+        index
+    }
+
+    fn optional(&mut self, s: &Option<String>) -> u64 {
+        s.as_deref().map_or(0, |s| self.intern(s) + 1)
+    }
+
+    fn encode(&mut self, snapshot: &Snapshot) -> Vec<u8> {
+        let mut body = Vec::with_capacity(64 + snapshot.tags.len() * 8);
+
+        write_i64(&mut body, snapshot.frame.wrapping_sub(self.state.frame) as i64);
+        self.state.frame = snapshot.frame;
+        let timestamp = self.intern(&snapshot.timestamp);
+        write_u64(&mut body, timestamp);
+        write_u64(&mut body, snapshot.duration_us);
+        write_deltas(&mut body, &mut self.state.summary, &summary_values(&snapshot.summary));
+
+        write_u64(&mut body, snapshot.threads.len() as u64);
+        for thread in &snapshot.threads {
+            let name = self.intern(&thread.name);
+            let id = self.intern(&thread.id);
+            write_u64(&mut body, name);
+            write_u64(&mut body, id);
+            let values = [
+                thread.frame_bytes as u64,
+                thread.pool_bytes as u64,
+                thread.heap_bytes as u64,
+                thread.peak_bytes as u64,
+                thread.epoch,
+            ];
+            let previous = self.state.threads.entry(name).or_default();
+            write_deltas(&mut body, previous, &values);
+            match &thread.budget {
+                Some(budget) => {
+                    body.push(1);
+                    write_u64(&mut body, budget.limit as u64);
+                    write_u64(&mut body, budget.used as u64);
+                    body.push(budget.percent);
+                }
+                None => body.push(0),
+            }
+        }
+
+        write_u64(&mut body, snapshot.tags.len() as u64);
+        for tag in &snapshot.tags {
+            let key = (
+                self.intern(&tag.path),
+                self.intern(&tag.thread),
+                self.intern(&tag.alloc_kind),
+            );
+            write_u64(&mut body, key.0);
+            write_u64(&mut body, key.1);
+            write_u64(&mut body, key.2);
+            let previous = self.state.tags.entry(key).or_default();
+            write_deltas(&mut body, previous, &[tag.bytes as u64, tag.alloc_count as u64]);
+            write_u64(&mut body, u64::from(tag.avg_lifetime_frames.to_bits()));
+            write_u64(&mut body, u64::from(tag.promotion_rate.to_bits()));
+            write_u64(&mut body, tag.diagnostics.len() as u64);
+            for code in &tag.diagnostics {
+                let code = self.intern(code);
+                write_u64(&mut body, code);
+            }
+        }
+
+        for value in [
+            snapshot.promotions.to_pool,
+            snapshot.promotions.to_heap,
+            snapshot.promotions.failed,
+            snapshot.transfers.pending,
+            snapshot.transfers.completed_this_frame,
+            snapshot.deferred.queue_depth,
+            snapshot.deferred.processed_this_frame,
+        ] {
+            write_u64(&mut body, value as u64);
+        }
+
+        write_u64(&mut body, snapshot.diagnostics.len() as u64);
+        for diag in &snapshot.diagnostics {
+            let header = [
+                self.intern(diag.kind.prefix()),
+                self.intern(&diag.code),
+                self.intern(&diag.message),
+                self.optional(&diag.note),
+                self.optional(&diag.help),
+            ];
+            header.iter().for_each(|&v| write_u64(&mut body, v));
+            write_u64(&mut body, diag.fields.len() as u64);
+            for (key, value) in &diag.fields {
+                let key = self.intern(key);
+                write_u64(&mut body, key);
+                match value {
+                    FieldValue::Bytes(n) => {
+                        body.push(FIELD_BYTES);
+                        write_u64(&mut body, *n as u64);
+                    }
+                    FieldValue::Number(n) => {
+                        body.push(FIELD_NUMBER);
+                        write_u64(&mut body, *n);
+                    }
+                    FieldValue::Text(s) => {
+                        let s = self.intern(s);
+                        body.push(FIELD_TEXT);
+                        write_u64(&mut body, s);
+                    }
+                }
+            }
+        }
+
+        // Strings first introduced by this frame precede the body
+        let mut payload = Vec::with_capacity(body.len() + 16);
+        write_u64(&mut payload, self.pending.len() as u64);
+        for s in self.pending.drain(..) {
+            write_u64(&mut payload, s.len() as u64);
+            payload.extend_from_slice(s.as_bytes());
+        }
+        payload.extend_from_slice(&body);
+        payload
+    }
+}
+
+/// Size and length of a recording so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RecordingStats {
+    /// Frames recorded
+    pub frames: u64,
+    /// Bytes written, including the header
+    pub bytes: u64,
+    /// Distinct strings in the string table
+    pub strings: usize,
+}
+
+/// Appends one compact record per frame to a recording file.
+///
+/// Writes are buffered; call `flush` to make frames visible to a reader
+/// while recording, or `finish` when done.
+pub struct SnapshotRecorder {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    encoder: Encoder,
+    stats: RecordingStats,
+}
+
+impl SnapshotRecorder {
+    /// Create a recording, replacing any existing file.
+    pub fn create<P: Into<PathBuf>>(path: P) -> io::Result<Self> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut header = RECORDING_MAGIC.to_vec();
+        header.push(RECORDING_VERSION);
+        write_u64(&mut header, u64::from(SNAPSHOT_VERSION));
+
+        let mut writer = BufWriter::new(File::create(&path)?);
+        writer.write_all(&header)?;
+
+        Ok(Self {
+            path,
+            writer,
+            encoder: Encoder::default(),
+            stats: RecordingStats {
+                bytes: header.len() as u64,
+                ..Default::default()
+            },
+        })
+    }
+
+    /// Append a snapshot.
+    pub fn record(&mut self, snapshot: &Snapshot) -> io::Result<()> {
+        let payload = self.encoder.encode(snapshot);
+        let mut length = Vec::with_capacity(4);
+        write_u64(&mut length, payload.len() as u64);
+
+        self.writer.write_all(&length)?;
+        self.writer.write_all(&payload)?;
+        self.stats.frames += 1;
+        self.stats.bytes += (length.len() + payload.len()) as u64;
+        self.stats.strings = self.encoder.strings.len();
+        Ok(())
+    }
+
+    /// Flush buffered records to the file.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Flush and close the recording.
+    pub fn finish(mut self) -> io::Result<RecordingStats> {
+        self.writer.flush()?;
+        Ok(self.stats)
+    }
+
+    /// Get the file being written.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get what has been recorded so far.
+    pub fn stats(&self) -> RecordingStats {
+        self.stats
+    }
+}
+
+/// A recording loaded for reading.
+pub struct SnapshotRecording {
+    data: Vec<u8>,
+    /// Offset of the first record
+    start: usize,
+    snapshot_version: u32,
+}
+
+impl SnapshotRecording {
+    /// Load a recording file.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, SnapshotError> {
+        Self::from_bytes(fs::read(path)?)
+    }
+
+    /// Load a recording from memory.
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, SnapshotError> {
+        let mut reader = VarintReader::new(&data);
+        if reader.bytes(RECORDING_MAGIC.len()) != Some(RECORDING_MAGIC.as_slice()) {
+            return Err(SnapshotError::Corrupt("not a framealloc recording"));
+        }
+        let format = reader.u8().ok_or(SnapshotError::Corrupt("truncated header"))?;
+        if format != RECORDING_VERSION {
+            return Err(SnapshotError::UnsupportedVersion {
+                found: u32::from(format),
+                supported: u32::from(RECORDING_VERSION),
+            });
+        }
+        let snapshot_version = reader
+            .u64()
+            .ok_or(SnapshotError::Corrupt("truncated header"))? as u32;
+        if snapshot_version == 0 || snapshot_version > SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion {
+                found: snapshot_version,
+                supported: SNAPSHOT_VERSION,
+            });
+        }
+
+        let start = data.len() - reader.remaining();
+        Ok(Self {
+            data,
+            start,
+            snapshot_version,
+        })
+    }
+
+    /// Get the snapshot schema version the recording was written with.
+    pub fn snapshot_version(&self) -> u32 {
+        self.snapshot_version
+    }
+
+    /// Reconstruct the snapshots for frames in `frames`, in recorded order.
+    ///
+    /// Frames are delta-coded, so earlier records are still decoded.
+    pub fn snapshots<R: RangeBounds<u64>>(&self, frames: R) -> Result<Vec<Snapshot>, SnapshotError> {
+        let mut snapshots = Vec::new();
+        self.decode_each(|snapshot| {
+            if frames.contains(&snapshot.frame) {
+                snapshots.push(snapshot);
+            }
+        })?;
+        Ok(snapshots)
+    }
+
+    /// Get `(frame, total_bytes)` for frames in `frames`.
+    ///
+    /// Same shape as `SnapshotHistory::memory_timeline`.
+    pub fn memory_timeline<R: RangeBounds<u64>>(
+        &self,
+        frames: R,
+    ) -> Result<Vec<(u64, usize)>, SnapshotError> {
+        let mut timeline = Vec::new();
+        self.decode_each(|snapshot| {
+            if frames.contains(&snapshot.frame) {
+                timeline.push((snapshot.frame, snapshot.summary.total_bytes));
+            }
+        })?;
+        Ok(timeline)
+    }
+
+    /// Get the number of complete frames in the recording.
+    pub fn frame_count(&self) -> Result<usize, SnapshotError> {
+        let mut count = 0;
+        self.decode_each(|_| count += 1)?;
+        Ok(count)
+    }
+
+    fn decode_each(&self, mut f: impl FnMut(Snapshot)) -> Result<(), SnapshotError> {
+        let mut reader = VarintReader::new(&self.data[self.start..]);
+        let mut decoder = Decoder {
+            version: self.snapshot_version,
+            ..Default::default()
+        };
+
+        while !reader.is_empty() {
+            // A partial trailing record is what a crash mid-write leaves behind
+            let Some(length) = reader.u64() else { break };
+            let Some(payload) = reader.bytes(length as usize) else { break };
+            let snapshot = decoder
+                .decode(payload)
+                .ok_or(SnapshotError::Corrupt("malformed frame record"))?;
+            f(snapshot);
+        }
+        Ok(())
+    }
+}
+
+/// Rebuilds snapshots from record payloads.
+#[derive(Default)]
+struct Decoder {
+    version: u32,
+    strings: Vec<String>,
+    state: DeltaState,
+}
+
+impl Decoder {
+    fn string(&self, index: u64) -> Option<String> {
+        self.strings.get(index as usize).cloned()
+    }
+
+    fn optional(&self, index: u64) -> Option<Option<String>> {
+        match index {
+            0 => Some(None),
+            n => self.string(n - 1).map(Some),
+        }
+    }
+
+    fn read_deltas(reader: &mut VarintReader<'_>, previous: &mut [u64]) -> Option<()> {
+        for prev in previous.iter_mut() {
+            *prev = prev.wrapping_add(reader.i64()? as u64);
+        }
+        Some(())
+    }
+
+    fn decode(&mut self, payload: &[u8]) -> Option<Snapshot> {
+        let mut r = VarintReader::new(payload);
+
+        for _ in 0..r.u64()? {
+            let len = r.u64()? as usize;
+            let s = std::str::from_utf8(r.bytes(len)?).ok()?;
+            self.strings.push(s.to_string());
+        }
+
+        self.state.frame = self.state.frame.wrapping_add(r.i64()? as u64);
+        let mut snapshot = Snapshot::new(self.state.frame);
+        snapshot.version = self.version;
+        snapshot.timestamp = self.string(r.u64()?)?;
+        snapshot.duration_us = r.u64()?;
+        Self::read_deltas(&mut r, &mut self.state.summary)?;
+        let [frame_bytes, pool_bytes, heap_bytes, total_bytes, peak_bytes] =
+            self.state.summary.map(|v| v as usize);
+        snapshot.summary = SnapshotSummary {
+            frame_bytes,
+            pool_bytes,
+            heap_bytes,
+            total_bytes,
+            peak_bytes,
+        };
+
+        for _ in 0..r.u64()? {
+            let name_index = r.u64()?;
+            let name = self.string(name_index)?;
+            let id = self.string(r.u64()?)?;
+            let values = self.state.threads.entry(name_index).or_default();
+            Self::read_deltas(&mut r, values)?;
+            let values = *values;
+            let budget = match r.u8()? {
+                0 => None,
+                _ => Some(BudgetInfo {
+                    limit: r.u64()? as usize,
+                    used: r.u64()? as usize,
+                    percent: r.u8()?,
+                }),
+            };
+            snapshot.add_thread(ThreadSnapshot {
+                id,
+                name,
+                frame_bytes: values[0] as usize,
+                pool_bytes: values[1] as usize,
+                heap_bytes: values[2] as usize,
+                peak_bytes: values[3] as usize,
+                epoch: values[4],
+                budget,
+            });
+        }
+
+        for _ in 0..r.u64()? {
+            let key = (r.u64()?, r.u64()?, r.u64()?);
+            let values = self.state.tags.entry(key).or_default();
+            Self::read_deltas(&mut r, values)?;
+            let [bytes, alloc_count] = *values;
+            let avg_lifetime_frames = f32::from_bits(r.u64()? as u32);
+            let promotion_rate = f32::from_bits(r.u64()? as u32);
+            let mut diagnostics = Vec::new();
+            for _ in 0..r.u64()? {
+                diagnostics.push(self.string(r.u64()?)?);
+            }
+            snapshot.add_tag(TagSnapshot {
+                path: self.string(key.0)?,
+                thread: self.string(key.1)?,
+                alloc_kind: self.string(key.2)?,
+                alloc_count: alloc_count as usize,
+                bytes: bytes as usize,
+                avg_lifetime_frames,
+                promotion_rate,
+                diagnostics,
+            });
+        }
+
+        let mut counters = [0usize; 7];
+        for counter in counters.iter_mut() {
+            *counter = r.u64()? as usize;
+        }
+        snapshot.promotions = PromotionStats {
+            to_pool: counters[0],
+            to_heap: counters[1],
+            failed: counters[2],
+        };
+        snapshot.transfers = TransferStats {
+            pending: counters[3],
+            completed_this_frame: counters[4],
+        };
+        snapshot.deferred = DeferredStats {
+            queue_depth: counters[5],
+            processed_this_frame: counters[6],
+        };
+
+        for _ in 0..r.u64()? {
+            let kind = DiagnosticKind::from_prefix(&self.string(r.u64()?)?)?;
+            let mut diag = RuntimeDiagnostic::new(kind, self.string(r.u64()?)?, self.string(r.u64()?)?);
+            diag.note = self.optional(r.u64()?)?;
+            diag.help = self.optional(r.u64()?)?;
+            for _ in 0..r.u64()? {
+                let key = self.string(r.u64()?)?;
+                let value = match r.u8()? {
+                    FIELD_BYTES => FieldValue::Bytes(r.u64()? as usize),
+                    FIELD_NUMBER => FieldValue::Number(r.u64()?),
+                    FIELD_TEXT => FieldValue::Text(self.string(r.u64()?)?),
+                    _ => return None,
+                };
+                diag.fields.push((key, value));
+            }
+            snapshot.add_diagnostic(diag);
+        }
+
+        r.is_empty().then_some(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame_snapshot(frame: u64) -> Snapshot {
+        let mut snapshot = Snapshot::new(frame).with_summary(SnapshotSummary {
+            frame_bytes: 1024 * frame as usize,
+            pool_bytes: 4096,
+            heap_bytes: 0,
+            total_bytes: 4096 + 1024 * frame as usize,
+            peak_bytes: 8192,
+        });
+        snapshot.timestamp = "2024-01-01T00:00:00Z".to_string();
+        snapshot.add_thread(ThreadSnapshot {
+            id: "ThreadId(1)".to_string(),
+            name: "main".to_string(),
+            frame_bytes: 1024 * frame as usize,
+            pool_bytes: 4096,
+            heap_bytes: 0,
+            peak_bytes: 8192,
+            epoch: frame,
+            budget: (frame % 2 == 0).then_some(BudgetInfo { limit: 65536, used: 4096, percent: 6 }),
+        });
+        snapshot.add_tag(TagSnapshot {
+            path: "physics::broadphase".to_string(),
+            thread: "main".to_string(),
+            alloc_kind: "frame".to_string(),
+            alloc_count: frame as usize,
+            bytes: 512 * frame as usize,
+            avg_lifetime_frames: 1.0,
+            promotion_rate: 0.125,
+            diagnostics: Vec::new(),
+        });
+        if frame == 3 {
+            snapshot.add_diagnostic(
+                RuntimeDiagnostic::warning("FA302", "allocation exceeds tag-specific budget")
+                    .with_tag("physics::broadphase")
+                    .with_size(1536)
+                    .with_frame(frame)
+                    .with_help("raise the tag budget"),
+            );
+        }
+        snapshot
+    }
+
+    #[test]
+    fn test_recording_round_trips_frames() {
+        let path = std::env::temp_dir().join(format!("framealloc_recording_{}.fasr", std::process::id()));
+        let mut recorder = SnapshotRecorder::create(&path).unwrap();
+        for frame in 1..=5 {
+            recorder.record(&frame_snapshot(frame)).unwrap();
+        }
+        let stats = recorder.finish().unwrap();
+        assert_eq!(stats.frames, 5);
+
+        // Repeated strings and small deltas keep records far below the JSON size
+        let json_bytes: usize = (1..=5).map(|f| frame_snapshot(f).to_json().len()).sum();
+        assert!((stats.bytes as usize) * 4 < json_bytes);
+
+        let recording = SnapshotRecording::open(&path).unwrap();
+        assert_eq!(recording.frame_count().unwrap(), 5);
+        let snapshots = recording.snapshots(2..=3).unwrap();
+        assert_eq!(snapshots.len(), 2);
+        for snapshot in &snapshots {
+            assert_eq!(snapshot.to_json(), frame_snapshot(snapshot.frame).to_json());
+        }
+        assert_eq!(
+            recording.memory_timeline(4..).unwrap(),
+            vec![(4, 4096 + 4096), (5, 4096 + 5120)]
+        );
+
+        // A torn final record is dropped rather than failing the whole file
+        let mut data = fs::read(&path).unwrap();
+        data.truncate(data.len() - 3);
+        assert_eq!(SnapshotRecording::from_bytes(data).unwrap().frame_count().unwrap(), 4);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_recording_rejects_foreign_files() {
+        assert_eq!(
+            SnapshotRecording::from_bytes(b"{\"version\": 1}".to_vec()).err(),
+            Some(SnapshotError::Corrupt("not a framealloc recording"))
+        );
+        let mut future = RECORDING_MAGIC.to_vec();
+        future.push(RECORDING_VERSION + 1);
+        assert!(matches!(
+            SnapshotRecording::from_bytes(future),
+            Err(SnapshotError::UnsupportedVersion { .. })
+        ));
+    }
+}
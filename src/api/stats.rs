@@ -29,6 +29,12 @@ pub struct AllocStats {
 
     /// Number of cross-thread frees processed.
     pub deferred_free_count: u64,
+
+    /// Bytes of pool allocations served by the large-object allocator.
+    pub large_object_allocated: usize,
+
+    /// Live pool objects served by the large-object allocator.
+    pub large_object_count: u64,
 }
 
 impl AllocStats {
@@ -63,6 +69,7 @@ impl std::fmt::Display for AllocStats {
         writeln!(f, "  Frame arena:     {} bytes", self.frame_allocated)?;
         writeln!(f, "  Pool:            {} bytes", self.pool_allocated)?;
         writeln!(f, "  Heap:            {} bytes", self.heap_allocated)?;
+        writeln!(f, "  Large objects:   {} bytes ({} live)", self.large_object_allocated, self.large_object_count)?;
         Ok(())
     }
 }
@@ -0,0 +1,400 @@
+//! Nested frame scopes with stack semantics.
+//!
+//! `SmartAlloc::sub_frame` runs a closure with a `SubFrame<'scope>` that
+//! hands out references bounded by the closure, so nothing allocated inside
+//! can outlive the scope. On exit the frame arena head is restored, which
+//! replaces manual `frame_checkpoint` / `rollback_to` pairs.
+//!
+//! Each allocator tracks, per thread, the active scope stack and the peak
+//! usage of every completed scope, in the same style as phase tracking.
+
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+use std::sync::Arc;
+
+use crate::core::global::GlobalState;
+use crate::core::tls::{self, FrameMark};
+
+/// A completed sub-frame scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubFrameRecord {
+    /// Nesting depth (1 = outermost)
+    pub depth: usize,
+    /// Highest arena usage above the scope's base, in bytes
+    pub peak_bytes: usize,
+}
+
+/// An active scope on the tracker stack.
+struct ActiveScope {
+    /// Arena head at entry
+    base: usize,
+    /// Highest head seen, relative to `base`
+    peak: usize,
+}
+
+/// Tracks nested sub-frame scopes for a thread.
+pub struct SubFrameTracker {
+    /// Stack of active scopes
+    stack: Vec<ActiveScope>,
+    /// Scopes completed this frame
+    completed: Vec<SubFrameRecord>,
+    /// Deepest nesting reached this frame
+    max_depth: usize,
+}
+
+impl SubFrameTracker {
+    /// Create a new tracker.
+    pub fn new() -> Self {
+        Self {
+            stack: Vec::with_capacity(8),
+            completed: Vec::with_capacity(16),
+            max_depth: 0,
+        }
+    }
+
+    /// Enter a scope whose allocations start at `base`. Returns its depth.
+    pub fn enter(&mut self, base: usize) -> usize {
+        self.stack.push(ActiveScope { base, peak: 0 });
+        self.max_depth = self.max_depth.max(self.stack.len());
+        self.stack.len()
+    }
+
+    /// Record the arena head observed inside the current scope.
+    pub fn observe(&mut self, head: usize) {
+        if let Some(scope) = self.stack.last_mut() {
+            scope.peak = scope.peak.max(head.saturating_sub(scope.base));
+        }
+    }
+
+    /// Exit the current scope, given the arena head before it is restored.
+    pub fn exit(&mut self, head: usize) -> Option<SubFrameRecord> {
+        self.observe(head);
+        let scope = self.stack.pop()?;
+
+        // The parent saw everything the child allocated
+        let peak_head = scope.base + scope.peak;
+        self.observe(peak_head);
+
+        let record = SubFrameRecord {
+            depth: self.stack.len() + 1,
+            peak_bytes: scope.peak,
+        };
+        self.completed.push(record);
+        Some(record)
+    }
+
+    /// Get the current nesting depth.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Get the deepest nesting reached this frame.
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Get scopes completed this frame, innermost first.
+    pub fn completed(&self) -> &[SubFrameRecord] {
+        &self.completed
+    }
+
+    /// Reset for a new frame.
+    pub fn reset(&mut self) {
+        self.stack.clear();
+        self.completed.clear();
+        self.max_depth = 0;
+    }
+}
+
+impl Default for SubFrameTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A nested frame scope.
+///
+/// References handed out by a `SubFrame<'scope>` live for `'scope`, which
+/// the borrow checker confines to the closure passed to `sub_frame`.
+/// A nested scope mutably borrows its parent, so the parent cannot allocate
+/// until the nested scope returns. Destructors of allocated values are not
+/// run.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use framealloc::SmartAlloc;
+///
+/// let alloc = SmartAlloc::with_defaults();
+/// alloc.begin_frame();
+///
+/// let best = alloc.sub_frame(|s| {
+///     let open = s.alloc_slice::<u32>(256).unwrap();
+///     open[0] = 7;
+///     s.sub_frame(|inner| *inner.alloc(open[0] * 2).unwrap())
+/// });
+/// assert_eq!(best, 14);
+///
+/// alloc.end_frame();
+/// ```
+///
+/// Using the outer scope inside a nested one does not compile:
+///
+/// ```rust,compile_fail,E0502
+/// use framealloc::SmartAlloc;
+///
+/// let alloc = SmartAlloc::with_defaults();
+/// alloc.sub_frame(|outer| {
+///     outer.sub_frame(|_inner| {
+///         outer.alloc(1u32);
+///     });
+/// });
+/// ```
+pub struct SubFrame<'scope> {
+    /// Allocator whose frame arena this scope uses
+    global: Arc<GlobalState>,
+    /// Depth on this thread's scope stack
+    depth: usize,
+    /// Invariant in `'scope`
+    _scope: PhantomData<fn(&'scope ()) -> &'scope ()>,
+    /// Not `Send`: the arena is thread-local
+    _thread: PhantomData<*const ()>,
+}
+
+impl<'scope> SubFrame<'scope> {
+    /// Enter a scope, run `f`, and restore the arena head on exit.
+    pub(crate) fn enter<R, F>(global: &Arc<GlobalState>, f: F) -> R
+    where
+        F: for<'inner> FnOnce(&mut SubFrame<'inner>) -> R,
+    {
+        /// Restores the head even if `f` panics.
+        struct Exit<'a> {
+            global: &'a Arc<GlobalState>,
+            base: FrameMark,
+        }
+
+        impl Drop for Exit<'_> {
+            fn drop(&mut self) {
+                tls::with_tls(self.global, |tls| {
+                    let head = tls.frame_head();
+                    tls.sub_frames_mut().exit(head);
+                    tls.reset_frame_to(self.base);
+                });
+            }
+        }
+
+        let (base, depth) = tls::with_tls(global, |tls| {
+            let base = tls.frame_mark();
+            (base, tls.sub_frames_mut().enter(base.head()))
+        });
+        let _exit = Exit { global, base };
+
+        let mut scope = SubFrame {
+            global: global.clone(),
+            depth,
+            _scope: PhantomData,
+            _thread: PhantomData,
+        };
+        f(&mut scope)
+    }
+
+    /// Run `f` in a nested scope.
+    ///
+    /// This scope is borrowed until the nested one returns, so it cannot
+    /// allocate in the meantime.
+    pub fn sub_frame<R, F>(&mut self, f: F) -> R
+    where
+        F: for<'inner> FnOnce(&mut SubFrame<'inner>) -> R,
+    {
+        SubFrame::enter(&self.global, f)
+    }
+
+    /// Get this scope's nesting depth (1 = outermost).
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Get the bytes allocated in this scope so far, including nested scopes.
+    pub fn peak_bytes(&self) -> usize {
+        tls::with_tls(&self.global, |tls| {
+            tls.sub_frames()
+                .stack
+                .get(self.depth - 1)
+                .map_or(0, |s| s.peak)
+        })
+    }
+
+    /// Allocate and initialize a value.
+    ///
+    /// Returns None if the frame arena is exhausted.
+    pub fn alloc<T>(&self, value: T) -> Option<&'scope mut T> {
+        let ptr = self.raw_alloc(|tls| tls.frame_alloc::<T>())?;
+        unsafe {
+            ptr.as_ptr().write(value);
+            Some(&mut *ptr.as_ptr())
+        }
+    }
+
+    /// Allocate a slice of default-initialized elements.
+    pub fn alloc_slice<T: Default>(&self, len: usize) -> Option<&'scope mut [T]> {
+        let slice = self.alloc_uninit_slice::<T>(len)?;
+        for i in 0..len {
+            unsafe { slice.as_ptr().add(i).write(T::default()) };
+        }
+        Some(unsafe { std::slice::from_raw_parts_mut(slice.as_ptr(), len) })
+    }
+
+    /// Allocate a copy of `src`.
+    pub fn alloc_slice_copy<T: Copy>(&self, src: &[T]) -> Option<&'scope mut [T]> {
+        let slice = self.alloc_uninit_slice::<T>(src.len())?;
+        unsafe {
+            std::ptr::copy_nonoverlapping(src.as_ptr(), slice.as_ptr(), src.len());
+            Some(std::slice::from_raw_parts_mut(slice.as_ptr(), src.len()))
+        }
+    }
+
+    fn alloc_uninit_slice<T>(&self, len: usize) -> Option<NonNull<T>> {
+        if len == 0 {
+            return Some(NonNull::dangling());
+        }
+        self.raw_alloc(|tls| tls.frame_alloc_slice::<T>(len))
+    }
+
+    fn raw_alloc<T>(
+        &self,
+        f: impl FnOnce(&mut tls::ThreadLocalState) -> *mut T,
+    ) -> Option<NonNull<T>> {
+        let ptr = tls::with_tls(&self.global, |tls| {
+            let ptr = f(tls);
+            let head = tls.frame_head();
+            tls.sub_frames_mut().observe(head);
+            ptr
+        });
+        NonNull::new(ptr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::alloc::SmartAlloc;
+    use crate::api::config::AllocConfig;
+
+    #[test]
+    fn test_head_restored_on_exit() {
+        let alloc = SmartAlloc::new(AllocConfig::minimal());
+        alloc.begin_frame();
+        let before = tls::with_tls(alloc.global(), |tls| tls.frame_head());
+
+        let sum = alloc.sub_frame(|s| {
+            let values = s.alloc_slice_copy(&[1u64, 2, 3]).unwrap();
+            values[2] = 10;
+            assert_eq!(s.depth(), 1);
+            values.iter().sum::<u64>()
+        });
+
+        assert_eq!(sum, 13);
+        assert_eq!(tls::with_tls(alloc.global(), |tls| tls.frame_head()), before);
+        alloc.end_frame();
+    }
+
+    #[test]
+    fn test_nested_depth_and_peak() {
+        let alloc = SmartAlloc::new(AllocConfig::minimal());
+        alloc.begin_frame();
+
+        alloc.sub_frame(|outer| {
+            outer.alloc([0u8; 64]).unwrap();
+            outer.sub_frame(|inner| {
+                assert_eq!(inner.depth(), 2);
+                assert_eq!(alloc.sub_frame_depth(), 2);
+                inner.alloc([0u8; 256]).unwrap();
+            });
+            assert_eq!(alloc.sub_frame_depth(), 1);
+            assert!(outer.peak_bytes() >= 320);
+        });
+
+        let records = alloc.completed_sub_frames();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0], SubFrameRecord { depth: 2, peak_bytes: 256 });
+        assert_eq!(records[1].depth, 1);
+        assert!(records[1].peak_bytes >= 320);
+        assert_eq!(alloc.max_sub_frame_depth(), 2);
+
+        alloc.end_frame();
+        assert!(alloc.completed_sub_frames().is_empty());
+    }
+
+    #[test]
+    fn test_scopes_are_per_allocator() {
+        let a = SmartAlloc::new(AllocConfig::minimal());
+        let b = SmartAlloc::new(AllocConfig::minimal());
+        a.begin_frame();
+        b.begin_frame();
+
+        a.sub_frame(|outer| {
+            // Another allocator neither sees nor unwinds this scope
+            b.sub_frame(|inner| {
+                assert_eq!(inner.depth(), 1);
+                inner.alloc(1u64).unwrap();
+            });
+            assert_eq!(b.sub_frame_depth(), 0);
+            b.end_frame();
+            assert_eq!(a.sub_frame_depth(), 1);
+            outer.alloc(2u64).unwrap();
+        });
+
+        assert_eq!(a.completed_sub_frames().len(), 1);
+        a.end_frame();
+    }
+
+    #[test]
+    fn test_nesting_through_allocator_panics() {
+        let alloc = SmartAlloc::new(AllocConfig::minimal());
+        alloc.begin_frame();
+        let before = tls::with_tls(alloc.global(), |tls| tls.frame_head());
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            alloc.sub_frame(|outer| {
+                alloc.sub_frame(|_inner| {
+                    outer.alloc(1u32);
+                });
+            });
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(alloc.sub_frame_depth(), 0);
+        assert_eq!(tls::with_tls(alloc.global(), |tls| tls.frame_head()), before);
+        alloc.end_frame();
+    }
+
+    // Phase budgets never overflow to the heap under `minimal`
+    #[test]
+    #[cfg(not(feature = "minimal"))]
+    fn test_overflow_released_on_exit_and_rollback() {
+        use crate::api::phase_budget::PhaseBudgetPolicy;
+
+        let alloc = SmartAlloc::new(AllocConfig::minimal());
+        alloc.set_phase_budget("ai", 0, PhaseBudgetPolicy::Fallback);
+        alloc.begin_frame();
+        let ai = alloc.phase_scope("ai");
+        let overflow = |alloc: &SmartAlloc| tls::with_tls(alloc.global(), |tls| tls.overflow_bytes());
+
+        alloc.frame_alloc::<[u8; 64]>();
+        alloc.sub_frame(|s| {
+            s.alloc([0u8; 128]).unwrap();
+            assert_eq!(overflow(&alloc), 192);
+        });
+        assert_eq!(overflow(&alloc), 64);
+
+        let checkpoint = alloc.frame_checkpoint();
+        alloc.frame_alloc::<[u8; 32]>();
+        assert_eq!(overflow(&alloc), 96);
+        alloc.rollback_to(checkpoint);
+        assert_eq!(overflow(&alloc), 64);
+
+        drop(ai);
+        alloc.end_frame();
+        assert_eq!(overflow(&alloc), 0);
+    }
+}
@@ -0,0 +1,1112 @@
+//! Live telemetry over a local socket.
+//!
+//! `SnapshotEmitter` talks to tools through files. A `TelemetryServer`
+//! instead listens on localhost TCP or a Unix domain socket and streams
+//! frame events, diagnostics and per-frame summaries to every connected
+//! client, so a dashboard can attach to a running game and detach again.
+//!
+//! # Protocol
+//!
+//! Server to client: each message is a little-endian `u32` byte length
+//! followed by the payload, either a JSON object with a `"type"` key or
+//! the compact binary form (see `TelemetryMessage::encode_binary`). The
+//! first message on every connection is `hello`.
+//!
+//! Client to server: newline-terminated text commands:
+//!
+//! | Command | Effect |
+//! |---------|--------|
+//! | `ping` | reply `pong` |
+//! | `snapshot` | send a full snapshot |
+//! | `reset-behavior` | reset behavior filter statistics |
+//! | `strict <mode>` | set strict mode (`warn`, `error`, `warning`) |
+//! | `leak-report` | send outstanding allocation totals |
+//!
+//! Commands are queued and run on the game thread in `end_frame`, at the
+//! same safe boundary snapshots use.
+//!
+//! # Overhead
+//!
+//! Nothing is encoded while no client is connected. Each client has a
+//! bounded queue drained by its own writer thread; when a slow client's
+//! queue is full, messages for it are dropped rather than stalling the
+//! frame.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! let telemetry = TelemetryServer::start(TelemetryConfig::tcp("127.0.0.1:7878"))?;
+//!
+//! let lifecycle = LifecycleManager::new();
+//! lifecycle.enable();
+//! let sink = telemetry.clone();
+//! lifecycle.on_event(move |event| sink.publish_frame_event(event));
+//!
+//! loop {
+//!     alloc.begin_frame();
+//!     // ... frame logic ...
+//!     alloc.end_frame();
+//!     telemetry.end_frame(&alloc);
+//! }
+//! ```
+
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Weak};
+use std::thread;
+use std::time::Duration;
+
+use super::alloc::SmartAlloc;
+use super::lifecycle::FrameEvent;
+use super::snapshot::SnapshotSummary;
+use crate::diagnostics::{
+    self, set_strict_mode, Diagnostic, DiagnosticSink, DiagnosticsEvent, FieldValue,
+    RuntimeDiagnostic, SinkId, StrictMode,
+};
+use crate::sync::mutex::Mutex;
+use crate::util::json::escape_json_str;
+use crate::util::varint::write_u64;
+
+/// Telemetry protocol version, sent in the `hello` message.
+pub const TELEMETRY_PROTOCOL_VERSION: u32 = 1;
+
+/// How long the accept loop sleeps when no connection is pending.
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Commands kept while waiting for `end_frame`; older ones are dropped.
+const MAX_PENDING_COMMANDS: usize = 64;
+
+/// Where the telemetry server listens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TelemetryAddr {
+    /// TCP address, normally on localhost
+    Tcp(String),
+    /// Unix domain socket path
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+/// Payload encoding for server messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TelemetryEncoding {
+    /// One JSON object per message
+    #[default]
+    Json,
+    /// Compact varint encoding
+    Binary,
+}
+
+/// Configuration for a telemetry server.
+#[derive(Debug, Clone)]
+pub struct TelemetryConfig {
+    /// Address to listen on
+    pub addr: TelemetryAddr,
+    /// Payload encoding
+    pub encoding: TelemetryEncoding,
+    /// Messages buffered per client before new ones are dropped
+    pub queue_capacity: usize,
+    /// Connections accepted at once; extra connections are closed
+    pub max_clients: usize,
+    /// Forward every emitted diagnostic to clients
+    pub forward_diagnostics: bool,
+}
+
+impl TelemetryConfig {
+    /// Listen on a TCP address such as `"127.0.0.1:7878"`.
+    pub fn tcp(addr: impl Into<String>) -> Self {
+        Self {
+            addr: TelemetryAddr::Tcp(addr.into()),
+            encoding: TelemetryEncoding::Json,
+            queue_capacity: 1024,
+            max_clients: 4,
+            forward_diagnostics: true,
+        }
+    }
+
+    /// Listen on a Unix domain socket, replacing any stale socket file.
+    #[cfg(unix)]
+    pub fn unix(path: impl Into<PathBuf>) -> Self {
+        Self {
+            addr: TelemetryAddr::Unix(path.into()),
+            ..Self::tcp("")
+        }
+    }
+
+    /// Builder: set the payload encoding.
+    pub fn with_encoding(mut self, encoding: TelemetryEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Builder: set the per-client queue capacity.
+    pub fn with_queue_capacity(mut self, capacity: usize) -> Self {
+        self.queue_capacity = capacity.max(1);
+        self
+    }
+
+    /// Builder: set the maximum number of clients.
+    pub fn with_max_clients(mut self, max: usize) -> Self {
+        self.max_clients = max;
+        self
+    }
+
+    /// Builder: enable or disable diagnostic forwarding.
+    pub fn with_forward_diagnostics(mut self, forward: bool) -> Self {
+        self.forward_diagnostics = forward;
+        self
+    }
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self::tcp("127.0.0.1:7878")
+    }
+}
+
+/// A message sent to telemetry clients.
+#[derive(Debug, Clone)]
+pub enum TelemetryMessage {
+    /// First message on every connection
+    Hello {
+        /// `TELEMETRY_PROTOCOL_VERSION`
+        protocol: u32,
+    },
+    /// A `FrameEvent` from a `LifecycleManager`
+    FrameEvent {
+        /// Event name, e.g. "frame_end"
+        event: &'static str,
+        /// Event fields
+        fields: Vec<(&'static str, FieldValue)>,
+    },
+    /// A `DiagnosticsEvent` from `DiagnosticsHooks`
+    DiagnosticsEvent {
+        /// Event name, e.g. "budget_exceeded"
+        event: &'static str,
+        /// Event fields
+        fields: Vec<(&'static str, FieldValue)>,
+    },
+    /// Allocator totals at the end of a frame
+    Summary {
+        /// Global frame number
+        frame: u64,
+        /// Memory totals
+        summary: SnapshotSummary,
+    },
+    /// An emitted diagnostic
+    Diagnostic(RuntimeDiagnostic),
+    /// A full snapshot, as `Snapshot::to_json` text
+    Snapshot(String),
+    /// Outstanding allocation totals
+    LeakReport {
+        /// Report fields
+        fields: Vec<(&'static str, FieldValue)>,
+    },
+    /// Result of a client command
+    Reply {
+        /// Command as received
+        command: String,
+        /// Whether it succeeded
+        ok: bool,
+        /// Result or error text
+        message: String,
+    },
+}
+
+impl TelemetryMessage {
+    /// Get the message type name used in both encodings.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::Hello { .. } => "hello",
+            Self::FrameEvent { .. } => "frame_event",
+            Self::DiagnosticsEvent { .. } => "diagnostics_event",
+            Self::Summary { .. } => "summary",
+            Self::Diagnostic(_) => "diagnostic",
+            Self::Snapshot(_) => "snapshot",
+            Self::LeakReport { .. } => "leak_report",
+            Self::Reply { .. } => "reply",
+        }
+    }
+
+    /// Serialize to a single-line JSON object.
+    pub fn to_json(&self) -> String {
+        let body = match self {
+            Self::Hello { protocol } => format!("\"protocol\": {}", protocol),
+            Self::FrameEvent { event, fields } | Self::DiagnosticsEvent { event, fields } => {
+                format!("\"event\": \"{}\", \"fields\": {}", event, fields_json(fields))
+            }
+            Self::Summary { frame, summary } => format!(
+                "\"frame\": {}, \"frame_bytes\": {}, \"pool_bytes\": {}, \"heap_bytes\": {}, \"total_bytes\": {}, \"peak_bytes\": {}",
+                frame,
+                summary.frame_bytes,
+                summary.pool_bytes,
+                summary.heap_bytes,
+                summary.total_bytes,
+                summary.peak_bytes
+            ),
+            Self::Diagnostic(diag) => format!("\"diagnostic\": {}", diag.to_json()),
+            Self::Snapshot(json) => format!("\"snapshot\": {}", json.trim_end()),
+            Self::LeakReport { fields } => format!("\"fields\": {}", fields_json(fields)),
+            Self::Reply { command, ok, message } => format!(
+                "\"command\": \"{}\", \"ok\": {}, \"message\": \"{}\"",
+                escape_json_str(command),
+                ok,
+                escape_json_str(message)
+            ),
+        };
+        format!("{{\"type\": \"{}\", {}}}", self.type_name(), body)
+    }
+
+    /// Serialize to the compact binary form.
+    ///
+    /// A type-name string, then the variant's values in declaration order:
+    /// integers as varints, strings as a varint length and UTF-8 bytes,
+    /// fields as a count then key, type byte (0 bytes, 1 number, 2 text)
+    /// and value.
+    pub fn encode_binary(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(64);
+        write_str(&mut out, self.type_name());
+        match self {
+            Self::Hello { protocol } => write_u64(&mut out, u64::from(*protocol)),
+            Self::FrameEvent { event, fields } | Self::DiagnosticsEvent { event, fields } => {
+                write_str(&mut out, event);
+                write_fields(&mut out, fields.iter().map(|(k, v)| (*k, v)));
+            }
+            Self::Summary { frame, summary } => {
+                for value in [
+                    *frame,
+                    summary.frame_bytes as u64,
+                    summary.pool_bytes as u64,
+                    summary.heap_bytes as u64,
+                    summary.total_bytes as u64,
+                    summary.peak_bytes as u64,
+                ] {
+                    write_u64(&mut out, value);
+                }
+            }
+            Self::Diagnostic(diag) => {
+                write_str(&mut out, diag.kind.prefix());
+                write_str(&mut out, &diag.code);
+                write_str(&mut out, &diag.message);
+                for text in [&diag.note, &diag.help] {
+                    match text {
+                        Some(text) => {
+                            out.push(1);
+                            write_str(&mut out, text);
+                        }
+                        None => out.push(0),
+                    }
+                }
+                write_fields(&mut out, diag.fields.iter().map(|(k, v)| (k.as_str(), v)));
+            }
+            Self::Snapshot(json) => write_str(&mut out, json),
+            Self::LeakReport { fields } => write_fields(&mut out, fields.iter().map(|(k, v)| (*k, v))),
+            Self::Reply { command, ok, message } => {
+                write_str(&mut out, command);
+                out.push(u8::from(*ok));
+                write_str(&mut out, message);
+            }
+        }
+        out
+    }
+
+    /// Encode as a length-prefixed frame ready to write to a socket.
+    pub fn to_frame(&self, encoding: TelemetryEncoding) -> Vec<u8> {
+        let payload = match encoding {
+            TelemetryEncoding::Json => self.to_json().into_bytes(),
+            TelemetryEncoding::Binary => self.encode_binary(),
+        };
+        let mut frame = Vec::with_capacity(payload.len() + 4);
+        frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        frame.extend_from_slice(&payload);
+        frame
+    }
+}
+
+fn thread_field(id: &thread::ThreadId) -> FieldValue {
+    FieldValue::Text(format!("{:?}", id))
+}
+
+impl From<&FrameEvent> for TelemetryMessage {
+    fn from(event: &FrameEvent) -> Self {
+        let (event, fields) = match event {
+            FrameEvent::FrameBegin { thread_id, frame_number, .. } => (
+                "frame_begin",
+                vec![("thread", thread_field(thread_id)), ("frame", FieldValue::Number(*frame_number))],
+            ),
+            FrameEvent::Alloc { thread_id, size, tag, frame_number } => {
+                let mut fields = vec![
+                    ("thread", thread_field(thread_id)),
+                    ("size", FieldValue::Bytes(*size)),
+                    ("frame", FieldValue::Number(*frame_number)),
+                ];
+                if let Some(tag) = tag {
+                    fields.push(("tag", FieldValue::from(*tag)));
+                }
+                ("alloc", fields)
+            }
+            FrameEvent::Free { thread_id, size, was_cross_thread } => (
+                "free",
+                vec![
+                    ("thread", thread_field(thread_id)),
+                    ("size", FieldValue::Bytes(*size)),
+                    ("cross_thread", FieldValue::Number(u64::from(*was_cross_thread))),
+                ],
+            ),
+            FrameEvent::FrameEnd { thread_id, frame_number, duration_us, total_allocated, peak_memory } => (
+                "frame_end",
+                vec![
+                    ("thread", thread_field(thread_id)),
+                    ("frame", FieldValue::Number(*frame_number)),
+                    ("duration_us", FieldValue::Number(*duration_us)),
+                    ("total_allocated", FieldValue::Bytes(*total_allocated)),
+                    ("peak_memory", FieldValue::Bytes(*peak_memory)),
+                ],
+            ),
+            FrameEvent::CrossThreadFreeQueued { from_thread, to_thread, size } => (
+                "cross_thread_free_queued",
+                vec![
+                    ("from_thread", thread_field(from_thread)),
+                    ("to_thread", thread_field(to_thread)),
+                    ("size", FieldValue::Bytes(*size)),
+                ],
+            ),
+            FrameEvent::DeferredProcessed { thread_id, count, total_bytes } => (
+                "deferred_processed",
+                vec![
+                    ("thread", thread_field(thread_id)),
+                    ("count", FieldValue::Number(*count as u64)),
+                    ("total_bytes", FieldValue::Bytes(*total_bytes)),
+                ],
+            ),
+            FrameEvent::TransferInitiated { from_thread, size } => (
+                "transfer_initiated",
+                vec![("from_thread", thread_field(from_thread)), ("size", FieldValue::Bytes(*size))],
+            ),
+            FrameEvent::TransferCompleted { to_thread, size } => (
+                "transfer_completed",
+                vec![("to_thread", thread_field(to_thread)), ("size", FieldValue::Bytes(*size))],
+            ),
+            FrameEvent::MemoryPressure { thread_id, used, budget } => (
+                "memory_pressure",
+                vec![
+                    ("thread", thread_field(thread_id)),
+                    ("used", FieldValue::Bytes(*used)),
+                    ("budget", FieldValue::Bytes(*budget)),
+                ],
+            ),
+            FrameEvent::BudgetExceeded { thread_id, requested, available, budget } => (
+                "budget_exceeded",
+                vec![
+                    ("thread", thread_field(thread_id)),
+                    ("requested", FieldValue::Bytes(*requested)),
+                    ("available", FieldValue::Bytes(*available)),
+                    ("budget", FieldValue::Bytes(*budget)),
+                ],
+            ),
+        };
+        Self::FrameEvent { event, fields }
+    }
+}
+
+impl From<&DiagnosticsEvent> for TelemetryMessage {
+    fn from(event: &DiagnosticsEvent) -> Self {
+        let (event, fields) = match event {
+            DiagnosticsEvent::FrameBegin { frame_number } => {
+                ("frame_begin", vec![("frame", FieldValue::Number(*frame_number))])
+            }
+            DiagnosticsEvent::FrameEnd { frame_number } => {
+                ("frame_end", vec![("frame", FieldValue::Number(*frame_number))])
+            }
+            DiagnosticsEvent::LargeAllocation { size, tag } => {
+                let mut fields = vec![("size", FieldValue::Bytes(*size))];
+                if let Some(tag) = tag {
+                    fields.push(("tag", FieldValue::from(*tag)));
+                }
+                ("large_allocation", fields)
+            }
+            DiagnosticsEvent::MemoryPressure { current, limit } => (
+                "memory_pressure",
+                vec![("current", FieldValue::Bytes(*current)), ("limit", FieldValue::Bytes(*limit))],
+            ),
+            DiagnosticsEvent::SlabRefill { size_class, count } => (
+                "slab_refill",
+                vec![
+                    ("size_class", FieldValue::Bytes(*size_class)),
+                    ("count", FieldValue::Number(*count as u64)),
+                ],
+            ),
+            DiagnosticsEvent::DeferredFree { count } => {
+                ("deferred_free", vec![("count", FieldValue::Number(*count as u64))])
+            }
+            DiagnosticsEvent::BudgetWarning { tag, current, limit } => (
+                "budget_warning",
+                vec![
+                    ("tag", FieldValue::from(*tag)),
+                    ("current", FieldValue::Bytes(*current)),
+                    ("limit", FieldValue::Bytes(*limit)),
+                ],
+            ),
+            DiagnosticsEvent::BudgetExceeded { tag, current, limit } => (
+                "budget_exceeded",
+                vec![
+                    ("tag", FieldValue::from(*tag)),
+                    ("current", FieldValue::Bytes(*current)),
+                    ("limit", FieldValue::Bytes(*limit)),
+                ],
+            ),
+        };
+        Self::DiagnosticsEvent { event, fields }
+    }
+}
+
+fn fields_json(fields: &[(&'static str, FieldValue)]) -> String {
+    let fields: Vec<_> = fields
+        .iter()
+        .map(|(k, v)| format!("\"{}\": {}", k, v.to_json()))
+        .collect();
+    format!("{{{}}}", fields.join(", "))
+}
+
+fn write_str(out: &mut Vec<u8>, s: &str) {
+    write_u64(out, s.len() as u64);
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn write_fields<'a>(out: &mut Vec<u8>, fields: impl ExactSizeIterator<Item = (&'a str, &'a FieldValue)>) {
+    write_u64(out, fields.len() as u64);
+    for (key, value) in fields {
+        write_str(out, key);
+        match value {
+            FieldValue::Bytes(n) => {
+                out.push(0);
+                write_u64(out, *n as u64);
+            }
+            FieldValue::Number(n) => {
+                out.push(1);
+                write_u64(out, *n);
+            }
+            FieldValue::Text(s) => {
+                out.push(2);
+                write_str(out, s);
+            }
+        }
+    }
+}
+
+/// A command sent by a telemetry client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TelemetryCommand {
+    /// Reply `pong`
+    Ping,
+    /// Send a full snapshot
+    Snapshot,
+    /// Reset behavior filter statistics
+    ResetBehaviorStats,
+    /// Change the global strict mode
+    SetStrictMode(StrictMode),
+    /// Send outstanding allocation totals
+    LeakReport,
+}
+
+impl TelemetryCommand {
+    /// Parse a command line.
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let mut words = line.split_whitespace();
+        let command = match (words.next(), words.next()) {
+            (Some("ping"), None) => Self::Ping,
+            (Some("snapshot"), None) => Self::Snapshot,
+            (Some("reset-behavior"), None) => Self::ResetBehaviorStats,
+            (Some("leak-report"), None) => Self::LeakReport,
+            (Some("strict"), Some(mode)) => Self::SetStrictMode(
+                StrictMode::from_name(mode).ok_or_else(|| format!("unknown strict mode '{}'", mode))?,
+            ),
+            _ => return Err(format!("unknown command '{}'", line.trim())),
+        };
+        if words.next().is_some() {
+            return Err(format!("unexpected arguments in '{}'", line.trim()));
+        }
+        Ok(command)
+    }
+}
+
+/// Counters for a telemetry server.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TelemetryStats {
+    /// Clients currently connected
+    pub clients: usize,
+    /// Connections accepted since start
+    pub connections: u64,
+    /// Messages queued for clients
+    pub messages_sent: u64,
+    /// Messages dropped because a client's queue was full
+    pub messages_dropped: u64,
+    /// Commands run
+    pub commands_handled: u64,
+}
+
+/// Either kind of connected socket.
+enum Stream {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Stream {
+    fn try_clone(&self) -> io::Result<Self> {
+        match self {
+            Self::Tcp(s) => s.try_clone().map(Self::Tcp),
+            #[cfg(unix)]
+            Self::Unix(s) => s.try_clone().map(Self::Unix),
+        }
+    }
+
+    fn shutdown(&self) {
+        let _ = match self {
+            Self::Tcp(s) => s.shutdown(Shutdown::Both),
+            #[cfg(unix)]
+            Self::Unix(s) => s.shutdown(Shutdown::Both),
+        };
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Tcp(s) => s.read(buf),
+            #[cfg(unix)]
+            Self::Unix(s) => s.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Tcp(s) => s.write(buf),
+            #[cfg(unix)]
+            Self::Unix(s) => s.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Tcp(s) => s.flush(),
+            #[cfg(unix)]
+            Self::Unix(s) => s.flush(),
+        }
+    }
+}
+
+/// Either kind of listening socket.
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener),
+}
+
+impl Listener {
+    fn bind(addr: &TelemetryAddr) -> io::Result<Self> {
+        let listener = match addr {
+            TelemetryAddr::Tcp(addr) => Self::Tcp(TcpListener::bind(addr.as_str())?),
+            #[cfg(unix)]
+            TelemetryAddr::Unix(path) => {
+                let _ = std::fs::remove_file(path);
+                Self::Unix(UnixListener::bind(path)?)
+            }
+        };
+        match &listener {
+            Self::Tcp(l) => l.set_nonblocking(true)?,
+            #[cfg(unix)]
+            Self::Unix(l) => l.set_nonblocking(true)?,
+        }
+        Ok(listener)
+    }
+
+    fn accept(&self) -> io::Result<Stream> {
+        match self {
+            Self::Tcp(l) => {
+                let (stream, _) = l.accept()?;
+                stream.set_nonblocking(false)?;
+                let _ = stream.set_nodelay(true);
+                Ok(Stream::Tcp(stream))
+            }
+            #[cfg(unix)]
+            Self::Unix(l) => {
+                let (stream, _) = l.accept()?;
+                stream.set_nonblocking(false)?;
+                Ok(Stream::Unix(stream))
+            }
+        }
+    }
+
+    fn local_addr(&self) -> Option<SocketAddr> {
+        match self {
+            Self::Tcp(l) => l.local_addr().ok(),
+            #[cfg(unix)]
+            Self::Unix(_) => None,
+        }
+    }
+}
+
+struct Client {
+    id: u64,
+    sender: SyncSender<Arc<[u8]>>,
+    stream: Stream,
+}
+
+struct Inner {
+    config: TelemetryConfig,
+    local_addr: Option<SocketAddr>,
+    clients: Mutex<Vec<Client>>,
+    commands: Mutex<VecDeque<(u64, String)>>,
+    shutdown: AtomicBool,
+    next_client: AtomicU64,
+    connections: AtomicU64,
+    messages_sent: AtomicU64,
+    messages_dropped: AtomicU64,
+    commands_handled: AtomicU64,
+    sink: Mutex<Option<SinkId>>,
+}
+
+impl Inner {
+    fn has_clients(&self) -> bool {
+        !self.clients.lock().is_empty()
+    }
+
+    /// Queue a frame for one client, or all clients when `to` is `None`.
+    fn send(&self, message: &TelemetryMessage, to: Option<u64>) {
+        let mut clients = self.clients.lock();
+        if clients.is_empty() {
+            return;
+        }
+
+        let frame: Arc<[u8]> = message.to_frame(self.config.encoding).into();
+        clients.retain(|client| {
+            if to.is_some_and(|id| id != client.id) {
+                return true;
+            }
+            match client.sender.try_send(frame.clone()) {
+                Ok(()) => {
+                    self.messages_sent.fetch_add(1, Ordering::Relaxed);
+                    true
+                }
+                Err(TrySendError::Full(_)) => {
+                    self.messages_dropped.fetch_add(1, Ordering::Relaxed);
+                    true
+                }
+                Err(TrySendError::Disconnected(_)) => {
+                    client.stream.shutdown();
+                    false
+                }
+            }
+        });
+    }
+
+    /// Accept clients until shutdown or until the last server handle drops.
+    fn accept_loop(inner: Weak<Self>, listener: Listener, addr: TelemetryAddr) {
+        while let Some(server) = inner.upgrade() {
+            if server.shutdown.load(Ordering::Acquire) {
+                break;
+            }
+            match listener.accept() {
+                Ok(stream) => server.add_client(stream),
+                Err(_) => {
+                    drop(server);
+                    thread::sleep(ACCEPT_POLL_INTERVAL);
+                }
+            }
+        }
+
+        #[cfg(unix)]
+        if let TelemetryAddr::Unix(path) = &addr {
+            let _ = std::fs::remove_file(path);
+        }
+        #[cfg(not(unix))]
+        let _ = addr;
+    }
+
+    fn add_client(self: &Arc<Self>, stream: Stream) {
+        if self.clients.lock().len() >= self.config.max_clients {
+            stream.shutdown();
+            return;
+        }
+        let (Ok(reader), Ok(writer)) = (stream.try_clone(), stream.try_clone()) else {
+            return;
+        };
+
+        let id = self.next_client.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = mpsc::sync_channel(self.config.queue_capacity.max(1));
+        // Queue Hello before `send` can see the client, so it arrives first
+        let hello = TelemetryMessage::Hello { protocol: TELEMETRY_PROTOCOL_VERSION };
+        if sender.try_send(hello.to_frame(self.config.encoding).into()).is_ok() {
+            self.messages_sent.fetch_add(1, Ordering::Relaxed);
+        }
+
+        thread::spawn(move || write_loop(writer, receiver));
+        let inner = Arc::downgrade(self);
+        thread::spawn(move || read_loop(inner, id, reader));
+
+        self.clients.lock().push(Client { id, sender, stream });
+        self.connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Stop accepting, disconnect clients and stop forwarding diagnostics.
+    fn close(&self) {
+        self.shutdown.store(true, Ordering::Release);
+        if let Some(id) = self.sink.lock().take() {
+            diagnostics::sinks().remove(id);
+        }
+        for client in self.clients.lock().drain(..) {
+            client.stream.shutdown();
+        }
+        self.commands.lock().clear();
+    }
+
+    fn remove_client(&self, id: u64) {
+        self.clients.lock().retain(|client| {
+            if client.id == id {
+                client.stream.shutdown();
+            }
+            client.id != id
+        });
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+fn write_loop(mut stream: Stream, receiver: Receiver<Arc<[u8]>>) {
+    // Ends when the client is removed (sender dropped) or the socket fails
+    for frame in receiver {
+        if stream.write_all(&frame).is_err() {
+            stream.shutdown();
+            return;
+        }
+    }
+}
+
+fn read_loop(inner: Weak<Inner>, id: u64, stream: Stream) {
+    for line in BufReader::new(stream).lines() {
+        let (Ok(line), Some(inner)) = (line, inner.upgrade()) else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut commands = inner.commands.lock();
+        if commands.len() >= MAX_PENDING_COMMANDS {
+            commands.pop_front();
+        }
+        commands.push_back((id, line));
+    }
+    if let Some(inner) = inner.upgrade() {
+        inner.remove_client(id);
+    }
+}
+
+/// Forwards emitted diagnostics to telemetry clients.
+struct TelemetrySink {
+    inner: Weak<Inner>,
+}
+
+impl TelemetrySink {
+    fn forward(&self, diag: RuntimeDiagnostic) {
+        if let Some(inner) = self.inner.upgrade() {
+            if inner.has_clients() {
+                inner.send(&TelemetryMessage::Diagnostic(diag), None);
+            }
+        }
+    }
+}
+
+impl DiagnosticSink for TelemetrySink {
+    fn emit(&self, diag: &Diagnostic) {
+        self.forward(RuntimeDiagnostic::from_diagnostic(diag));
+    }
+
+    fn emit_with_context(&self, diag: &Diagnostic, context: &str) {
+        self.forward(RuntimeDiagnostic::from_diagnostic(diag).with_field("context", FieldValue::from(context)));
+    }
+
+    fn emit_runtime(&self, diag: &RuntimeDiagnostic) {
+        self.forward(diag.clone());
+    }
+}
+
+/// In-process telemetry endpoint.
+///
+/// Cheap to clone; clones share the listener and clients. Call `shutdown`
+/// to stop accepting connections and disconnect everyone; dropping the
+/// last clone does the same.
+#[derive(Clone)]
+pub struct TelemetryServer {
+    inner: Arc<Inner>,
+}
+
+impl TelemetryServer {
+    /// Bind the configured address and start accepting clients.
+    pub fn start(config: TelemetryConfig) -> io::Result<Self> {
+        if let TelemetryAddr::Tcp(addr) = &config.addr {
+            // Resolve early so a bad address is reported here, not on a thread
+            addr.as_str().to_socket_addrs()?;
+        }
+        let listener = Listener::bind(&config.addr)?;
+        let forward_diagnostics = config.forward_diagnostics;
+
+        let inner = Arc::new(Inner {
+            local_addr: listener.local_addr(),
+            config,
+            clients: Mutex::new(Vec::new()),
+            commands: Mutex::new(VecDeque::new()),
+            shutdown: AtomicBool::new(false),
+            next_client: AtomicU64::new(0),
+            connections: AtomicU64::new(0),
+            messages_sent: AtomicU64::new(0),
+            messages_dropped: AtomicU64::new(0),
+            commands_handled: AtomicU64::new(0),
+            sink: Mutex::new(None),
+        });
+
+        if forward_diagnostics {
+            let sink = Arc::new(TelemetrySink {
+                inner: Arc::downgrade(&inner),
+            });
+            *inner.sink.lock() = Some(diagnostics::sinks().add(sink));
+        }
+
+        let accept = Arc::downgrade(&inner);
+        let addr = inner.config.addr.clone();
+        thread::Builder::new()
+            .name("framealloc-telemetry".to_string())
+            .spawn(move || Inner::accept_loop(accept, listener, addr))?;
+
+        Ok(Self { inner })
+    }
+
+    /// Get the bound TCP address, e.g. to find the port chosen for `:0`.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.inner.local_addr
+    }
+
+    /// Check if any client is connected.
+    pub fn has_clients(&self) -> bool {
+        self.inner.has_clients()
+    }
+
+    /// Send a message to every client.
+    pub fn publish(&self, message: &TelemetryMessage) {
+        self.inner.send(message, None);
+    }
+
+    /// Send a `FrameEvent`, e.g. from a `LifecycleManager` callback.
+    pub fn publish_frame_event(&self, event: &FrameEvent) {
+        if self.has_clients() {
+            self.publish(&TelemetryMessage::from(event));
+        }
+    }
+
+    /// Send a `DiagnosticsEvent`, e.g. from a `DiagnosticsHooks` listener.
+    pub fn publish_diagnostics_event(&self, event: &DiagnosticsEvent) {
+        if self.has_clients() {
+            self.publish(&TelemetryMessage::from(event));
+        }
+    }
+
+    /// Send the frame summary and run pending client commands.
+    ///
+    /// Call on the frame authority's thread after `SmartAlloc::end_frame`.
+    /// Returns the number of commands run.
+    pub fn end_frame(&self, alloc: &SmartAlloc) -> usize {
+        if !self.has_clients() {
+            self.inner.commands.lock().clear();
+            return 0;
+        }
+
+        let stats = alloc.stats();
+        self.publish(&TelemetryMessage::Summary {
+            frame: alloc.frame_number(),
+            summary: SnapshotSummary {
+                frame_bytes: stats.frame_allocated,
+                pool_bytes: stats.pool_allocated,
+                heap_bytes: stats.heap_allocated,
+                total_bytes: stats.total_allocated,
+                peak_bytes: stats.peak_allocated,
+            },
+        });
+
+        let commands: Vec<_> = self.inner.commands.lock().drain(..).collect();
+        for (client, line) in &commands {
+            let reply = self.run_command(alloc, *client, line);
+            self.inner.send(&reply, Some(*client));
+        }
+        self.inner
+            .commands_handled
+            .fetch_add(commands.len() as u64, Ordering::Relaxed);
+        commands.len()
+    }
+
+    fn run_command(&self, alloc: &SmartAlloc, client: u64, line: &str) -> TelemetryMessage {
+        let reply = |ok: bool, message: String| TelemetryMessage::Reply {
+            command: line.to_string(),
+            ok,
+            message,
+        };
+
+        let command = match TelemetryCommand::parse(line) {
+            Ok(command) => command,
+            Err(e) => return reply(false, e),
+        };
+        match command {
+            TelemetryCommand::Ping => reply(true, "pong".to_string()),
+            TelemetryCommand::Snapshot => {
+                let snapshot = alloc.snapshot();
+                self.inner.send(&TelemetryMessage::Snapshot(snapshot.to_json()), Some(client));
+                reply(true, format!("snapshot of frame {}", snapshot.frame))
+            }
+            TelemetryCommand::ResetBehaviorStats => {
+                alloc.reset_behavior_stats();
+                reply(true, "behavior stats reset".to_string())
+            }
+            TelemetryCommand::SetStrictMode(mode) => {
+                set_strict_mode(mode);
+                reply(true, format!("strict mode {:?}", mode))
+            }
+            TelemetryCommand::LeakReport => {
+                let stats = alloc.stats();
+                let fields = vec![
+                    ("frame", FieldValue::Number(alloc.frame_number())),
+                    ("live_allocations", FieldValue::Number(stats.active_allocations())),
+                    ("pool_bytes", FieldValue::Bytes(stats.pool_allocated)),
+                    ("heap_bytes", FieldValue::Bytes(stats.heap_allocated)),
+                    ("large_object_bytes", FieldValue::Bytes(stats.large_object_allocated)),
+                    ("large_objects", FieldValue::Number(stats.large_object_count)),
+                    ("retained", FieldValue::Number(alloc.retained_count() as u64)),
+                ];
+                self.inner.send(&TelemetryMessage::LeakReport { fields }, Some(client));
+                reply(true, format!("{} live allocations", stats.active_allocations()))
+            }
+        }
+    }
+
+    /// Get server counters.
+    pub fn stats(&self) -> TelemetryStats {
+        TelemetryStats {
+            clients: self.inner.clients.lock().len(),
+            connections: self.inner.connections.load(Ordering::Relaxed),
+            messages_sent: self.inner.messages_sent.load(Ordering::Relaxed),
+            messages_dropped: self.inner.messages_dropped.load(Ordering::Relaxed),
+            commands_handled: self.inner.commands_handled.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Stop accepting connections, disconnect clients and stop forwarding
+    /// diagnostics.
+    pub fn shutdown(&self) {
+        self.inner.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::config::AllocConfig;
+    use std::time::Instant;
+
+    fn read_message(stream: &mut TcpStream) -> String {
+        let mut len = [0u8; 4];
+        stream.read_exact(&mut len).unwrap();
+        let mut payload = vec![0u8; u32::from_le_bytes(len) as usize];
+        stream.read_exact(&mut payload).unwrap();
+        String::from_utf8(payload).unwrap()
+    }
+
+    #[test]
+    fn test_commands_parse() {
+        assert_eq!(TelemetryCommand::parse("snapshot\r"), Ok(TelemetryCommand::Snapshot));
+        assert_eq!(
+            TelemetryCommand::parse("strict error"),
+            Ok(TelemetryCommand::SetStrictMode(StrictMode::PanicOnError))
+        );
+        assert!(TelemetryCommand::parse("strict sometimes").is_err());
+        assert!(TelemetryCommand::parse("ping twice").is_err());
+    }
+
+    #[test]
+    fn test_client_receives_summaries_and_command_replies() {
+        let server = TelemetryServer::start(
+            TelemetryConfig::tcp("127.0.0.1:0").with_forward_diagnostics(false),
+        )
+        .unwrap();
+        let mut client = TcpStream::connect(server.local_addr().unwrap()).unwrap();
+        client.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        assert!(read_message(&mut client).contains("\"type\": \"hello\", \"protocol\": 1"));
+
+        let alloc = SmartAlloc::new(AllocConfig::default());
+        client.write_all(b"ping\nleak-report\n").unwrap();
+
+        // Commands arrive asynchronously; run frames until both are handled
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut handled = 0;
+        while handled < 2 && Instant::now() < deadline {
+            alloc.begin_frame();
+            alloc.end_frame();
+            handled += server.end_frame(&alloc);
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(handled, 2);
+
+        // Each handling frame sends a summary before its replies
+        let replies: Vec<_> = (0..8)
+            .map(|_| read_message(&mut client))
+            .filter(|m| !m.contains("\"type\": \"summary\""))
+            .take(3)
+            .collect();
+        assert!(replies[0].contains("\"command\": \"ping\", \"ok\": true, \"message\": \"pong\""));
+        assert!(replies[1].contains("\"type\": \"leak_report\""));
+        assert!(replies[1].contains("\"live_allocations\": "));
+        assert!(replies[2].contains("\"command\": \"leak-report\", \"ok\": true"));
+
+        let event = TelemetryMessage::from(&DiagnosticsEvent::SlabRefill { size_class: 64, count: 32 });
+        assert_eq!(
+            event.to_json(),
+            "{\"type\": \"diagnostics_event\", \"event\": \"slab_refill\", \"fields\": {\"size_class\": 64, \"count\": 32}}"
+        );
+        assert_eq!(&event.encode_binary()[..18], b"\x11diagnostics_event");
+
+        server.shutdown();
+        assert_eq!(server.stats().clients, 0);
+    }
+
+    #[test]
+    fn test_dropping_last_handle_stops_server() {
+        let server = TelemetryServer::start(
+            TelemetryConfig::tcp("127.0.0.1:0").with_forward_diagnostics(false),
+        )
+        .unwrap();
+        let addr = server.local_addr().unwrap();
+        drop(server.clone());
+
+        let mut client = TcpStream::connect(addr).unwrap();
+        client.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        assert!(read_message(&mut client).contains("\"type\": \"hello\""));
+        drop(server);
+
+        // The client is disconnected and the listener closes
+        assert_eq!(client.read(&mut [0u8; 1]).unwrap(), 0);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while TcpStream::connect(addr).is_ok() {
+            assert!(Instant::now() < deadline, "listener still accepting");
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+}
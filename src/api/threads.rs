@@ -0,0 +1,205 @@
+//! Per-allocator thread registration and teardown.
+//!
+//! Every thread that touches an allocator is registered implicitly the first
+//! time it does so. `SmartAlloc::register_thread` gives a thread a name for
+//! diagnostics and snapshots, and `SmartAlloc::unregister_thread` (or thread
+//! exit) tears its state down: pending deferred frees are drained and the
+//! thread's pool free lists go back to the slab registry for other threads.
+
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, ThreadId};
+
+use crate::sync::mutex::Mutex;
+
+/// Get the current thread's std name.
+///
+/// Names passed to `register_thread` belong to one allocator; see
+/// `ThreadInfo::name`.
+pub fn current_thread_name() -> Option<String> {
+    thread::current().name().map(String::from)
+}
+
+/// A registered thread, as seen by one allocator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreadInfo {
+    /// Thread ID
+    pub id: ThreadId,
+    /// Registered name, the std thread name, or "unnamed"
+    pub name: String,
+    /// Whether the thread called `register_thread`
+    pub explicit: bool,
+    /// The thread's frame epoch
+    pub epoch: u64,
+    /// Frame arena bytes in use when the thread last ended a frame
+    pub last_frame_bytes: usize,
+    /// Highest frame arena high-water mark of any frame on the thread
+    pub peak_frame_bytes: usize,
+    /// Pool bytes allocated minus pool bytes freed on the thread
+    ///
+    /// A free counts against the thread that frees, so a thread that frees
+    /// objects allocated elsewhere can go negative. Always 0 under `minimal`.
+    pub pool_bytes: i64,
+    /// Heap bytes allocated minus heap bytes freed on the thread, counted
+    /// like `pool_bytes`
+    pub heap_bytes: i64,
+}
+
+/// Totals for threads whose state has been torn down.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ThreadTeardownStats {
+    /// Threads unregistered or exited
+    pub threads_torn_down: u64,
+    /// Pool objects returned to the slab registry
+    pub objects_reclaimed: u64,
+    /// Deferred frees drained during teardown
+    pub deferred_drained: u64,
+}
+
+/// Shared record for one thread, updated by its thread-local state.
+pub(crate) struct ThreadRecord {
+    id: ThreadId,
+    name: Mutex<String>,
+    explicit: AtomicBool,
+    epoch: AtomicU64,
+    last_frame_bytes: AtomicUsize,
+    peak_frame_bytes: AtomicUsize,
+    pool_bytes: AtomicI64,
+    heap_bytes: AtomicI64,
+}
+
+impl ThreadRecord {
+    /// Get the thread's ID.
+    pub(crate) fn id(&self) -> ThreadId {
+        self.id
+    }
+
+    /// Get the name passed to `register_thread`, if any.
+    pub(crate) fn registered_name(&self) -> Option<String> {
+        let name = self.name.lock();
+        self.explicit.load(Ordering::Relaxed).then(|| name.clone())
+    }
+
+    /// Record the thread's new frame epoch.
+    pub(crate) fn set_epoch(&self, epoch: u64) {
+        self.epoch.store(epoch, Ordering::Relaxed);
+    }
+
+    /// Record the frame arena usage at the end of a frame.
+    pub(crate) fn set_last_frame_bytes(&self, bytes: usize) {
+        self.last_frame_bytes.store(bytes, Ordering::Relaxed);
+    }
+
+    /// Record a finished frame's arena high-water mark.
+    pub(crate) fn note_frame_high_water(&self, bytes: usize) {
+        self.peak_frame_bytes.fetch_max(bytes, Ordering::Relaxed);
+    }
+
+    /// Add to the thread's net pool bytes. Only the owning thread writes.
+    pub(crate) fn add_pool_bytes(&self, delta: i64) {
+        let bytes = self.pool_bytes.load(Ordering::Relaxed);
+        self.pool_bytes.store(bytes + delta, Ordering::Relaxed);
+    }
+
+    /// Add to the thread's net heap bytes. Only the owning thread writes.
+    pub(crate) fn add_heap_bytes(&self, delta: i64) {
+        let bytes = self.heap_bytes.load(Ordering::Relaxed);
+        self.heap_bytes.store(bytes + delta, Ordering::Relaxed);
+    }
+
+    fn info(&self) -> ThreadInfo {
+        ThreadInfo {
+            id: self.id,
+            name: self.name.lock().clone(),
+            explicit: self.explicit.load(Ordering::Relaxed),
+            epoch: self.epoch.load(Ordering::Relaxed),
+            last_frame_bytes: self.last_frame_bytes.load(Ordering::Relaxed),
+            peak_frame_bytes: self.peak_frame_bytes.load(Ordering::Relaxed),
+            pool_bytes: self.pool_bytes.load(Ordering::Relaxed),
+            heap_bytes: self.heap_bytes.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Threads currently using an allocator.
+pub struct ThreadRegistry {
+    threads: Mutex<Vec<Arc<ThreadRecord>>>,
+    teardown: Mutex<ThreadTeardownStats>,
+}
+
+impl ThreadRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self {
+            threads: Mutex::new(Vec::new()),
+            teardown: Mutex::new(ThreadTeardownStats::default()),
+        }
+    }
+
+    /// Register the current thread implicitly.
+    pub(crate) fn attach(&self) -> Arc<ThreadRecord> {
+        let record = Arc::new(ThreadRecord {
+            id: thread::current().id(),
+            name: Mutex::new(current_thread_name().unwrap_or_else(|| "unnamed".to_string())),
+            explicit: AtomicBool::new(false),
+            epoch: AtomicU64::new(0),
+            last_frame_bytes: AtomicUsize::new(0),
+            peak_frame_bytes: AtomicUsize::new(0),
+            pool_bytes: AtomicI64::new(0),
+            heap_bytes: AtomicI64::new(0),
+        });
+        self.threads.lock().push(record.clone());
+        record
+    }
+
+    /// Name the thread behind `record` and mark it explicitly registered.
+    pub(crate) fn name(&self, record: &ThreadRecord, name: &str) {
+        *record.name.lock() = name.to_string();
+        record.explicit.store(true, Ordering::Relaxed);
+    }
+
+    /// Remove a torn-down thread and account for what it gave back.
+    pub(crate) fn detach(&self, record: &Arc<ThreadRecord>, reclaimed: usize, deferred: usize) {
+        self.threads.lock().retain(|r| !Arc::ptr_eq(r, record));
+
+        let mut stats = self.teardown.lock();
+        stats.threads_torn_down += 1;
+        stats.objects_reclaimed += reclaimed as u64;
+        stats.deferred_drained += deferred as u64;
+    }
+
+    /// Get all registered threads.
+    pub fn threads(&self) -> Vec<ThreadInfo> {
+        self.threads.lock().iter().map(|r| r.info()).collect()
+    }
+
+    /// Get a registered thread by ID.
+    pub fn get(&self, id: ThreadId) -> Option<ThreadInfo> {
+        self.threads
+            .lock()
+            .iter()
+            .find(|r| r.id == id)
+            .map(|r| r.info())
+    }
+
+    /// Get the number of registered threads.
+    pub fn len(&self) -> usize {
+        self.threads.lock().len()
+    }
+
+    /// Check if no threads are registered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get totals for torn-down threads.
+    pub fn teardown_stats(&self) -> ThreadTeardownStats {
+        *self.teardown.lock()
+    }
+}
+
+impl Default for ThreadRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
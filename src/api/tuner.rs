@@ -0,0 +1,492 @@
+//! Configuration tuning from recorded runs.
+//!
+//! `ConfigTuner` turns what representative runs observed into a
+//! recommended `AllocConfig`, with a justification for each field:
+//!
+//! - `frame_arena_size` from per-thread peak arena use and arena high-water
+//!   histograms
+//! - `slab_size_classes` from pool allocation size histograms
+//! - `global_memory_limit` and `tag_budgets` from budget events
+//!
+//! `slab_pages_per_class` keeps the base value: slab pages are allocated
+//! on demand and nothing reads it. Its justification still reports slab
+//! refills and pages allocated per class.
+//!
+//! Phase budgets are not part of `AllocConfig`; pressure on them is
+//! reported as justifications for `phase.<phase>`.
+//!
+//! ```rust,ignore
+//! alloc.enable_allocation_histograms();
+//! let tuner = Arc::new(Mutex::new(ConfigTuner::new(alloc.config().clone())));
+//! let events = tuner.clone();
+//! alloc.budgets().unwrap().set_event_callback(move |e| events.lock().unwrap().record_budget_event(&e));
+//!
+//! // ... run representative frames ...
+//!
+//! tuner.lock().unwrap().observe(&alloc);
+//! let recommendation = tuner.lock().unwrap().recommend();
+//! std::fs::write("framealloc.toml", recommendation.to_toml())?;
+//!
+//! // At the next startup
+//! let alloc = SmartAlloc::new(AllocConfig::from_file("framealloc.toml")?);
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::allocators::slab::{effective_size_classes, DepotStats, MAX_SLAB_ALIGN, NUM_SIZE_CLASSES};
+use crate::api::alloc::SmartAlloc;
+use crate::api::config::AllocConfig;
+use crate::core::budget::BudgetEvent;
+use crate::diagnostics::behavior::AllocKind;
+use crate::diagnostics::histogram::AllocationHistograms;
+use crate::util::json::escape_json_str;
+use crate::util::size::{format_bytes, kb, mb};
+
+/// Smallest frame arena the tuner recommends.
+const MIN_FRAME_ARENA: usize = kb(64);
+
+/// Why the tuner chose a value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Justification {
+    /// Config field or table, or `phase.<phase>` for phase budgets, which
+    /// are configured at runtime
+    pub field: String,
+    /// The evidence behind the value
+    pub reason: String,
+}
+
+impl fmt::Display for Justification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.reason)
+    }
+}
+
+/// A recommended config and the evidence for it.
+#[derive(Debug, Clone)]
+pub struct TuningRecommendation {
+    /// The recommended config
+    pub config: AllocConfig,
+    /// One justification per field the tuner had evidence for
+    pub justifications: Vec<Justification>,
+}
+
+impl TuningRecommendation {
+    /// Get the justification for `field`.
+    pub fn justification(&self, field: &str) -> Option<&Justification> {
+        self.justifications.iter().find(|j| j.field == field)
+    }
+
+    /// Write the config as TOML, with justifications as comments.
+    ///
+    /// The result loads with `AllocConfig::from_toml` or `from_file`.
+    pub fn to_toml(&self) -> String {
+        let mut out = String::from("# Recommended by framealloc's ConfigTuner\n");
+        for note in self.runtime_notes() {
+            out.push_str(&format!("# {}\n", note));
+        }
+        out.push('\n');
+        out.push_str(&self.config.write_toml(|field| self.justification(field).map(|j| j.reason.clone())));
+        out
+    }
+
+    /// Write the config as JSON, with justifications under `"justification"`.
+    ///
+    /// The result loads with `AllocConfig::from_json` or `from_file`.
+    pub fn to_json(&self) -> String {
+        let reasons: Vec<_> = self
+            .justifications
+            .iter()
+            .map(|j| format!("    \"{}\": \"{}\"", escape_json_str(&j.field), escape_json_str(&j.reason)))
+            .collect();
+        let justification = format!("{{\n{}\n  }}", reasons.join(",\n"));
+        self.config.write_json(vec![("justification".to_string(), justification)])
+    }
+
+    /// Justifications for budgets that are not config fields or tables.
+    fn runtime_notes(&self) -> impl Iterator<Item = &Justification> {
+        let fields: Vec<_> = self.config.fields().into_iter().map(|(field, _)| field).collect();
+        self.justifications.iter().filter(move |j| {
+            let table = format!("{}.", j.field);
+            !fields.iter().any(|field| *field == j.field || field.starts_with(&table))
+        })
+    }
+}
+
+impl fmt::Display for TuningRecommendation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for justification in &self.justifications {
+            writeln!(f, "{}", justification)?;
+        }
+        Ok(())
+    }
+}
+
+/// Budget events seen for one tag or phase.
+#[derive(Debug, Clone, Copy, Default)]
+struct Pressure {
+    soft_exceeded: u64,
+    hard_exceeded: u64,
+    soft_limit: usize,
+    hard_limit: usize,
+    peak: usize,
+}
+
+/// Recommends an `AllocConfig` from recorded runs.
+///
+/// Feed it evidence with `observe` (or the individual `record_*` methods,
+/// once per run) and budget events as they happen, then call `recommend`.
+#[derive(Debug, Clone)]
+pub struct ConfigTuner {
+    base: AllocConfig,
+    arena_peaks: Vec<usize>,
+    histograms: AllocationHistograms,
+    frames: u64,
+    refills: u64,
+    /// Most pages any run allocated, per class size
+    class_pages: BTreeMap<usize, u64>,
+    global_exceeded: u64,
+    global_limit: usize,
+    global_peak: usize,
+    tags: BTreeMap<&'static str, Pressure>,
+    phases: BTreeMap<&'static str, Pressure>,
+}
+
+impl ConfigTuner {
+    /// Create a tuner that adjusts `base`.
+    pub fn new(base: AllocConfig) -> Self {
+        Self {
+            base,
+            arena_peaks: Vec::new(),
+            histograms: AllocationHistograms::default(),
+            frames: 0,
+            refills: 0,
+            class_pages: BTreeMap::new(),
+            global_exceeded: 0,
+            global_limit: 0,
+            global_peak: 0,
+            tags: BTreeMap::new(),
+            phases: BTreeMap::new(),
+        }
+    }
+
+    /// Record everything `alloc` has observed, at the end of a run.
+    ///
+    /// Threads report their peak arena use only while registered, so call
+    /// this before worker threads exit. Histograms are only available if
+    /// `enable_allocation_histograms` was called.
+    pub fn observe(&mut self, alloc: &SmartAlloc) {
+        for thread in alloc.threads() {
+            self.record_arena_peak(thread.peak_frame_bytes);
+        }
+        self.record_histograms(&alloc.allocation_histograms());
+        self.record_refills(alloc.stats().slab_refill_count, alloc.frame_number());
+        self.record_depot_stats(&alloc.depot_stats());
+    }
+
+    /// Record one thread's peak frame arena use.
+    pub fn record_arena_peak(&mut self, bytes: usize) {
+        if bytes > 0 {
+            self.arena_peaks.push(bytes);
+        }
+    }
+
+    /// Record a run's allocation histograms.
+    pub fn record_histograms(&mut self, histograms: &AllocationHistograms) {
+        self.histograms.merge(histograms);
+    }
+
+    /// Record a run's slab refill count and its length in frames.
+    pub fn record_refills(&mut self, refills: u64, frames: u64) {
+        self.refills += refills;
+        self.frames += frames;
+    }
+
+    /// Record a run's per-class depot statistics.
+    pub fn record_depot_stats(&mut self, stats: &[DepotStats]) {
+        for class in stats {
+            let pages = self.class_pages.entry(class.class_size).or_default();
+            *pages = (*pages).max(class.pages_allocated);
+        }
+    }
+
+    /// Record a budget event, e.g. from `BudgetManager::set_event_callback`.
+    pub fn record_budget_event(&mut self, event: &BudgetEvent) {
+        match *event {
+            BudgetEvent::SoftLimitExceeded { tag, current, limit } => {
+                let pressure = self.tags.entry(tag).or_default();
+                pressure.soft_exceeded += 1;
+                pressure.soft_limit = limit;
+                pressure.peak = pressure.peak.max(current);
+            }
+            BudgetEvent::HardLimitExceeded { tag, current, limit } => {
+                let pressure = self.tags.entry(tag).or_default();
+                pressure.hard_exceeded += 1;
+                pressure.hard_limit = limit;
+                pressure.peak = pressure.peak.max(current);
+            }
+            BudgetEvent::GlobalLimitExceeded { current, limit } => {
+                self.global_exceeded += 1;
+                self.global_limit = limit;
+                self.global_peak = self.global_peak.max(current);
+            }
+            BudgetEvent::PhaseLimitExceeded { phase, current, limit } => {
+                let pressure = self.phases.entry(phase).or_default();
+                pressure.hard_exceeded += 1;
+                pressure.hard_limit = limit;
+                pressure.peak = pressure.peak.max(current);
+            }
+            BudgetEvent::NewPeak { tag, peak } => {
+                if let Some(pressure) = self.tags.get_mut(tag) {
+                    pressure.peak = pressure.peak.max(peak);
+                }
+            }
+        }
+    }
+
+    /// Recommend a config from the evidence recorded so far.
+    ///
+    /// Fields without evidence keep the base config's value.
+    pub fn recommend(&self) -> TuningRecommendation {
+        let mut config = self.base.clone();
+        let mut justifications = Vec::new();
+        let mut justify = |field: &str, reason: String| {
+            justifications.push(Justification { field: field.to_string(), reason });
+        };
+
+        let (arena, reason) = self.frame_arena_size();
+        config.frame_arena_size = arena;
+        justify("frame_arena_size", reason);
+
+        let (classes, reason) = self.slab_size_classes();
+        config.slab_size_classes = classes;
+        justify("slab_size_classes", reason);
+
+        let (pages, reason) = self.slab_pages_per_class();
+        config.slab_pages_per_class = pages;
+        justify("slab_pages_per_class", reason);
+
+        if self.global_exceeded > 0 {
+            config.enable_budgets = true;
+            config.global_memory_limit = round_up(with_headroom(self.global_peak), mb(1));
+            justify(
+                "global_memory_limit",
+                format!(
+                    "global limit {} exceeded {} times with a peak of {}; raised to the peak plus 25%",
+                    format_bytes(self.global_limit),
+                    self.global_exceeded,
+                    format_bytes(self.global_peak)
+                ),
+            );
+        }
+
+        for (&tag, pressure) in &self.tags {
+            let (soft, hard) = pressure.limits();
+            if hard == 0 {
+                continue;
+            }
+            config.enable_budgets = true;
+            config = config.with_tag_budget(tag, soft, hard);
+            justify(
+                &format!("tag_budgets.{}", tag),
+                format!("{}; set soft {}, hard {}", pressure.describe("tag"), format_bytes(soft), format_bytes(hard)),
+            );
+        }
+        for (phase, pressure) in &self.phases {
+            let (soft, hard) = pressure.limits();
+            justify(
+                &format!("phase.{}", phase),
+                format!("{}; suggested soft {}, hard {}", pressure.describe("phase"), format_bytes(soft), format_bytes(hard)),
+            );
+        }
+
+        TuningRecommendation { config, justifications }
+    }
+
+    fn frame_arena_size(&self) -> (usize, String) {
+        let thread_peak = self.arena_peaks.iter().copied().max().unwrap_or(0);
+        let high_water = &self.histograms.arena_high_water;
+        let peak = thread_peak.max(high_water.max().unwrap_or(0) as usize);
+        if peak == 0 {
+            return (
+                self.base.frame_arena_size,
+                format!("no frame arena use recorded; kept {}", format_bytes(self.base.frame_arena_size)),
+            );
+        }
+
+        let size = with_headroom(peak).max(MIN_FRAME_ARENA).next_power_of_two();
+        let mut reason = format!(
+            "peak frame arena use {} across {} thread peaks",
+            format_bytes(peak),
+            self.arena_peaks.len()
+        );
+        if !high_water.is_empty() {
+            reason.push_str(&format!(" and {} frames (p99 <= {})", high_water.count(), format_bytes(high_water.percentile(0.99).unwrap_or(0) as usize)));
+        }
+        reason.push_str("; peak plus 25% rounded up to a power of two");
+        (size, reason)
+    }
+
+    fn slab_size_classes(&self) -> (Vec<usize>, String) {
+        let mut base = effective_size_classes(&self.base).to_vec();
+        base.dedup();
+        let pool = self.histograms.sizes_for_kind(AllocKind::Pool);
+        let Some(largest) = pool.max() else {
+            return (base, "no pool allocation sizes recorded; kept the base classes".to_string());
+        };
+
+        // The largest observed size keeps a class, the most frequent sizes
+        // get the rest, and base then default classes fill what is left
+        let largest = (largest as usize).clamp(16, MAX_SLAB_ALIGN).next_power_of_two();
+        let mut classes = vec![largest];
+        for class in self.histograms.suggested_slab_size_classes(NUM_SIZE_CLASSES) {
+            if classes.len() < NUM_SIZE_CLASSES && !classes.contains(&class) {
+                classes.push(class);
+            }
+        }
+        let evidence = classes.len();
+        let defaults = AllocConfig::default().slab_size_classes;
+        for &class in base.iter().chain(&defaults) {
+            if classes.len() < NUM_SIZE_CLASSES && !classes.contains(&class) {
+                classes.push(class);
+            }
+        }
+        classes.sort_unstable();
+
+        let added: Vec<_> = classes.iter().filter(|c| !base.contains(c)).collect();
+        let removed: Vec<_> = base.iter().filter(|c| !classes.contains(c)).collect();
+        let mut reason = format!("pool sizes {}; {} classes chosen from observed sizes", pool, evidence);
+        if !added.is_empty() || !removed.is_empty() {
+            reason.push_str(&format!(", added {:?}, removed {:?}", added, removed));
+        }
+        (classes, reason)
+    }
+
+    fn slab_pages_per_class(&self) -> (usize, String) {
+        // SmartAlloc carves slab pages on demand and never reads this field,
+        // so the evidence is reported but the base value is kept
+        let kept = self.base.slab_pages_per_class;
+        let busiest = self.class_pages.iter().max_by_key(|(_, &pages)| pages);
+        let reason = match busiest {
+            Some((&class, &pages)) if pages > 0 => format!(
+                "{} slab refills over {} frames; the busiest class ({} B) allocated {} pages; not read by the allocator, kept {}",
+                self.refills, self.frames, class, pages, kept
+            ),
+            _ => format!("{} slab refills recorded and no pages allocated; not read by the allocator, kept {}", self.refills, kept),
+        };
+        (kept, reason)
+    }
+}
+
+impl Pressure {
+    /// Soft limit at the peak plus 25%, hard limit 25% above that.
+    fn limits(&self) -> (usize, usize) {
+        let soft = with_headroom(self.peak);
+        (soft, with_headroom(soft))
+    }
+
+    fn describe(&self, what: &str) -> String {
+        format!(
+            "{} exceeded its soft limit {} times ({}) and its hard limit {} times ({}) with a peak of {}",
+            what,
+            self.soft_exceeded,
+            format_bytes(self.soft_limit),
+            self.hard_exceeded,
+            format_bytes(self.hard_limit),
+            format_bytes(self.peak)
+        )
+    }
+}
+
+/// Add 25% headroom.
+fn with_headroom(bytes: usize) -> usize {
+    bytes.saturating_add(bytes / 4)
+}
+
+fn round_up(bytes: usize, multiple: usize) -> usize {
+    bytes.saturating_add(multiple - 1) / multiple * multiple
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::histogram::{Log2Histogram, TagHistogram};
+
+    fn depot(class_size: usize, pages_allocated: u64) -> DepotStats {
+        DepotStats {
+            class_size,
+            batch_size: 32,
+            magazines: 0,
+            depot_hits: 0,
+            remote_hits: 0,
+            pages_allocated,
+            magazines_returned: 0,
+        }
+    }
+
+    #[test]
+    fn test_recommendation_from_evidence() {
+        let base = AllocConfig::minimal().with_budgets(true).with_memory_limit(mb(8));
+        let mut tuner = ConfigTuner::new(base.clone());
+
+        let mut sizes = Log2Histogram::new();
+        for size in [40, 40, 40, 200, 700] {
+            sizes.record(size);
+        }
+        let mut histograms = AllocationHistograms::default();
+        histograms.sizes.push(TagHistogram { tag: "ai", kind: AllocKind::Pool, histogram: sizes });
+        histograms.arena_high_water.record(300_000);
+
+        tuner.record_arena_peak(200_000);
+        tuner.record_histograms(&histograms);
+        tuner.record_refills(12, 600);
+        tuner.record_depot_stats(&[depot(64, 3), depot(1024, 7)]);
+        tuner.record_depot_stats(&[depot(64, 5)]);
+        tuner.record_budget_event(&BudgetEvent::GlobalLimitExceeded { current: mb(9), limit: mb(8) });
+        tuner.record_budget_event(&BudgetEvent::HardLimitExceeded { tag: "ai", current: kb(80), limit: kb(64) });
+
+        let recommendation = tuner.recommend();
+        let config = &recommendation.config;
+        assert_eq!(config.frame_arena_size, 1 << 19);
+        assert_eq!(config.slab_pages_per_class, base.slab_pages_per_class);
+        assert!(recommendation.justification("slab_pages_per_class").unwrap().reason.contains("allocated 7 pages"));
+        assert_eq!(config.global_memory_limit, mb(12));
+
+        // 40 -> 64, 200 -> 256 and 700 -> 1024 join the minimal classes
+        let classes = &config.slab_size_classes;
+        assert_eq!(classes.len(), NUM_SIZE_CLASSES);
+        assert!(classes.windows(2).all(|w| w[0] < w[1]));
+        for class in [64, 256, 1024, 32, 128, 512, 2048] {
+            assert!(classes.contains(&class), "{:?}", classes);
+        }
+
+        // Peak 80 KB: soft 100 KB, hard 125 KB
+        assert_eq!(config.tag_budgets.len(), 1);
+        assert_eq!(config.tag_budgets[0].tag, "ai");
+        assert_eq!((config.tag_budgets[0].soft_limit, config.tag_budgets[0].hard_limit), (kb(100), kb(125)));
+        assert!(recommendation.justification("budget.ai").is_none());
+        let reason = &recommendation.justification("tag_budgets.ai").unwrap().reason;
+        assert!(reason.contains("hard limit 1 times"), "{}", reason);
+        assert!(recommendation.justification("frame_arena_size").unwrap().reason.contains("292.97 KB"));
+    }
+
+    #[test]
+    fn test_serialized_recommendation_loads() {
+        let mut tuner = ConfigTuner::new(AllocConfig::default());
+        tuner.record_arena_peak(mb(3));
+        tuner.record_budget_event(&BudgetEvent::PhaseLimitExceeded { phase: "physics", current: kb(10), limit: kb(8) });
+        tuner.record_budget_event(&BudgetEvent::SoftLimitExceeded { tag: "audio", current: mb(2), limit: mb(1) });
+        let recommendation = tuner.recommend();
+        assert!(recommendation.config.enable_budgets);
+        assert_eq!(recommendation.config.frame_arena_size, mb(4));
+        assert_eq!(recommendation.config.slab_size_classes, AllocConfig::default().slab_size_classes);
+
+        let toml = recommendation.to_toml();
+        assert!(toml.contains("# phase.physics: phase exceeded"), "{}", toml);
+        assert!(toml.contains("[tag_budgets.audio]\n# tag exceeded"), "{}", toml);
+        assert!(!toml.contains("# tag_budgets.audio:"), "{}", toml);
+        assert_eq!(AllocConfig::from_toml(&toml).unwrap().fields(), recommendation.config.fields());
+        let json = recommendation.to_json();
+        assert_eq!(AllocConfig::from_json(&json).unwrap().fields(), recommendation.config.fields());
+    }
+}
@@ -146,7 +146,7 @@ impl<T> DerefMut for PoolBox<T> {
 
 impl<T> Drop for PoolBox<T> {
     fn drop(&mut self) {
-        tls::with_tls(|tls| {
+        tls::with_tls(&self.global, |tls| {
             tls.pool_free(self.ptr.as_ptr(), &self.global);
         });
     }
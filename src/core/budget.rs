@@ -97,7 +97,10 @@ pub enum BudgetStatus {
 }
 
 /// Events emitted by the budget manager.
+///
+/// New kinds of event may be added; match with a wildcard arm.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum BudgetEvent {
     /// Soft limit exceeded
     SoftLimitExceeded {
@@ -116,6 +119,12 @@ pub enum BudgetEvent {
         current: usize,
         limit: usize,
     },
+    /// A phase exceeded its per-frame budget
+    PhaseLimitExceeded {
+        phase: &'static str,
+        current: usize,
+        limit: usize,
+    },
     /// New peak usage recorded
     NewPeak {
         tag: &'static str,
@@ -1,18 +1,51 @@
 //! Global shared state.
 
 use std::alloc::Layout;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
 
 use crate::allocators::heap::SystemHeap;
+use crate::allocators::large::LargeObjectAllocator;
+use crate::allocators::page_source::{page_source_for, PageSource};
 use crate::allocators::slab::SlabRegistry;
 use crate::api::config::AllocConfig;
+use crate::api::frame_clock::FrameClock;
 use crate::api::stats::AllocStats;
+use crate::api::tagged;
+use crate::api::threads::ThreadRegistry;
 use crate::core::budget::BudgetManager;
+#[cfg(not(feature = "minimal"))]
+use crate::core::tls;
+use crate::diagnostics::behavior::{AllocKind, BehaviorFilter};
+use crate::diagnostics::{MemoryPool, ProfilerSlot};
+
+/// Unique identifier of an allocator instance.
+///
+/// Thread-local state (frame arena, pools) is keyed by this ID, so several
+/// allocators can be used on the same thread without sharing memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AllocatorId(u64);
+
+impl AllocatorId {
+    /// Allocate a fresh, process-unique ID.
+    pub(crate) fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(1);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Get the raw ID value.
+    pub fn as_u64(self) -> u64 {
+        self.0
+    }
+}
 
 /// Global state shared across all threads.
 ///
 /// This is wrapped in an `Arc` by `SmartAlloc` for thread-safe sharing.
 pub struct GlobalState {
+    /// Identifier keying this allocator's thread-local state
+    id: AllocatorId,
+
     /// Configuration
     config: AllocConfig,
 
@@ -22,9 +55,24 @@ pub struct GlobalState {
     /// Slab registry for small object pools
     slabs: SlabRegistry,
 
+    /// Pool objects that do not fit a slab class
+    large: LargeObjectAllocator,
+
     /// Budget manager (optional)
     budgets: Option<BudgetManager>,
 
+    /// Threads using this allocator
+    threads: ThreadRegistry,
+
+    /// Global frame number and the authority that advances it
+    frame_clock: FrameClock,
+
+    /// Profiler allocations are reported to
+    profiler: Arc<ProfilerSlot>,
+
+    /// Behavior filter and histograms allocations are recorded in
+    behavior: Arc<BehaviorFilter>,
+
     /// Global statistics (atomics)
     total_allocated: AtomicUsize,
     peak_allocated: AtomicUsize,
@@ -35,16 +83,32 @@ pub struct GlobalState {
 impl GlobalState {
     /// Create new global state with the given configuration.
     pub fn new(config: AllocConfig) -> Self {
+        let page_source = page_source_for(&config);
+        Self::with_page_source(config, page_source)
+    }
+
+    /// Create new global state whose slab pages come from `page_source`.
+    pub fn with_page_source(config: AllocConfig, page_source: Arc<dyn PageSource>) -> Self {
         let budgets = if config.enable_budgets {
-            Some(BudgetManager::new(config.global_memory_limit))
+            let budgets = BudgetManager::new(config.global_memory_limit);
+            for budget in &config.tag_budgets {
+                budgets.register_tag_budget(budget.tag, budget.soft_limit, budget.hard_limit);
+            }
+            Some(budgets)
         } else {
             None
         };
 
         Self {
-            slabs: SlabRegistry::new(&config),
+            id: AllocatorId::next(),
+            slabs: SlabRegistry::with_page_source(&config, page_source),
             heap: SystemHeap::new(),
+            large: LargeObjectAllocator::new(),
             budgets,
+            threads: ThreadRegistry::new(),
+            frame_clock: FrameClock::new(),
+            profiler: Arc::new(ProfilerSlot::new()),
+            behavior: Arc::new(BehaviorFilter::new()),
             config,
             total_allocated: AtomicUsize::new(0),
             peak_allocated: AtomicUsize::new(0),
@@ -53,6 +117,11 @@ impl GlobalState {
         }
     }
 
+    /// Get this allocator's ID.
+    pub fn id(&self) -> AllocatorId {
+        self.id
+    }
+
     /// Get the configuration.
     pub fn config(&self) -> &AllocConfig {
         &self.config
@@ -63,16 +132,34 @@ impl GlobalState {
         &self.slabs
     }
 
+    /// Get the large-object allocator.
+    pub fn large_objects(&self) -> &LargeObjectAllocator {
+        &self.large
+    }
+
+    /// Get the thread registry.
+    pub fn threads(&self) -> &ThreadRegistry {
+        &self.threads
+    }
+
+    /// Get the global frame clock.
+    pub(crate) fn frame_clock(&self) -> &FrameClock {
+        &self.frame_clock
+    }
+
+    /// Get the profiler slot shared with this allocator's thread-local state.
+    pub(crate) fn profiler(&self) -> &Arc<ProfilerSlot> {
+        &self.profiler
+    }
+
+    /// Get the behavior filter shared with this allocator's thread-local state.
+    pub(crate) fn behavior(&self) -> &Arc<BehaviorFilter> {
+        &self.behavior
+    }
+
     /// Allocate from system heap.
     pub fn heap_alloc<T>(&self) -> *mut T {
-        let layout = Layout::new::<T>();
-        let ptr = self.heap.alloc(layout);
-        
-        if !ptr.is_null() {
-            self.record_alloc(layout.size());
-        }
-        
-        ptr as *mut T
+        self.heap_alloc_layout(Layout::new::<T>()) as *mut T
     }
 
     /// Free to system heap.
@@ -80,9 +167,7 @@ impl GlobalState {
     /// # Safety
     /// Pointer must have been allocated by `heap_alloc`.
     pub unsafe fn heap_free<T>(&self, ptr: *mut T) {
-        let layout = Layout::new::<T>();
-        self.heap.dealloc(ptr as *mut u8, layout);
-        self.record_dealloc(layout.size());
+        self.heap_free_layout(ptr as *mut u8, Layout::new::<T>());
     }
 
     /// Record an allocation in global stats.
@@ -118,6 +203,7 @@ impl GlobalState {
 
     /// Get current statistics.
     pub fn stats(&self) -> AllocStats {
+        let large = self.large.stats();
         AllocStats {
             total_allocated: self.total_allocated.load(Ordering::Relaxed),
             peak_allocated: self.peak_allocated.load(Ordering::Relaxed),
@@ -128,6 +214,8 @@ impl GlobalState {
             heap_allocated: self.heap.allocated_bytes(),
             slab_refill_count: self.slabs.refill_count(),
             deferred_free_count: 0, // TODO: aggregate from TLS
+            large_object_allocated: large.allocated_bytes,
+            large_object_count: large.live_objects,
         }
     }
 
@@ -141,6 +229,15 @@ impl GlobalState {
         let ptr = self.heap.alloc(layout);
         if !ptr.is_null() {
             self.record_alloc(layout.size());
+            #[cfg(not(feature = "minimal"))]
+            tls::with_existing(self.id, |tls| tls.record_heap_alloc(layout.size()));
+            if self.profiler.is_active() {
+                self.profiler.alloc(MemoryPool::Heap, ptr, layout.size(), tagged::current_tag());
+            }
+            if self.behavior.histograms_enabled() {
+                let tag = tagged::current_tag().unwrap_or("untagged");
+                self.behavior.record_histogram_alloc(ptr, tag, AllocKind::Heap, layout.size());
+            }
         }
         ptr
     }
@@ -150,8 +247,12 @@ impl GlobalState {
     /// # Safety
     /// Pointer must have been allocated with the same layout.
     pub unsafe fn heap_free_layout(&self, ptr: *mut u8, layout: Layout) {
+        self.profiler.free(MemoryPool::Heap, ptr, layout.size());
+        self.behavior.record_histogram_free(ptr);
         self.heap.dealloc(ptr, layout);
         self.record_dealloc(layout.size());
+        #[cfg(not(feature = "minimal"))]
+        tls::with_existing(self.id, |tls| tls.record_heap_free(layout.size()));
     }
 }
 
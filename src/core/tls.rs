@@ -1,14 +1,46 @@
 //! Thread-local state management.
+//!
+//! Each thread holds one `ThreadLocalState` per allocator it has used,
+//! keyed by `AllocatorId` and created on first access. Allocators on the
+//! same thread therefore never share a frame arena or pools.
 
+use std::alloc::Layout;
 use std::cell::RefCell;
-use std::sync::Arc;
+use std::ptr::NonNull;
+use std::rc::Rc;
+use std::sync::{Arc, Weak};
 
 use crate::allocators::deferred::DeferredFreeQueue;
 use crate::allocators::frame::FrameArena;
-use crate::allocators::slab::LocalPools;
+use crate::allocators::slab::{LocalPools, PoolBackend};
+use crate::api::phases::{PhaseAllocKind, PhaseTracker};
+use crate::api::retention::RetentionRegistry;
 use crate::api::stats::ThreadStats;
-use crate::core::global::GlobalState;
-use crate::util::size::mb;
+use crate::api::sub_frame::SubFrameTracker;
+use crate::api::tagged;
+use crate::api::threads::ThreadRecord;
+use crate::core::global::{AllocatorId, GlobalState};
+use crate::diagnostics::behavior::{AllocKind, BehaviorFilter};
+use crate::diagnostics::{MemoryPool, ProfilerSlot};
+
+/// A saved frame position: the arena head and the heap overflow count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FrameMark {
+    head: usize,
+    overflow: usize,
+}
+
+impl FrameMark {
+    /// Create a mark at `head` with `overflow` heap blocks.
+    pub(crate) fn new(head: usize, overflow: usize) -> Self {
+        Self { head, overflow }
+    }
+
+    /// Get the saved arena head.
+    pub(crate) fn head(&self) -> usize {
+        self.head
+    }
+}
 
 /// Thread-local state for the allocator.
 pub struct ThreadLocalState {
@@ -26,34 +58,274 @@ pub struct ThreadLocalState {
 
     /// Whether a frame is currently active
     frame_active: bool,
+
+    /// Heap blocks serving frame allocations over a phase budget
+    overflow: Vec<(NonNull<u8>, Layout)>,
+
+    /// Number of `begin_frame` calls on this thread
+    epoch: u64,
+
+    /// Global frame observed at the last `begin_frame`
+    global_frame: u64,
+
+    /// This thread's entry in the allocator's thread registry
+    record: Arc<ThreadRecord>,
+
+    /// The allocator's profiler slot
+    profiler: Arc<ProfilerSlot>,
+
+    /// Frame allocations reported to the profiler, freed when the frame ends
+    profiled_frame: Vec<(*const u8, usize)>,
+
+    /// The allocator's behavior filter, for histograms
+    behavior: Arc<BehaviorFilter>,
+
+    /// Sub-frame scopes opened on this allocator
+    sub_frames: SubFrameTracker,
+
+    /// Phases begun on this allocator
+    phases: PhaseTracker,
+
+    /// Frame allocations awaiting promotion at frame end
+    retained: RetentionRegistry,
+}
+
+/// One allocator's state on this thread.
+struct TlsEntry {
+    id: AllocatorId,
+    /// Used to drop state left behind by allocators that no longer exist
+    owner: Weak<GlobalState>,
+    state: Rc<RefCell<ThreadLocalState>>,
+}
+
+impl Drop for TlsEntry {
+    fn drop(&mut self) {
+        // Thread exit or unregister: hand pool memory back to the allocator
+        let Some(global) = self.owner.upgrade() else {
+            return;
+        };
+        if let Ok(mut state) = self.state.try_borrow_mut() {
+            state.teardown(&global);
+        }
+    }
 }
 
 thread_local! {
-    static TLS: RefCell<Option<ThreadLocalState>> = const { RefCell::new(None) };
+    static TLS: RefCell<Vec<TlsEntry>> = const { RefCell::new(Vec::new()) };
 }
 
 impl ThreadLocalState {
-    /// Create new thread-local state.
-    fn new() -> Self {
+    /// Create new thread-local state for `global`, registering the thread.
+    fn new(global: &GlobalState) -> Self {
         Self {
-            frame: FrameArena::new(mb(16)), // Default size, can be configured
+            frame: FrameArena::new(global.config().frame_arena_size),
             pools: LocalPools::new(),
             deferred: DeferredFreeQueue::new(),
             stats: ThreadStats::new(),
             frame_active: false,
+            overflow: Vec::new(),
+            epoch: 0,
+            global_frame: 0,
+            record: global.threads().attach(),
+            profiler: global.profiler().clone(),
+            profiled_frame: Vec::new(),
+            behavior: global.behavior().clone(),
+            sub_frames: SubFrameTracker::new(),
+            phases: PhaseTracker::new(),
+            retained: RetentionRegistry::new(),
         }
     }
 
-    /// Begin a new frame.
-    pub fn begin_frame(&mut self) {
+    /// Get this thread's registry record.
+    pub(crate) fn record(&self) -> &ThreadRecord {
+        &self.record
+    }
+
+    /// Drain deferred frees, return pooled memory and leave the registry.
+    fn teardown(&mut self, global: &GlobalState) {
+        let deferred = self.deferred.len();
+        self.deferred.drain(&mut self.pools, global.slabs());
+        let reclaimed = self.pools.release_to(global.slabs());
+
+        self.end_frame();
+        global.frame_clock().release(self.record.id());
+        global.threads().detach(&self.record, reclaimed, deferred);
+    }
+
+    /// Bump-allocate from the frame arena, honoring phase budgets.
+    #[inline]
+    fn frame_bump(&mut self, layout: Layout) -> *mut u8 {
+        #[cfg(not(feature = "minimal"))]
+        let ptr = if self.phases.frame_budget_fallback(layout.size()) && layout.size() > 0 {
+            self.overflow_alloc(layout)
+        } else {
+            self.frame.alloc_layout(layout)
+        };
+        #[cfg(feature = "minimal")]
+        let ptr = self.frame.alloc_layout(layout);
+
+        // Zero-sized allocations share an address with the next one
+        if self.profiler.is_active() && !ptr.is_null() && layout.size() > 0 {
+            self.profile_frame_alloc(ptr, layout.size());
+        }
+        if self.behavior.histograms_enabled() && !ptr.is_null() {
+            self.record_frame_histogram(ptr, layout.size());
+        }
+        ptr
+    }
+
+    /// Record a frame allocation's size in the behavior histograms.
+    #[cold]
+    fn record_frame_histogram(&self, ptr: *const u8, size: usize) {
+        let tag = tagged::current_tag().unwrap_or("untagged");
+        self.behavior.record_histogram_alloc(ptr, tag, AllocKind::Frame, size);
+    }
+
+    /// Report a frame allocation and remember it for `end_frame`.
+    #[cold]
+    fn profile_frame_alloc(&mut self, ptr: *const u8, size: usize) {
+        self.profiler.alloc(MemoryPool::Frame, ptr, size, tagged::current_tag());
+        self.profiled_frame.push((ptr, size));
+    }
+
+    /// Report frees for profiled frame allocations that `keep` rejects.
+    fn release_profiled_frame(&mut self, keep: impl Fn(&FrameArena, *const u8) -> bool) {
+        if self.profiled_frame.is_empty() {
+            return;
+        }
+        let frame = &self.frame;
+        let profiler = &self.profiler;
+        self.profiled_frame.retain(|&(ptr, size)| {
+            let kept = keep(frame, ptr);
+            if !kept {
+                profiler.free(MemoryPool::Frame, ptr, size);
+            }
+            kept
+        });
+    }
+
+    /// Bump-allocate an array of `count` values of T.
+    #[inline]
+    fn frame_bump_array<T>(&mut self, count: usize) -> *mut T {
+        let layout = Layout::array::<T>(count).expect("Invalid array layout");
+        self.frame_bump(layout) as *mut T
+    }
+
+    /// Serve a frame allocation from the heap until the frame ends.
+    #[cold]
+    fn overflow_alloc(&mut self, layout: Layout) -> *mut u8 {
+        // SAFETY: layout has a non-zero size
+        let ptr = unsafe { std::alloc::alloc(layout) };
+        if let Some(block) = NonNull::new(ptr) {
+            self.overflow.push((block, layout));
+        }
+        ptr
+    }
+
+    /// Release heap blocks that stood in for frame memory.
+    fn release_overflow(&mut self) {
+        self.release_overflow_from(0);
+    }
+
+    /// Release the heap blocks allocated after the first `keep`.
+    fn release_overflow_from(&mut self, keep: usize) {
+        if keep >= self.overflow.len() {
+            return;
+        }
+        for (block, layout) in self.overflow.drain(keep..) {
+            // SAFETY: allocated in `overflow_alloc` with this layout
+            unsafe { std::alloc::dealloc(block.as_ptr(), layout) };
+        }
+    }
+
+    /// Get the bytes of frame allocations currently served from the heap.
+    pub fn overflow_bytes(&self) -> usize {
+        self.overflow.iter().map(|(_, layout)| layout.size()).sum()
+    }
+
+    /// Charge an allocation to thread stats and the active phases.
+    ///
+    /// Phase accounting and the thread's net pool bytes are compiled out
+    /// under `minimal` for every allocator.
+    #[inline]
+    fn record_alloc(&mut self, size: usize, kind: PhaseAllocKind) {
+        self.stats.record_alloc(size);
+        #[cfg(not(feature = "minimal"))]
+        {
+            if kind == PhaseAllocKind::Pool {
+                self.record.add_pool_bytes(size as i64);
+            }
+            if self.phases.is_in_phase() {
+                self.phases.record_alloc_kind(size, kind);
+            }
+        }
+        #[cfg(feature = "minimal")]
+        let _ = kind;
+    }
+
+    /// Record a pool free in thread stats and the active phases.
+    #[inline]
+    fn record_dealloc(&mut self, size: usize) {
+        self.stats.record_dealloc(size);
+        #[cfg(not(feature = "minimal"))]
+        {
+            self.record.add_pool_bytes(-(size as i64));
+            if self.phases.is_in_phase() {
+                self.phases.record_free(size);
+            }
+        }
+    }
+
+    /// Charge a heap allocation made on this thread to its phases and net
+    /// heap bytes.
+    #[cfg(not(feature = "minimal"))]
+    pub(crate) fn record_heap_alloc(&mut self, size: usize) {
+        self.record.add_heap_bytes(size as i64);
+        self.phases.record_alloc_kind(size, PhaseAllocKind::Heap);
+    }
+
+    /// Record a heap free made on this thread.
+    #[cfg(not(feature = "minimal"))]
+    pub(crate) fn record_heap_free(&mut self, size: usize) {
+        self.record.add_heap_bytes(-(size as i64));
+        self.phases.record_free(size);
+    }
+
+    /// Begin a new frame, entering global frame `frame`.
+    pub fn begin_frame(&mut self, frame: u64, global: &GlobalState) {
         // Process any deferred frees first
-        self.deferred.drain(&mut self.pools);
+        self.deferred.drain(&mut self.pools, global.slabs());
         self.frame_active = true;
+        self.phases.reset();
+        self.sub_frames.reset();
+        self.epoch += 1;
+        self.global_frame = frame;
+        self.record.set_epoch(self.epoch);
+    }
+
+    /// Get this thread's frame epoch (its `begin_frame` count).
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Get the global frame this thread last began.
+    pub fn global_frame(&self) -> u64 {
+        self.global_frame
     }
 
     /// End the current frame.
     pub fn end_frame(&mut self) {
+        self.release_profiled_frame(|_, _| false);
+        if self.frame_active {
+            let high_water = self.frame.high_water();
+            self.record.note_frame_high_water(high_water);
+            self.behavior.record_arena_high_water(high_water);
+        }
+        self.record.set_last_frame_bytes(self.frame.head());
         self.frame.reset();
+        self.release_overflow();
+        self.phases.reset();
+        self.sub_frames.reset();
         self.frame_active = false;
     }
 
@@ -62,22 +334,66 @@ impl ThreadLocalState {
         self.frame_active
     }
 
+    /// Get this allocator's sub-frame scopes on this thread.
+    pub fn sub_frames(&self) -> &SubFrameTracker {
+        &self.sub_frames
+    }
+
+    /// Get this allocator's sub-frame scopes on this thread, mutably.
+    pub(crate) fn sub_frames_mut(&mut self) -> &mut SubFrameTracker {
+        &mut self.sub_frames
+    }
+
+    /// Get this allocator's phases on this thread.
+    pub fn phases(&self) -> &PhaseTracker {
+        &self.phases
+    }
+
+    /// Get this allocator's phases on this thread, mutably.
+    pub(crate) fn phases_mut(&mut self) -> &mut PhaseTracker {
+        &mut self.phases
+    }
+
+    /// Get this allocator's pending retained allocations on this thread.
+    pub(crate) fn retained_mut(&mut self) -> &mut RetentionRegistry {
+        &mut self.retained
+    }
+
     /// Get current frame arena head position.
     pub fn frame_head(&self) -> usize {
         self.frame.head()
     }
 
-    /// Reset frame arena to a saved position.
-    pub fn reset_frame_to(&mut self, head: usize) {
-        self.frame.reset_to(head);
+    /// Get the current frame position, including heap overflow.
+    pub(crate) fn frame_mark(&self) -> FrameMark {
+        FrameMark::new(self.frame.head(), self.overflow.len())
+    }
+
+    /// Reset the frame to a saved position.
+    ///
+    /// Arena memory past the mark is reused and heap overflow blocks
+    /// allocated after it are released.
+    pub(crate) fn reset_frame_to(&mut self, mark: FrameMark) {
+        let released: Vec<*const u8> = self
+            .overflow
+            .iter()
+            .skip(mark.overflow)
+            .map(|(block, _)| block.as_ptr() as *const u8)
+            .collect();
+        self.release_profiled_frame(|frame, ptr| match frame.offset_of(ptr) {
+            Some(offset) => offset < mark.head,
+            None => !released.contains(&ptr),
+        });
+        self.frame.reset_to(mark.head);
+        self.release_overflow_from(mark.overflow);
     }
 
     /// Allocate from frame arena.
     pub fn frame_alloc<T>(&mut self) -> *mut T {
-        let ptr = self.frame.alloc::<T>();
+        let ptr = self.frame_bump(Layout::new::<T>()) as *mut T;
         #[cfg(not(feature = "minimal"))]
         if !ptr.is_null() {
-            self.stats.record_alloc(std::mem::size_of::<T>());
+            self.record_alloc(std::mem::size_of::<T>(), PhaseAllocKind::Frame);
         }
         #[cfg(all(target_arch = "x86_64", feature = "prefetch"))]
         if !ptr.is_null() {
@@ -91,10 +407,10 @@ impl ThreadLocalState {
     /// 
     /// Returns None on OOM instead of returning null.
     pub fn try_frame_alloc<T>(&mut self) -> Option<*mut T> {
-        let ptr = self.frame.alloc::<T>();
+        let ptr = self.frame_bump(Layout::new::<T>()) as *mut T;
         #[cfg(not(feature = "minimal"))]
         if !ptr.is_null() {
-            self.stats.record_alloc(std::mem::size_of::<T>());
+            self.record_alloc(std::mem::size_of::<T>(), PhaseAllocKind::Frame);
         }
         #[cfg(all(target_arch = "x86_64", feature = "prefetch"))]
         if !ptr.is_null() {
@@ -110,10 +426,10 @@ impl ThreadLocalState {
 
     /// Allocate a slice from frame arena.
     pub fn frame_alloc_slice<T>(&mut self, count: usize) -> *mut T {
-        let ptr = self.frame.alloc_slice::<T>(count);
+        let ptr = self.frame_bump_array::<T>(count);
         #[cfg(not(feature = "minimal"))]
         if !ptr.is_null() {
-            self.stats.record_alloc(std::mem::size_of::<T>() * count);
+            self.record_alloc(std::mem::size_of::<T>() * count, PhaseAllocKind::Frame);
         }
         #[cfg(all(target_arch = "x86_64", feature = "prefetch"))]
         if !ptr.is_null() && count > 0 {
@@ -128,11 +444,11 @@ impl ThreadLocalState {
     /// Returns a pointer to uninitialized memory for N values.
     /// More efficient than N separate allocations when statistics are enabled.
     pub fn frame_alloc_batch<T>(&mut self, count: usize) -> *mut T {
-        let ptr = self.frame.alloc_slice::<T>(count);
+        let ptr = self.frame_bump_array::<T>(count);
         #[cfg(not(feature = "minimal"))]
         if !ptr.is_null() {
             // Single bookkeeping update for all allocations
-            self.stats.record_alloc(std::mem::size_of::<T>() * count);
+            self.record_alloc(std::mem::size_of::<T>() * count, PhaseAllocKind::Frame);
         }
         #[cfg(all(target_arch = "x86_64", feature = "prefetch"))]
         if !ptr.is_null() && count > 0 {
@@ -145,10 +461,10 @@ impl ThreadLocalState {
     /// Allocate 2 instances of T with optimized single allocation.
     #[inline(always)]
     pub fn frame_alloc_2<T>(&mut self) -> *mut [T; 2] {
-        let ptr = self.frame.alloc_slice::<T>(2) as *mut [T; 2];
+        let ptr = self.frame_bump_array::<T>(2) as *mut [T; 2];
         #[cfg(not(feature = "minimal"))]
         if !ptr.is_null() {
-            self.stats.record_alloc(std::mem::size_of::<T>() * 2);
+            self.record_alloc(std::mem::size_of::<T>() * 2, PhaseAllocKind::Frame);
         }
         #[cfg(all(target_arch = "x86_64", feature = "prefetch"))]
         if !ptr.is_null() {
@@ -160,10 +476,10 @@ impl ThreadLocalState {
     /// Allocate 4 instances of T with optimized single allocation.
     #[inline(always)]
     pub fn frame_alloc_4<T>(&mut self) -> *mut [T; 4] {
-        let ptr = self.frame.alloc_slice::<T>(4) as *mut [T; 4];
+        let ptr = self.frame_bump_array::<T>(4) as *mut [T; 4];
         #[cfg(not(feature = "minimal"))]
         if !ptr.is_null() {
-            self.stats.record_alloc(std::mem::size_of::<T>() * 4);
+            self.record_alloc(std::mem::size_of::<T>() * 4, PhaseAllocKind::Frame);
         }
         #[cfg(all(target_arch = "x86_64", feature = "prefetch"))]
         if !ptr.is_null() {
@@ -175,10 +491,10 @@ impl ThreadLocalState {
     /// Allocate 8 instances of T with optimized single allocation.
     #[inline(always)]
     pub fn frame_alloc_8<T>(&mut self) -> *mut [T; 8] {
-        let ptr = self.frame.alloc_slice::<T>(8) as *mut [T; 8];
+        let ptr = self.frame_bump_array::<T>(8) as *mut [T; 8];
         #[cfg(not(feature = "minimal"))]
         if !ptr.is_null() {
-            self.stats.record_alloc(std::mem::size_of::<T>() * 8);
+            self.record_alloc(std::mem::size_of::<T>() * 8, PhaseAllocKind::Frame);
         }
         #[cfg(all(target_arch = "x86_64", feature = "prefetch"))]
         if !ptr.is_null() {
@@ -187,21 +503,57 @@ impl ThreadLocalState {
         ptr
     }
 
-    /// Allocate from local pool.
+    /// Allocate from local pool, or the large-object path if T does not fit.
     pub fn pool_alloc<T>(&mut self, global: &Arc<GlobalState>) -> *mut T {
         let size = std::mem::size_of::<T>();
-        let ptr = self.pools.alloc(size, global.slabs());
+        let ptr = self.pool_alloc_routed(PoolBackend::of::<T>(), Layout::new::<T>(), global);
         if !ptr.is_null() {
-            self.stats.record_alloc(size);
+            self.record_alloc(size, PhaseAllocKind::Pool);
         }
         ptr as *mut T
     }
 
     /// Free to local pool (or defer if cross-thread).
-    pub fn pool_free<T>(&mut self, ptr: *mut T, _global: &Arc<GlobalState>) {
+    pub fn pool_free<T>(&mut self, ptr: *mut T, global: &Arc<GlobalState>) {
         let size = std::mem::size_of::<T>();
-        self.pools.free(ptr as *mut u8, size);
-        self.stats.record_dealloc(size);
+        self.pool_free_routed(PoolBackend::of::<T>(), ptr as *mut u8, Layout::new::<T>(), global);
+        self.record_dealloc(size);
+    }
+
+    /// Allocate from the backend chosen for `layout`.
+    #[inline]
+    fn pool_alloc_routed(&mut self, backend: PoolBackend, layout: Layout, global: &GlobalState) -> *mut u8 {
+        let ptr = match backend {
+            PoolBackend::Slab { class_size } => self.pools.alloc(class_size, global.slabs()),
+            PoolBackend::LargeObject => global.large_objects().alloc(layout),
+        };
+        if !ptr.is_null() {
+            if self.profiler.is_active() {
+                self.profiler.alloc(MemoryPool::Pool, ptr, layout.size(), tagged::current_tag());
+            }
+            if self.behavior.histograms_enabled() {
+                let tag = tagged::current_tag().unwrap_or("untagged");
+                self.behavior.record_histogram_alloc(ptr, tag, backend.alloc_kind(), layout.size());
+            }
+        }
+        ptr
+    }
+
+    /// Free to the backend chosen for `layout`.
+    #[inline]
+    fn pool_free_routed(&mut self, backend: PoolBackend, ptr: *mut u8, layout: Layout, global: &GlobalState) {
+        self.profiler.free(MemoryPool::Pool, ptr, layout.size());
+        self.behavior.record_histogram_free(ptr);
+        match backend {
+            PoolBackend::Slab { class_size } => self.pools.free(ptr, class_size, global.slabs()),
+            // SAFETY: the same layout routed the allocation to this path
+            PoolBackend::LargeObject => unsafe { global.large_objects().dealloc(ptr, layout) },
+        }
+    }
+
+    /// Get the number of cross-thread frees waiting to be drained.
+    pub fn deferred_len(&self) -> usize {
+        self.deferred.len()
     }
 
     /// Queue a deferred free from another thread.
@@ -212,45 +564,128 @@ impl ThreadLocalState {
 
     /// Allocate from frame arena with a specific layout.
     pub fn frame_alloc_layout(&mut self, layout: std::alloc::Layout) -> *mut u8 {
-        let ptr = self.frame.alloc_layout(layout);
+        let ptr = self.frame_bump(layout);
         #[cfg(not(feature = "minimal"))]
         if !ptr.is_null() {
-            self.stats.record_alloc(layout.size());
+            self.record_alloc(layout.size(), PhaseAllocKind::Frame);
         }
         ptr
     }
 
     /// Allocate from pool with a specific layout.
     pub fn pool_alloc_layout(&mut self, layout: std::alloc::Layout, global: &Arc<GlobalState>) -> *mut u8 {
-        let ptr = self.pools.alloc(layout.size(), global.slabs());
+        let backend = PoolBackend::for_layout(layout.size(), layout.align());
+        let ptr = self.pool_alloc_routed(backend, layout, global);
         if !ptr.is_null() {
-            self.stats.record_alloc(layout.size());
+            self.record_alloc(layout.size(), PhaseAllocKind::Pool);
         }
         ptr
     }
 
     /// Free to pool with a specific layout.
-    pub fn pool_free_layout(&mut self, ptr: *mut u8, layout: std::alloc::Layout, _global: &Arc<GlobalState>) {
-        self.pools.free(ptr, layout.size());
-        self.stats.record_dealloc(layout.size());
+    pub fn pool_free_layout(&mut self, ptr: *mut u8, layout: std::alloc::Layout, global: &Arc<GlobalState>) {
+        let backend = PoolBackend::for_layout(layout.size(), layout.align());
+        self.pool_free_routed(backend, ptr, layout, global);
+        self.record_dealloc(layout.size());
+    }
+}
+
+impl Drop for ThreadLocalState {
+    fn drop(&mut self) {
+        self.release_overflow();
     }
 }
 
-/// Execute a closure with access to thread-local state.
+/// Execute a closure with access to `global`'s thread-local state.
 ///
-/// Initializes TLS lazily on first access.
-pub fn with_tls<F, R>(f: F) -> R
+/// Initializes the state lazily on first access from this thread.
+pub fn with_tls<F, R>(global: &Arc<GlobalState>, f: F) -> R
 where
     F: FnOnce(&mut ThreadLocalState) -> R,
 {
-    TLS.with(|cell| {
-        let mut borrow = cell.borrow_mut();
-        let tls = borrow.get_or_insert_with(ThreadLocalState::new);
-        f(tls)
-    })
+    let id = global.id();
+    let state = TLS.with(|cell| {
+        let mut entries = cell.borrow_mut();
+        if let Some(entry) = entries.iter().find(|e| e.id == id) {
+            return entry.state.clone();
+        }
+
+        entries.retain(|e| e.owner.strong_count() > 0);
+        let state = Rc::new(RefCell::new(ThreadLocalState::new(global)));
+        entries.push(TlsEntry {
+            id,
+            owner: Arc::downgrade(global),
+            state: state.clone(),
+        });
+        state
+    });
+
+    // The entry list is released first, so `f` may use other allocators
+    let (result, breaches) = {
+        let mut tls = state.borrow_mut();
+        let result = f(&mut tls);
+        (result, tls.phases.take_breaches())
+    };
+
+    // Budget callbacks and sinks run once the state is released, so they
+    // may call back into this allocator
+    for breach in breaches {
+        breach.emit();
+    }
+    result
 }
 
-/// Check if TLS is initialized for the current thread.
-pub fn is_tls_initialized() -> bool {
-    TLS.with(|cell| cell.borrow().is_some())
+/// Execute a closure with allocator `id`'s thread-local state, if this
+/// thread has one and it is not already in use.
+///
+/// Unlike `with_tls`, never creates state.
+pub(crate) fn with_existing<F, R>(id: AllocatorId, f: F) -> Option<R>
+where
+    F: FnOnce(&mut ThreadLocalState) -> R,
+{
+    let state = TLS
+        .try_with(|cell| {
+            let entries = cell.try_borrow().ok()?;
+            entries.iter().find(|e| e.id == id).map(|e| e.state.clone())
+        })
+        .ok()??;
+    let mut tls = state.try_borrow_mut().ok()?;
+    Some(f(&mut tls))
+}
+
+/// Tear down `global`'s state on the current thread.
+///
+/// Pending deferred frees are drained and pooled memory is returned to the
+/// slab registry. Returns false if the thread had no state, or if frame
+/// memory may still be referenced: a frame is active, the arena holds
+/// allocations, or the state is borrowed by an allocation in progress.
+pub fn release(global: &GlobalState) -> bool {
+    let id = global.id();
+    let entry = TLS.with(|cell| {
+        let mut entries = cell.borrow_mut();
+        let index = entries.iter().position(|e| e.id == id)?;
+        let state = entries[index].state.try_borrow().ok()?;
+        if state.is_frame_active() || state.frame.head() > 0 {
+            return None;
+        }
+        drop(state);
+        Some(entries.swap_remove(index))
+    });
+
+    // Dropped outside the borrow so teardown can run freely
+    entry.is_some()
+}
+
+/// Check if an allocator's TLS is initialized for the current thread.
+pub fn is_tls_initialized(id: AllocatorId) -> bool {
+    TLS.with(|cell| cell.borrow().iter().any(|e| e.id == id))
+}
+
+/// Check if any allocator has an active frame on the current thread.
+pub fn any_frame_active() -> bool {
+    TLS.with(|cell| {
+        cell.borrow()
+            .iter()
+            .any(|e| e.state.try_borrow().is_ok_and(|s| s.is_frame_active()))
+    })
 }
@@ -17,11 +17,23 @@ pub use crate::diagnostics;
 // to avoid duplicate paths and confusion
 
 // Re-export all public API items at module level for convenience
+pub use crate::core::global::AllocatorId;
 pub use api::alloc::SmartAlloc;
-pub use api::config::AllocConfig;
+pub use allocators::large::LargeObjectStats;
+pub use allocators::slab::{
+    default_batch_size, slab_class_align, DepotStats, PoolBackend, MAX_SLAB_ALIGN, SLAB_ALIGN,
+};
+pub use allocators::page_source::{PageSource, SystemPageSource};
+#[cfg(target_os = "linux")]
+pub use allocators::page_source::NumaPageSource;
+pub use api::config::{AllocConfig, ConfigError, ConfigFormat, TagBudgetConfig, CONFIG_ENV_VAR};
+pub use api::frame_clock::{FrameAuthority, FrameClock};
 pub use api::scope::{FrameGuard, FrameScope};
+pub use api::sub_frame::{SubFrame, SubFrameRecord, SubFrameTracker};
+pub use api::threads::{current_thread_name, ThreadInfo, ThreadRegistry, ThreadTeardownStats};
 pub use api::stats::AllocStats;
 pub use api::tag::{AllocationIntent, AllocationTag};
+pub use api::tuner::{ConfigTuner, Justification, TuningRecommendation};
 
 // Safe wrapper types
 pub use api::wrappers::{FrameBox, FrameSlice, PoolBox, HeapBox};
@@ -31,29 +43,42 @@ pub use api::wrappers::{FrameBox, FrameSlice, PoolBox, HeapBox};
 pub use api::allocator_impl::{FrameAllocator, PoolAllocator, HeapAllocator};
 
 // Allocation groups
-pub use api::groups::{GroupAllocator, GroupId, GroupHandle, GroupStats};
+pub use api::groups::{GroupAllocator, GroupFreeListener, GroupHandle, GroupId, GroupStats};
 
 // Handle-based allocation
 pub use allocators::handles::{Handle, HandleAllocator, HandleAllocatorStats, PinGuard};
 
 // Streaming allocation
 pub use allocators::streaming::{StreamId, StreamPriority, StreamState, StreamingAllocator, StreamingStats};
+pub use allocators::streaming::{StreamGuard, MAX_STREAM_ALIGN};
+pub use allocators::eviction::{
+    AccessTrace, DistancePolicy, EvictionCandidate, EvictionHint, EvictionPlan, EvictionPolicy,
+    LfuPolicy, LruPolicy, SizeWeightedPolicy, TraceEvent, TraceReport, TwoQueuePolicy,
+};
+pub use allocators::stream_loader::{
+    FileLoader, LoadContext, LoadError, LoadResult, LoadTicket, StreamLoader, StreamLoaderPool,
+};
 
 // Budgets
 pub use core::budget::{BudgetEvent, BudgetManager, BudgetStatus, TagBudget};
 
 // Diagnostics - UI hooks
 pub use diagnostics::{DiagnosticsHooks, DiagnosticsEvent, SharedDiagnostics, MemoryGraphData};
-pub use diagnostics::{ProfilerHooks, ProfilerZone, MemoryEvent};
+pub use diagnostics::{ProfilerHooks, ProfilerZone, MemoryEvent, MemoryPool};
+#[cfg(feature = "tracy")]
+pub use diagnostics::tracy_client;
+pub use diagnostics::{TraceExporter, TraceFormat};
 pub use diagnostics::{AllocatorSnapshot, SnapshotHistory};
 
 // Diagnostics - Core types and predefined codes
-pub use diagnostics::{Diagnostic, DiagnosticKind};
+pub use diagnostics::{Diagnostic, DiagnosticKind, FieldValue};
+pub use diagnostics::{CollectingSink, DiagnosticSink, DiagnosticSinks, SinkFilter, SinkId, SinkRateLimit};
 pub use diagnostics::{StrictMode, set_strict_mode, StrictModeGuard};
-pub use diagnostics::{FA001, FA002, FA003, FA101, FA102, FA201, FA202, FA301, FA302, FA401, FA402, FA901};
+pub use diagnostics::{FA001, FA002, FA003, FA101, FA102, FA201, FA202, FA301, FA302, FA303, FA401, FA402, FA901};
 
 // v0.2.0: Frame phases
-pub use api::phases::{Phase, PhaseGuard, PhaseTracker};
+pub use api::phases::{Phase, PhaseAllocKind, PhaseGuard, PhaseTracker};
+pub use api::phase_budget::{PhaseBudget, PhaseBudgetPolicy, PhaseBudgets, PhaseHighWater};
 pub use api::phases::{begin_phase, end_phase, current_phase, is_in_phase};
 
 // v0.2.0: Frame checkpoints
@@ -66,7 +91,10 @@ pub use api::frame_collections::{FrameVec, FrameVecIntoIter, FrameMap};
 pub use api::tagged::{TagGuard, TagStack, with_tag, current_tag, tag_path};
 
 // v0.2.0: Scratch pools
-pub use api::scratch::{ScratchPool, ScratchRegistry, ScratchPoolHandle, ScratchPoolStats};
+pub use api::scratch::{
+    ScratchGrowth, ScratchGuard, ScratchKey, ScratchPool, ScratchPoolConfig, ScratchPoolHandle,
+    ScratchPoolStats, ScratchRegistry,
+};
 
 // v0.3.0: Frame retention and promotion
 pub use api::retention::{RetentionPolicy, Importance, FrameRetained, PromotedAllocation, PromotionFailure};
@@ -77,6 +105,7 @@ pub use diagnostics::behavior::{
     AllocKind, BehaviorFilter, BehaviorIssue, BehaviorReport, BehaviorThresholds, TagBehaviorStats,
     FA501, FA502, FA510, FA520, FA530,
 };
+pub use diagnostics::histogram::{AllocationHistograms, Log2Bucket, Log2Histogram, TagHistogram};
 pub use diagnostics::{DiagnosticCode, DiagnosticLevel};
 
 // v0.6.0: Thread coordination and observability
@@ -99,5 +128,16 @@ pub use api::snapshot::{
     PromotionStats as SnapshotPromotionStats, 
     TransferStats as SnapshotTransferStats,
     DeferredStats as SnapshotDeferredStats, 
-    RuntimeDiagnostic, SNAPSHOT_VERSION,
+    RuntimeDiagnostic, SnapshotError, SnapshotReader, SNAPSHOT_VERSION,
+};
+pub use api::snapshot_recording::{
+    RecordingStats, SnapshotRecorder, SnapshotRecording, RECORDING_VERSION,
+};
+#[cfg(feature = "telemetry")]
+pub use api::telemetry::{
+    TelemetryAddr, TelemetryCommand, TelemetryConfig, TelemetryEncoding, TelemetryMessage,
+    TelemetryServer, TelemetryStats, TELEMETRY_PROTOCOL_VERSION,
+};
+pub use api::snapshot_diff::{
+    DeltaStatus, SnapshotDiff, SummaryDelta, TagDelta, ThreadDelta,
 };
@@ -32,20 +32,36 @@
 //!     eprintln!("{}", issue);
 //! }
 //! ```
+//!
+//! Size and lifetime histograms are collected separately, see
+//! `enable_histograms` and the `histogram` module.
 
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::cell::RefCell;
 
+use crate::diagnostics::histogram::{AllocationHistograms, Log2Histogram, TagHistogram};
 use crate::diagnostics::{DiagnosticCode, DiagnosticLevel};
+use crate::sync::mutex::Mutex;
 
 /// Allocation kind for behavior tracking.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+///
+/// New kinds may be added; match with a wildcard arm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[non_exhaustive]
 pub enum AllocKind {
     Frame,
     Pool,
     Heap,
     Scratch,
+    LargeObject,
+    Handle,
+}
+
+impl AllocKind {
+    /// Check if allocations of this kind are freed individually.
+    fn has_lifetime(self) -> bool {
+        matches!(self, Self::Pool | Self::Heap | Self::LargeObject | Self::Handle)
+    }
 }
 
 impl std::fmt::Display for AllocKind {
@@ -55,6 +71,8 @@ impl std::fmt::Display for AllocKind {
             Self::Pool => write!(f, "pool"),
             Self::Heap => write!(f, "heap"),
             Self::Scratch => write!(f, "scratch"),
+            Self::LargeObject => write!(f, "large_object"),
+            Self::Handle => write!(f, "handle"),
         }
     }
 }
@@ -252,6 +270,8 @@ pub struct BehaviorReport {
     pub frames_analyzed: u64,
     /// Whether the filter was enabled
     pub filter_enabled: bool,
+    /// Size and lifetime histograms, if any were recorded
+    pub histograms: Option<AllocationHistograms>,
 }
 
 impl BehaviorReport {
@@ -276,13 +296,42 @@ impl BehaviorReport {
         let warnings = self.issues.iter().filter(|i| i.level == DiagnosticLevel::Warning).count();
         let hints = self.issues.iter().filter(|i| i.level == DiagnosticLevel::Hint).count();
         
-        format!(
+        let mut summary = format!(
             "Behavior analysis: {} errors, {} warnings, {} hints ({} frames, {} tags)",
             errors, warnings, hints, self.frames_analyzed, self.stats.len()
-        )
+        );
+        if let Some(histograms) = &self.histograms {
+            let mut kinds: Vec<_> = histograms.sizes.iter().map(|h| h.kind).collect();
+            kinds.sort();
+            kinds.dedup();
+            for kind in kinds {
+                summary.push_str(&format!("\n  {} sizes (bytes): {}", kind, histograms.sizes_for_kind(kind)));
+                let lifetimes = histograms.lifetimes_for_kind(kind);
+                if !lifetimes.is_empty() {
+                    summary.push_str(&format!("\n  {} lifetimes (frames): {}", kind, lifetimes));
+                }
+            }
+            if !histograms.arena_high_water.is_empty() {
+                summary.push_str(&format!(
+                    "\n  frame arena high-water (bytes): {}",
+                    histograms.arena_high_water
+                ));
+            }
+        }
+        summary
     }
 }
 
+/// Histogram data guarded by one lock.
+#[derive(Default)]
+struct HistogramState {
+    sizes: HashMap<(&'static str, AllocKind), Log2Histogram>,
+    lifetimes: HashMap<(&'static str, AllocKind), Log2Histogram>,
+    arena_high_water: Log2Histogram,
+    /// Live pool, heap and handle allocations and the frame they were made in
+    live: HashMap<usize, (&'static str, AllocKind, u64)>,
+}
+
 /// The behavior filter - tracks and analyzes allocation patterns.
 pub struct BehaviorFilter {
     /// Whether filtering is enabled
@@ -292,9 +341,13 @@ pub struct BehaviorFilter {
     /// Detection thresholds
     thresholds: BehaviorThresholds,
     /// Per-tag statistics (tag+kind -> stats)
-    stats: RefCell<HashMap<(&'static str, AllocKind), TagBehaviorStats>>,
+    stats: Mutex<HashMap<(&'static str, AllocKind), TagBehaviorStats>>,
     /// Pending allocations this frame (for same-frame-free detection)
-    pending_this_frame: RefCell<HashMap<*const u8, (&'static str, AllocKind, usize)>>,
+    pending_this_frame: Mutex<HashMap<usize, (&'static str, AllocKind, usize)>>,
+    /// Whether size and lifetime histograms are recorded
+    histograms_enabled: AtomicBool,
+    /// Size and lifetime histograms
+    histograms: Mutex<HistogramState>,
 }
 
 impl BehaviorFilter {
@@ -304,8 +357,10 @@ impl BehaviorFilter {
             enabled: AtomicBool::new(false),
             current_frame: AtomicU64::new(0),
             thresholds: BehaviorThresholds::default(),
-            stats: RefCell::new(HashMap::new()),
-            pending_this_frame: RefCell::new(HashMap::new()),
+            stats: Mutex::new(HashMap::new()),
+            pending_this_frame: Mutex::new(HashMap::new()),
+            histograms_enabled: AtomicBool::new(false),
+            histograms: Mutex::new(HistogramState::default()),
         }
     }
     
@@ -315,8 +370,10 @@ impl BehaviorFilter {
             enabled: AtomicBool::new(false),
             current_frame: AtomicU64::new(0),
             thresholds,
-            stats: RefCell::new(HashMap::new()),
-            pending_this_frame: RefCell::new(HashMap::new()),
+            stats: Mutex::new(HashMap::new()),
+            pending_this_frame: Mutex::new(HashMap::new()),
+            histograms_enabled: AtomicBool::new(false),
+            histograms: Mutex::new(HistogramState::default()),
         }
     }
     
@@ -334,6 +391,25 @@ impl BehaviorFilter {
     pub fn is_enabled(&self) -> bool {
         self.enabled.load(Ordering::SeqCst)
     }
+
+    /// Start recording size and lifetime histograms.
+    ///
+    /// Independent of `enable`; every allocation then takes a lock, so
+    /// leave this off outside profiling sessions.
+    pub fn enable_histograms(&self) {
+        self.histograms_enabled.store(true, Ordering::SeqCst);
+    }
+
+    /// Stop recording histograms, keeping what was recorded.
+    pub fn disable_histograms(&self) {
+        self.histograms_enabled.store(false, Ordering::SeqCst);
+    }
+
+    /// Check if histograms are being recorded.
+    #[inline]
+    pub fn histograms_enabled(&self) -> bool {
+        self.histograms_enabled.load(Ordering::Relaxed)
+    }
     
     /// Set thresholds.
     pub fn set_thresholds(&mut self, thresholds: BehaviorThresholds) {
@@ -354,7 +430,7 @@ impl BehaviorFilter {
         let frame = self.current_frame.load(Ordering::SeqCst);
         let key = (tag, kind);
         
-        let mut stats = self.stats.borrow_mut();
+        let mut stats = self.stats.lock();
         let entry = stats.entry(key).or_insert_with(|| TagBehaviorStats::new(tag, kind, frame));
         
         entry.total_allocs += 1;
@@ -363,7 +439,7 @@ impl BehaviorFilter {
         entry.last_alloc_frame = frame;
         
         // Track for same-frame-free detection
-        self.pending_this_frame.borrow_mut().insert(ptr, (tag, kind, size));
+        self.pending_this_frame.lock().insert(ptr as usize, (tag, kind, size));
     }
     
     /// Record a deallocation.
@@ -375,9 +451,9 @@ impl BehaviorFilter {
         let key = (tag, kind);
         
         // Check if this was allocated this frame
-        let same_frame = self.pending_this_frame.borrow_mut().remove(&ptr).is_some();
+        let same_frame = self.pending_this_frame.lock().remove(&(ptr as usize)).is_some();
         
-        let mut stats = self.stats.borrow_mut();
+        let mut stats = self.stats.lock();
         if let Some(entry) = stats.get_mut(&key) {
             entry.current_bytes = entry.current_bytes.saturating_sub(size);
             if same_frame {
@@ -393,7 +469,7 @@ impl BehaviorFilter {
         }
         
         let key = (tag, from_kind);
-        let mut stats = self.stats.borrow_mut();
+        let mut stats = self.stats.lock();
         if let Some(entry) = stats.get_mut(&key) {
             entry.promotion_count += 1;
         }
@@ -406,23 +482,105 @@ impl BehaviorFilter {
         }
         
         let key = (tag, kind);
-        let mut stats = self.stats.borrow_mut();
+        let mut stats = self.stats.lock();
         if let Some(entry) = stats.get_mut(&key) {
             entry.survived_frame_count += 1;
             entry.total_lifetime_frames += frames_alive;
         }
     }
     
+    /// Record an allocation's size, and its frame for lifetime tracking.
+    pub fn record_histogram_alloc(&self, ptr: *const u8, tag: &'static str, kind: AllocKind, size: usize) {
+        if !self.histograms_enabled() {
+            return;
+        }
+
+        let frame = self.current_frame.load(Ordering::SeqCst);
+        let mut state = self.histograms.lock();
+        state.sizes.entry((tag, kind)).or_default().record(size as u64);
+        if kind.has_lifetime() {
+            state.live.insert(ptr as usize, (tag, kind, frame));
+        }
+    }
+
+    /// Record the lifetime of a pool, heap or handle allocation being freed.
+    ///
+    /// Frees of allocations made before histograms were enabled are ignored.
+    pub fn record_histogram_free(&self, ptr: *const u8) {
+        if !self.histograms_enabled() {
+            return;
+        }
+
+        let frame = self.current_frame.load(Ordering::SeqCst);
+        let mut state = self.histograms.lock();
+        if let Some((tag, kind, allocated)) = state.live.remove(&(ptr as usize)) {
+            let lifetime = frame.saturating_sub(allocated);
+            state.lifetimes.entry((tag, kind)).or_default().record(lifetime);
+        }
+    }
+
+    /// Carry a live allocation's lifetime over to its relocated address.
+    pub fn record_histogram_move(&self, from: *const u8, to: *const u8) {
+        if !self.histograms_enabled() {
+            return;
+        }
+
+        let mut state = self.histograms.lock();
+        if let Some(entry) = state.live.remove(&(from as usize)) {
+            state.live.insert(to as usize, entry);
+        }
+    }
+
+    /// Record the frame arena high-water mark of a finished frame.
+    pub fn record_arena_high_water(&self, bytes: usize) {
+        if !self.histograms_enabled() {
+            return;
+        }
+        self.histograms.lock().arena_high_water.record(bytes as u64);
+    }
+
+    /// Get the histograms recorded so far.
+    pub fn histograms(&self) -> AllocationHistograms {
+        let state = self.histograms.lock();
+        let sorted = |map: &HashMap<(&'static str, AllocKind), Log2Histogram>| {
+            let mut histograms: Vec<_> = map
+                .iter()
+                .map(|(&(tag, kind), histogram)| TagHistogram { tag, kind, histogram: histogram.clone() })
+                .collect();
+            histograms.sort_by_key(|h| (h.tag, h.kind));
+            histograms
+        };
+        AllocationHistograms {
+            sizes: sorted(&state.sizes),
+            lifetimes: sorted(&state.lifetimes),
+            arena_high_water: state.arena_high_water.clone(),
+        }
+    }
+
     /// Called at frame end.
     pub fn end_frame(&self) {
-        if !self.is_enabled() {
+        if !self.is_enabled() && !self.histograms_enabled() {
             return;
         }
         
         self.current_frame.fetch_add(1, Ordering::SeqCst);
-        self.pending_this_frame.borrow_mut().clear();
+        self.pending_this_frame.lock().clear();
     }
     
+    /// Signal the end of global frame `frame`.
+    ///
+    /// Unlike `end_frame`, calling this from several threads in the same
+    /// frame only advances the filter once.
+    pub fn end_frame_at(&self, frame: u64) {
+        if !self.is_enabled() && !self.histograms_enabled() {
+            return;
+        }
+
+        if self.current_frame.fetch_max(frame, Ordering::SeqCst) < frame {
+            self.pending_this_frame.lock().clear();
+        }
+    }
+
     /// Get current frame number.
     pub fn current_frame(&self) -> u64 {
         self.current_frame.load(Ordering::SeqCst)
@@ -430,7 +588,7 @@ impl BehaviorFilter {
     
     /// Analyze behavior and generate report.
     pub fn analyze(&self) -> BehaviorReport {
-        let stats_map = self.stats.borrow();
+        let stats_map = self.stats.lock();
         let stats: Vec<_> = stats_map.values().cloned().collect();
         let frames = self.current_frame.load(Ordering::SeqCst);
         
@@ -527,19 +685,23 @@ impl BehaviorFilter {
         
         // Sort by severity
         issues.sort_by(|a, b| b.level.cmp(&a.level));
-        
+        drop(stats_map);
+
+        let histograms = self.histograms();
         BehaviorReport {
             issues,
             stats,
             frames_analyzed: frames,
             filter_enabled: self.is_enabled(),
+            histograms: (!histograms.is_empty()).then_some(histograms),
         }
     }
     
     /// Reset all statistics.
     pub fn reset(&self) {
-        self.stats.borrow_mut().clear();
-        self.pending_this_frame.borrow_mut().clear();
+        self.stats.lock().clear();
+        self.pending_this_frame.lock().clear();
+        *self.histograms.lock() = HistogramState::default();
         self.current_frame.store(0, Ordering::SeqCst);
     }
 }
@@ -606,6 +768,7 @@ mod tests {
             stats: vec![],
             frames_analyzed: 100,
             filter_enabled: true,
+            histograms: None,
         };
         
         let summary = report.summary();
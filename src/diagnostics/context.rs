@@ -32,11 +32,11 @@ impl DiagContext {
     /// Capture the current context.
     pub fn capture() -> Self {
         let thread = std::thread::current();
-        let thread_name = thread.name().map(String::from);
+        let thread_name = crate::api::threads::current_thread_name();
         
         Self {
             is_bevy: IS_BEVY_CONTEXT.load(Ordering::Relaxed),
-            frame_active: crate::core::tls::with_tls(|tls| tls.is_frame_active()),
+            frame_active: crate::core::tls::any_frame_active(),
             frame_number: FRAME_NUMBER.load(Ordering::Relaxed),
             thread_id: thread.id(),
             thread_name,
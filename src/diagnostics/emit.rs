@@ -1,12 +1,24 @@
 //! Diagnostic emission backend.
 //!
 //! Handles outputting diagnostics to stderr, logs, or custom sinks.
-
+//!
+//! `emit` routes every diagnostic through the global sink registry
+//! returned by `sinks()`. By default it holds a single `StderrSink`; tests,
+//! editor consoles and telemetry can add their own sinks, each with a
+//! filter on code prefix and kind and an optional per-frame rate limit.
+//! Frame windows start whenever a frame authority calls `begin_frame`.
+//! Registered sinks receive diagnostics in every build; only the default
+//! `StderrSink` is limited to debug builds or the `diagnostics` feature.
+
+use std::collections::{HashMap, VecDeque};
 use std::io::Write;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
 
 use super::kind::{Diagnostic, DiagnosticKind};
-use super::strict::should_panic;
+use super::runtime::{FieldValue, RuntimeDiagnostic};
+use super::strict::{should_panic, should_panic_on_warning};
+use crate::sync::mutex::Mutex;
 
 /// Global flag to suppress diagnostic output (for testing).
 static DIAGNOSTICS_SUPPRESSED: AtomicBool = AtomicBool::new(false);
@@ -29,22 +41,17 @@ pub fn is_suppressed() -> bool {
     DIAGNOSTICS_SUPPRESSED.load(Ordering::Relaxed)
 }
 
-/// Emit a diagnostic to stderr.
+/// Emit a diagnostic to the registered sinks.
 ///
-/// In release builds without the `diagnostics` feature, this is a no-op.
-/// In debug builds, this always emits.
-/// In release builds with `diagnostics`, this emits based on configuration.
+/// Registered sinks always receive it. The default `StderrSink` only
+/// writes in debug builds or with the `diagnostics` feature.
 pub fn emit(diag: &Diagnostic) {
     // Check suppression first
     if is_suppressed() {
         return;
     }
 
-    // Only emit in debug builds by default, unless diagnostics feature is on
-    #[cfg(any(debug_assertions, feature = "diagnostics"))]
-    {
-        emit_to_stderr(diag);
-    }
+    sinks().dispatch(diag, None);
 
     // Check if we should panic (strict mode)
     if diag.kind == DiagnosticKind::Error && should_panic() {
@@ -61,10 +68,7 @@ pub fn emit_with_context(diag: &Diagnostic, context: &str) {
         return;
     }
 
-    #[cfg(any(debug_assertions, feature = "diagnostics"))]
-    {
-        emit_to_stderr_with_context(diag, context);
-    }
+    sinks().dispatch(diag, Some(context));
 
     if diag.kind == DiagnosticKind::Error && should_panic() {
         panic!(
@@ -74,8 +78,32 @@ pub fn emit_with_context(diag: &Diagnostic, context: &str) {
     }
 }
 
+/// Emit a runtime diagnostic with structured fields.
+///
+/// Follows the same build-configuration rules as `emit`. Strict mode
+/// panics on errors, and on warnings under `PanicOnWarning`.
+pub fn emit_runtime(diag: &RuntimeDiagnostic) {
+    if is_suppressed() {
+        return;
+    }
+
+    sinks().dispatch_runtime(diag);
+
+    let fatal = match diag.kind {
+        DiagnosticKind::Error => should_panic(),
+        DiagnosticKind::Warning => should_panic_on_warning(),
+        _ => false,
+    };
+    if fatal {
+        panic!(
+            "{}Strict mode enabled - {}s are fatal.",
+            diag.render(),
+            diag.kind.prefix()
+        );
+    }
+}
+
 /// Internal: emit to stderr.
-#[cfg(any(debug_assertions, feature = "diagnostics"))]
 fn emit_to_stderr(diag: &Diagnostic) {
     let mut stderr = std::io::stderr();
     let verbose = VERBOSE_DIAGNOSTICS.load(Ordering::Relaxed);
@@ -109,7 +137,6 @@ fn emit_to_stderr(diag: &Diagnostic) {
 }
 
 /// Internal: emit to stderr with context.
-#[cfg(any(debug_assertions, feature = "diagnostics"))]
 fn emit_to_stderr_with_context(diag: &Diagnostic, context: &str) {
     let mut stderr = std::io::stderr();
 
@@ -165,12 +192,387 @@ pub fn emit_to_log(diag: &Diagnostic) {
 pub trait DiagnosticSink: Send + Sync {
     /// Handle a diagnostic.
     fn emit(&self, diag: &Diagnostic);
+
+    /// Handle a diagnostic emitted with runtime context.
+    ///
+    /// Defaults to `emit`, dropping the context.
+    fn emit_with_context(&self, diag: &Diagnostic, context: &str) {
+        let _ = context;
+        self.emit(diag);
+    }
+
+    /// Handle a runtime diagnostic with structured fields.
+    ///
+    /// Defaults to `emit_with_context` with the predefined diagnostic it was
+    /// built from and its fields as context. Diagnostics without one (such
+    /// as `fa_diagnostic!` with fields) go to a generic diagnostic of the
+    /// same kind, with their code, message and fields as context.
+    fn emit_runtime(&self, diag: &RuntimeDiagnostic) {
+        match diag.base {
+            Some(base) => self.emit_with_context(base, &diag.fields_line()),
+            None => self.emit_with_context(runtime_stand_in(diag.kind), &runtime_context(diag)),
+        }
+    }
+}
+
+/// Generic diagnostics for runtime diagnostics without a predefined base.
+static RUNTIME_STAND_INS: [Diagnostic; 4] = [
+    stand_in(DiagnosticKind::Error),
+    stand_in(DiagnosticKind::Warning),
+    stand_in(DiagnosticKind::Note),
+    stand_in(DiagnosticKind::Help),
+];
+
+const fn stand_in(kind: DiagnosticKind) -> Diagnostic {
+    Diagnostic {
+        kind,
+        code: "FA000",
+        message: "runtime diagnostic",
+        note: None,
+        help: None,
+    }
+}
+
+/// Get the generic diagnostic of `kind`.
+fn runtime_stand_in(kind: DiagnosticKind) -> &'static Diagnostic {
+    match kind {
+        DiagnosticKind::Error => &RUNTIME_STAND_INS[0],
+        DiagnosticKind::Warning => &RUNTIME_STAND_INS[1],
+        DiagnosticKind::Note => &RUNTIME_STAND_INS[2],
+        DiagnosticKind::Help => &RUNTIME_STAND_INS[3],
+    }
+}
+
+/// Render a runtime diagnostic as one context line, e.g.
+/// `FA302: budget exceeded (size=4096 bytes, tag="ai")`.
+fn runtime_context(diag: &RuntimeDiagnostic) -> String {
+    let mut context = format!("{}: {}", diag.code, diag.message);
+    if !diag.fields.is_empty() {
+        context.push_str(&format!(" ({})", diag.fields_line()));
+    }
+    context
+}
+
+/// Sink that writes diagnostics to stderr (the default sink).
+///
+/// Writes nothing in release builds without the `diagnostics` feature.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StderrSink;
+
+impl StderrSink {
+    /// Whether this build writes diagnostics to stderr.
+    const ENABLED: bool = cfg!(any(debug_assertions, feature = "diagnostics"));
+}
+
+impl DiagnosticSink for StderrSink {
+    fn emit(&self, diag: &Diagnostic) {
+        if Self::ENABLED {
+            emit_to_stderr(diag);
+        }
+    }
+
+    fn emit_with_context(&self, diag: &Diagnostic, context: &str) {
+        if Self::ENABLED {
+            emit_to_stderr_with_context(diag, context);
+        }
+    }
+
+    fn emit_runtime(&self, diag: &RuntimeDiagnostic) {
+        if Self::ENABLED {
+            let _ = writeln!(std::io::stderr(), "{}", diag.render());
+        }
+    }
+}
+
+/// Sink that forwards diagnostics to the `log` crate.
+#[cfg(feature = "log")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogSink;
+
+#[cfg(feature = "log")]
+impl DiagnosticSink for LogSink {
+    fn emit(&self, diag: &Diagnostic) {
+        emit_to_log(diag);
+    }
+
+    fn emit_runtime(&self, diag: &RuntimeDiagnostic) {
+        let text = diag.render();
+        match diag.kind {
+            DiagnosticKind::Error => log::error!("{}", text.trim_end()),
+            DiagnosticKind::Warning => log::warn!("{}", text.trim_end()),
+            DiagnosticKind::Note | DiagnosticKind::Help => log::info!("{}", text.trim_end()),
+        }
+    }
+}
+
+// =============================================================================
+// Sink registry
+// =============================================================================
+
+/// Identifies a sink registered with `DiagnosticSinks`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SinkId(u64);
+
+/// Which diagnostics a sink receives.
+///
+/// An empty list of prefixes or kinds matches everything.
+#[derive(Debug, Clone, Default)]
+pub struct SinkFilter {
+    code_prefixes: Vec<&'static str>,
+    kinds: Vec<DiagnosticKind>,
+}
+
+impl SinkFilter {
+    /// Match every diagnostic.
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// Also match codes starting with `prefix`, e.g. "FA3" for FA3xx.
+    pub fn with_code_prefix(mut self, prefix: &'static str) -> Self {
+        self.code_prefixes.push(prefix);
+        self
+    }
+
+    /// Also match diagnostics of `kind`.
+    pub fn with_kind(mut self, kind: DiagnosticKind) -> Self {
+        self.kinds.push(kind);
+        self
+    }
+
+    /// Check whether a diagnostic passes the filter.
+    pub fn matches(&self, diag: &Diagnostic) -> bool {
+        self.accepts(diag.code, diag.kind)
+    }
+
+    /// Check whether a code and kind pass the filter.
+    pub fn accepts(&self, code: &str, kind: DiagnosticKind) -> bool {
+        let code_ok = self.code_prefixes.is_empty()
+            || self.code_prefixes.iter().any(|p| code.starts_with(p));
+        let kind_ok = self.kinds.is_empty() || self.kinds.contains(&kind);
+        code_ok && kind_ok
+    }
+}
+
+/// How often a sink may receive the same code within one frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SinkRateLimit {
+    /// Deliver every diagnostic
+    #[default]
+    Unlimited,
+    /// Deliver each code at most once per frame
+    OncePerFrame,
+    /// Deliver each code at most this many times per frame
+    PerFrame(u32),
+}
+
+impl SinkRateLimit {
+    fn max_per_frame(self) -> Option<u32> {
+        match self {
+            Self::Unlimited => None,
+            Self::OncePerFrame => Some(1),
+            Self::PerFrame(n) => Some(n),
+        }
+    }
+}
+
+/// A registered sink and its routing state.
+struct SinkEntry {
+    id: SinkId,
+    sink: Arc<dyn DiagnosticSink>,
+    filter: SinkFilter,
+    limit: SinkRateLimit,
+    /// Deliveries per code in the current frame
+    delivered: HashMap<String, u32>,
+    /// Diagnostics dropped by the rate limit
+    suppressed: u64,
+}
+
+/// Diagnostics kept for the current frame window.
+const RECENT_CAPACITY: usize = 256;
+
+/// Registry of diagnostic sinks.
+///
+/// `emit` dispatches to the global registry from `sinks()`. Sinks are
+/// called outside the registry lock, so they may emit diagnostics
+/// themselves. The registry also keeps the diagnostics dispatched in the
+/// current frame window, which allocator snapshots report.
+pub struct DiagnosticSinks {
+    entries: Mutex<Vec<SinkEntry>>,
+    next_id: AtomicU64,
+    recent: Mutex<VecDeque<RuntimeDiagnostic>>,
+}
+
+impl DiagnosticSinks {
+    /// Create a registry with no sinks.
+    pub fn new() -> Self {
+        Self {
+            entries: Mutex::new(Vec::new()),
+            next_id: AtomicU64::new(1),
+            recent: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Create a registry holding the default `StderrSink`.
+    pub fn with_defaults() -> Self {
+        let sinks = Self::new();
+        sinks.add(Arc::new(StderrSink));
+        sinks
+    }
+
+    /// Add a sink that receives every diagnostic.
+    pub fn add(&self, sink: Arc<dyn DiagnosticSink>) -> SinkId {
+        self.add_with(sink, SinkFilter::all(), SinkRateLimit::Unlimited)
+    }
+
+    /// Add a sink with a filter and rate limit.
+    pub fn add_with(
+        &self,
+        sink: Arc<dyn DiagnosticSink>,
+        filter: SinkFilter,
+        limit: SinkRateLimit,
+    ) -> SinkId {
+        let id = SinkId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        self.entries.lock().push(SinkEntry {
+            id,
+            sink,
+            filter,
+            limit,
+            delivered: HashMap::new(),
+            suppressed: 0,
+        });
+        id
+    }
+
+    /// Remove a sink. Returns false if it was not registered.
+    pub fn remove(&self, id: SinkId) -> bool {
+        let mut entries = self.entries.lock();
+        let before = entries.len();
+        entries.retain(|e| e.id != id);
+        entries.len() != before
+    }
+
+    /// Remove every sink, including the default one.
+    pub fn clear(&self) {
+        self.entries.lock().clear();
+    }
+
+    /// Replace all sinks with the default `StderrSink`.
+    pub fn restore_defaults(&self) {
+        self.clear();
+        self.add(Arc::new(StderrSink));
+    }
+
+    /// Start a new frame window for rate limits and recent diagnostics.
+    pub fn begin_frame(&self) {
+        for entry in self.entries.lock().iter_mut() {
+            entry.delivered.clear();
+        }
+        self.recent.lock().clear();
+    }
+
+    /// Get the diagnostics dispatched in the current frame window.
+    ///
+    /// Holds at most the latest 256; older ones are dropped.
+    pub fn recent(&self) -> Vec<RuntimeDiagnostic> {
+        self.recent.lock().iter().cloned().collect()
+    }
+
+    /// Get how many diagnostics a sink's rate limit has dropped.
+    pub fn suppressed_count(&self, id: SinkId) -> Option<u64> {
+        self.entries
+            .lock()
+            .iter()
+            .find(|e| e.id == id)
+            .map(|e| e.suppressed)
+    }
+
+    /// Get the number of registered sinks.
+    pub fn len(&self) -> usize {
+        self.entries.lock().len()
+    }
+
+    /// Check if no sinks are registered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Deliver a diagnostic to every sink that accepts it.
+    pub(crate) fn dispatch(&self, diag: &Diagnostic, context: Option<&str>) {
+        let mut recorded = RuntimeDiagnostic::from_diagnostic(diag);
+        if let Some(context) = context {
+            recorded = recorded.with_field("context", FieldValue::Text(context.to_string()));
+        }
+        self.record(recorded);
+
+        for sink in self.targets(diag.code, diag.kind) {
+            match context {
+                Some(context) => sink.emit_with_context(diag, context),
+                None => sink.emit(diag),
+            }
+        }
+    }
+
+    /// Deliver a runtime diagnostic to every sink that accepts it.
+    pub(crate) fn dispatch_runtime(&self, diag: &RuntimeDiagnostic) {
+        self.record(diag.clone());
+        for sink in self.targets(&diag.code, diag.kind) {
+            sink.emit_runtime(diag);
+        }
+    }
+
+    /// Keep a diagnostic for the current frame window.
+    fn record(&self, diag: RuntimeDiagnostic) {
+        let mut recent = self.recent.lock();
+        if recent.len() == RECENT_CAPACITY {
+            recent.pop_front();
+        }
+        recent.push_back(diag);
+    }
+
+    /// Pick the sinks that accept a diagnostic, applying rate limits.
+    fn targets(&self, code: &str, kind: DiagnosticKind) -> Vec<Arc<dyn DiagnosticSink>> {
+        let mut entries = self.entries.lock();
+        entries
+            .iter_mut()
+            .filter(|e| e.filter.accepts(code, kind))
+            .filter_map(|e| {
+                if let Some(max) = e.limit.max_per_frame() {
+                    let count = e.delivered.get(code).copied().unwrap_or(0);
+                    if count >= max {
+                        e.suppressed += 1;
+                        return None;
+                    }
+                    match e.delivered.get_mut(code) {
+                        Some(count) => *count += 1,
+                        None => {
+                            e.delivered.insert(code.to_string(), 1);
+                        }
+                    }
+                }
+                Some(e.sink.clone())
+            })
+            .collect()
+    }
+}
+
+impl Default for DiagnosticSinks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Get the global sink registry used by `emit`.
+pub fn sinks() -> &'static DiagnosticSinks {
+    static SINKS: OnceLock<DiagnosticSinks> = OnceLock::new();
+    SINKS.get_or_init(DiagnosticSinks::with_defaults)
 }
 
 /// A simple sink that collects diagnostics.
 #[derive(Default)]
 pub struct CollectingSink {
     diagnostics: std::sync::Mutex<Vec<Diagnostic>>,
+    runtime: std::sync::Mutex<Vec<RuntimeDiagnostic>>,
 }
 
 impl CollectingSink {
@@ -184,9 +586,15 @@ impl CollectingSink {
         self.diagnostics.lock().unwrap().clone()
     }
 
+    /// Get all collected runtime diagnostics.
+    pub fn runtime_diagnostics(&self) -> Vec<RuntimeDiagnostic> {
+        self.runtime.lock().unwrap().clone()
+    }
+
     /// Clear collected diagnostics.
     pub fn clear(&self) {
         self.diagnostics.lock().unwrap().clear();
+        self.runtime.lock().unwrap().clear();
     }
 
     /// Check if any errors were collected.
@@ -196,6 +604,12 @@ impl CollectingSink {
             .unwrap()
             .iter()
             .any(|d| d.kind == DiagnosticKind::Error)
+            || self
+                .runtime
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|d| d.kind == DiagnosticKind::Error)
     }
 }
 
@@ -203,6 +617,10 @@ impl DiagnosticSink for CollectingSink {
     fn emit(&self, diag: &Diagnostic) {
         self.diagnostics.lock().unwrap().push(diag.clone());
     }
+
+    fn emit_runtime(&self, diag: &RuntimeDiagnostic) {
+        self.runtime.lock().unwrap().push(diag.clone());
+    }
 }
 
 #[cfg(test)]
@@ -222,6 +640,87 @@ mod tests {
         assert_eq!(sink.diagnostics().len(), 0);
     }
 
+    #[test]
+    fn test_sink_filters_and_rate_limits() {
+        use crate::diagnostics::kind::{FA003, FA301, FA302};
+
+        let sinks = DiagnosticSinks::new();
+        let budget = Arc::new(CollectingSink::new());
+        let errors = Arc::new(CollectingSink::new());
+        let budget_id = sinks.add_with(
+            budget.clone(),
+            SinkFilter::all().with_code_prefix("FA3"),
+            SinkRateLimit::OncePerFrame,
+        );
+        sinks.add_with(
+            errors.clone(),
+            SinkFilter::all().with_kind(DiagnosticKind::Error),
+            SinkRateLimit::Unlimited,
+        );
+
+        for _ in 0..3 {
+            sinks.dispatch(&FA301, None);
+        }
+        sinks.dispatch(&FA302, Some("tag=\"physics\""));
+        sinks.dispatch(&FA003, None);
+        sinks.dispatch(&FA001, None);
+        sinks.dispatch(&FA001, None);
+
+        let codes: Vec<_> = budget.diagnostics().iter().map(|d| d.code).collect();
+        assert_eq!(codes, ["FA301", "FA302"]);
+        assert_eq!(sinks.suppressed_count(budget_id), Some(2));
+        assert_eq!(errors.diagnostics().len(), 2);
+
+        // A new frame opens a new window
+        sinks.begin_frame();
+        sinks.dispatch(&FA301, None);
+        assert_eq!(budget.diagnostics().len(), 3);
+
+        // Runtime diagnostics share the filters and limits, and every
+        // dispatch is kept for the frame window
+        sinks.dispatch_runtime(&RuntimeDiagnostic::from(&FA301).with_tag("ai"));
+        sinks.dispatch_runtime(&RuntimeDiagnostic::error("FA350", "dynamic").with_size(64));
+        assert_eq!(budget.runtime_diagnostics().len(), 1);
+        assert_eq!(budget.runtime_diagnostics()[0].code, "FA350");
+        assert_eq!(errors.runtime_diagnostics().len(), 1);
+        let recent = sinks.recent();
+        assert_eq!(recent.len(), 3);
+        assert_eq!(recent[1].tag(), Some("ai"));
+        sinks.begin_frame();
+        assert!(sinks.recent().is_empty());
+
+        assert!(sinks.remove(budget_id));
+        assert!(!sinks.remove(budget_id));
+        sinks.restore_defaults();
+        assert_eq!(sinks.len(), 1);
+    }
+
+    #[test]
+    fn test_default_emit_runtime_keeps_field_diagnostics() {
+        use crate::diagnostics::kind::FA302;
+
+        #[derive(Default)]
+        struct ContextSink(std::sync::Mutex<Vec<(&'static str, String)>>);
+
+        impl DiagnosticSink for ContextSink {
+            fn emit(&self, diag: &Diagnostic) {
+                self.emit_with_context(diag, "");
+            }
+
+            fn emit_with_context(&self, diag: &Diagnostic, context: &str) {
+                self.0.lock().unwrap().push((diag.code, context.to_string()));
+            }
+        }
+
+        let sink = ContextSink::default();
+        sink.emit_runtime(&RuntimeDiagnostic::from(&FA302).with_size(64));
+        sink.emit_runtime(&RuntimeDiagnostic::warning("FA350", "dynamic").with_size(64));
+
+        let seen = sink.0.lock().unwrap();
+        assert_eq!(seen[0], ("FA302", "size=64 bytes".to_string()));
+        assert_eq!(seen[1], ("FA000", "FA350: dynamic (size=64 bytes)".to_string()));
+    }
+
     #[test]
     fn test_suppression() {
         suppress_diagnostics(true);
@@ -0,0 +1,349 @@
+//! Log2-bucketed histograms of allocation sizes and lifetimes.
+//!
+//! Totals and averages hide the shape of a workload: a tag averaging 200
+//! bytes may be all 200-byte objects or a mix of 16-byte and 4 KiB ones,
+//! which want very different slab size classes. When histograms are enabled
+//! on the `BehaviorFilter`, it records:
+//!
+//! - allocation sizes per tag and allocation kind
+//! - lifetimes in frames of pool, heap and handle allocations
+//! - the frame arena high-water mark of every thread's frame
+//!
+//! Bucket `i` holds values in `[2^(i-1), 2^i)`, with bucket 0 holding zero,
+//! so recording is a `leading_zeros` and an increment.
+
+use std::fmt;
+
+use super::behavior::AllocKind;
+use crate::allocators::slab::MAX_SLAB_ALIGN;
+
+/// One bucket for zero and one per bit of a `u64`.
+const BUCKETS: usize = 65;
+
+/// Histogram with power-of-two buckets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Log2Histogram {
+    buckets: [u64; BUCKETS],
+    count: u64,
+    sum: u64,
+    min: u64,
+    max: u64,
+}
+
+/// A non-empty histogram bucket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Log2Bucket {
+    /// Smallest value in the bucket
+    pub low: u64,
+    /// Largest value in the bucket
+    pub high: u64,
+    /// Values recorded in the bucket
+    pub count: u64,
+}
+
+impl Log2Histogram {
+    /// Create an empty histogram.
+    pub fn new() -> Self {
+        Self {
+            buckets: [0; BUCKETS],
+            count: 0,
+            sum: 0,
+            min: u64::MAX,
+            max: 0,
+        }
+    }
+
+    /// Record one value.
+    #[inline]
+    pub fn record(&mut self, value: u64) {
+        self.buckets[Self::bucket_of(value)] += 1;
+        self.count += 1;
+        self.sum = self.sum.saturating_add(value);
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    /// Add every value recorded in `other`.
+    pub fn merge(&mut self, other: &Log2Histogram) {
+        for (bucket, count) in self.buckets.iter_mut().zip(other.buckets.iter()) {
+            *bucket += count;
+        }
+        self.count += other.count;
+        self.sum = self.sum.saturating_add(other.sum);
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    /// Check if nothing was recorded.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Get the number of recorded values.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Get the sum of recorded values, saturating at `u64::MAX`.
+    pub fn sum(&self) -> u64 {
+        self.sum
+    }
+
+    /// Get the smallest recorded value.
+    pub fn min(&self) -> Option<u64> {
+        (!self.is_empty()).then_some(self.min)
+    }
+
+    /// Get the largest recorded value.
+    pub fn max(&self) -> Option<u64> {
+        (!self.is_empty()).then_some(self.max)
+    }
+
+    /// Get the mean of recorded values.
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.sum as f64 / self.count as f64
+        }
+    }
+
+    /// Get an upper bound for the `q` quantile (0.0 - 1.0).
+    ///
+    /// Returns the top of the bucket holding the quantile, capped at the
+    /// largest recorded value, or `None` if the histogram is empty.
+    pub fn percentile(&self, q: f64) -> Option<u64> {
+        if self.is_empty() {
+            return None;
+        }
+        let rank = ((q.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, &count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return Some(Self::bounds(index).1.min(self.max));
+            }
+        }
+        Some(self.max)
+    }
+
+    /// Get the non-empty buckets, smallest first.
+    pub fn buckets(&self) -> impl Iterator<Item = Log2Bucket> + '_ {
+        self.buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count > 0)
+            .map(|(index, &count)| {
+                let (low, high) = Self::bounds(index);
+                Log2Bucket { low, high, count }
+            })
+    }
+
+    #[inline]
+    fn bucket_of(value: u64) -> usize {
+        (u64::BITS - value.leading_zeros()) as usize
+    }
+
+    fn bounds(index: usize) -> (u64, u64) {
+        match index {
+            0 => (0, 0),
+            64 => (1 << 63, u64::MAX),
+            _ => (1 << (index - 1), (1 << index) - 1),
+        }
+    }
+}
+
+impl Default for Log2Histogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for Log2Histogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (Some(min), Some(max)) = (self.min(), self.max()) else {
+            return write!(f, "n=0");
+        };
+        write!(
+            f,
+            "n={} min={} p50<={} p90<={} p99<={} max={}",
+            self.count,
+            min,
+            self.percentile(0.5).unwrap_or(0),
+            self.percentile(0.9).unwrap_or(0),
+            self.percentile(0.99).unwrap_or(0),
+            max
+        )
+    }
+}
+
+/// Histogram for one tag and allocation kind.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagHistogram {
+    pub tag: &'static str,
+    pub kind: AllocKind,
+    pub histogram: Log2Histogram,
+}
+
+/// Histograms collected by the behavior filter.
+#[derive(Debug, Clone, Default)]
+pub struct AllocationHistograms {
+    /// Allocation sizes in bytes, sorted by tag and kind
+    pub sizes: Vec<TagHistogram>,
+    /// Lifetimes in frames of pool, heap and handle allocations
+    pub lifetimes: Vec<TagHistogram>,
+    /// Frame arena high-water mark in bytes of each thread's frames
+    pub arena_high_water: Log2Histogram,
+}
+
+impl AllocationHistograms {
+    /// Check if nothing was recorded.
+    pub fn is_empty(&self) -> bool {
+        self.sizes.is_empty() && self.lifetimes.is_empty() && self.arena_high_water.is_empty()
+    }
+
+    /// Add every value recorded in `other`, e.g. from another run.
+    pub fn merge(&mut self, other: &AllocationHistograms) {
+        merge_tagged(&mut self.sizes, &other.sizes);
+        merge_tagged(&mut self.lifetimes, &other.lifetimes);
+        self.arena_high_water.merge(&other.arena_high_water);
+    }
+
+    /// Get allocation sizes of `kind` across all tags.
+    pub fn sizes_for_kind(&self, kind: AllocKind) -> Log2Histogram {
+        merged(self.sizes.iter().filter(|h| h.kind == kind))
+    }
+
+    /// Get allocation sizes of `tag` across all kinds.
+    pub fn sizes_for_tag(&self, tag: &str) -> Log2Histogram {
+        merged(self.sizes.iter().filter(|h| h.tag == tag))
+    }
+
+    /// Get lifetimes of `kind` across all tags.
+    pub fn lifetimes_for_kind(&self, kind: AllocKind) -> Log2Histogram {
+        merged(self.lifetimes.iter().filter(|h| h.kind == kind))
+    }
+
+    /// Suggest slab size classes from observed pool allocation sizes.
+    ///
+    /// Returns up to `max_classes` powers of two covering the most frequent
+    /// pool sizes, smallest first, for `AllocConfig::slab_size_classes`.
+    /// Classes stop at `MAX_SLAB_ALIGN`, as larger pool allocations go to
+    /// the large-object allocator.
+    pub fn suggested_slab_size_classes(&self, max_classes: usize) -> Vec<usize> {
+        let mut buckets: Vec<_> = self.sizes_for_kind(AllocKind::Pool).buckets().collect();
+        buckets.sort_by_key(|b| std::cmp::Reverse(b.count));
+        let mut classes: Vec<usize> = buckets
+            .iter()
+            .take(max_classes)
+            .map(|b| (b.high as usize).saturating_add(1).clamp(16, MAX_SLAB_ALIGN))
+            .collect();
+        classes.sort_unstable();
+        classes.dedup();
+        classes
+    }
+
+    /// Suggest a frame arena size that fits every observed frame.
+    ///
+    /// Returns the largest high-water mark rounded up to a power of two,
+    /// for `AllocConfig::frame_arena_size`.
+    pub fn suggested_frame_arena_size(&self) -> Option<usize> {
+        self.arena_high_water
+            .max()
+            .map(|max| (max as usize).max(1).next_power_of_two())
+    }
+}
+
+fn merge_tagged(into: &mut Vec<TagHistogram>, from: &[TagHistogram]) {
+    for tagged in from {
+        match into.iter_mut().find(|h| h.tag == tagged.tag && h.kind == tagged.kind) {
+            Some(existing) => existing.histogram.merge(&tagged.histogram),
+            None => into.push(tagged.clone()),
+        }
+    }
+    into.sort_by_key(|h| (h.tag, h.kind));
+}
+
+fn merged<'a>(histograms: impl Iterator<Item = &'a TagHistogram>) -> Log2Histogram {
+    let mut total = Log2Histogram::new();
+    for tagged in histograms {
+        total.merge(&tagged.histogram);
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log2_buckets_and_percentiles() {
+        let mut histogram = Log2Histogram::new();
+        assert_eq!(histogram.percentile(0.5), None);
+        assert_eq!(histogram.to_string(), "n=0");
+
+        for value in [0, 1, 3, 4, 7, 8, 100, u64::MAX] {
+            histogram.record(value);
+        }
+        let buckets: Vec<_> = histogram.buckets().map(|b| (b.low, b.high, b.count)).collect();
+        assert_eq!(
+            buckets,
+            vec![
+                (0, 0, 1),
+                (1, 1, 1),
+                (2, 3, 1),
+                (4, 7, 2),
+                (8, 15, 1),
+                (64, 127, 1),
+                (1 << 63, u64::MAX, 1),
+            ]
+        );
+        assert_eq!(histogram.percentile(0.5), Some(7));
+        assert_eq!(histogram.percentile(0.75), Some(15));
+        assert_eq!(histogram.min(), Some(0));
+        assert_eq!(histogram.sum(), u64::MAX);
+
+        let mut small = Log2Histogram::new();
+        small.record(100);
+        assert_eq!(small.percentile(0.99), Some(100));
+        small.merge(&histogram);
+        assert_eq!(small.count(), 9);
+        assert_eq!(small.buckets().find(|b| b.low == 64).unwrap().count, 2);
+    }
+
+    #[test]
+    fn test_suggestions_from_evidence() {
+        let mut sizes = Log2Histogram::new();
+        for _ in 0..10 {
+            sizes.record(40);
+        }
+        for _ in 0..5 {
+            sizes.record(200);
+        }
+        sizes.record(3000);
+        let mut arena_high_water = Log2Histogram::new();
+        arena_high_water.record(300_000);
+        arena_high_water.record(700_000);
+
+        let histograms = AllocationHistograms {
+            sizes: vec![TagHistogram { tag: "ai", kind: AllocKind::Pool, histogram: sizes }],
+            lifetimes: Vec::new(),
+            arena_high_water,
+        };
+        assert_eq!(histograms.suggested_slab_size_classes(2), vec![64, 256]);
+
+        // Huge sizes fold into the largest slab class
+        let mut huge = histograms.clone();
+        huge.sizes[0].histogram.record(u64::MAX);
+        assert_eq!(huge.suggested_slab_size_classes(4), vec![64, 256, MAX_SLAB_ALIGN]);
+        assert_eq!(histograms.suggested_frame_arena_size(), Some(1 << 20));
+        assert_eq!(histograms.sizes_for_tag("ai").count(), 16);
+        assert!(histograms.sizes_for_kind(AllocKind::Heap).is_empty());
+
+        let mut total = histograms.clone();
+        total.merge(&histograms);
+        assert_eq!(total.sizes.len(), 1);
+        assert_eq!(total.sizes_for_tag("ai").count(), 32);
+        assert_eq!(total.arena_high_water.count(), 4);
+    }
+}
@@ -69,6 +69,17 @@ impl DiagnosticKind {
         }
     }
 
+    /// Parse a kind from its display prefix.
+    pub fn from_prefix(prefix: &str) -> Option<Self> {
+        match prefix {
+            "error" => Some(DiagnosticKind::Error),
+            "warning" => Some(DiagnosticKind::Warning),
+            "note" => Some(DiagnosticKind::Note),
+            "help" => Some(DiagnosticKind::Help),
+            _ => None,
+        }
+    }
+
     /// Get the emoji for this kind (for build.rs style output).
     pub fn emoji(&self) -> &'static str {
         match self {
@@ -218,6 +229,13 @@ pub const FA302: Diagnostic = Diagnostic::warning(
 ).with_note("this allocation tag has exceeded its hard limit")
  .with_help("check for memory leaks in this subsystem or increase the tag budget");
 
+/// FA303: Phase frame budget exceeded.
+pub const FA303: Diagnostic = Diagnostic::warning(
+    "FA303",
+    "frame allocation exceeds phase budget"
+).with_note("this phase has used more frame memory than its budget allows this frame")
+ .with_help("check the phase's high-water marks and raise its budget, or move allocations out of the phase");
+
 // =============================================================================
 // Predefined diagnostics (FA4xx - Handles/Streaming)
 // =============================================================================
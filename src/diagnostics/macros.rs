@@ -4,6 +4,12 @@
 
 /// Emit a runtime diagnostic.
 ///
+/// With a trailing `fields = { ... }` block the message may be any string
+/// expression, and the diagnostic is emitted as a `RuntimeDiagnostic`
+/// carrying those fields plus the current thread. `size`, `limit`, `tag`,
+/// `thread`, `frame` and `phase` are typed; other keys are converted with
+/// `FieldValue::from`.
+///
 /// # Example
 ///
 /// ```rust,ignore
@@ -14,9 +20,39 @@
 ///     note = "this allocation was requested after end_frame()",
 ///     help = "call alloc.begin_frame() before allocating"
 /// );
+///
+/// fa_diagnostic!(
+///     Warning,
+///     code = "FA302",
+///     message = format!("tag '{}' is over budget", tag),
+///     fields = { tag: tag, size: bytes, limit: budget.hard_limit }
+/// );
 /// ```
 #[macro_export]
 macro_rules! fa_diagnostic {
+    (
+        $kind:ident,
+        code = $code:expr,
+        message = $msg:expr
+        $(, note = $note:expr)?
+        $(, help = $help:expr)?
+        , fields = { $($field:ident : $value:expr),* $(,)? }
+    ) => {{
+        #[cfg(any(debug_assertions, feature = "diagnostics"))]
+        {
+            #[allow(unused_mut)]
+            let mut diag = $crate::diagnostics::RuntimeDiagnostic::new(
+                $crate::diagnostics::DiagnosticKind::$kind,
+                $code,
+                $msg,
+            )
+            $(.with_note($note))?
+            $(.with_help($help))?
+            .with_current_thread();
+            $(diag = $crate::__fa_field!(diag, $field, $value);)*
+            $crate::diagnostics::emit::emit_runtime(&diag);
+        }
+    }};
     (
         $kind:ident,
         code = $code:expr,
@@ -38,6 +74,21 @@ macro_rules! fa_diagnostic {
     }};
 }
 
+/// Apply one `fa_diagnostic!` field to a `RuntimeDiagnostic`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __fa_field {
+    ($diag:expr, size, $value:expr) => { $diag.with_size($value) };
+    ($diag:expr, limit, $value:expr) => { $diag.with_limit($value) };
+    ($diag:expr, tag, $value:expr) => { $diag.with_tag($value) };
+    ($diag:expr, thread, $value:expr) => { $diag.with_thread($value) };
+    ($diag:expr, frame, $value:expr) => { $diag.with_frame($value) };
+    ($diag:expr, phase, $value:expr) => { $diag.with_phase($value) };
+    ($diag:expr, $key:ident, $value:expr) => {
+        $diag.with_field(stringify!($key), $crate::diagnostics::FieldValue::from($value))
+    };
+}
+
 /// Emit a runtime diagnostic with captured context.
 ///
 /// # Example
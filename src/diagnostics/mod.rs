@@ -3,7 +3,7 @@
 //! This module provides:
 //! - **Runtime diagnostics**: Allocator-aware error messages with codes
 //! - **UI integration**: Hooks for imgui, egui, or custom overlays
-//! - **Profiler integration**: Tracy and custom profiler support
+//! - **Profiler integration**: Tracy, Chrome trace / Perfetto export and custom profilers
 //! - **Strict mode**: Optional panic-on-error for CI
 //! - **Behavior filtering**: Runtime detection of allocation pattern issues (v0.4.0)
 //!
@@ -42,31 +42,44 @@ pub mod emit;
 pub mod context;
 pub mod strict;
 pub mod macros;
+pub mod runtime;
 
 // Behavior filtering (v0.4.0)
 pub mod behavior;
+pub mod histogram;
 
 // UI integration
 mod hooks;
 mod snapshot;
+mod trace;
 mod tracy;
 
 // Re-export core types
 pub use kind::{Diagnostic, DiagnosticKind, DiagnosticCode, DiagnosticLevel};
 pub use emit::{emit, emit_with_context, suppress_diagnostics, set_verbose, DiagnosticSink, CollectingSink};
+pub use emit::emit_runtime;
+pub use runtime::{FieldValue, RuntimeDiagnostic};
+pub use emit::{sinks, DiagnosticSinks, SinkFilter, SinkId, SinkRateLimit, StderrSink};
+#[cfg(feature = "log")]
+pub use emit::LogSink;
 pub use context::{DiagContext, set_bevy_context, is_bevy_context, increment_frame, frame_number};
 pub use strict::{StrictMode, set_strict_mode, strict_mode, StrictModeGuard, init_from_env};
 
 // Re-export predefined diagnostics
-pub use kind::{FA001, FA002, FA003, FA101, FA102, FA201, FA202, FA301, FA302, FA401, FA402, FA901};
+pub use kind::{FA001, FA002, FA003, FA101, FA102, FA201, FA202, FA301, FA302, FA303, FA401, FA402, FA901};
 
 // Behavior diagnostics (v0.4.0)
 pub use behavior::{
     AllocKind, BehaviorFilter, BehaviorIssue, BehaviorReport, BehaviorThresholds,
     TagBehaviorStats, FA501, FA502, FA510, FA520, FA530,
 };
+pub use histogram::{AllocationHistograms, Log2Bucket, Log2Histogram, TagHistogram};
 
 // UI hooks
 pub use hooks::{DiagnosticsHooks, DiagnosticsEvent, SharedDiagnostics, MemoryGraphData};
 pub use snapshot::{AllocatorSnapshot, FrameSnapshot, PoolSnapshot, TagSnapshot, GlobalSnapshot, StreamingSnapshot, SnapshotHistory};
-pub use tracy::{ProfilerHooks, ProfilerZone, MemoryEvent};
+pub use tracy::{ProfilerHooks, ProfilerZone, MemoryEvent, MemoryPool};
+pub(crate) use tracy::ProfilerSlot;
+#[cfg(feature = "tracy")]
+pub use tracy::tracy_client;
+pub use trace::{TraceExporter, TraceFormat};
@@ -0,0 +1,324 @@
+//! Owned runtime diagnostics with structured fields.
+//!
+//! `Diagnostic` is a compile-time constant. A `RuntimeDiagnostic` is built
+//! when the problem is detected and can say which tag, how many bytes,
+//! which thread, frame or phase were involved. It renders as the usual
+//! rustc-like text and as JSON, and is what snapshots record.
+
+use std::fmt;
+
+use super::kind::{Diagnostic, DiagnosticKind};
+use crate::util::json::escape_json_str;
+
+/// Value of a structured diagnostic field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldValue {
+    /// A byte count
+    Bytes(usize),
+    /// Any other number (frame, count, ...)
+    Number(u64),
+    /// Free text (tag, thread, phase, ...)
+    Text(String),
+}
+
+impl FieldValue {
+    /// Get the value as a number, if it is one.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Self::Bytes(n) => Some(*n as u64),
+            Self::Number(n) => Some(*n),
+            Self::Text(_) => None,
+        }
+    }
+
+    /// Get the value as text, if it is text.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::Text(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn to_json(&self) -> String {
+        match self {
+            Self::Bytes(n) => n.to_string(),
+            Self::Number(n) => n.to_string(),
+            Self::Text(s) => format!("\"{}\"", escape_json_str(s)),
+        }
+    }
+}
+
+impl From<usize> for FieldValue {
+    fn from(bytes: usize) -> Self {
+        Self::Bytes(bytes)
+    }
+}
+
+impl From<u64> for FieldValue {
+    fn from(n: u64) -> Self {
+        Self::Number(n)
+    }
+}
+
+impl From<&str> for FieldValue {
+    fn from(s: &str) -> Self {
+        Self::Text(s.to_string())
+    }
+}
+
+impl From<String> for FieldValue {
+    fn from(s: String) -> Self {
+        Self::Text(s)
+    }
+}
+
+impl fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bytes(n) => write!(f, "{} bytes", n),
+            Self::Number(n) => write!(f, "{}", n),
+            Self::Text(s) => write!(f, "\"{}\"", s),
+        }
+    }
+}
+
+/// A diagnostic with owned text and typed key/value fields.
+#[derive(Debug, Clone)]
+pub struct RuntimeDiagnostic {
+    /// Severity level
+    pub kind: DiagnosticKind,
+    /// Diagnostic code (e.g., "FA302")
+    pub code: String,
+    /// Primary message
+    pub message: String,
+    /// Optional additional context
+    pub note: Option<String>,
+    /// Optional fix suggestion
+    pub help: Option<String>,
+    /// Structured fields, in insertion order
+    pub fields: Vec<(String, FieldValue)>,
+    /// Predefined diagnostic this was built from, if any
+    pub base: Option<&'static Diagnostic>,
+    /// Copy of the `tag` field, kept for code written against the old
+    /// snapshot diagnostic
+    #[deprecated(since = "0.12.0", note = "use `tag()` or the `tag` entry in `fields`")]
+    pub tag: Option<String>,
+}
+
+impl RuntimeDiagnostic {
+    /// Create a diagnostic with no fields.
+    #[allow(deprecated)]
+    pub fn new(kind: DiagnosticKind, code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            code: code.into(),
+            message: message.into(),
+            note: None,
+            help: None,
+            fields: Vec::new(),
+            base: None,
+            tag: None,
+        }
+    }
+
+    /// Create an error diagnostic.
+    pub fn error(code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::new(DiagnosticKind::Error, code, message)
+    }
+
+    /// Create a warning diagnostic.
+    pub fn warning(code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::new(DiagnosticKind::Warning, code, message)
+    }
+
+    /// Copy the text of a (possibly non-static) diagnostic.
+    ///
+    /// Unlike `From<&'static Diagnostic>`, the result has no `base`.
+    pub fn from_diagnostic(diag: &Diagnostic) -> Self {
+        let mut runtime = Self::new(diag.kind, diag.code, diag.message);
+        runtime.note = diag.note.map(String::from);
+        runtime.help = diag.help.map(String::from);
+        runtime
+    }
+
+    /// Add a note.
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.note = Some(note.into());
+        self
+    }
+
+    /// Add a help message.
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    /// Add a field, replacing any field with the same key.
+    #[allow(deprecated)]
+    pub fn with_field(mut self, key: impl Into<String>, value: FieldValue) -> Self {
+        let key = key.into();
+        if key == "tag" {
+            self.tag = value.as_str().map(String::from);
+        }
+        match self.fields.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, existing)) => *existing = value,
+            None => self.fields.push((key, value)),
+        }
+        self
+    }
+
+    /// Add the allocation size in bytes.
+    pub fn with_size(self, bytes: usize) -> Self {
+        self.with_field("size", FieldValue::Bytes(bytes))
+    }
+
+    /// Add the limit in bytes that was hit.
+    pub fn with_limit(self, bytes: usize) -> Self {
+        self.with_field("limit", FieldValue::Bytes(bytes))
+    }
+
+    /// Add the allocation tag.
+    pub fn with_tag(self, tag: impl Into<String>) -> Self {
+        self.with_field("tag", FieldValue::Text(tag.into()))
+    }
+
+    /// Add the thread name.
+    pub fn with_thread(self, thread: impl Into<String>) -> Self {
+        self.with_field("thread", FieldValue::Text(thread.into()))
+    }
+
+    /// Add the frame number.
+    pub fn with_frame(self, frame: u64) -> Self {
+        self.with_field("frame", FieldValue::Number(frame))
+    }
+
+    /// Add the frame phase.
+    pub fn with_phase(self, phase: impl Into<String>) -> Self {
+        self.with_field("phase", FieldValue::Text(phase.into()))
+    }
+
+    /// Add the ID of the allocator that raised the diagnostic.
+    pub fn with_allocator(self, id: u64) -> Self {
+        self.with_field("allocator", FieldValue::Number(id))
+    }
+
+    /// Get the allocator field, if set.
+    pub fn allocator(&self) -> Option<u64> {
+        self.field("allocator").and_then(FieldValue::as_u64)
+    }
+
+    /// Add the calling thread's registered or std name, or its ID.
+    pub fn with_current_thread(self) -> Self {
+        let thread = crate::api::threads::current_thread_name()
+            .unwrap_or_else(|| format!("{:?}", std::thread::current().id()));
+        self.with_thread(thread)
+    }
+
+    /// Get a field by key.
+    pub fn field(&self, key: &str) -> Option<&FieldValue> {
+        self.fields.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Get the tag field, if set.
+    pub fn tag(&self) -> Option<&str> {
+        self.field("tag").and_then(FieldValue::as_str)
+    }
+
+    /// Render the fields as `key=value` pairs, e.g. `size=4096 bytes, tag="ai"`.
+    pub fn fields_line(&self) -> String {
+        self.fields
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Render as rustc-like text, matching the stderr output.
+    pub fn render(&self) -> String {
+        let mut out = format!("[framealloc][{}] {}: {}\n", self.code, self.kind.prefix(), self.message);
+        if !self.fields.is_empty() {
+            out.push_str(&format!("  fields: {}\n", self.fields_line()));
+        }
+        if let Some(ref note) = self.note {
+            out.push_str(&format!("  note: {}\n", note));
+        }
+        if let Some(ref help) = self.help {
+            out.push_str(&format!("  help: {}\n", help));
+        }
+        out
+    }
+
+    /// Serialize to a single-line JSON object.
+    pub fn to_json(&self) -> String {
+        let optional = |s: &Option<String>| match s {
+            Some(s) => format!("\"{}\"", escape_json_str(s)),
+            None => "null".to_string(),
+        };
+
+        format!(
+            "{{\"code\": \"{}\", \"kind\": \"{}\", \"message\": \"{}\", \"note\": {}, \"help\": {}, \"fields\": {}}}",
+            escape_json_str(&self.code),
+            self.kind.prefix(),
+            escape_json_str(&self.message),
+            optional(&self.note),
+            optional(&self.help),
+            self.fields_json(),
+        )
+    }
+
+    /// Serialize the fields as a JSON object.
+    pub fn fields_json(&self) -> String {
+        let fields: Vec<_> = self
+            .fields
+            .iter()
+            .map(|(k, v)| format!("\"{}\": {}", escape_json_str(k), v.to_json()))
+            .collect();
+        format!("{{{}}}", fields.join(", "))
+    }
+}
+
+impl From<&'static Diagnostic> for RuntimeDiagnostic {
+    fn from(diag: &'static Diagnostic) -> Self {
+        let mut runtime = Self::from_diagnostic(diag);
+        runtime.base = Some(diag);
+        runtime
+    }
+}
+
+impl fmt::Display for RuntimeDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::kind::FA302;
+
+    #[test]
+    fn test_fields_render_as_text_and_json() {
+        let diag = RuntimeDiagnostic::from(&FA302)
+            .with_tag("ai\"planner")
+            .with_size(4096)
+            .with_limit(1024)
+            .with_frame(7)
+            .with_size(8192);
+
+        assert_eq!(diag.tag(), Some("ai\"planner"));
+        #[allow(deprecated)]
+        let legacy = diag.tag.as_deref();
+        assert_eq!(legacy, Some("ai\"planner"));
+        assert_eq!(diag.field("size"), Some(&FieldValue::Bytes(8192)));
+        assert_eq!(diag.fields.len(), 4);
+
+        let text = diag.render();
+        assert!(text.starts_with("[framealloc][FA302] warning: allocation exceeds tag-specific budget\n"));
+        assert!(text.contains("  fields: tag=\"ai\"planner\", size=8192 bytes, limit=1024 bytes, frame=7\n"));
+        assert!(text.contains("  help: "));
+
+        let json = diag.to_json();
+        assert!(json.contains("\"kind\": \"warning\""));
+        assert!(json.contains("\"fields\": {\"tag\": \"ai\\\"planner\", \"size\": 8192, \"limit\": 1024, \"frame\": 7}"));
+    }
+}
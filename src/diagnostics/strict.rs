@@ -29,6 +29,18 @@ impl From<u8> for StrictMode {
     }
 }
 
+impl StrictMode {
+    /// Parse a mode name as accepted by `FRAMEALLOC_STRICT`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "0" | "warn" | "false" => Some(StrictMode::Warn),
+            "1" | "error" | "true" => Some(StrictMode::PanicOnError),
+            "2" | "warning" | "all" => Some(StrictMode::PanicOnWarning),
+            _ => None,
+        }
+    }
+}
+
 /// Global strict mode setting.
 static STRICT_MODE: AtomicU8 = AtomicU8::new(0);
 
@@ -90,13 +102,7 @@ impl Drop for StrictModeGuard {
 /// - "2" or "warning" -> PanicOnWarning
 pub fn init_from_env() {
     if let Ok(val) = std::env::var("FRAMEALLOC_STRICT") {
-        let mode = match val.to_lowercase().as_str() {
-            "0" | "warn" | "false" => StrictMode::Warn,
-            "1" | "error" | "true" => StrictMode::PanicOnError,
-            "2" | "warning" | "all" => StrictMode::PanicOnWarning,
-            _ => StrictMode::Warn,
-        };
-        set_strict_mode(mode);
+        set_strict_mode(StrictMode::from_name(&val).unwrap_or(StrictMode::Warn));
     }
 }
 
@@ -0,0 +1,693 @@
+//! Chrome Trace Event and Perfetto export of allocator activity.
+//!
+//! A `TraceExporter` collects phase slices, counter samples and instant
+//! events in memory and writes them as Chrome Trace Event JSON or as a
+//! Perfetto protobuf trace. Both open directly in ui.perfetto.dev (and the
+//! JSON form in `chrome://tracing`), with no Tracy install needed.
+//!
+//! Sources:
+//! - `profiler_hooks()` turns `MemoryEvent`s into slices (phase zones),
+//!   frame markers, a per-thread `profiled_bytes` counter and one unitless
+//!   counter per plot
+//! - `record_frame()` samples frame, pool and heap bytes per thread, and
+//!   heap and streaming bytes for the allocator
+//! - `record_diagnostics_event()` adds `DiagnosticsEvent`s as instants
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! let trace = Arc::new(TraceExporter::new());
+//! alloc.set_profiler_hooks(trace.profiler_hooks());
+//!
+//! for _ in 0..600 {
+//!     alloc.begin_frame();
+//!     // ... phases and allocations ...
+//!     alloc.end_frame();
+//!     trace.record_frame(&alloc);
+//! }
+//!
+//! trace.save("target/framealloc/capture.pftrace", TraceFormat::Perfetto)?;
+//! ```
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::thread::{self, ThreadId};
+use std::time::Instant;
+
+use super::hooks::DiagnosticsEvent;
+use super::runtime::FieldValue;
+use super::tracy::{MemoryEvent, MemoryPool, ProfilerHooks};
+use crate::api::alloc::SmartAlloc;
+use crate::sync::mutex::Mutex;
+use crate::util::json::escape_json_str;
+use crate::util::varint::write_u64;
+
+/// Process ID written into traces; all tracks belong to one process.
+const TRACE_PID: u64 = 1;
+
+/// Default cap on buffered events.
+const DEFAULT_MAX_EVENTS: usize = 1 << 20;
+
+/// Output format for a trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceFormat {
+    /// Chrome Trace Event JSON (`.json`)
+    ChromeJson,
+    /// Perfetto protobuf trace (`.pftrace`)
+    Perfetto,
+}
+
+impl TraceFormat {
+    /// Pick a format from a file extension, defaulting to Chrome JSON.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("pftrace" | "perfetto-trace" | "pb") => Self::Perfetto,
+            _ => Self::ChromeJson,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EventKind {
+    SliceBegin,
+    SliceEnd,
+    Instant,
+    Counter(CounterValue),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CounterValue {
+    /// A byte count
+    Bytes(i64),
+    /// A unitless sample, such as a percentage or queue depth
+    Count(f64),
+}
+
+impl CounterValue {
+    fn unit(self) -> CounterUnit {
+        match self {
+            CounterValue::Bytes(_) => CounterUnit::Bytes,
+            CounterValue::Count(_) => CounterUnit::Count,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CounterUnit {
+    Bytes,
+    Count,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Track {
+    /// Thread track, by index into `TraceState::threads`
+    Thread(usize),
+    /// Counter track, by index into `TraceState::counters`
+    Counter(usize),
+}
+
+#[derive(Debug, Clone)]
+struct TraceEvent {
+    ts_ns: u64,
+    track: Track,
+    kind: EventKind,
+    name: String,
+    args: Vec<(&'static str, FieldValue)>,
+}
+
+struct CounterTrack {
+    name: String,
+    /// Owning thread, or `None` for an allocator-wide counter
+    thread: Option<usize>,
+    /// Unit of the first sample; later samples are written as given
+    unit: CounterUnit,
+}
+
+#[derive(Default)]
+struct TraceState {
+    threads: Vec<(ThreadId, String)>,
+    thread_index: HashMap<ThreadId, usize>,
+    counters: Vec<CounterTrack>,
+    counter_index: HashMap<(Option<usize>, String), usize>,
+    events: Vec<TraceEvent>,
+    dropped: u64,
+    /// Live sizes of allocations seen through `MemoryEvent::Alloc`
+    live: HashMap<(MemoryPool, usize), (usize, usize)>,
+    /// Per-thread bytes allocated through `MemoryEvent::Alloc`
+    profiled: HashMap<usize, i64>,
+}
+
+impl TraceState {
+    fn thread(&mut self, id: ThreadId, name: impl FnOnce() -> String) -> usize {
+        if let Some(&index) = self.thread_index.get(&id) {
+            return index;
+        }
+        let index = self.threads.len();
+        self.threads.push((id, name()));
+        self.thread_index.insert(id, index);
+        index
+    }
+
+    fn current_thread(&mut self) -> usize {
+        let current = thread::current();
+        self.thread(current.id(), || {
+            crate::api::threads::current_thread_name()
+                .unwrap_or_else(|| format!("{:?}", current.id()))
+        })
+    }
+
+    fn counter(&mut self, thread: Option<usize>, name: &str, unit: CounterUnit) -> usize {
+        let key = (thread, name.to_string());
+        if let Some(&index) = self.counter_index.get(&key) {
+            return index;
+        }
+        let index = self.counters.len();
+        self.counters.push(CounterTrack {
+            name: name.to_string(),
+            thread,
+            unit,
+        });
+        self.counter_index.insert(key, index);
+        index
+    }
+}
+
+/// Collects allocator activity and writes it as a Chrome or Perfetto trace.
+///
+/// Events are buffered in memory up to `max_events`; later events are
+/// counted as dropped.
+pub struct TraceExporter {
+    start: Instant,
+    max_events: usize,
+    state: Mutex<TraceState>,
+}
+
+impl TraceExporter {
+    /// Create an empty exporter. Timestamps are relative to now.
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            max_events: DEFAULT_MAX_EVENTS,
+            state: Mutex::new(TraceState::default()),
+        }
+    }
+
+    /// Builder: cap the number of buffered events.
+    pub fn with_max_events(mut self, max_events: usize) -> Self {
+        self.max_events = max_events;
+        self
+    }
+
+    fn now_ns(&self) -> u64 {
+        self.start.elapsed().as_nanos() as u64
+    }
+
+    fn push(&self, state: &mut TraceState, event: TraceEvent) {
+        if state.events.len() >= self.max_events {
+            state.dropped += 1;
+        } else {
+            state.events.push(event);
+        }
+    }
+
+    fn on_thread(&self, kind: EventKind, name: &str, args: Vec<(&'static str, FieldValue)>) {
+        let ts_ns = self.now_ns();
+        let mut state = self.state.lock();
+        let thread = state.current_thread();
+        self.push(&mut state, TraceEvent {
+            ts_ns,
+            track: Track::Thread(thread),
+            kind,
+            name: name.to_string(),
+            args,
+        });
+    }
+
+    fn sample(&self, state: &mut TraceState, ts_ns: u64, thread: Option<usize>, name: &str, value: CounterValue) {
+        let counter = state.counter(thread, name, value.unit());
+        self.push(state, TraceEvent {
+            ts_ns,
+            track: Track::Counter(counter),
+            kind: EventKind::Counter(value),
+            name: name.to_string(),
+            args: Vec::new(),
+        });
+    }
+
+    /// Get profiler hooks that feed this exporter.
+    ///
+    /// Pass to `SmartAlloc::set_profiler_hooks` to trace phases as slices.
+    pub fn profiler_hooks(self: &Arc<Self>) -> ProfilerHooks {
+        let exporter = self.clone();
+        let mut hooks = ProfilerHooks::new();
+        hooks.set_callback(move |event| exporter.record_memory_event(&event));
+        hooks
+    }
+
+    /// Record a `MemoryEvent` on the calling thread's track.
+    pub fn record_memory_event(&self, event: &MemoryEvent) {
+        match event {
+            MemoryEvent::ZoneBegin { name } => self.begin_slice(name),
+            MemoryEvent::ZoneEnd => self.end_slice(),
+            MemoryEvent::FrameMark { frame_number } => self.on_thread(
+                EventKind::Instant,
+                "frame",
+                vec![("frame", FieldValue::Number(*frame_number))],
+            ),
+            MemoryEvent::Alloc { ptr, size, pool, .. } => {
+                let ts_ns = self.now_ns();
+                let mut state = self.state.lock();
+                let thread = state.current_thread();
+                state.live.insert((*pool, *ptr), (*size, thread));
+                let total = {
+                    let bytes = state.profiled.entry(thread).or_insert(0);
+                    *bytes += *size as i64;
+                    *bytes
+                };
+                self.sample(&mut state, ts_ns, Some(thread), "profiled_bytes", CounterValue::Bytes(total));
+            }
+            MemoryEvent::Free { ptr, pool } => {
+                let ts_ns = self.now_ns();
+                let mut state = self.state.lock();
+                // The allocating thread's counter drops, wherever the free happens
+                let Some((size, thread)) = state.live.remove(&(*pool, *ptr)) else {
+                    return;
+                };
+                let total = {
+                    let bytes = state.profiled.entry(thread).or_insert(0);
+                    *bytes -= size as i64;
+                    *bytes
+                };
+                self.sample(&mut state, ts_ns, Some(thread), "profiled_bytes", CounterValue::Bytes(total));
+            }
+            MemoryEvent::Plot { name, value } => self.plot(name, *value),
+        }
+    }
+
+    /// Record a `DiagnosticsEvent` as an instant on the calling thread.
+    pub fn record_diagnostics_event(&self, event: &DiagnosticsEvent) {
+        let bytes = |n: &usize| FieldValue::Bytes(*n);
+        let (name, args) = match event {
+            DiagnosticsEvent::FrameBegin { frame_number } => {
+                ("frame_begin", vec![("frame", FieldValue::Number(*frame_number))])
+            }
+            DiagnosticsEvent::FrameEnd { frame_number } => {
+                ("frame_end", vec![("frame", FieldValue::Number(*frame_number))])
+            }
+            DiagnosticsEvent::LargeAllocation { size, tag } => {
+                let mut args = vec![("size", bytes(size))];
+                if let Some(tag) = tag {
+                    args.push(("tag", FieldValue::from(*tag)));
+                }
+                ("large_allocation", args)
+            }
+            DiagnosticsEvent::MemoryPressure { current, limit } => {
+                ("memory_pressure", vec![("current", bytes(current)), ("limit", bytes(limit))])
+            }
+            DiagnosticsEvent::SlabRefill { size_class, count } => (
+                "slab_refill",
+                vec![("size_class", bytes(size_class)), ("count", FieldValue::Number(*count as u64))],
+            ),
+            DiagnosticsEvent::DeferredFree { count } => {
+                ("deferred_free", vec![("count", FieldValue::Number(*count as u64))])
+            }
+            DiagnosticsEvent::BudgetWarning { tag, current, limit } => (
+                "budget_warning",
+                vec![("tag", FieldValue::from(*tag)), ("current", bytes(current)), ("limit", bytes(limit))],
+            ),
+            DiagnosticsEvent::BudgetExceeded { tag, current, limit } => (
+                "budget_exceeded",
+                vec![("tag", FieldValue::from(*tag)), ("current", bytes(current)), ("limit", bytes(limit))],
+            ),
+        };
+        self.on_thread(EventKind::Instant, name, args);
+    }
+
+    /// Open a slice on the calling thread.
+    pub fn begin_slice(&self, name: &str) {
+        self.on_thread(EventKind::SliceBegin, name, Vec::new());
+    }
+
+    /// Close the calling thread's innermost slice.
+    pub fn end_slice(&self) {
+        self.on_thread(EventKind::SliceEnd, "", Vec::new());
+    }
+
+    /// Record an instant event on the calling thread.
+    pub fn instant(&self, name: &str, args: Vec<(&'static str, FieldValue)>) {
+        self.on_thread(EventKind::Instant, name, args);
+    }
+
+    /// Sample an allocator-wide byte counter.
+    pub fn counter(&self, name: &str, value: i64) {
+        let ts_ns = self.now_ns();
+        let mut state = self.state.lock();
+        self.sample(&mut state, ts_ns, None, name, CounterValue::Bytes(value));
+    }
+
+    /// Sample a byte counter on the calling thread's track.
+    pub fn thread_counter(&self, name: &str, value: i64) {
+        let ts_ns = self.now_ns();
+        let mut state = self.state.lock();
+        let thread = state.current_thread();
+        self.sample(&mut state, ts_ns, Some(thread), name, CounterValue::Bytes(value));
+    }
+
+    /// Sample an allocator-wide unitless counter, e.g. a percentage.
+    pub fn plot(&self, name: &str, value: f64) {
+        let ts_ns = self.now_ns();
+        let mut state = self.state.lock();
+        self.sample(&mut state, ts_ns, None, name, CounterValue::Count(value));
+    }
+
+    /// Sample the allocator's memory counters, e.g. after `end_frame`.
+    ///
+    /// Adds `frame_bytes` (usage at its last `end_frame`), `pool_bytes` and
+    /// `heap_bytes` counters for every registered thread, see `ThreadInfo`,
+    /// and allocator-wide `heap_bytes` and `streaming_bytes` counters.
+    pub fn record_frame(&self, alloc: &SmartAlloc) {
+        let stats = alloc.stats();
+        let streaming = alloc.streaming().stats();
+        let threads = alloc.threads();
+
+        let ts_ns = self.now_ns();
+        let mut state = self.state.lock();
+        for info in threads {
+            let thread = state.thread(info.id, || info.name.clone());
+            // Threads named after their first event pick up registered names
+            state.threads[thread].1 = info.name;
+            self.sample(&mut state, ts_ns, Some(thread), "frame_bytes", CounterValue::Bytes(info.last_frame_bytes as i64));
+            self.sample(&mut state, ts_ns, Some(thread), "pool_bytes", CounterValue::Bytes(info.pool_bytes));
+            self.sample(&mut state, ts_ns, Some(thread), "heap_bytes", CounterValue::Bytes(info.heap_bytes));
+        }
+        self.sample(&mut state, ts_ns, None, "heap_bytes", CounterValue::Bytes(stats.heap_allocated as i64));
+        self.sample(&mut state, ts_ns, None, "streaming_bytes", CounterValue::Bytes(streaming.total_reserved as i64));
+    }
+
+    /// Get the number of buffered events.
+    pub fn event_count(&self) -> usize {
+        self.state.lock().events.len()
+    }
+
+    /// Get the number of events dropped because the buffer was full.
+    pub fn dropped_events(&self) -> u64 {
+        self.state.lock().dropped
+    }
+
+    /// Discard all buffered events, keeping known threads and counters.
+    pub fn clear(&self) {
+        let mut state = self.state.lock();
+        state.events.clear();
+        state.dropped = 0;
+    }
+
+    /// Write the trace as Chrome Trace Event JSON.
+    pub fn write_chrome_json<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let state = self.state.lock();
+        writeln!(out, "{{\"displayTimeUnit\": \"ms\", \"traceEvents\": [")?;
+        writeln!(
+            out,
+            "{{\"ph\": \"M\", \"pid\": {}, \"name\": \"process_name\", \"args\": {{\"name\": \"framealloc\"}}}}",
+            TRACE_PID
+        )?;
+        for (tid, (_, name)) in state.threads.iter().enumerate() {
+            writeln!(
+                out,
+                ",{{\"ph\": \"M\", \"pid\": {}, \"tid\": {}, \"name\": \"thread_name\", \"args\": {{\"name\": \"{}\"}}}}",
+                TRACE_PID,
+                tid + 1,
+                escape_json_str(name)
+            )?;
+        }
+
+        for event in &state.events {
+            let ts = event.ts_ns as f64 / 1000.0;
+            let line = match (event.kind, event.track) {
+                (EventKind::Counter(value), Track::Counter(counter)) => {
+                    let track = &state.counters[counter];
+                    // Chrome groups counters by process, so thread counters carry the thread name
+                    let name = match track.thread {
+                        Some(thread) => format!("{} ({})", track.name, state.threads[thread].1),
+                        None => track.name.clone(),
+                    };
+                    let arg = match value {
+                        CounterValue::Bytes(bytes) => format!("\"bytes\": {}", bytes),
+                        CounterValue::Count(count) => format!("\"value\": {}", count),
+                    };
+                    format!(
+                        "{{\"ph\": \"C\", \"pid\": {}, \"ts\": {:.3}, \"name\": \"{}\", \"args\": {{{}}}}}",
+                        TRACE_PID,
+                        ts,
+                        escape_json_str(&name),
+                        arg
+                    )
+                }
+                (kind, Track::Thread(thread)) => {
+                    let phase = match kind {
+                        EventKind::SliceBegin => "\"ph\": \"B\"",
+                        EventKind::SliceEnd => "\"ph\": \"E\"",
+                        _ => "\"ph\": \"i\", \"s\": \"t\"",
+                    };
+                    format!(
+                        "{{{}, \"pid\": {}, \"tid\": {}, \"ts\": {:.3}, \"name\": \"{}\", \"args\": {}}}",
+                        phase,
+                        TRACE_PID,
+                        thread + 1,
+                        ts,
+                        escape_json_str(&event.name),
+                        args_json(&event.args)
+                    )
+                }
+                _ => continue,
+            };
+            writeln!(out, ",{}", line)?;
+        }
+        writeln!(out, "]}}")
+    }
+
+    /// Write the trace as a Perfetto protobuf (`perfetto.protos.Trace`).
+    pub fn write_perfetto<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let state = self.state.lock();
+        let mut trace = Vec::with_capacity(64 + state.events.len() * 24);
+
+        let process_uuid = 1;
+        let thread_uuid = |thread: usize| 0x100 + thread as u64;
+        let counter_uuid = |counter: usize| 0x1_0000_0000 + counter as u64;
+
+        let mut process = Vec::new();
+        proto_varint(&mut process, 1, TRACE_PID);
+        proto_bytes(&mut process, 6, b"framealloc");
+        let mut descriptor = Vec::new();
+        proto_varint(&mut descriptor, 1, process_uuid);
+        proto_bytes(&mut descriptor, 3, &process);
+        trace_packet(&mut trace, None, 60, &descriptor, true);
+
+        for (index, (_, name)) in state.threads.iter().enumerate() {
+            let mut thread = Vec::new();
+            proto_varint(&mut thread, 1, TRACE_PID);
+            proto_varint(&mut thread, 2, index as u64 + 1);
+            proto_bytes(&mut thread, 5, name.as_bytes());
+            let mut descriptor = Vec::new();
+            proto_varint(&mut descriptor, 1, thread_uuid(index));
+            proto_varint(&mut descriptor, 5, process_uuid);
+            proto_bytes(&mut descriptor, 4, &thread);
+            trace_packet(&mut trace, None, 60, &descriptor, false);
+        }
+
+        for (index, counter) in state.counters.iter().enumerate() {
+            // CounterDescriptor.unit = UNIT_SIZE_BYTES or UNIT_COUNT
+            let mut unit = Vec::new();
+            proto_varint(&mut unit, 3, match counter.unit {
+                CounterUnit::Bytes => 3,
+                CounterUnit::Count => 2,
+            });
+            let mut descriptor = Vec::new();
+            proto_varint(&mut descriptor, 1, counter_uuid(index));
+            proto_varint(&mut descriptor, 5, counter.thread.map_or(process_uuid, thread_uuid));
+            proto_bytes(&mut descriptor, 2, counter.name.as_bytes());
+            proto_bytes(&mut descriptor, 8, &unit);
+            trace_packet(&mut trace, None, 60, &descriptor, false);
+        }
+
+        for event in &state.events {
+            let mut track_event = Vec::new();
+            let (kind, uuid) = match (event.kind, event.track) {
+                (EventKind::SliceBegin, Track::Thread(t)) => (1, thread_uuid(t)),
+                (EventKind::SliceEnd, Track::Thread(t)) => (2, thread_uuid(t)),
+                (EventKind::Instant, Track::Thread(t)) => (3, thread_uuid(t)),
+                (EventKind::Counter(_), Track::Counter(c)) => (4, counter_uuid(c)),
+                _ => continue,
+            };
+            proto_varint(&mut track_event, 9, kind);
+            proto_varint(&mut track_event, 11, uuid);
+            match event.kind {
+                EventKind::Counter(CounterValue::Bytes(value)) => proto_varint(&mut track_event, 30, value as u64),
+                EventKind::Counter(CounterValue::Count(value)) => proto_double(&mut track_event, 44, value),
+                EventKind::SliceEnd => {}
+                _ => proto_bytes(&mut track_event, 23, event.name.as_bytes()),
+            }
+            for (key, value) in &event.args {
+                let mut annotation = Vec::new();
+                proto_bytes(&mut annotation, 10, key.as_bytes());
+                match value {
+                    FieldValue::Bytes(n) => proto_varint(&mut annotation, 3, *n as u64),
+                    FieldValue::Number(n) => proto_varint(&mut annotation, 3, *n),
+                    FieldValue::Text(s) => proto_bytes(&mut annotation, 6, s.as_bytes()),
+                }
+                proto_bytes(&mut track_event, 4, &annotation);
+            }
+            trace_packet(&mut trace, Some(event.ts_ns), 11, &track_event, false);
+        }
+
+        out.write_all(&trace)
+    }
+
+    /// Write the trace to a file, creating parent directories.
+    pub fn save<P: AsRef<Path>>(&self, path: P, format: TraceFormat) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut out = BufWriter::new(File::create(path)?);
+        match format {
+            TraceFormat::ChromeJson => self.write_chrome_json(&mut out)?,
+            TraceFormat::Perfetto => self.write_perfetto(&mut out)?,
+        }
+        out.flush()
+    }
+}
+
+impl Default for TraceExporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn args_json(args: &[(&'static str, FieldValue)]) -> String {
+    let args: Vec<_> = args
+        .iter()
+        .map(|(k, v)| format!("\"{}\": {}", k, v.to_json()))
+        .collect();
+    format!("{{{}}}", args.join(", "))
+}
+
+/// Append a varint field (wire type 0).
+fn proto_varint(out: &mut Vec<u8>, field: u64, value: u64) {
+    write_u64(out, field << 3);
+    write_u64(out, value);
+}
+
+/// Append a double field (wire type 1).
+fn proto_double(out: &mut Vec<u8>, field: u64, value: f64) {
+    write_u64(out, (field << 3) | 1);
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Append a length-delimited field (wire type 2).
+fn proto_bytes(out: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    write_u64(out, (field << 3) | 2);
+    write_u64(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+/// Append a `Trace.packet` holding `payload` as field `field`.
+fn trace_packet(trace: &mut Vec<u8>, ts_ns: Option<u64>, field: u64, payload: &[u8], first: bool) {
+    let mut packet = Vec::with_capacity(payload.len() + 16);
+    if let Some(ts_ns) = ts_ns {
+        proto_varint(&mut packet, 8, ts_ns);
+    }
+    proto_bytes(&mut packet, field, payload);
+    // trusted_packet_sequence_id; every packet shares one sequence
+    proto_varint(&mut packet, 10, 1);
+    if first {
+        // sequence_flags = SEQ_INCREMENTAL_STATE_CLEARED
+        proto_varint(&mut packet, 13, 1);
+    }
+    proto_bytes(trace, 1, &packet);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::config::AllocConfig;
+    use crate::util::json::JsonValue;
+
+    #[test]
+    fn test_chrome_trace_has_slices_counters_and_instants() {
+        let alloc = SmartAlloc::new(AllocConfig::default());
+        let trace = Arc::new(TraceExporter::new());
+        alloc.set_profiler_hooks(trace.profiler_hooks());
+
+        alloc.begin_frame();
+        {
+            let _physics = alloc.phase_scope("physics");
+            let _data = alloc.frame_alloc::<[u8; 256]>();
+        }
+        let _pooled = alloc.pool_box([0u8; 48]).unwrap();
+        let _boxed = alloc.heap_box([0u8; 100]).unwrap();
+        alloc.end_frame();
+        trace.record_frame(&alloc);
+        trace.record_memory_event(&MemoryEvent::Plot { name: "budget_percent".to_string(), value: 42.5 });
+        trace.record_diagnostics_event(&DiagnosticsEvent::BudgetExceeded {
+            tag: "ai",
+            current: 2048,
+            limit: 1024,
+        });
+
+        let mut out = Vec::new();
+        trace.write_chrome_json(&mut out).unwrap();
+        let json = JsonValue::parse(std::str::from_utf8(&out).unwrap()).unwrap();
+        let events = json.get("traceEvents").and_then(JsonValue::as_array).unwrap();
+        let find = |ph: &str, name: &str| {
+            events.iter().find(|e| {
+                e.get("ph").and_then(JsonValue::as_str) == Some(ph)
+                    && e.get("name").and_then(JsonValue::as_str).is_some_and(|n| n.starts_with(name))
+            })
+        };
+
+        assert!(find("B", "physics").is_some());
+        assert!(find("E", "").is_some());
+        assert!(find("C", "frame_bytes (").is_some());
+        // Pool and heap bytes are attributed to the allocating thread
+        let bytes = |name: &str| {
+            find("C", name).and_then(|e| e.get("args")).and_then(|a| a.get("bytes")).and_then(JsonValue::as_u64)
+        };
+        let expected = if cfg!(feature = "minimal") { (0, 0) } else { (48, 100) };
+        assert_eq!(bytes("pool_bytes ("), Some(expected.0));
+        assert_eq!(bytes("heap_bytes ("), Some(expected.1));
+        // Plots are unitless and keep their fraction
+        let plot = find("C", "budget_percent").unwrap();
+        assert_eq!(plot.get("args").and_then(|a| a.get("value")).and_then(JsonValue::as_f64), Some(42.5));
+        let exceeded = find("i", "budget_exceeded").unwrap();
+        assert_eq!(exceeded.get("args").and_then(|a| a.get("current")).and_then(JsonValue::as_u64), Some(2048));
+    }
+
+    #[test]
+    fn test_perfetto_packets_are_well_formed() {
+        let trace = TraceExporter::new().with_max_events(3);
+        trace.begin_slice("render");
+        trace.counter("heap_bytes", 4096);
+        trace.end_slice();
+        trace.instant("dropped", Vec::new());
+        assert_eq!(trace.dropped_events(), 1);
+
+        let mut out = Vec::new();
+        trace.write_perfetto(&mut out).unwrap();
+
+        // Walk the top-level Trace message: only length-delimited field 1
+        let mut reader = crate::util::varint::VarintReader::new(&out);
+        let mut packets = 0;
+        while !reader.is_empty() {
+            assert_eq!(reader.u64(), Some((1 << 3) | 2));
+            let len = reader.u64().unwrap() as usize;
+            assert!(reader.bytes(len).is_some());
+            packets += 1;
+        }
+        // Process, one thread and one counter descriptor, then three events
+        assert_eq!(packets, 6);
+        assert!(out.windows(6).any(|w| w == b"render"));
+    }
+}
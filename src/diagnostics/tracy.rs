@@ -2,6 +2,19 @@
 //!
 //! When the `tracy` feature is enabled, this module provides hooks
 //! for visualizing memory allocations in Tracy.
+//!
+//! `SmartAlloc::set_profiler_hooks` reports every frame, pool, heap,
+//! streaming and handle allocation to the hooks, tagged with its
+//! `MemoryPool`. At the end of each frame the frame authority also emits a
+//! frame mark and plots for arena usage, pool usage, deferred queue depth
+//! and per-tag budget utilization. `ProfilerHooks::tracy()` forwards all of
+//! it to a running Tracy client, with each pool shown as a separately named
+//! memory pool.
+
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::Arc;
+
+use crate::sync::mutex::Mutex;
 
 /// Tracy zone for memory allocations.
 #[cfg(feature = "tracy")]
@@ -19,18 +32,68 @@ pub trait TracyIntegration {
     fn tracy_free(&self, ptr: *const u8);
 }
 
+/// Allocator a memory event belongs to.
+///
+/// Each pool is reported to Tracy as a separately named memory pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MemoryPool {
+    /// Frame arena allocations, all freed at `end_frame`
+    Frame,
+    /// Small object pool allocations
+    Pool,
+    /// System heap allocations
+    Heap,
+    /// Streaming reservations; relocation is a free and an alloc
+    Streaming,
+    /// Handle allocations; relocation is a free and an alloc
+    Handle,
+}
+
+impl MemoryPool {
+    /// Every pool, in declaration order.
+    pub const ALL: [MemoryPool; 5] = [
+        MemoryPool::Frame,
+        MemoryPool::Pool,
+        MemoryPool::Heap,
+        MemoryPool::Streaming,
+        MemoryPool::Handle,
+    ];
+
+    /// Get the name the pool is reported under.
+    pub fn name(self) -> &'static str {
+        let name = self.c_name();
+        &name[..name.len() - 1]
+    }
+
+    /// Get the name with a trailing NUL, as Tracy keys pools by pointer.
+    fn c_name(self) -> &'static str {
+        match self {
+            MemoryPool::Frame => "framealloc/frame\0",
+            MemoryPool::Pool => "framealloc/pool\0",
+            MemoryPool::Heap => "framealloc/heap\0",
+            MemoryPool::Streaming => "framealloc/streaming\0",
+            MemoryPool::Handle => "framealloc/handle\0",
+        }
+    }
+}
+
 /// Memory event for external profilers.
+///
+/// New kinds of event may be added; match with a wildcard arm.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum MemoryEvent {
     /// Memory was allocated
     Alloc {
         ptr: usize,
         size: usize,
         tag: Option<&'static str>,
+        pool: MemoryPool,
     },
     /// Memory was freed
     Free {
         ptr: usize,
+        pool: MemoryPool,
     },
     /// Frame boundary
     FrameMark {
@@ -42,6 +105,11 @@ pub enum MemoryEvent {
     },
     /// Memory zone end
     ZoneEnd,
+    /// Sampled value, such as a per-frame usage plot
+    Plot {
+        name: String,
+        value: f64,
+    },
 }
 
 /// Callback type for external profiler integration.
@@ -81,25 +149,55 @@ impl ProfilerHooks {
         self.enabled && self.callback.is_some()
     }
 
-    /// Emit an allocation event.
+    /// Emit an allocation event for the heap pool.
     pub fn emit_alloc(&self, ptr: *const u8, size: usize, tag: Option<&'static str>) {
+        self.emit_pool_alloc(MemoryPool::Heap, ptr, size, tag);
+    }
+
+    /// Emit a free event for the heap pool.
+    pub fn emit_free(&self, ptr: *const u8) {
+        self.emit_pool_free(MemoryPool::Heap, ptr);
+    }
+
+    /// Emit an allocation event for a specific pool.
+    pub fn emit_pool_alloc(
+        &self,
+        pool: MemoryPool,
+        ptr: *const u8,
+        size: usize,
+        tag: Option<&'static str>,
+    ) {
         if let Some(ref callback) = self.callback {
             if self.enabled {
                 callback(MemoryEvent::Alloc {
                     ptr: ptr as usize,
                     size,
                     tag,
+                    pool,
                 });
             }
         }
     }
 
-    /// Emit a free event.
-    pub fn emit_free(&self, ptr: *const u8) {
+    /// Emit a free event for a specific pool.
+    pub fn emit_pool_free(&self, pool: MemoryPool, ptr: *const u8) {
         if let Some(ref callback) = self.callback {
             if self.enabled {
                 callback(MemoryEvent::Free {
                     ptr: ptr as usize,
+                    pool,
+                });
+            }
+        }
+    }
+
+    /// Emit a plot sample.
+    pub fn emit_plot(&self, name: &str, value: f64) {
+        if let Some(ref callback) = self.callback {
+            if self.enabled {
+                callback(MemoryEvent::Plot {
+                    name: name.to_string(),
+                    value,
                 });
             }
         }
@@ -139,6 +237,98 @@ impl Default for ProfilerHooks {
     }
 }
 
+#[cfg(feature = "tracy")]
+impl ProfilerHooks {
+    /// Create hooks that forward every event to Tracy.
+    ///
+    /// Starts the Tracy client if it is not running. Allocations appear as
+    /// one named memory pool per `MemoryPool`, zones as Tracy zones on the
+    /// emitting thread, frame marks as Tracy frames and plots as plots.
+    pub fn tracy() -> Self {
+        let backend = tracy_backend::TracyBackend::new();
+        let mut hooks = Self::new();
+        hooks.set_callback(move |event| backend.forward(event));
+        hooks
+    }
+}
+
+#[cfg(feature = "tracy")]
+mod tracy_backend {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    use tracy_client::{Client, PlotName, Span};
+
+    use super::{MemoryEvent, MemoryPool};
+    use crate::sync::mutex::Mutex;
+
+    thread_local! {
+        /// Open zones on this thread; Tracy spans cannot leave their thread
+        static ZONES: RefCell<Vec<Span>> = const { RefCell::new(Vec::new()) };
+    }
+
+    pub(super) struct TracyBackend {
+        client: Client,
+        /// Plot names are leaked once, Tracy keys plots by pointer
+        plots: Mutex<HashMap<String, PlotName>>,
+    }
+
+    impl TracyBackend {
+        pub(super) fn new() -> Self {
+            Self {
+                client: Client::start(),
+                plots: Mutex::new(HashMap::new()),
+            }
+        }
+
+        pub(super) fn forward(&self, event: MemoryEvent) {
+            match event {
+                MemoryEvent::Alloc { ptr, size, pool, .. } => {
+                    // SAFETY: pool names are static and NUL-terminated
+                    unsafe {
+                        tracy_client::sys::___tracy_emit_memory_alloc_named(
+                            ptr as *const _,
+                            size,
+                            0,
+                            pool_name(pool),
+                        );
+                    }
+                }
+                MemoryEvent::Free { ptr, pool } => {
+                    // SAFETY: as above
+                    unsafe {
+                        tracy_client::sys::___tracy_emit_memory_free_named(
+                            ptr as *const _,
+                            0,
+                            pool_name(pool),
+                        );
+                    }
+                }
+                MemoryEvent::FrameMark { .. } => self.client.frame_mark(),
+                MemoryEvent::ZoneBegin { name } => {
+                    let span = self.client.clone().span_alloc(Some(name), name, file!(), line!(), 0);
+                    ZONES.with(|zones| zones.borrow_mut().push(span));
+                }
+                MemoryEvent::ZoneEnd => {
+                    ZONES.with(|zones| zones.borrow_mut().pop());
+                }
+                MemoryEvent::Plot { name, value } => {
+                    let plot = *self
+                        .plots
+                        .lock()
+                        .entry(name)
+                        .or_insert_with_key(|name| PlotName::new_leak(name.clone()));
+                    self.client.plot(plot, value);
+                }
+            }
+        }
+    }
+
+    fn pool_name(pool: MemoryPool) -> *const std::os::raw::c_char {
+        pool.c_name().as_ptr().cast()
+    }
+}
+
 /// RAII guard for profiler zones.
 pub struct ProfilerZone<'a> {
     hooks: &'a ProfilerHooks,
@@ -158,6 +348,90 @@ impl<'a> Drop for ProfilerZone<'a> {
     }
 }
 
+/// Shared slot for the hooks an allocator reports to.
+///
+/// Held by the allocator state and every allocator `SmartAlloc` owns, so
+/// attaching hooks reaches all of them. Allocation paths check `is_active`
+/// with one atomic load before doing anything else.
+pub(crate) struct ProfilerSlot {
+    hooks: Mutex<Option<Arc<ProfilerHooks>>>,
+    active: AtomicBool,
+    /// Live bytes per pool, counting only allocations made while active
+    live: [AtomicI64; MemoryPool::ALL.len()],
+}
+
+impl ProfilerSlot {
+    pub(crate) fn new() -> Self {
+        Self {
+            hooks: Mutex::new(None),
+            active: AtomicBool::new(false),
+            live: Default::default(),
+        }
+    }
+
+    /// Attach hooks, replacing any previous ones.
+    pub(crate) fn set(&self, hooks: ProfilerHooks) {
+        let enabled = hooks.is_enabled();
+        *self.hooks.lock() = Some(Arc::new(hooks));
+        for live in &self.live {
+            live.store(0, Ordering::Relaxed);
+        }
+        self.active.store(enabled, Ordering::Release);
+    }
+
+    /// Detach the hooks.
+    pub(crate) fn clear(&self) {
+        self.active.store(false, Ordering::Release);
+        *self.hooks.lock() = None;
+    }
+
+    /// Get the hooks if attached, regardless of whether they are enabled.
+    pub(crate) fn hooks(&self) -> Option<Arc<ProfilerHooks>> {
+        self.hooks.lock().clone()
+    }
+
+    /// Check if allocations should be reported.
+    #[inline]
+    pub(crate) fn is_active(&self) -> bool {
+        self.active.load(Ordering::Acquire)
+    }
+
+    /// Get the hooks only if they are attached and enabled.
+    #[inline]
+    pub(crate) fn active(&self) -> Option<Arc<ProfilerHooks>> {
+        if !self.is_active() {
+            return None;
+        }
+        self.hooks()
+    }
+
+    /// Report an allocation from `pool`.
+    #[inline]
+    pub(crate) fn alloc(&self, pool: MemoryPool, ptr: *const u8, size: usize, tag: Option<&'static str>) {
+        if let Some(hooks) = self.active() {
+            self.live[pool as usize].fetch_add(size as i64, Ordering::Relaxed);
+            hooks.emit_pool_alloc(pool, ptr, size, tag);
+        }
+    }
+
+    /// Report a free of `size` bytes back to `pool`.
+    #[inline]
+    pub(crate) fn free(&self, pool: MemoryPool, ptr: *const u8, size: usize) {
+        if let Some(hooks) = self.active() {
+            self.live[pool as usize].fetch_sub(size as i64, Ordering::Relaxed);
+            hooks.emit_pool_free(pool, ptr);
+        }
+    }
+
+    /// Get the live bytes reported for `pool` since hooks were attached.
+    ///
+    /// Frees of allocations made before that are not offset, so the count
+    /// is clamped at zero.
+    pub(crate) fn live_bytes(&self, pool: MemoryPool) -> usize {
+        self.live[pool as usize].load(Ordering::Relaxed).max(0) as usize
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -196,4 +470,27 @@ mod tests {
 
         assert_eq!(counter.load(Ordering::Relaxed), 0);
     }
+
+    #[test]
+    fn test_pool_events_and_plots() {
+        let mut hooks = ProfilerHooks::new();
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        let sink = events.clone();
+        hooks.set_callback(move |event| sink.lock().unwrap().push(event));
+
+        hooks.emit_alloc(8 as *const u8, 16, Some("ai"));
+        hooks.emit_pool_free(MemoryPool::Streaming, 32 as *const u8);
+        hooks.emit_plot("framealloc/pool_bytes", 64.0);
+
+        let events = events.lock().unwrap();
+        assert!(matches!(
+            events[0],
+            MemoryEvent::Alloc { ptr: 8, size: 16, tag: Some("ai"), pool: MemoryPool::Heap }
+        ));
+        assert!(matches!(events[1], MemoryEvent::Free { ptr: 32, pool: MemoryPool::Streaming }));
+        assert!(matches!(&events[2], MemoryEvent::Plot { name, value } if name == "framealloc/pool_bytes" && *value == 64.0));
+        assert_eq!(MemoryPool::Frame.name(), "framealloc/frame");
+        assert!(MemoryPool::ALL.iter().all(|p| p.c_name().ends_with('\0')));
+    }
 }
@@ -39,19 +39,19 @@
 
 // Internal modules (not directly exported)
 #[allow(dead_code)]
-mod api;
+pub mod api;
 #[allow(dead_code)]
-mod allocators;
+pub mod allocators;
 #[allow(dead_code)]
-mod core;
+pub mod core;
 #[allow(dead_code)]
-mod sync;
+pub mod sync;
 #[allow(dead_code)]
-mod util;
+pub mod util;
 #[allow(dead_code)]
-mod diagnostics;
-mod handles;
-mod streaming;
+pub mod diagnostics;
+pub mod handles;
+pub mod streaming;
 
 // Feature-gated modules
 #[cfg(feature = "rapier")]
@@ -3,3 +3,5 @@
 //! Re-exports from internal module for public API.
 
 pub use crate::allocators::streaming::*;
+pub use crate::allocators::stream_loader::*;
+pub use crate::allocators::eviction::*;
@@ -0,0 +1,77 @@
+//! Condvar wrapper - uses parking_lot if available, std otherwise.
+//!
+//! Pairs with `sync::mutex::Mutex`. `wait` takes and returns the guard in
+//! both builds.
+
+#[cfg(feature = "parking_lot")]
+mod pl_condvar {
+    use parking_lot::Condvar as PlCondvar;
+
+    use crate::sync::mutex::MutexGuard;
+
+    /// Thin wrapper around parking_lot::Condvar.
+    #[derive(Default)]
+    pub struct Condvar(PlCondvar);
+
+    impl Condvar {
+        /// Create a new condition variable.
+        pub const fn new() -> Self {
+            Self(PlCondvar::new())
+        }
+
+        /// Block until notified, releasing the guard's lock meanwhile.
+        pub fn wait<'a, T>(&self, mut guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+            self.0.wait(&mut guard);
+            guard
+        }
+
+        /// Wake one waiting thread.
+        pub fn notify_one(&self) {
+            self.0.notify_one();
+        }
+
+        /// Wake all waiting threads.
+        pub fn notify_all(&self) {
+            self.0.notify_all();
+        }
+    }
+}
+
+#[cfg(feature = "parking_lot")]
+pub use pl_condvar::Condvar;
+
+#[cfg(not(feature = "parking_lot"))]
+mod std_condvar {
+    use std::sync::Condvar as StdCondvar;
+
+    use crate::sync::mutex::MutexGuard;
+
+    /// Thin wrapper around std::sync::Condvar.
+    #[derive(Default)]
+    pub struct Condvar(StdCondvar);
+
+    impl Condvar {
+        /// Create a new condition variable.
+        pub const fn new() -> Self {
+            Self(StdCondvar::new())
+        }
+
+        /// Block until notified, releasing the guard's lock meanwhile.
+        pub fn wait<'a, T>(&self, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+            MutexGuard(self.0.wait(guard.0).expect("Mutex poisoned"))
+        }
+
+        /// Wake one waiting thread.
+        pub fn notify_one(&self) {
+            self.0.notify_one();
+        }
+
+        /// Wake all waiting threads.
+        pub fn notify_all(&self) {
+            self.0.notify_all();
+        }
+    }
+}
+
+#[cfg(not(feature = "parking_lot"))]
+pub use std_condvar::Condvar;
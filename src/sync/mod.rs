@@ -1,6 +1,7 @@
 //! Synchronization primitives.
 //!
-//! Provides thin wrappers over std or parking_lot mutexes.
+//! Provides thin wrappers over std or parking_lot mutexes and condvars.
 
 pub(crate) mod atomics;
+pub(crate) mod condvar;
 pub(crate) mod mutex;
@@ -23,7 +23,7 @@ mod std_mutex {
     }
 
     /// Guard for std mutex.
-    pub struct MutexGuard<'a, T>(StdMutexGuard<'a, T>);
+    pub struct MutexGuard<'a, T>(pub(in crate::sync) StdMutexGuard<'a, T>);
 
     impl<'a, T> std::ops::Deref for MutexGuard<'a, T> {
         type Target = T;
@@ -41,4 +41,4 @@ mod std_mutex {
 }
 
 #[cfg(not(feature = "parking_lot"))]
-pub use std_mutex::Mutex;
+pub use std_mutex::{Mutex, MutexGuard};
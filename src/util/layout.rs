@@ -1,6 +1,7 @@
 //! Layout utilities.
 
 use std::alloc::Layout;
+use std::marker::PhantomData;
 
 /// Align a size up to the given alignment.
 #[inline]
@@ -33,6 +34,27 @@ pub fn type_layout<T>() -> Layout {
     Layout::new::<T>()
 }
 
+/// Layout of T with its alignment raised to at least `A`.
+///
+/// Evaluated at compile time; a non-power-of-two `A` fails to build.
+pub struct AlignedLayout<T, const A: usize>(PhantomData<T>);
+
+impl<T, const A: usize> AlignedLayout<T, A> {
+    /// The raised layout.
+    pub const LAYOUT: Layout = {
+        assert!(A.is_power_of_two(), "alignment must be a power of two");
+        let align = if A > std::mem::align_of::<T>() {
+            A
+        } else {
+            std::mem::align_of::<T>()
+        };
+        match Layout::from_size_align(std::mem::size_of::<T>(), align) {
+            Ok(layout) => layout,
+            Err(_) => panic!("invalid aligned layout"),
+        }
+    };
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -52,4 +74,11 @@ mod tests {
         assert_eq!(padding_for(8, 8), 0);
         assert_eq!(padding_for(9, 8), 7);
     }
+
+    #[test]
+    fn test_aligned_layout() {
+        assert_eq!(AlignedLayout::<u8, 64>::LAYOUT.align(), 64);
+        assert_eq!(AlignedLayout::<u64, 4>::LAYOUT.align(), 8);
+        assert_eq!(AlignedLayout::<[f32; 3], 16>::LAYOUT.size(), 12);
+    }
 }
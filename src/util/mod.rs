@@ -1,4 +1,7 @@
 //! Utility helpers.
 
+pub(crate) mod json;
 pub(crate) mod layout;
 pub(crate) mod size;
+pub(crate) mod toml;
+pub(crate) mod varint;
@@ -35,6 +35,45 @@ pub fn format_bytes(bytes: usize) -> String {
     }
 }
 
+/// Parse a human size such as `"64KiB"`, `"1.5 GB"` or `"4096"`.
+///
+/// Units are case-insensitive powers of 1024 (`K`, `KB` and `KiB` are all
+/// 1024 bytes). Returns `None` for malformed or overflowing sizes.
+pub fn parse_size(text: &str) -> Option<usize> {
+    let text = text.trim().replace('_', "");
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let multiplier: usize = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => kb(1),
+        "m" | "mb" | "mib" => mb(1),
+        "g" | "gb" | "gib" => gb(1),
+        "t" | "tb" | "tib" => gb(1).checked_mul(1024)?,
+        _ => return None,
+    };
+
+    if let Ok(whole) = number.parse::<usize>() {
+        return whole.checked_mul(multiplier);
+    }
+    let value: f64 = number.parse().ok()?;
+    let bytes = (value * multiplier as f64).round();
+    (value.is_finite() && bytes >= 0.0 && bytes <= usize::MAX as f64).then_some(bytes as usize)
+}
+
+/// Format a size so `parse_size` reads it back exactly.
+///
+/// Uses the largest binary unit that divides `bytes`, e.g. `"16MiB"`.
+pub fn format_size_exact(bytes: usize) -> String {
+    for (unit, size) in [("GiB", gb(1)), ("MiB", mb(1)), ("KiB", kb(1))] {
+        if bytes >= size && bytes % size == 0 {
+            return format!("{}{}", bytes / size, unit);
+        }
+    }
+    bytes.to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -53,4 +92,21 @@ mod tests {
         assert_eq!(format_bytes(1024 * 1024), "1.00 MB");
         assert_eq!(format_bytes(1024 * 1024 * 1024), "1.00 GB");
     }
+
+    #[test]
+    fn test_parse_and_format_sizes() {
+        assert_eq!(parse_size("4096"), Some(4096));
+        assert_eq!(parse_size("64KiB"), Some(kb(64)));
+        assert_eq!(parse_size("32 mb"), Some(mb(32)));
+        assert_eq!(parse_size("1.5G"), Some(gb(1) + mb(512)));
+        assert_eq!(parse_size("1_000 B"), Some(1000));
+        assert_eq!(parse_size("12 parsecs"), None);
+        assert_eq!(parse_size("-1MiB"), None);
+        assert_eq!(parse_size(""), None);
+
+        for bytes in [0, 1000, kb(48), mb(16), gb(2)] {
+            assert_eq!(parse_size(&format_size_exact(bytes)), Some(bytes));
+        }
+        assert_eq!(format_size_exact(mb(16)), "16MiB");
+    }
 }
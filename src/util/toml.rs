@@ -0,0 +1,274 @@
+//! Minimal TOML reader for configuration files.
+//!
+//! Supports the subset config files need: comments, `[table]` headers,
+//! bare and dotted keys, and integer, float, boolean, basic string and
+//! (possibly multi-line) array values. Entries are returned flattened, with
+//! table names joined to keys by dots.
+
+/// A parsed TOML value.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum TomlValue {
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    String(String),
+    Array(Vec<TomlValue>),
+}
+
+/// A TOML syntax error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TomlError {
+    /// 1-based line of the error
+    pub line: usize,
+    /// What was wrong
+    pub message: &'static str,
+}
+
+/// Parse a document into `(dotted.key, value)` entries in file order.
+pub(crate) fn parse(input: &str) -> Result<Vec<(String, TomlValue)>, TomlError> {
+    let mut parser = Parser { bytes: input.as_bytes(), pos: 0 };
+    let mut entries: Vec<(String, TomlValue)> = Vec::new();
+    let mut table = String::new();
+
+    loop {
+        parser.skip_trivia();
+        let Some(&byte) = parser.bytes.get(parser.pos) else {
+            break;
+        };
+
+        if byte == b'[' {
+            parser.pos += 1;
+            table = parser.key()?;
+            parser.skip_spaces();
+            parser.expect(b']', "expected ']' after table name")?;
+        } else {
+            let key = parser.key()?;
+            parser.skip_spaces();
+            parser.expect(b'=', "expected '=' after key")?;
+            parser.skip_spaces();
+            let value = parser.value()?;
+            let key = if table.is_empty() { key } else { format!("{}.{}", table, key) };
+            if entries.iter().any(|(k, _)| *k == key) {
+                return Err(parser.error("duplicate key"));
+            }
+            entries.push((key, value));
+        }
+
+        parser.skip_spaces();
+        parser.skip_comment();
+        match parser.bytes.get(parser.pos) {
+            None | Some(b'\n') => {}
+            Some(b'\r') if parser.bytes.get(parser.pos + 1) == Some(&b'\n') => {}
+            Some(_) => return Err(parser.error("expected end of line")),
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Recursive-descent TOML parser over UTF-8 bytes.
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &'static str) -> TomlError {
+        let line = self.bytes[..self.pos.min(self.bytes.len())]
+            .iter()
+            .filter(|&&b| b == b'\n')
+            .count()
+            + 1;
+        TomlError { line, message }
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.bytes.get(self.pos), Some(b' ' | b'\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.bytes.get(self.pos) == Some(&b'#') {
+            while !matches!(self.bytes.get(self.pos), None | Some(b'\n')) {
+                self.pos += 1;
+            }
+        }
+    }
+
+    /// Skip whitespace, newlines and comments.
+    fn skip_trivia(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            match self.bytes.get(self.pos) {
+                Some(b'\n' | b'\r') => self.pos += 1,
+                _ => return,
+            }
+        }
+    }
+
+    fn expect(&mut self, byte: u8, message: &'static str) -> Result<(), TomlError> {
+        if self.bytes.get(self.pos) == Some(&byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(message))
+        }
+    }
+
+    /// Parse a bare or dotted key.
+    fn key(&mut self) -> Result<String, TomlError> {
+        let mut key = String::new();
+        loop {
+            self.skip_spaces();
+            let start = self.pos;
+            while matches!(self.bytes.get(self.pos), Some(b) if b.is_ascii_alphanumeric() || *b == b'_' || *b == b'-') {
+                self.pos += 1;
+            }
+            if self.pos == start {
+                return Err(self.error("expected a key"));
+            }
+            key.push_str(std::str::from_utf8(&self.bytes[start..self.pos]).expect("ASCII key"));
+            self.skip_spaces();
+            if self.bytes.get(self.pos) != Some(&b'.') {
+                return Ok(key);
+            }
+            self.pos += 1;
+            key.push('.');
+        }
+    }
+
+    fn value(&mut self) -> Result<TomlValue, TomlError> {
+        match self.bytes.get(self.pos) {
+            Some(b'"') => self.string().map(TomlValue::String),
+            Some(b'[') => self.array(),
+            Some(b't') => self.literal("true", TomlValue::Bool(true)),
+            Some(b'f') => self.literal("false", TomlValue::Bool(false)),
+            Some(b'-' | b'+' | b'0'..=b'9') => self.number(),
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    fn literal(&mut self, text: &'static str, value: TomlValue) -> Result<TomlValue, TomlError> {
+        if self.bytes[self.pos..].starts_with(text.as_bytes()) {
+            self.pos += text.len();
+            Ok(value)
+        } else {
+            Err(self.error("invalid literal"))
+        }
+    }
+
+    fn number(&mut self) -> Result<TomlValue, TomlError> {
+        let start = self.pos;
+        while matches!(self.bytes.get(self.pos), Some(b) if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'+' | b'.' | b'_')) {
+            self.pos += 1;
+        }
+        let text: String = std::str::from_utf8(&self.bytes[start..self.pos])
+            .expect("ASCII number")
+            .chars()
+            .filter(|&c| c != '_')
+            .collect();
+        if let Ok(integer) = text.parse::<i64>() {
+            return Ok(TomlValue::Integer(integer));
+        }
+        let is_float = text.contains(['.', 'e', 'E']) && !text.contains("inf") && !text.contains("nan");
+        match text.parse::<f64>() {
+            Ok(float) if is_float => Ok(TomlValue::Float(float)),
+            _ => Err(self.error("invalid number")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, TomlError> {
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let start = self.pos;
+            while !matches!(self.bytes.get(self.pos), None | Some(b'"' | b'\\' | b'\n')) {
+                self.pos += 1;
+            }
+            out.push_str(std::str::from_utf8(&self.bytes[start..self.pos]).map_err(|_| self.error("invalid UTF-8"))?);
+            match self.bytes.get(self.pos) {
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                Some(b'\\') => {
+                    let escaped = match self.bytes.get(self.pos + 1) {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'n') => '\n',
+                        Some(b't') => '\t',
+                        Some(b'r') => '\r',
+                        Some(b'u') => {
+                            let hex = self.bytes.get(self.pos + 2..self.pos + 6).ok_or_else(|| self.error("invalid escape"))?;
+                            let code = std::str::from_utf8(hex).ok().and_then(|h| u32::from_str_radix(h, 16).ok());
+                            let escaped = code.and_then(char::from_u32).ok_or_else(|| self.error("invalid escape"))?;
+                            out.push(escaped);
+                            self.pos += 6;
+                            continue;
+                        }
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    out.push(escaped);
+                    self.pos += 2;
+                }
+                _ => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<TomlValue, TomlError> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        loop {
+            self.skip_trivia();
+            if self.bytes.get(self.pos) == Some(&b']') {
+                self.pos += 1;
+                return Ok(TomlValue::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_trivia();
+            match self.bytes.get(self.pos) {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {}
+                _ => return Err(self.error("expected ',' or ']' in array")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tables_arrays_and_comments() {
+        let entries = parse(
+            "# tuned\nframe_arena_size = 1_048_576 # 1 MiB\nslab_size_classes = [\n  16, 32, # small\n  64,\n]\n\n[budgets]\nenabled = true\nname = \"a \\\"b\\\"\\u00e9\"\nratio = 0.5\n",
+        )
+        .unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                ("frame_arena_size".to_string(), TomlValue::Integer(1 << 20)),
+                (
+                    "slab_size_classes".to_string(),
+                    TomlValue::Array(vec![TomlValue::Integer(16), TomlValue::Integer(32), TomlValue::Integer(64)])
+                ),
+                ("budgets.enabled".to_string(), TomlValue::Bool(true)),
+                ("budgets.name".to_string(), TomlValue::String("a \"b\"\u{e9}".to_string())),
+                ("budgets.ratio".to_string(), TomlValue::Float(0.5)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_errors_report_line() {
+        assert_eq!(parse("a = 1\nb = \n").unwrap_err(), TomlError { line: 2, message: "expected a value" });
+        assert_eq!(parse("a = 1\na = 2").unwrap_err().message, "duplicate key");
+        assert_eq!(parse("a = 1 2").unwrap_err().message, "expected end of line");
+        assert_eq!(parse("a = 12abc").unwrap_err().message, "invalid number");
+        assert!(parse("a = \"open").is_err());
+    }
+}
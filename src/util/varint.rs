@@ -0,0 +1,100 @@
+//! LEB128 variable-length integers.
+//!
+//! Unsigned values use 7 bits per byte with a continuation bit; signed
+//! values are zigzag-encoded first so small negative deltas stay short.
+
+/// Append an unsigned varint.
+pub(crate) fn write_u64(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Append a zigzag-encoded signed varint.
+pub(crate) fn write_i64(out: &mut Vec<u8>, value: i64) {
+    write_u64(out, ((value << 1) ^ (value >> 63)) as u64);
+}
+
+/// Cursor over varint-encoded bytes.
+pub(crate) struct VarintReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> VarintReader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    /// Check if every byte has been consumed.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    /// Get the number of unread bytes.
+    pub(crate) fn remaining(&self) -> usize {
+        self.bytes.len().saturating_sub(self.pos)
+    }
+
+    /// Read one raw byte.
+    pub(crate) fn u8(&mut self) -> Option<u8> {
+        let byte = *self.bytes.get(self.pos)?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    /// Read `len` raw bytes.
+    pub(crate) fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let end = self.pos.checked_add(len)?;
+        let slice = self.bytes.get(self.pos..end)?;
+        self.pos = end;
+        Some(slice)
+    }
+
+    /// Read an unsigned varint. Returns `None` if truncated or overlong.
+    pub(crate) fn u64(&mut self) -> Option<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    /// Read a zigzag-encoded signed varint.
+    pub(crate) fn i64(&mut self) -> Option<i64> {
+        let raw = self.u64()?;
+        Some((raw >> 1) as i64 ^ -((raw & 1) as i64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_varint_round_trip() {
+        let unsigned = [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX];
+        let signed = [0, -1, 1, -64, 64, i64::MIN, i64::MAX];
+
+        let mut out = Vec::new();
+        unsigned.iter().for_each(|&v| write_u64(&mut out, v));
+        signed.iter().for_each(|&v| write_i64(&mut out, v));
+        assert_eq!(&out[..4], &[0, 1, 127, 0x80]);
+
+        let mut reader = VarintReader::new(&out);
+        for &v in &unsigned {
+            assert_eq!(reader.u64(), Some(v));
+        }
+        for &v in &signed {
+            assert_eq!(reader.i64(), Some(v));
+        }
+        assert!(reader.is_empty());
+        assert_eq!(VarintReader::new(&[0x80]).u64(), None);
+    }
+}
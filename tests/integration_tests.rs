@@ -318,3 +318,721 @@ fn test_config_high_performance() {
 
     alloc.end_frame();
 }
+
+#[test]
+fn test_phase_accounting_and_profiler_zones() {
+    use framealloc::{MemoryEvent, ProfilerHooks};
+    use std::sync::Mutex;
+
+    let alloc = SmartAlloc::new(AllocConfig::minimal());
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = events.clone();
+    let mut hooks = ProfilerHooks::new();
+    hooks.set_callback(move |event| {
+        if let MemoryEvent::ZoneBegin { name } = event {
+            sink.lock().unwrap().push(name);
+        }
+    });
+    alloc.set_profiler_hooks(hooks);
+
+    alloc.begin_frame();
+    {
+        let _update = alloc.phase_scope("update");
+        alloc.frame_alloc::<[u8; 128]>();
+        {
+            let _physics = alloc.phase_scope("physics");
+            let ptr = alloc.pool_alloc::<[u8; 64]>();
+            unsafe { alloc.pool_free(ptr) };
+            alloc.frame_alloc::<[u8; 32]>();
+        }
+    }
+    let summary = alloc.end_frame_with_summary();
+    assert_eq!(*events.lock().unwrap(), vec!["update", "physics"]);
+
+    // Phase accounting is compiled out under `minimal`
+    #[cfg(not(feature = "minimal"))]
+    {
+        let update = summary.by_phase.iter().find(|p| p.phase == "update").unwrap();
+        let physics = summary.by_phase.iter().find(|p| p.phase == "physics").unwrap();
+        assert_eq!(physics.bytes_allocated, 96);
+        assert_eq!(physics.allocation_count, 2);
+        assert_eq!(physics.peak_bytes, 64);
+        assert_eq!(physics.discarded_bytes, 32);
+        assert_eq!(update.bytes_allocated, 224);
+        // Nested frame bytes are discarded by the innermost phase only
+        assert_eq!(update.discarded_bytes, 128);
+    }
+    #[cfg(feature = "minimal")]
+    let _ = summary;
+}
+
+#[test]
+fn test_profiler_reports_named_pools_and_frame_plots() {
+    use framealloc::{MemoryEvent, MemoryPool, ProfilerHooks, StreamPriority};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    let alloc = SmartAlloc::new(AllocConfig::default().with_budgets(true));
+    alloc.budgets().unwrap().register_tag_budget("ai", 1024, 4096);
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = events.clone();
+    let mut hooks = ProfilerHooks::new();
+    hooks.set_callback(move |event| sink.lock().unwrap().push(event));
+    alloc.set_profiler_hooks(hooks);
+
+    alloc.begin_frame();
+    alloc.begin_phase("update");
+    alloc.frame_alloc::<[u8; 128]>();
+    let checkpoint = alloc.frame_checkpoint();
+    alloc.frame_alloc::<[u8; 32]>();
+    alloc.rollback_to(checkpoint);
+    let pooled = alloc.pool_box(7u64).unwrap();
+    let heaped = alloc.heap_box([0u8; 256]).unwrap();
+    let stream = alloc.streaming().reserve(4096, StreamPriority::Normal).unwrap();
+    let handle = alloc.handles().alloc::<u32>().unwrap();
+    alloc.end_phase();
+    alloc.end_frame();
+
+    drop((pooled, heaped));
+    alloc.streaming().free(stream);
+    alloc.handles().free(handle);
+    alloc.clear_profiler_hooks();
+    alloc.begin_frame();
+    alloc.frame_alloc::<u64>();
+    alloc.end_frame();
+
+    let events = events.lock().unwrap();
+    let mut live: HashMap<(MemoryPool, usize), usize> = HashMap::new();
+    let mut allocated: HashMap<MemoryPool, usize> = HashMap::new();
+    for event in events.iter() {
+        match event {
+            MemoryEvent::Alloc { ptr, size, pool, .. } => {
+                assert!(live.insert((*pool, *ptr), *size).is_none());
+                *allocated.entry(*pool).or_default() += size;
+            }
+            MemoryEvent::Free { ptr, pool } => {
+                assert!(live.remove(&(*pool, *ptr)).is_some());
+            }
+            _ => {}
+        }
+    }
+    assert!(live.is_empty(), "unbalanced events: {:?}", live);
+    assert_eq!(allocated[&MemoryPool::Frame], 160);
+    assert_eq!(allocated[&MemoryPool::Pool], 8);
+    assert_eq!(allocated[&MemoryPool::Heap], 256);
+    assert_eq!(allocated[&MemoryPool::Streaming], 4096);
+    assert_eq!(allocated[&MemoryPool::Handle], 4);
+
+    let plots: Vec<_> = events
+        .iter()
+        .filter_map(|e| match e {
+            MemoryEvent::Plot { name, value } => Some((name.as_str(), *value)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        plots,
+        vec![
+            ("framealloc/frame_arena_bytes", 128.0),
+            ("framealloc/pool_bytes", 8.0),
+            ("framealloc/deferred_queue_depth", 0.0),
+            ("framealloc/budget/ai", 0.0),
+        ]
+    );
+
+    assert!(matches!(events.first(), Some(MemoryEvent::ZoneBegin { name: "update" })));
+    let mark = events.iter().position(|e| matches!(e, MemoryEvent::FrameMark { .. }));
+    let zone_end = events.iter().position(|e| matches!(e, MemoryEvent::ZoneEnd));
+    assert!(zone_end < mark);
+    assert!(matches!(
+        events[mark.unwrap()],
+        MemoryEvent::FrameMark { frame_number } if frame_number == alloc.frame_number() - 1
+    ));
+}
+
+#[test]
+fn test_allocation_histograms_in_behavior_report() {
+    use framealloc::AllocKind;
+
+    let alloc = SmartAlloc::with_defaults();
+    alloc.enable_allocation_histograms();
+
+    let mut kept = Vec::new();
+    for frame in 0..4 {
+        alloc.begin_frame();
+        alloc.frame_alloc::<[u8; 1000]>();
+        let checkpoint = alloc.frame_checkpoint();
+        alloc.frame_alloc::<[u8; 3000]>();
+        alloc.rollback_to(checkpoint);
+        alloc.with_tag("ai", |a| kept.push(a.pool_box([0u8; 48]).unwrap()));
+        if frame == 3 {
+            kept.clear();
+        }
+        let handle = alloc.handles().alloc::<u64>().unwrap();
+        alloc.handles().free(handle);
+        alloc.end_frame();
+    }
+
+    let histograms = alloc.allocation_histograms();
+    let pool = histograms.sizes_for_tag("ai");
+    assert_eq!(pool.count(), 4);
+    assert_eq!(pool.max(), Some(48));
+    assert_eq!(histograms.sizes_for_kind(AllocKind::Frame).count(), 8);
+
+    // Pool boxes lived 3, 2, 1 and 0 frames; handles were freed in their frame
+    let lifetimes = histograms.lifetimes_for_kind(AllocKind::Pool);
+    assert_eq!((lifetimes.min(), lifetimes.max()), (Some(0), Some(3)));
+    assert_eq!(histograms.lifetimes_for_kind(AllocKind::Handle).max(), Some(0));
+
+    // The rolled-back allocation still counts towards the frame's high-water mark
+    assert_eq!(histograms.arena_high_water.count(), 4);
+    assert!(histograms.arena_high_water.min().unwrap() >= 4000);
+    assert_eq!(histograms.suggested_frame_arena_size(), Some(4096));
+    assert_eq!(histograms.suggested_slab_size_classes(4), vec![64]);
+
+    let report = alloc.behavior_report();
+    assert!(report.histograms.is_some());
+    let summary = report.summary();
+    assert!(summary.contains("pool sizes (bytes): n=4"), "{}", summary);
+    assert!(summary.contains("pool lifetimes (frames)"), "{}", summary);
+    assert!(summary.contains("frame arena high-water (bytes): n=4"), "{}", summary);
+
+    alloc.reset_behavior_stats();
+    assert!(alloc.allocation_histograms().is_empty());
+}
+
+#[test]
+fn test_config_tuner_recommendation_loads_at_startup() {
+    use framealloc::ConfigTuner;
+
+    let alloc = SmartAlloc::new(AllocConfig::minimal());
+    alloc.enable_allocation_histograms();
+    let mut kept = Vec::new();
+    for _ in 0..8 {
+        alloc.begin_frame();
+        alloc.frame_alloc::<[u8; 100_000]>();
+        kept.push(alloc.pool_box([0u8; 48]).unwrap());
+        alloc.end_frame();
+    }
+    assert!(alloc.threads().iter().any(|t| t.peak_frame_bytes >= 100_000));
+
+    let mut tuner = ConfigTuner::new(alloc.config().clone());
+    tuner.observe(&alloc);
+    let recommendation = tuner.recommend();
+    assert_eq!(recommendation.config.frame_arena_size, 128 * 1024);
+    assert!(recommendation.config.slab_size_classes.contains(&64));
+    assert!(recommendation.justification("slab_pages_per_class").is_some());
+
+    let path = std::env::temp_dir().join(format!("framealloc_tuned_{}.toml", std::process::id()));
+    std::fs::write(&path, recommendation.to_toml()).unwrap();
+    let loaded = AllocConfig::from_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.frame_arena_size, recommendation.config.frame_arena_size);
+    assert_eq!(loaded.slab_size_classes, recommendation.config.slab_size_classes);
+
+    let tuned = SmartAlloc::new(loaded);
+    tuned.begin_frame();
+    assert!(!tuned.frame_alloc::<[u8; 100_000]>().is_null());
+    tuned.end_frame();
+}
+
+#[test]
+fn test_config_file_tag_budgets_and_validation() {
+    use framealloc::ConfigError;
+
+    let config = AllocConfig::from_toml(
+        "frame_arena_size = \"2MiB\"\nenable_budgets = true\n\n[tag_budgets.physics]\nsoft_limit = \"1MiB\"\nhard_limit = \"2MiB\"\n",
+    )
+    .unwrap();
+    assert_eq!(config.frame_arena_size, 2 * 1024 * 1024);
+
+    let alloc = SmartAlloc::new(config);
+    let budgets = alloc.budgets().unwrap().get_all_tag_budgets();
+    assert_eq!(budgets.len(), 1);
+    assert_eq!(budgets[0].name, "physics");
+    assert_eq!(budgets[0].hard_limit, 2 * 1024 * 1024);
+
+    let err = AllocConfig::from_toml("slab_page_size = \"1KiB\"").unwrap_err();
+    assert!(matches!(err, ConfigError::Invalid { ref field, .. } if field == "slab_page_size"), "{}", err);
+}
+
+// Phase budgets are compiled out under `minimal`
+#[cfg(not(feature = "minimal"))]
+#[test]
+fn test_phase_budget_fallback_and_high_water() {
+    use framealloc::{BudgetEvent, PhaseBudgetPolicy};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let alloc = SmartAlloc::new(AllocConfig::minimal());
+    alloc.set_phase_budget("ai", 256, PhaseBudgetPolicy::Fallback);
+    let breaches = Arc::new(AtomicUsize::new(0));
+    let counter = breaches.clone();
+    alloc.phase_budgets().set_event_callback(move |event| {
+        if let BudgetEvent::PhaseLimitExceeded { phase: "ai", .. } = event {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+    });
+
+    for _ in 0..2 {
+        alloc.begin_frame();
+        {
+            let _ai = alloc.phase_scope("ai");
+            for _ in 0..4 {
+                let ptr = alloc.frame_alloc::<[u8; 128]>();
+                assert!(!ptr.is_null());
+                unsafe { ptr.write([7; 128]) };
+            }
+        }
+        alloc.end_frame();
+    }
+
+    assert_eq!(breaches.load(Ordering::Relaxed), 2);
+    let mark = alloc.phase_high_water("ai").unwrap();
+    assert_eq!(mark.peak_frame_bytes, 512);
+    assert_eq!(mark.samples, 2);
+    assert_eq!(mark.over_budget, 2);
+}
+
+// Phase budgets are compiled out under `minimal`
+#[cfg(not(feature = "minimal"))]
+#[test]
+fn test_phase_budget_callback_may_allocate() {
+    use framealloc::PhaseBudgetPolicy;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let alloc = SmartAlloc::new(AllocConfig::minimal());
+    alloc.set_phase_budget("ai", 64, PhaseBudgetPolicy::Diagnostic);
+    let seen = Arc::new(AtomicUsize::new(0));
+    let counter = seen.clone();
+    let inner = alloc.clone();
+    alloc.phase_budgets().set_event_callback(move |_| {
+        let pooled = inner.pool_box(1u64).unwrap();
+        let frame = inner.frame_alloc::<u64>();
+        assert!(!frame.is_null());
+        counter.fetch_add(*pooled as usize, Ordering::Relaxed);
+    });
+
+    alloc.begin_frame();
+    alloc.begin_phase("ai");
+    assert!(!alloc.frame_alloc::<[u8; 128]>().is_null());
+    alloc.end_phase();
+    alloc.end_frame();
+
+    assert_eq!(seen.load(Ordering::Relaxed), 1);
+    // Drop the callback's handle to the allocator
+    alloc.phase_budgets().set_event_callback(|_| {});
+}
+
+#[test]
+fn test_global_frame_vs_thread_epochs() {
+    use framealloc::{PromotionFailure, PromotedAllocation, RetentionPolicy};
+
+    // Run on a fresh thread so this thread's epoch starts at zero
+    thread::spawn(|| {
+        let alloc = SmartAlloc::new(AllocConfig::minimal());
+
+        for frame in 1..=3u64 {
+            alloc.begin_frame();
+            let workers: Vec<_> = (0..3)
+                .map(|_| {
+                    let alloc = alloc.clone();
+                    thread::spawn(move || {
+                        alloc.begin_frame();
+                        let seen = alloc.thread_frame();
+                        alloc.end_frame();
+                        (seen, alloc.thread_epoch())
+                    })
+                })
+                .collect();
+            for worker in workers {
+                assert_eq!(worker.join().unwrap(), (frame, 1));
+            }
+            assert_eq!(alloc.frame_number(), frame);
+            assert_eq!(alloc.thread_epoch(), frame);
+            assert_eq!(alloc.snapshot().frame, frame);
+            alloc.end_frame();
+        }
+
+        // A retained allocation from an earlier epoch is not promoted
+        alloc.begin_frame();
+        let _stale = alloc.frame_retained::<u64>(RetentionPolicy::PromoteToPool);
+        alloc.end_frame();
+        alloc.begin_frame();
+        let result = alloc.end_frame_with_promotions();
+        assert!(matches!(
+            result.promoted[0],
+            PromotedAllocation::Failed { reason: PromotionFailure::StaleEpoch, .. }
+        ));
+    })
+    .join()
+    .unwrap();
+}
+
+#[test]
+fn test_frame_clock_outlives_authority_thread() {
+    let alloc = SmartAlloc::new(AllocConfig::minimal());
+
+    let worker = alloc.clone();
+    thread::spawn(move || {
+        worker.begin_frame();
+        worker.end_frame();
+    })
+    .join()
+    .unwrap();
+    assert_eq!(alloc.frame_number(), 1);
+
+    // The exited thread's claim is gone; this thread takes over
+    alloc.begin_frame();
+    alloc.end_frame();
+    assert_eq!(alloc.frame_number(), 2);
+
+    // Unregistering gives it up as well
+    assert!(alloc.unregister_thread());
+    let worker = alloc.clone();
+    thread::spawn(move || {
+        worker.begin_frame();
+        worker.end_frame();
+    })
+    .join()
+    .unwrap();
+    assert_eq!(alloc.frame_number(), 3);
+}
+
+#[test]
+fn test_interleaved_instances_keep_phases_and_retention_apart() {
+    use framealloc::RetentionPolicy;
+
+    let game = SmartAlloc::new(AllocConfig::default());
+    let editor = SmartAlloc::new(AllocConfig::minimal());
+
+    game.begin_frame();
+    game.begin_phase("simulate");
+    let mut kept = game.frame_retained::<u64>(RetentionPolicy::PromoteToHeap);
+    *kept = 99;
+    game.frame_alloc::<[u8; 64]>();
+
+    // The editor's frame neither sees nor resets the game's phase or
+    // retained allocations
+    editor.begin_frame();
+    assert_eq!(editor.current_phase(), None);
+    editor.begin_phase("preview");
+    editor.frame_alloc::<[u8; 512]>();
+    let preview = editor.end_phase().unwrap();
+    assert_eq!(preview.name, "preview");
+    // Phase accounting is compiled out under `minimal`
+    #[cfg(not(feature = "minimal"))]
+    assert_eq!(preview.bytes_allocated, 512);
+    assert_eq!(editor.retained_count(), 0);
+    let summary = editor.end_frame_with_summary();
+    assert_eq!(summary.total_retained_count() + summary.failed_count, 0);
+
+    assert_eq!(game.current_phase(), Some("simulate"));
+    assert_eq!(game.retained_count(), 1);
+    let phase = game.end_phase().unwrap();
+    assert_eq!(phase.name, "simulate");
+    #[cfg(not(feature = "minimal"))]
+    assert_eq!(phase.bytes_allocated, 64 + 8);
+
+    let result = game.end_frame_with_promotions();
+    assert_eq!(result.promoted.len(), 1);
+    assert_eq!(game.retained_count(), 0);
+}
+
+#[test]
+fn test_independent_instances_on_one_thread() {
+    let game = SmartAlloc::new(AllocConfig::default());
+    let editor = SmartAlloc::new(AllocConfig::minimal());
+    assert_ne!(game.id(), editor.id());
+    assert_eq!(game.clone().id(), game.id());
+
+    game.begin_frame();
+    editor.begin_frame();
+
+    let value = game.frame_box(1234u64).unwrap();
+    {
+        let preview = editor.frame_box([7u8; 256]).unwrap();
+        assert_eq!(preview[255], 7);
+    }
+
+    // Ending the editor's frame leaves the game's arena untouched
+    editor.end_frame();
+    assert_eq!(editor.thread_epoch(), 1);
+    let other = game.frame_box(5678u64).unwrap();
+    assert_eq!(*value, 1234);
+    assert_eq!(*other, 5678);
+
+    // A new editor frame does not advance the game's epoch
+    editor.begin_frame();
+    assert_eq!(game.thread_epoch(), 1);
+    assert_eq!(editor.thread_epoch(), 2);
+    editor.end_frame();
+
+    // Pools are separate as well
+    let pooled = editor.pool_box(42u32).unwrap();
+    drop(editor);
+    assert_eq!(*pooled, 42);
+    drop(pooled);
+
+    game.end_frame();
+}
+
+#[test]
+fn test_thread_registration_and_teardown() {
+    let alloc = SmartAlloc::new(AllocConfig::default());
+    let other = SmartAlloc::new(AllocConfig::minimal());
+
+    let worker = {
+        let alloc = alloc.clone();
+        let other = other.clone();
+        thread::spawn(move || {
+            alloc.register_thread("physics-worker");
+            alloc.begin_frame();
+            let boxes: Vec<_> = (0..8).map(|i| alloc.pool_box(i as u64).unwrap()).collect();
+            let scratch = alloc.frame_box([0u8; 256]).unwrap();
+
+            let snapshot = alloc.snapshot();
+            let worker = snapshot.threads.iter().find(|t| t.name == "physics-worker").unwrap();
+            assert_eq!(worker.epoch, 1);
+            assert!(worker.frame_bytes >= 256 && worker.peak_bytes >= worker.frame_bytes);
+            // Per-thread pool bytes are compiled out under `minimal`
+            #[cfg(not(feature = "minimal"))]
+            assert_eq!(worker.pool_bytes, 64);
+            drop((boxes, scratch));
+
+            // The name belongs to `alloc` only
+            assert_eq!(framealloc::current_thread_name(), None);
+            other.begin_frame();
+            assert!(other.threads().iter().all(|t| t.name != "physics-worker" && !t.explicit));
+            other.end_frame();
+            alloc.end_frame();
+        })
+    };
+    worker.join().unwrap();
+
+    // Thread exit tore the worker's state down and reclaimed its pools
+    assert!(alloc.threads().iter().all(|t| t.name != "physics-worker"));
+    let stats = alloc.thread_teardown_stats();
+    assert_eq!(stats.threads_torn_down, 1);
+    assert!(stats.objects_reclaimed >= 8);
+
+    // Explicit unregistration on this thread
+    alloc.register_thread("main");
+    assert!(alloc.threads().iter().any(|t| t.name == "main" && t.explicit));
+
+    // Refused while frame memory may still be borrowed
+    alloc.begin_frame();
+    let held = alloc.frame_box(7u64).unwrap();
+    assert!(!alloc.unregister_thread());
+    assert_eq!(*held, 7);
+    alloc.end_frame();
+
+    assert!(alloc.unregister_thread());
+    assert!(!alloc.unregister_thread());
+    assert!(alloc.threads().is_empty());
+    assert_eq!(alloc.thread_teardown_stats().threads_torn_down, 2);
+}
+
+#[test]
+fn test_pool_alloc_routes_large_objects() {
+    use framealloc::{AllocKind, PoolBackend};
+
+    #[repr(align(8192))]
+    struct PageAligned {
+        _bytes: [u8; 64],
+    }
+
+    let alloc = SmartAlloc::new(AllocConfig::default());
+    alloc.enable_behavior_filter();
+
+    assert_eq!(SmartAlloc::pool_backend::<u32>(), PoolBackend::Slab { class_size: 16 });
+    assert_eq!(SmartAlloc::pool_backend::<[u8; 8192]>(), PoolBackend::LargeObject);
+    assert_eq!(SmartAlloc::pool_backend::<PageAligned>(), PoolBackend::LargeObject);
+
+    let big = alloc.pool_alloc::<[u8; 8192]>();
+    let aligned = alloc.pool_alloc::<PageAligned>();
+    assert!(!big.is_null());
+    assert_eq!(aligned as usize % 8192, 0);
+
+    let stats = alloc.stats();
+    assert_eq!(stats.large_object_allocated, 8192 * 2);
+    assert_eq!(stats.large_object_count, 2);
+    assert!(alloc
+        .behavior_filter()
+        .analyze()
+        .stats
+        .iter()
+        .any(|s| s.kind == AllocKind::LargeObject));
+
+    unsafe {
+        alloc.pool_free(big);
+        alloc.pool_free(aligned);
+    }
+    assert_eq!(alloc.large_object_stats().live_objects, 0);
+    assert_eq!(alloc.large_object_stats().peak_bytes, 8192 * 2);
+}
+
+#[test]
+fn test_over_aligned_allocations_on_every_path() {
+    use framealloc::{PoolBackend, StreamPriority};
+
+    #[repr(align(64))]
+    struct CacheLine {
+        _line: [u8; 64],
+    }
+
+    let alloc = SmartAlloc::new(AllocConfig::default());
+    alloc.begin_frame();
+
+    // Frame arena
+    for _ in 0..4 {
+        let _ = alloc.frame_alloc::<u8>();
+        let lanes = alloc.frame_alloc_aligned::<[f32; 8], 32>();
+        assert_eq!(lanes as usize % 32, 0);
+        let line = alloc.frame_alloc_aligned::<u64, 128>();
+        assert_eq!(line as usize % 128, 0);
+    }
+
+    // Pools serve cache-line types from a slab class that guarantees them
+    assert_eq!(SmartAlloc::pool_backend::<CacheLine>(), PoolBackend::Slab { class_size: 64 });
+    let lines: Vec<_> = (0..16).map(|_| alloc.pool_alloc::<CacheLine>()).collect();
+    assert!(lines.iter().all(|&p| !p.is_null() && p as usize % 64 == 0));
+    for ptr in lines {
+        unsafe { alloc.pool_free(ptr) };
+    }
+    assert_eq!(alloc.large_object_stats().allocation_count, 0);
+
+    // Scratch pools
+    let scratch = alloc.scratch_pool("simd");
+    let _ = scratch.alloc::<u8>();
+    assert_eq!(scratch.alloc::<CacheLine>() as usize % 64, 0);
+
+    // Streaming
+    let id = alloc
+        .streaming()
+        .reserve_aligned(4096, 256, StreamPriority::Normal)
+        .unwrap();
+    assert_eq!(alloc.streaming().begin_load(id).unwrap() as usize % 256, 0);
+    alloc.streaming().free(id);
+
+    alloc.end_frame();
+}
+
+#[test]
+fn test_pool_magazines_shared_through_depot() {
+    use framealloc::SystemPageSource;
+
+    let config = AllocConfig::default().with_slab_batch_sizes(vec![0, 0, 16]);
+    let alloc = SmartAlloc::with_page_source(config, Arc::new(SystemPageSource));
+
+    // A worker frees more than two magazines' worth, then exits
+    let worker = alloc.clone();
+    thread::spawn(move || {
+        let ptrs: Vec<_> = (0..64).map(|_| worker.pool_alloc::<[u64; 8]>()).collect();
+        for ptr in ptrs {
+            unsafe { worker.pool_free(ptr) };
+        }
+    })
+    .join()
+    .unwrap();
+
+    let stats = alloc.depot_stats()[2];
+    assert_eq!(stats.class_size, 64);
+    assert_eq!(stats.batch_size, 16);
+    assert!(stats.magazines_returned > 0);
+
+    // This thread refills from the depot instead of allocating a page
+    let ptr = alloc.pool_alloc::<[u64; 8]>();
+    assert!(!ptr.is_null());
+    assert_eq!(alloc.depot_stats()[2].pages_allocated, stats.pages_allocated);
+    unsafe { alloc.pool_free(ptr) };
+}
+
+// FA303 comes from phase budgets, which are compiled out under `minimal`
+#[cfg(not(feature = "minimal"))]
+#[test]
+fn test_diagnostics_routed_to_registered_sink() {
+    use framealloc::{diagnostics, Diagnostic, DiagnosticSink, PhaseBudgetPolicy, SinkFilter, SinkRateLimit};
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Console(Mutex<Vec<(&'static str, String)>>);
+
+    impl DiagnosticSink for Console {
+        fn emit(&self, diag: &Diagnostic) {
+            self.0.lock().unwrap().push((diag.code, String::new()));
+        }
+
+        fn emit_with_context(&self, diag: &Diagnostic, context: &str) {
+            self.0.lock().unwrap().push((diag.code, context.to_string()));
+        }
+    }
+
+    let console = Arc::new(Console::default());
+    let id = diagnostics::sinks().add_with(
+        console.clone(),
+        SinkFilter::all().with_code_prefix("FA3"),
+        SinkRateLimit::Unlimited,
+    );
+
+    let alloc = SmartAlloc::new(AllocConfig::default());
+    alloc.register_thread("sink_test_thread");
+    alloc.set_phase_budget("sink_test_phase", 64, PhaseBudgetPolicy::Diagnostic);
+    alloc.begin_frame();
+    alloc.begin_phase("sink_test_phase");
+    let _ = alloc.frame_alloc::<[u8; 256]>();
+    alloc.end_phase();
+    // Another allocator's snapshot leaves this allocator's diagnostics out
+    let other = SmartAlloc::new(AllocConfig::default());
+    assert!(other.snapshot().diagnostics.iter().all(|d| d.allocator() == Some(other.id().as_u64())));
+    alloc.end_frame();
+
+    assert!(diagnostics::sinks().remove(id));
+    let received = console.0.lock().unwrap();
+    assert!(received.iter().all(|(code, _)| code.starts_with("FA3")));
+    let tagged = format!("allocator={}", alloc.id().as_u64());
+    assert!(received
+        .iter()
+        .any(|(code, context)| *code == "FA303"
+            && context.contains("sink_test_phase")
+            && context.contains("sink_test_thread")
+            && context.contains(&tagged)));
+}
+
+// `fa_diagnostic!` compiles to nothing in other builds
+#[cfg(any(debug_assertions, feature = "diagnostics"))]
+#[test]
+fn test_structured_diagnostics_reach_snapshot() {
+    use framealloc::{diagnostics, fa_diagnostic, CollectingSink, FieldValue, SinkFilter, SinkRateLimit};
+
+    let sink = Arc::new(CollectingSink::new());
+    let id = diagnostics::sinks().add_with(
+        sink.clone(),
+        SinkFilter::all().with_code_prefix("FA399"),
+        SinkRateLimit::Unlimited,
+    );
+
+    let bytes: usize = 3 * 1024;
+    fa_diagnostic!(
+        Warning,
+        code = "FA399",
+        message = format!("tag '{}' used {} bytes", "snapshot_test", bytes),
+        fields = { tag: "snapshot_test", size: bytes, limit: 1024usize, retries: 2u64 }
+    );
+    assert!(diagnostics::sinks().remove(id));
+
+    let received = sink.runtime_diagnostics();
+    assert_eq!(received.len(), 1);
+    let diag = &received[0];
+    assert_eq!(diag.tag(), Some("snapshot_test"));
+    assert_eq!(diag.field("size"), Some(&FieldValue::Bytes(3072)));
+    assert_eq!(diag.field("retries"), Some(&FieldValue::Number(2)));
+    assert!(diag.field("thread").is_some());
+    assert!(diag.render().contains("tag 'snapshot_test' used 3072 bytes"));
+
+    let mut snapshot = framealloc::Snapshot::new(1);
+    snapshot.add_diagnostic(diag.clone());
+    let json = snapshot.to_json();
+    assert!(json.contains("\"tag\": \"snapshot_test\""));
+    assert!(json.contains("\"size\": 3072"));
+}